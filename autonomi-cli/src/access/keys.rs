@@ -6,8 +6,10 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use autonomi::client::key_chain::KeyChain;
 use autonomi::client::registers::RegisterSecretKey;
-use autonomi::client::vault::VaultSecretKey;
+use autonomi::client::scratchpad::ScratchpadSecretKey;
+use autonomi::client::vault::{derive_vault_key, VaultSecretKey};
 use autonomi::{get_evm_network_from_env, Wallet};
 use color_eyre::eyre::{Context, Result};
 use color_eyre::Section;
@@ -18,6 +20,9 @@ use std::path::PathBuf;
 const SECRET_KEY_ENV: &str = "SECRET_KEY";
 const REGISTER_SIGNING_KEY_ENV: &str = "REGISTER_SIGNING_KEY";
 
+/// App name used to derive the CLI keys from the key chain
+const CLI_APP_NAME: &str = "autonomi-cli";
+
 const SECRET_KEY_FILE: &str = "secret_key";
 const REGISTER_SIGNING_KEY_FILE: &str = "register_signing_key";
 
@@ -52,10 +57,43 @@ pub fn get_secret_key() -> Result<String> {
         .with_suggestion(|| "the secret key should be a hex encoded string of your evm wallet private key")
 }
 
+/// The key chain deriving all the other keys from the `SECRET_KEY`
+/// Everything stored with those keys can be recovered from the `SECRET_KEY` alone
+pub fn get_key_chain() -> Result<KeyChain> {
+    let secret_key = get_secret_key()?;
+    KeyChain::from_evm_secret_key(&secret_key)
+        .wrap_err("Failed to derive key chain from EVM secret key")
+}
+
 pub fn get_vault_secret_key() -> Result<VaultSecretKey> {
+    get_key_chain()?
+        .vault_key()
+        .wrap_err("Failed to derive vault key from EVM secret key")
+}
+
+/// The key of the vaults created before the vault key got derived from the key chain
+pub fn get_legacy_vault_secret_key() -> Result<VaultSecretKey> {
     let secret_key = get_secret_key()?;
-    autonomi::client::vault::derive_vault_key(&secret_key)
-        .wrap_err("Failed to derive vault secret key from EVM secret key")
+    derive_vault_key(&secret_key).wrap_err("Failed to derive legacy vault key from EVM secret key")
+}
+
+/// The owner key of the scratchpads created with the CLI, each scratchpad key is derived from it and the scratchpad name
+pub fn get_scratchpad_owner_key() -> Result<ScratchpadSecretKey> {
+    get_key_chain()?
+        .scratchpad_owner_key(CLI_APP_NAME)
+        .wrap_err("Failed to derive scratchpad owner key from EVM secret key")
+}
+
+/// The signing key of the register with the given name
+/// A register signing key set with the env var or generated with `register generate-key` takes precedence,
+/// otherwise the key is derived from the `SECRET_KEY` and the register name
+pub fn get_register_key(name: &str) -> Result<RegisterSecretKey> {
+    if let Ok(key) = get_register_signing_key() {
+        return Ok(key);
+    }
+    get_key_chain()?
+        .register_key(name)
+        .wrap_err("Failed to derive register key from EVM secret key")
 }
 
 pub fn create_register_signing_key_file(key: RegisterSecretKey) -> Result<PathBuf> {
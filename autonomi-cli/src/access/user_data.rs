@@ -15,19 +15,44 @@ use autonomi::client::{
     registers::{RegisterAddress, RegisterSecretKey},
     vault::UserData,
 };
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{eyre, Result};
 
 use super::{
     data_dir::get_client_data_dir_path,
-    keys::{create_register_signing_key_file, get_register_signing_key},
+    keys::{create_register_signing_key_file, get_key_chain, get_register_signing_key},
 };
 
 use serde::{Deserialize, Serialize};
 
+/// File holding the user data as of the last vault sync, used as base for three-way merges
+const VAULT_SYNC_BASE_FILE: &str = "vault_sync_base";
+
 #[derive(Serialize, Deserialize)]
 struct PrivateFileArchive {
     name: String,
-    secret_access: String,
+    /// Plaintext hex of the access, only found in files written before the access got encrypted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    secret_access: Option<String>,
+    /// Hex of the access encrypted with the private data key of the key chain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    encrypted_access: Option<String>,
+}
+
+impl PrivateFileArchive {
+    fn access(&self) -> Result<PrivateArchiveAccess> {
+        match (&self.encrypted_access, &self.secret_access) {
+            (Some(encrypted_access), _) => {
+                let private_data_key = get_key_chain()?.private_data_key()?;
+                let access = PrivateArchiveAccess::decrypt(
+                    &hex::decode(encrypted_access)?,
+                    &private_data_key,
+                )?;
+                Ok(access)
+            }
+            (None, Some(secret_access)) => Ok(PrivateArchiveAccess::from_hex(secret_access)?),
+            (None, None) => Err(eyre!("Private file archive {} has no access", self.name)),
+        }
+    }
 }
 
 pub fn get_local_user_data() -> Result<UserData> {
@@ -59,8 +84,7 @@ pub fn get_local_private_file_archives() -> Result<HashMap<PrivateArchiveAccess,
         let entry = entry?;
         let file_content = std::fs::read_to_string(entry.path())?;
         let private_file_archive: PrivateFileArchive = serde_json::from_str(&file_content)?;
-        let private_file_archive_access =
-            PrivateArchiveAccess::from_hex(&private_file_archive.secret_access)?;
+        let private_file_archive_access = private_file_archive.access()?;
         private_file_archives.insert(private_file_archive_access, private_file_archive.name);
     }
     Ok(private_file_archives)
@@ -73,9 +97,7 @@ pub fn get_local_private_archive_access(local_addr: &str) -> Result<PrivateArchi
     let file_path = private_file_archives_path.join(local_addr);
     let file_content = std::fs::read_to_string(file_path)?;
     let private_file_archive: PrivateFileArchive = serde_json::from_str(&file_content)?;
-    let private_file_archive_access =
-        PrivateArchiveAccess::from_hex(&private_file_archive.secret_access)?;
-    Ok(private_file_archive_access)
+    private_file_archive.access()
 }
 
 pub fn get_local_registers() -> Result<HashMap<RegisterAddress, String>> {
@@ -134,9 +156,69 @@ pub fn write_local_user_data(user_data: &UserData) -> Result<()> {
     }
 
     for (archive, name) in user_data.private_file_archives.iter() {
-        write_local_private_file_archive(archive.to_hex(), archive.address(), name)?;
+        write_local_private_file_archive(archive, name)?;
+    }
+
+    Ok(())
+}
+
+/// Replaces the local user data with the given user data, removing local entries it doesn't contain
+pub fn replace_local_user_data(user_data: &UserData) -> Result<()> {
+    write_local_user_data(user_data)?;
+
+    let data_dir = get_client_data_dir_path()?;
+    let user_data_path = data_dir.join("user_data");
+
+    for register in get_local_registers()?.keys() {
+        if !user_data.registers.contains_key(register) {
+            std::fs::remove_file(user_data_path.join("registers").join(register.to_hex()))?;
+        }
     }
 
+    for archive in get_local_public_file_archives()?.keys() {
+        if !user_data.file_archives.contains_key(archive) {
+            std::fs::remove_file(
+                user_data_path
+                    .join("file_archives")
+                    .join(addr_to_str(*archive)),
+            )?;
+        }
+    }
+
+    for archive in get_local_private_file_archives()?.keys() {
+        if !user_data.private_file_archives.contains_key(archive) {
+            std::fs::remove_file(
+                user_data_path
+                    .join("private_file_archives")
+                    .join(archive.address()),
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Get the user data as of the last vault sync, if any
+pub fn get_vault_sync_base() -> Result<Option<UserData>> {
+    let data_dir = get_client_data_dir_path()?;
+    let base_path = data_dir.join("user_data").join(VAULT_SYNC_BASE_FILE);
+    if !base_path.exists() {
+        return Ok(None);
+    }
+    let bytes = std::fs::read(base_path)?;
+    let user_data = UserData::from_bytes(bytes.into())?;
+    Ok(Some(user_data))
+}
+
+/// Record the user data as of the last vault sync
+pub fn write_vault_sync_base(user_data: &UserData) -> Result<()> {
+    let data_dir = get_client_data_dir_path()?;
+    let user_data_path = data_dir.join("user_data");
+    std::fs::create_dir_all(&user_data_path)?;
+    std::fs::write(
+        user_data_path.join(VAULT_SYNC_BASE_FILE),
+        user_data.to_bytes()?,
+    )?;
     Ok(())
 }
 
@@ -158,19 +240,18 @@ pub fn write_local_public_file_archive(archive: String, name: &str) -> Result<()
     Ok(())
 }
 
-pub fn write_local_private_file_archive(
-    archive: String,
-    local_addr: String,
-    name: &str,
-) -> Result<()> {
+/// Saves the access to a private file archive, encrypted with the private data key of the key chain
+pub fn write_local_private_file_archive(archive: &PrivateArchiveAccess, name: &str) -> Result<()> {
     let data_dir = get_client_data_dir_path()?;
     let user_data_path = data_dir.join("user_data");
     let private_file_archives_path = user_data_path.join("private_file_archives");
     std::fs::create_dir_all(&private_file_archives_path)?;
-    let file_name = local_addr;
+    let file_name = archive.address();
+    let private_data_key = get_key_chain()?.private_data_key()?;
     let content = serde_json::to_string(&PrivateFileArchive {
         name: name.to_string(),
-        secret_access: archive,
+        secret_access: None,
+        encrypted_access: Some(hex::encode(archive.encrypt(&private_data_key.public_key()))),
     })?;
     std::fs::write(private_file_archives_path.join(file_name), content)?;
     Ok(())
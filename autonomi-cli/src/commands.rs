@@ -8,6 +8,7 @@
 
 mod file;
 mod register;
+mod scratchpad;
 mod vault;
 
 use clap::Subcommand;
@@ -29,6 +30,12 @@ pub enum SubCmd {
         command: RegisterCmd,
     },
 
+    /// Operations related to scratchpad management.
+    Scratchpad {
+        #[command(subcommand)]
+        command: ScratchpadCmd,
+    },
+
     /// Operations related to vault management.
     Vault {
         #[command(subcommand)]
@@ -68,6 +75,8 @@ pub enum FileCmd {
 #[derive(Subcommand, Debug)]
 pub enum RegisterCmd {
     /// Generate a new register key.
+    /// Without it, the key of each register is derived from your `SECRET_KEY` and the register name.
+    /// The generated key is used for all registers instead.
     GenerateKey {
         /// Overwrite existing key if it exists
         /// Warning: overwriting the existing key will result in loss of access to any existing registers created using that key
@@ -120,6 +129,52 @@ pub enum RegisterCmd {
     List,
 }
 
+#[derive(Subcommand, Debug)]
+pub enum ScratchpadCmd {
+    /// Estimate cost to create a scratchpad.
+    Cost {
+        /// The name of the scratchpad.
+        name: String,
+    },
+
+    /// Create a new scratchpad with the given name and value.
+    /// The scratchpad address is derived from your `SECRET_KEY` and the name.
+    Create {
+        /// The name of the scratchpad.
+        name: String,
+        /// The value to store in the scratchpad.
+        value: String,
+        /// Store the value unencrypted. Everyone can read public scratchpads.
+        #[arg(short, long)]
+        public: bool,
+    },
+
+    /// Replace the value of an existing scratchpad. Updating a scratchpad is free.
+    /// The scratchpad stays public or encrypted as it was, unless `--public` or `--private` is given.
+    Update {
+        /// The name of the scratchpad.
+        name: String,
+        /// The new value to store in the scratchpad.
+        value: String,
+        /// Store the value unencrypted. Everyone can read public scratchpads.
+        #[arg(short, long, conflicts_with = "private")]
+        public: bool,
+        /// Store the value encrypted, only readable with your secret key.
+        #[arg(long)]
+        private: bool,
+    },
+
+    /// Get the value of a scratchpad.
+    Get {
+        /// The name of the scratchpad.
+        name: String,
+        /// The hex encoded public key of the owner of the scratchpad, defaults to your own.
+        /// Only public scratchpads of other owners can be read.
+        #[arg(short, long)]
+        owner: Option<String>,
+    },
+}
+
 #[derive(Subcommand, Debug)]
 pub enum VaultCmd {
     /// Estimate cost to create a vault.
@@ -135,8 +190,9 @@ pub enum VaultCmd {
     Load,
 
     /// Sync vault with the network, including registers and files.
-    /// Loads existing user data from the network and merges it with your local user data.
-    /// Pushes your local user data to the network.
+    /// Loads existing user data from the network and merges it with your local user data,
+    /// using the last synced user data as common base so additions and removals on both sides are kept.
+    /// Pushes the merged user data to the network, retrying if another device updated the vault meanwhile.
     Sync {
         /// Force push your local user data to the network.
         /// This will overwrite any existing data in your vault.
@@ -174,6 +230,28 @@ pub async fn handle_subcommand(opt: Opt) -> Result<()> {
             RegisterCmd::Get { address, name } => register::get(address, name, peers.await?).await,
             RegisterCmd::List => register::list(),
         },
+        SubCmd::Scratchpad { command } => match command {
+            ScratchpadCmd::Cost { name } => scratchpad::cost(&name, peers.await?).await,
+            ScratchpadCmd::Create {
+                name,
+                value,
+                public,
+            } => scratchpad::create(&name, &value, public, peers.await?).await,
+            ScratchpadCmd::Update {
+                name,
+                value,
+                public,
+                private,
+            } => {
+                let encrypt = match (public, private) {
+                    (true, _) => Some(false),
+                    (_, true) => Some(true),
+                    _ => None,
+                };
+                scratchpad::update(&name, &value, encrypt, peers.await?).await
+            }
+            ScratchpadCmd::Get { name, owner } => scratchpad::get(&name, owner, peers.await?).await,
+        },
         SubCmd::Vault { command } => match command {
             VaultCmd::Cost => vault::cost(peers.await?).await,
             VaultCmd::Create => vault::create(peers.await?).await,
@@ -50,20 +50,20 @@ pub async fn upload(file: &str, public: bool, peers: Vec<Multiaddr>) -> Result<(
 
     // upload dir
     let local_addr;
-    let archive = if public {
+    let mut private_data_access = None;
+    if public {
         let xor_name = client
             .dir_upload(dir_path, &wallet)
             .await
             .wrap_err("Failed to upload file")?;
         local_addr = addr_to_str(xor_name);
-        local_addr.clone()
     } else {
-        let private_data_access = client
+        let access = client
             .private_dir_upload(dir_path, &wallet)
             .await
             .wrap_err("Failed to upload file")?;
-        local_addr = private_data_access.address();
-        private_data_access.to_hex()
+        local_addr = access.address();
+        private_data_access = Some(access);
     };
 
     // wait for upload to complete
@@ -86,10 +86,9 @@ pub async fn upload(file: &str, public: bool, peers: Vec<Multiaddr>) -> Result<(
     info!("Summary for upload of file {file} at {local_addr:?}: {summary:?}");
 
     // save to local user data
-    let writer = if public {
-        crate::user_data::write_local_public_file_archive(archive, &name)
-    } else {
-        crate::user_data::write_local_private_file_archive(archive, local_addr, &name)
+    let writer = match &private_data_access {
+        None => crate::user_data::write_local_public_file_archive(local_addr, &name),
+        Some(access) => crate::user_data::write_local_private_file_archive(access, &name),
     };
     writer
         .wrap_err("Failed to save file to local user data")
@@ -37,7 +37,7 @@ pub fn generate_key(overwrite: bool) -> Result<()> {
 }
 
 pub async fn cost(name: &str, peers: Vec<Multiaddr>) -> Result<()> {
-    let register_key = crate::keys::get_register_signing_key()
+    let register_key = crate::keys::get_register_key(name)
         .wrap_err("The register key is required to perform this action")?;
     let client = crate::actions::connect_to_network(peers).await?;
 
@@ -52,7 +52,7 @@ pub async fn cost(name: &str, peers: Vec<Multiaddr>) -> Result<()> {
 
 pub async fn create(name: &str, value: &str, public: bool, peers: Vec<Multiaddr>) -> Result<()> {
     let wallet = crate::keys::load_evm_wallet()?;
-    let register_key = crate::keys::get_register_signing_key()
+    let register_key = crate::keys::get_register_key(name)
         .wrap_err("The register key is required to perform this action")?;
     let mut client = crate::actions::connect_to_network(peers).await?;
     let event_receiver = client.enable_client_events();
@@ -117,19 +117,22 @@ pub async fn create(name: &str, value: &str, public: bool, peers: Vec<Multiaddr>
 }
 
 pub async fn edit(address: String, name: bool, value: &str, peers: Vec<Multiaddr>) -> Result<()> {
-    let register_key = crate::keys::get_register_signing_key()
-        .wrap_err("The register key is required to perform this action")?;
-    let client = crate::actions::connect_to_network(peers).await?;
-
-    let address = if name {
-        Client::register_address(&address, &register_key)
+    let (address, register_key) = if name {
+        let register_key = crate::keys::get_register_key(&address)
+            .wrap_err("The register key is required to perform this action")?;
+        (
+            Client::register_address(&address, &register_key),
+            register_key,
+        )
     } else {
-        RegisterAddress::from_hex(&address)
+        let address = RegisterAddress::from_hex(&address)
             .wrap_err(format!("Failed to parse register address: {address}"))
             .with_suggestion(|| {
                 "if you want to use the name as the address, run the command with the --name flag"
-            })?
+            })?;
+        (address, register_key_for_address(&address)?)
     };
+    let client = crate::actions::connect_to_network(peers).await?;
 
     println!("Getting register at address: {address}");
     info!("Getting register at address: {address}");
@@ -155,11 +158,11 @@ pub async fn edit(address: String, name: bool, value: &str, peers: Vec<Multiaddr
 }
 
 pub async fn get(address: String, name: bool, peers: Vec<Multiaddr>) -> Result<()> {
-    let register_key = crate::keys::get_register_signing_key()
-        .wrap_err("The register key is required to perform this action")?;
     let client = crate::actions::connect_to_network(peers).await?;
 
     let address = if name {
+        let register_key = crate::keys::get_register_key(&address)
+            .wrap_err("The register key is required to find a register by name")?;
         Client::register_address(&address, &register_key)
     } else {
         RegisterAddress::from_hex(&address)
@@ -191,6 +194,20 @@ pub async fn get(address: String, name: bool, peers: Vec<Multiaddr>) -> Result<(
     Ok(())
 }
 
+/// Finds the signing key of a register from its name in the local user data
+fn register_key_for_address(address: &RegisterAddress) -> Result<RegisterSecretKey> {
+    if let Ok(key) = crate::keys::get_register_signing_key() {
+        return Ok(key);
+    }
+    let registers = crate::user_data::get_local_registers()?;
+    let name = registers
+        .get(address)
+        .ok_or_else(|| eyre!("Register {address} is not in your local user data"))
+        .with_suggestion(|| "run the command with the --name flag and the name of the register")?;
+    crate::keys::get_register_key(name)
+        .wrap_err("The register key is required to perform this action")
+}
+
 pub fn list() -> Result<()> {
     println!("Retrieving local user data...");
     let registers = crate::user_data::get_local_registers()?;
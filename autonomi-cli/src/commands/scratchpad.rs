@@ -0,0 +1,144 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use autonomi::client::scratchpad::{
+    derive_scratchpad_key, scratchpad_address, ScratchpadPublicKey,
+};
+use autonomi::client::vault::app_name_to_vault_content_type;
+use autonomi::Multiaddr;
+use color_eyre::eyre::eyre;
+use color_eyre::eyre::Context;
+use color_eyre::eyre::Result;
+use color_eyre::Section;
+
+/// Name used to derive the content type of the Scratchpads created with the CLI
+const CLI_SCRATCHPAD_APP_NAME: &str = "autonomi-cli scratchpad";
+
+pub async fn cost(name: &str, peers: Vec<Multiaddr>) -> Result<()> {
+    let owner_key = crate::keys::get_scratchpad_owner_key()?;
+    let client = crate::actions::connect_to_network(peers).await?;
+
+    let cost = client
+        .scratchpad_cost(&owner_key.public_key(), name)
+        .await
+        .wrap_err("Failed to get cost for scratchpad")?;
+    info!("Estimated cost to create a scratchpad with name {name}: {cost}");
+    if cost.is_zero() {
+        println!("✅ The scratchpad with name {name} already exists, updating it is free");
+    } else {
+        println!("✅ The estimated cost to create a scratchpad with name {name} is: {cost}");
+    }
+    Ok(())
+}
+
+pub async fn create(name: &str, value: &str, public: bool, peers: Vec<Multiaddr>) -> Result<()> {
+    let wallet = crate::keys::load_evm_wallet()?;
+    let owner_key = crate::keys::get_scratchpad_owner_key()?;
+    let client = crate::actions::connect_to_network(peers).await?;
+
+    println!("Creating scratchpad with name: {name}");
+    info!("Creating scratchpad with name: {name}, public: {public}");
+    let (address, cost) = client
+        .scratchpad_create(
+            &owner_key,
+            name,
+            app_name_to_vault_content_type(CLI_SCRATCHPAD_APP_NAME),
+            value.as_bytes().to_vec().into(),
+            !public,
+            &wallet,
+        )
+        .await
+        .wrap_err("Failed to create scratchpad")?;
+
+    println!("✅ Scratchpad created at address: {}", address.to_hex());
+    if public {
+        println!("With a public value, readable by anyone: [{value}]");
+    } else {
+        println!("With an encrypted value, only readable with your secret key");
+    }
+    println!("Total cost: {cost} AttoTokens");
+    info!("Scratchpad created at address: {address:?} with name: {name}");
+    Ok(())
+}
+
+pub async fn update(
+    name: &str,
+    value: &str,
+    encrypt: Option<bool>,
+    peers: Vec<Multiaddr>,
+) -> Result<()> {
+    let owner_key = crate::keys::get_scratchpad_owner_key()?;
+    let client = crate::actions::connect_to_network(peers).await?;
+
+    println!("Updating scratchpad with name: {name}");
+    info!("Updating scratchpad with name: {name}, encrypt: {encrypt:?}");
+    let scratchpad = client
+        .scratchpad_update(&owner_key, name, value.as_bytes().to_vec().into(), encrypt)
+        .await
+        .wrap_err("Failed to update scratchpad")
+        .with_suggestion(|| {
+            "make sure the scratchpad was created with the `scratchpad create` subcommand"
+        })?;
+
+    println!(
+        "✅ Scratchpad at address {} updated to version {}",
+        scratchpad.address().to_hex(),
+        scratchpad.count()
+    );
+    Ok(())
+}
+
+pub async fn get(name: &str, owner: Option<String>, peers: Vec<Multiaddr>) -> Result<()> {
+    let client = crate::actions::connect_to_network(peers).await?;
+
+    // scratchpads of other owners can only be read if they are public
+    let (address, scratchpad_key) = match owner {
+        Some(owner_hex) => {
+            let owner = ScratchpadPublicKey::from_hex(&owner_hex)
+                .wrap_err(format!("Failed to parse owner public key: {owner_hex}"))?;
+            (scratchpad_address(&owner, name), None)
+        }
+        None => {
+            let owner_key = crate::keys::get_scratchpad_owner_key()?;
+            let scratchpad_key = derive_scratchpad_key(&owner_key, name);
+            (
+                scratchpad_address(&owner_key.public_key(), name),
+                Some(scratchpad_key),
+            )
+        }
+    };
+
+    println!("Getting scratchpad at address: {}", address.to_hex());
+    info!("Getting scratchpad with name: {name} at address: {address:?}");
+    let scratchpad = client
+        .scratchpad_get(&address)
+        .await
+        .wrap_err(format!("Failed to get scratchpad with name: {name}"))?;
+
+    let decrypted = match (scratchpad.is_encrypted(), scratchpad_key) {
+        (false, _) => None,
+        (true, Some(sk)) => Some(
+            scratchpad
+                .decrypt_data(&sk)
+                .wrap_err(format!("Failed to decrypt scratchpad with name: {name}"))?,
+        ),
+        (true, None) => {
+            return Err(eyre!(
+                "Scratchpad with name {name} is encrypted, only its owner can read it"
+            ))
+        }
+    };
+    let value = match &decrypted {
+        Some(data) => String::from_utf8_lossy(data),
+        None => String::from_utf8_lossy(scratchpad.unencrypted_data()),
+    };
+
+    println!("✅ Scratchpad found at version {}", scratchpad.count());
+    println!("With value: [{value}]");
+    Ok(())
+}
@@ -6,17 +6,28 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use autonomi::client::data::PutError;
+use autonomi::client::vault::user_data::{
+    UserDataVaultGetError, USER_DATA_VAULT_CONTENT_IDENTIFIER,
+};
+use autonomi::client::vault::{UserData, VaultError};
 use autonomi::Multiaddr;
+use color_eyre::eyre::eyre;
 use color_eyre::eyre::Context;
 use color_eyre::eyre::Result;
 use color_eyre::Section;
 
+/// Number of times a sync is attempted when the vault is concurrently updated by another device
+const MAX_SYNC_ATTEMPTS: usize = 3;
+
 pub async fn cost(peers: Vec<Multiaddr>) -> Result<()> {
     let client = crate::actions::connect_to_network(peers).await?;
     let vault_sk = crate::keys::get_vault_secret_key()?;
 
     println!("Getting cost to create a new vault...");
-    let total_cost = client.vault_cost(&vault_sk).await?;
+    let total_cost = client
+        .vault_cost(&vault_sk, *USER_DATA_VAULT_CONTENT_IDENTIFIER)
+        .await?;
 
     if total_cost.is_zero() {
         println!("Vault already exists, modifying an existing vault is free");
@@ -39,8 +50,9 @@ pub async fn create(peers: Vec<Multiaddr>) -> Result<()> {
 
     println!("Pushing to network vault...");
     let total_cost = client
-        .put_user_data_to_vault(&vault_sk, &wallet, local_user_data)
+        .put_user_data_to_vault(&vault_sk, &wallet, local_user_data.clone())
         .await?;
+    crate::user_data::write_vault_sync_base(&local_user_data)?;
 
     if total_cost.is_zero() {
         println!("✅ Successfully pushed user data to existing vault");
@@ -61,35 +73,54 @@ pub async fn sync(peers: Vec<Multiaddr>, force: bool) -> Result<()> {
     let vault_sk = crate::keys::get_vault_secret_key()?;
     let wallet = crate::keys::load_evm_wallet()?;
 
-    println!("Fetching vault from network...");
-    let net_user_data = client
-        .get_user_data_from_vault(&vault_sk)
-        .await
-        .wrap_err("Failed to fetch vault from network")
-        .with_suggestion(|| "Make sure you have already created a vault on the network")?;
-
-    if force {
-        println!("The force flag was provided, overwriting user data in the vault with local user data...");
-    } else {
-        println!("Syncing vault with local user data...");
-        crate::user_data::write_local_user_data(&net_user_data)?;
+    for attempt in 1..=MAX_SYNC_ATTEMPTS {
+        println!("Fetching vault from network...");
+        let (net_user_data, version) = client
+            .get_user_data_from_vault_with_version(&vault_sk)
+            .await
+            .wrap_err("Failed to fetch vault from network")
+            .with_suggestion(|| "Make sure you have already created a vault on the network")?;
+
+        let local_user_data = crate::user_data::get_local_user_data()?;
+        let user_data = if force {
+            println!("The force flag was provided, overwriting user data in the vault with local user data...");
+            local_user_data
+        } else {
+            println!("Merging vault with local user data...");
+            let base = crate::user_data::get_vault_sync_base()?.unwrap_or_default();
+            UserData::merge(&base, &local_user_data, &net_user_data)
+        };
+
+        println!("Pushing merged user data to network vault...");
+        match client
+            .put_user_data_to_vault_if_version(&vault_sk, &wallet, user_data.clone(), Some(version))
+            .await
+        {
+            Ok(_) => {
+                crate::user_data::replace_local_user_data(&user_data)?;
+                crate::user_data::write_vault_sync_base(&user_data)?;
+
+                println!("✅ Successfully synced vault");
+                println!("Vault contains:");
+                println!("{} public file archive(s)", user_data.file_archives.len());
+                println!(
+                    "{} private file archive(s)",
+                    user_data.private_file_archives.len()
+                );
+                println!("{} register(s)", user_data.registers.len());
+                return Ok(());
+            }
+            Err(PutError::Vault(VaultError::VersionConflict { .. })) => {
+                println!("The vault was updated by another device during sync (attempt {attempt}/{MAX_SYNC_ATTEMPTS}), retrying...");
+            }
+            Err(err) => return Err(err.into()),
+        }
     }
 
-    println!("Pushing local user data to network vault...");
-    let local_user_data = crate::user_data::get_local_user_data()?;
-    let file_archives_len = local_user_data.file_archives.len();
-    let private_file_archives_len = local_user_data.private_file_archives.len();
-    let registers_len = local_user_data.registers.len();
-    client
-        .put_user_data_to_vault(&vault_sk, &wallet, local_user_data)
-        .await?;
-
-    println!("✅ Successfully synced vault");
-    println!("Vault contains:");
-    println!("{file_archives_len} public file archive(s)");
-    println!("{private_file_archives_len} private file archive(s)");
-    println!("{registers_len} register(s)");
-    Ok(())
+    Err(eyre!(
+        "Failed to sync vault after {MAX_SYNC_ATTEMPTS} attempts, it kept being updated concurrently"
+    ))
+    .with_suggestion(|| "Try again once the other devices are done syncing")
 }
 
 pub async fn load(peers: Vec<Multiaddr>) -> Result<()> {
@@ -97,9 +128,20 @@ pub async fn load(peers: Vec<Multiaddr>) -> Result<()> {
     let vault_sk = crate::keys::get_vault_secret_key()?;
 
     println!("Retrieving vault from network...");
-    let user_data = client.get_user_data_from_vault(&vault_sk).await?;
+    let user_data = match client.get_user_data_from_vault(&vault_sk).await {
+        Err(UserDataVaultGetError::Vault(VaultError::Missing)) => {
+            println!("No vault found, looking for a vault created by an older version...");
+            let legacy_vault_sk = crate::keys::get_legacy_vault_secret_key()?;
+            client
+                .get_user_data_from_vault(&legacy_vault_sk)
+                .await
+                .with_suggestion(|| "Make sure you have already created a vault on the network")?
+        }
+        result => result?,
+    };
     println!("Writing user data to disk...");
     crate::user_data::write_local_user_data(&user_data)?;
+    crate::user_data::write_vault_sync_base(&user_data)?;
 
     println!("✅ Successfully loaded vault with:");
     println!("{} public file archive(s)", user_data.file_archives.len());
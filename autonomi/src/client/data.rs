@@ -45,6 +45,12 @@ pub enum PutError {
     Wallet(#[from] sn_evm::EvmError),
     #[error("The vault owner key does not match the client's public key")]
     VaultBadOwner,
+    #[cfg(feature = "scratchpad")]
+    #[error("Scratchpad error: {0}")]
+    Scratchpad(#[from] crate::client::scratchpad::ScratchpadError),
+    #[cfg(feature = "vault")]
+    #[error("Vault error: {0}")]
+    Vault(#[from] crate::client::vault::VaultError),
     #[error("Payment unexpectedly invalid for {0:?}")]
     PaymentUnexpectedlyInvalid(NetworkAddress),
     #[error("Could not simultaneously upload chunks: {0:?}")]
@@ -10,7 +10,7 @@ use std::hash::{DefaultHasher, Hash, Hasher};
 
 use bytes::Bytes;
 use serde::{Deserialize, Serialize};
-use sn_evm::{Amount, EvmWallet};
+use sn_evm::{Amount, AttoTokens, EvmWallet};
 use sn_protocol::storage::Chunk;
 use tokio::task::JoinSet;
 
@@ -18,6 +18,15 @@ use super::data::{GetError, PutError};
 use crate::client::{ClientEvent, UploadSummary};
 use crate::{self_encryption::encrypt, Client};
 
+/// Errors that can occur when decrypting a [`PrivateDataAccess`]
+#[derive(Debug, thiserror::Error)]
+pub enum PrivateDataAccessError {
+    #[error("Invalid ciphertext: {0}")]
+    InvalidCiphertext(#[from] bls::Error),
+    #[error("Failed to decrypt the private data access")]
+    Decryption,
+}
+
 /// Private data on the network can be accessed with this
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct PrivateDataAccess(Chunk);
@@ -32,6 +41,21 @@ impl PrivateDataAccess {
         Ok(Self(Chunk::new(Bytes::from(data))))
     }
 
+    /// Encrypt the access so it can be kept outside of the network, such as in local files, without revealing the data.
+    /// Usually encrypted with [`crate::client::key_chain::KeyChain::private_data_key`], so it can be recovered from the master secret.
+    pub fn encrypt(&self, pk: &bls::PublicKey) -> Bytes {
+        Bytes::from(pk.encrypt(self.0.value()).to_bytes())
+    }
+
+    /// Decrypt an access encrypted with [`PrivateDataAccess::encrypt`]
+    pub fn decrypt(encrypted: &[u8], sk: &bls::SecretKey) -> Result<Self, PrivateDataAccessError> {
+        let ciphertext = bls::Ciphertext::from_bytes(encrypted)?;
+        let data = sk
+            .decrypt(&ciphertext)
+            .ok_or(PrivateDataAccessError::Decryption)?;
+        Ok(Self(Chunk::new(Bytes::from(data))))
+    }
+
     /// Get a private address for [`PrivateDataAccess`]. Note that this is not a network address, it is only used for refering to private data client side.
     pub fn address(&self) -> String {
         hash_to_short_string(&self.to_hex())
@@ -65,6 +89,17 @@ impl Client {
         data: Bytes,
         wallet: &EvmWallet,
     ) -> Result<PrivateDataAccess, PutError> {
+        let (access, _) = self.private_data_put_with_cost(data, wallet).await?;
+        Ok(access)
+    }
+
+    /// Upload a piece of private data to the network, returning the [`PrivateDataAccess`] along with the tokens spent.
+    /// Chunks that are already stored on the network are not paid for nor uploaded again.
+    pub(crate) async fn private_data_put_with_cost(
+        &self,
+        data: Bytes,
+        wallet: &EvmWallet,
+    ) -> Result<(PrivateDataAccess, AttoTokens), PutError> {
         let now = sn_networking::target_arch::Instant::now();
         let (data_map_chunk, chunks) = encrypt(data)?;
         debug!("Encryption took: {:.2?}", now.elapsed());
@@ -104,13 +139,13 @@ impl Client {
             record_count += 1;
         }
 
+        let tokens_spent = payment_proofs
+            .values()
+            .map(|proof| proof.quote.cost.as_atto())
+            .sum::<Amount>();
+
         // Reporting
         if let Some(channel) = self.client_event_sender.as_ref() {
-            let tokens_spent = payment_proofs
-                .values()
-                .map(|proof| proof.quote.cost.as_atto())
-                .sum::<Amount>();
-
             let summary = UploadSummary {
                 record_count,
                 tokens_spent,
@@ -120,7 +155,10 @@ impl Client {
             }
         }
 
-        Ok(PrivateDataAccess(data_map_chunk))
+        Ok((
+            PrivateDataAccess(data_map_chunk),
+            AttoTokens::from_atto(tokens_spent),
+        ))
     }
 }
 
@@ -135,4 +173,16 @@ mod tests {
         let data_map2 = PrivateDataAccess::from_hex(&hex).expect("Failed to decode hex");
         assert_eq!(data_map, data_map2);
     }
+
+    #[test]
+    fn test_encrypt() {
+        let data_map = PrivateDataAccess(Chunk::new(Bytes::from_static(b"hello")));
+        let sk = bls::SecretKey::random();
+        let encrypted = data_map.encrypt(&sk.public_key());
+        assert_ne!(encrypted.as_ref(), b"hello");
+
+        let decrypted = PrivateDataAccess::decrypt(&encrypted, &sk).expect("Failed to decrypt");
+        assert_eq!(data_map, decrypted);
+        assert!(PrivateDataAccess::decrypt(&encrypted, &bls::SecretKey::random()).is_err());
+    }
 }
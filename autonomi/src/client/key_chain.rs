@@ -0,0 +1,182 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Derivation of all the keys of a user from one master secret.
+//!
+//! The master key is derived from the EVM secret key, so keeping the EVM secret key is enough to
+//! recover every key below, and with them everything stored on the network, after losing local files.
+//!
+//! ```text
+//! master
+//! ├── vault                       see [`KeyChain::vault_key`]
+//! ├── scratchpads/<app name>      owner of the Scratchpads of an app, see [`KeyChain::scratchpad_owner_key`]
+//! │   └── <scratchpad name>       see [`crate::client::scratchpad::derive_scratchpad_key`]
+//! ├── registers/<register name>   signing key of a Register, see [`KeyChain::register_key`]
+//! └── private_data                encryption key for private data, see [`KeyChain::private_data_key`]
+//! ```
+//!
+//! All the branches are hardened: each key is generated from a hash of the master secret key and the path.
+//! Knowing one of the keys, secret or public, does not reveal the others, so an app given one of them can't
+//! derive the keys of the other apps, and the keys used by different apps, Registers and Scratchpads
+//! can't be linked together on the network. The master secret key itself is never handed out.
+
+use crate::client::registers::RegisterSecretKey;
+use crate::client::scratchpad::ScratchpadSecretKey;
+use crate::client::vault::key::{blst_to_blsttc, derive_secret_key_from_seed};
+use crate::client::vault::{VaultKeyError, VaultSecretKey};
+
+/// Secret key all the other keys of a [`KeyChain`] are derived from
+pub type MasterSecretKey = bls::SecretKey;
+
+/// Message signed with the EVM secret key to generate the master secret key
+const MASTER_SECRET_KEY_SEED: &[u8] = b"Autonomi key chain master secret key";
+
+/// Path of the vault key
+const VAULT_PATH: &[u8] = b"vault";
+/// Path of the Scratchpad owner keys, followed by the app name
+const SCRATCHPADS_PATH: &[u8] = b"scratchpads/";
+/// Path of the Register signing keys, followed by the Register name
+const REGISTERS_PATH: &[u8] = b"registers/";
+/// Path of the private data encryption key
+const PRIVATE_DATA_PATH: &[u8] = b"private_data";
+
+/// Derives every key of a user from one master secret, see the [module](self) documentation for the derivation tree
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyChain {
+    master: MasterSecretKey,
+}
+
+impl KeyChain {
+    /// Create a KeyChain from a master secret key
+    pub fn new(master: MasterSecretKey) -> Self {
+        Self { master }
+    }
+
+    /// Create a KeyChain from the EVM secret key hex string
+    /// The EVM secret key signs a message whose signature is hashed into the master key, the same way
+    /// [`crate::client::vault::derive_vault_key`] derives the vault key of vaults created before the KeyChain existed
+    pub fn from_evm_secret_key(evm_sk_hex: &str) -> Result<Self, VaultKeyError> {
+        let signature = sn_evm::cryptography::sign_message(evm_sk_hex, MASTER_SECRET_KEY_SEED)
+            .map_err(VaultKeyError::FailedToSignMessage)?;
+        let blst_key = derive_secret_key_from_seed(&signature)?;
+        Ok(Self::new(blst_to_blsttc(&blst_key)?))
+    }
+
+    /// The master secret key
+    pub fn master_key(&self) -> &MasterSecretKey {
+        &self.master
+    }
+
+    /// The vault secret key
+    pub fn vault_key(&self) -> Result<VaultSecretKey, VaultKeyError> {
+        self.derive_hardened(VAULT_PATH)
+    }
+
+    /// The key owning the Scratchpads of an app
+    /// Each Scratchpad of the app is then found by name with [`crate::client::scratchpad::derive_scratchpad_key`]
+    pub fn scratchpad_owner_key(
+        &self,
+        app_name: &str,
+    ) -> Result<ScratchpadSecretKey, VaultKeyError> {
+        self.derive_hardened(&[SCRATCHPADS_PATH, app_name.as_bytes()].concat())
+    }
+
+    /// The signing key of the Register with the given name
+    pub fn register_key(&self, name: &str) -> Result<RegisterSecretKey, VaultKeyError> {
+        self.derive_hardened(&[REGISTERS_PATH, name.as_bytes()].concat())
+    }
+
+    /// The key used to encrypt private data, such as data maps kept in local files or on the network
+    pub fn private_data_key(&self) -> Result<bls::SecretKey, VaultKeyError> {
+        self.derive_hardened(PRIVATE_DATA_PATH)
+    }
+
+    fn derive_hardened(&self, path: &[u8]) -> Result<bls::SecretKey, VaultKeyError> {
+        let seed = [self.master.to_bytes().as_slice(), path].concat();
+        let blst_key = derive_secret_key_from_seed(&seed)?;
+        blst_to_blsttc(&blst_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_chain_derivation() {
+        let key_chain = KeyChain::new(MasterSecretKey::random());
+
+        let scratchpads_a = key_chain.scratchpad_owner_key("a").expect("derive");
+        let scratchpads_b = key_chain.scratchpad_owner_key("b").expect("derive");
+        let register_a = key_chain.register_key("a").expect("derive");
+        let register_b = key_chain.register_key("b").expect("derive");
+        let private_data = key_chain.private_data_key().expect("derive");
+        let vault = key_chain.vault_key().expect("derive");
+
+        let public_keys = [
+            key_chain.master_key().public_key(),
+            vault.public_key(),
+            scratchpads_a.public_key(),
+            scratchpads_b.public_key(),
+            register_a.public_key(),
+            register_b.public_key(),
+            private_data.public_key(),
+        ];
+        for (i, a) in public_keys.iter().enumerate() {
+            for b in public_keys.iter().skip(i + 1) {
+                assert_ne!(a, b);
+            }
+        }
+
+        // the same master key always gives the same keys
+        let recovered = KeyChain::new(key_chain.master_key().clone());
+        assert_eq!(
+            recovered.register_key("a").expect("derive").public_key(),
+            register_a.public_key()
+        );
+        assert_eq!(
+            recovered
+                .scratchpad_owner_key("a")
+                .expect("derive")
+                .public_key(),
+            scratchpads_a.public_key()
+        );
+        assert_eq!(
+            recovered.private_data_key().expect("derive").public_key(),
+            private_data.public_key()
+        );
+    }
+
+    #[test]
+    fn test_vault_key_does_not_derive_sibling_keys() {
+        let key_chain = KeyChain::new(MasterSecretKey::random());
+        let vault = key_chain.vault_key().expect("derive");
+        assert_ne!(&vault, key_chain.master_key());
+
+        // an app given the vault key derives other keys than the ones of the key chain
+        let from_vault = KeyChain::new(vault);
+        assert_ne!(
+            from_vault
+                .scratchpad_owner_key("a")
+                .expect("derive")
+                .public_key(),
+            key_chain
+                .scratchpad_owner_key("a")
+                .expect("derive")
+                .public_key()
+        );
+        assert_ne!(
+            from_vault.register_key("a").expect("derive").public_key(),
+            key_chain.register_key("a").expect("derive").public_key()
+        );
+        assert_ne!(
+            from_vault.private_data_key().expect("derive").public_key(),
+            key_chain.private_data_key().expect("derive").public_key()
+        );
+    }
+}
@@ -22,8 +22,12 @@ pub mod external_signer;
 pub mod fs;
 #[cfg(feature = "fs")]
 pub mod fs_private;
+#[cfg(feature = "vault")]
+pub mod key_chain;
 #[cfg(feature = "registers")]
 pub mod registers;
+#[cfg(feature = "scratchpad")]
+pub mod scratchpad;
 #[cfg(feature = "vault")]
 pub mod vault;
 
@@ -37,6 +41,8 @@ pub use sn_evm::Amount;
 
 use libp2p::{identity::Keypair, Multiaddr};
 use sn_networking::{interval, multiaddr_is_global, Network, NetworkBuilder, NetworkEvent};
+#[cfg(not(target_arch = "wasm32"))]
+use sn_peers_acquisition::BootstrapCache;
 use sn_protocol::{version::IDENTIFY_PROTOCOL_STR, CLOSE_GROUP_SIZE};
 use std::{collections::HashSet, sync::Arc, time::Duration};
 use tokio::sync::mpsc;
@@ -131,7 +137,21 @@ impl Client {
 }
 
 fn build_client_and_run_swarm(local: bool) -> (Network, mpsc::Receiver<NetworkEvent>) {
-    let network_builder = NetworkBuilder::new(Keypair::generate_ed25519(), local);
+    let mut network_builder = NetworkBuilder::new(Keypair::generate_ed25519(), local);
+    // Able to dial the nodes listening on TCP, when UDP is blocked.
+    network_builder.tcp(true);
+
+    // Record the peers reached to the bootstrap cache shared with the nodes, for the next
+    // connection to bootstrap from.
+    #[cfg(not(target_arch = "wasm32"))]
+    if !local {
+        match BootstrapCache::default_path() {
+            Ok(path) => network_builder.bootstrap_cache(BootstrapCache::new(path)),
+            Err(err) => {
+                tracing::warn!("Not recording the peers reached to the bootstrap cache: {err}")
+            }
+        }
+    }
 
     // TODO: Re-export `Receiver<T>` from `sn_networking`. Else users need to keep their `tokio` dependency in sync.
     // TODO: Think about handling the mDNS error here.
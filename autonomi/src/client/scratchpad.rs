@@ -0,0 +1,360 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+/// Scratchpad Secret Key
+pub use bls::{PublicKey as ScratchpadPublicKey, SecretKey as ScratchpadSecretKey};
+pub use sn_protocol::storage::{Scratchpad, ScratchpadAddress};
+
+use crate::client::data::{CostError, PayError};
+use crate::client::Client;
+use bytes::Bytes;
+use libp2p::kad::{Quorum, Record};
+use sn_evm::{Amount, AttoTokens, EvmWallet};
+use sn_networking::{GetRecordCfg, GetRecordError, NetworkError, PutRecordCfg, VerificationKind};
+use sn_protocol::storage::{
+    try_deserialize_record, try_serialize_record, RecordKind, RetryStrategy,
+};
+use sn_protocol::NetworkAddress;
+use std::collections::HashSet;
+
+/// Prefix of the derivation index of named Scratchpad keys, keeps them apart from other keys derived from the same owner
+const SCRATCHPAD_DERIVATION_PREFIX: &[u8] = b"scratchpad/";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ScratchpadError {
+    #[error("Scratchpad found at {0:?} was not a valid record.")]
+    CouldNotDeserialize(ScratchpadAddress),
+    #[error("Scratchpad not found")]
+    Missing,
+    #[error("Scratchpad at {0:?} already exists")]
+    AlreadyExists(ScratchpadAddress),
+    #[error("The Scratchpad owner key does not match the Scratchpad at {0:?}")]
+    BadOwner(ScratchpadAddress),
+    #[error("Cost error: {0}")]
+    Cost(#[from] CostError),
+    #[error("Payment failure occurred during Scratchpad creation.")]
+    Pay(#[from] PayError),
+    #[error("Payment unexpectedly invalid for {0:?}")]
+    PaymentUnexpectedlyInvalid(NetworkAddress),
+    #[error("Serialization error: {0}")]
+    Serialization(String),
+    #[error("Protocol: {0}")]
+    Protocol(#[from] sn_protocol::Error),
+    #[error("Network: {0}")]
+    Network(#[from] NetworkError),
+}
+
+/// Derives the secret key owning the Scratchpad with the given name from any owner key
+/// Owners only need to keep track of their key to find their Scratchpads back by name
+pub fn derive_scratchpad_key(owner: &ScratchpadSecretKey, name: &str) -> ScratchpadSecretKey {
+    owner.derive_child(&scratchpad_derivation_index(name))
+}
+
+/// Returns the address of the Scratchpad with the given name, as created by the owner of the public key
+/// This lets anyone find a named Scratchpad without knowing the owner's secret key
+pub fn scratchpad_address(owner: &ScratchpadPublicKey, name: &str) -> ScratchpadAddress {
+    ScratchpadAddress::new(owner.derive_child(&scratchpad_derivation_index(name)))
+}
+
+fn scratchpad_derivation_index(name: &str) -> Vec<u8> {
+    [SCRATCHPAD_DERIVATION_PREFIX, name.as_bytes()].concat()
+}
+
+impl Client {
+    /// Fetches a Scratchpad from the network
+    ///
+    /// Use [`Scratchpad::decrypt_data`] with the Scratchpad key to read encrypted content,
+    /// or [`Scratchpad::unencrypted_data`] for content stored in plaintext.
+    pub async fn scratchpad_get(
+        &self,
+        address: &ScratchpadAddress,
+    ) -> Result<Scratchpad, ScratchpadError> {
+        let network_address = NetworkAddress::from_scratchpad_address(*address);
+        info!("Fetching scratchpad from network at {network_address:?}",);
+        let scratch_key = network_address.to_record_key();
+
+        let get_cfg = GetRecordCfg {
+            get_quorum: Quorum::Majority,
+            retry_strategy: None,
+            target_record: None,
+            expected_holders: HashSet::new(),
+            is_register: false,
+        };
+
+        let pad = match self
+            .network
+            .get_record_from_network(scratch_key.clone(), &get_cfg)
+            .await
+        {
+            Ok(record) => {
+                debug!("Got scratchpad for {scratch_key:?}");
+                try_deserialize_record::<Scratchpad>(&record)
+                    .map_err(|_| ScratchpadError::CouldNotDeserialize(*address))?
+            }
+            Err(NetworkError::GetRecordError(GetRecordError::SplitRecord { result_map })) => {
+                debug!("Got multiple scratchpads for {scratch_key:?}");
+                let mut pads = result_map
+                    .values()
+                    .map(|(record, _)| try_deserialize_record::<Scratchpad>(record))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| ScratchpadError::CouldNotDeserialize(*address))?;
+
+                // take the latest versions
+                pads.sort_by_key(|s| s.count());
+                let max_version = pads.last().map(|p| p.count()).unwrap_or_else(|| {
+                    error!("Got empty scratchpad vector for {scratch_key:?}");
+                    u64::MAX
+                });
+                let latest_pads: Vec<_> = pads
+                    .into_iter()
+                    .filter(|s| s.count() == max_version)
+                    .collect();
+
+                // make sure we only have one of latest version
+                let pad = match &latest_pads[..] {
+                    [one] => one,
+                    [first, ..] => {
+                        // Concurrent writers got conflicting scratchpads stored with the same counter.
+                        // Pick the same one as every other reader, the next write supersedes them all.
+                        warn!("Got multiple conflicting scratchpads for {scratch_key:?} with the latest version, returning the greatest one");
+                        latest_pads.iter().max().unwrap_or(first)
+                    }
+                    [] => {
+                        error!("Got empty scratchpad vector for {scratch_key:?}");
+                        return Err(ScratchpadError::Missing);
+                    }
+                };
+                pad.to_owned()
+            }
+            Err(NetworkError::GetRecordError(GetRecordError::RecordNotFound)) => {
+                debug!("No scratchpad found at {network_address:?}");
+                return Err(ScratchpadError::Missing);
+            }
+            Err(e) => {
+                warn!("Failed to fetch scratchpad {network_address:?} from network: {e}");
+                return Err(e)?;
+            }
+        };
+
+        Ok(pad)
+    }
+
+    /// Fetches a Scratchpad from the network, returns `None` if there is none at that address
+    pub(crate) async fn scratchpad_get_if_exists(
+        &self,
+        address: &ScratchpadAddress,
+    ) -> Result<Option<Scratchpad>, ScratchpadError> {
+        match self.scratchpad_get(address).await {
+            Ok(pad) => Ok(Some(pad)),
+            Err(ScratchpadError::Missing) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Creates a new Scratchpad with the given name, owned by a key derived from `owner`, see [`derive_scratchpad_key`]
+    ///
+    /// The `content_type` lets apps identify the type of data they store.
+    /// The data is encrypted for the owner unless `encrypt` is false, in which case anyone can read it.
+    /// Returns the address of the Scratchpad along with the cost of creating it.
+    pub async fn scratchpad_create(
+        &self,
+        owner: &ScratchpadSecretKey,
+        name: &str,
+        content_type: u64,
+        data: Bytes,
+        encrypt: bool,
+        wallet: &EvmWallet,
+    ) -> Result<(ScratchpadAddress, AttoTokens), ScratchpadError> {
+        let scratchpad_sk = derive_scratchpad_key(owner, name);
+        let mut scratch = Scratchpad::new(scratchpad_sk.public_key(), content_type);
+        let address = *scratch.address();
+        info!("Creating scratchpad {name:?} at {address:?}");
+
+        if self.scratchpad_get_if_exists(&address).await?.is_some() {
+            return Err(ScratchpadError::AlreadyExists(address));
+        }
+
+        sign_scratchpad(&mut scratch, data, &scratchpad_sk, encrypt);
+        let cost = self.scratchpad_upload(scratch, Some(wallet), false).await?;
+
+        Ok((address, cost))
+    }
+
+    /// Updates the content of an existing Scratchpad with the given name, owned by a key derived from `owner`
+    ///
+    /// The data is encrypted for the owner if `encrypt` is true, and stored in plaintext for anyone to read if false.
+    /// With `None`, the Scratchpad keeps its current visibility.
+    /// Updating a Scratchpad is free, returns the updated Scratchpad.
+    pub async fn scratchpad_update(
+        &self,
+        owner: &ScratchpadSecretKey,
+        name: &str,
+        data: Bytes,
+        encrypt: Option<bool>,
+    ) -> Result<Scratchpad, ScratchpadError> {
+        let scratchpad_sk = derive_scratchpad_key(owner, name);
+        let address = ScratchpadAddress::new(scratchpad_sk.public_key());
+        info!("Updating scratchpad {name:?} at {address:?}");
+
+        let mut scratch = self.scratchpad_get(&address).await?;
+        if scratch.owner() != &scratchpad_sk.public_key() {
+            return Err(ScratchpadError::BadOwner(address));
+        }
+
+        let encrypt = encrypt.unwrap_or_else(|| scratch.is_encrypted());
+        sign_scratchpad(&mut scratch, data, &scratchpad_sk, encrypt);
+        let _ = self.scratchpad_upload(scratch.clone(), None, false).await?;
+
+        Ok(scratch)
+    }
+
+    /// Get the cost of creating a Scratchpad with the given name for the owner of the public key
+    /// Returns zero if the Scratchpad already exists, as updating it is free
+    pub async fn scratchpad_cost(
+        &self,
+        owner: &ScratchpadPublicKey,
+        name: &str,
+    ) -> Result<AttoTokens, ScratchpadError> {
+        let address = scratchpad_address(owner, name);
+        info!("Getting cost for scratchpad {name:?} at {address:?}");
+
+        if self.scratchpad_get_if_exists(&address).await?.is_some() {
+            debug!("Scratchpad {name:?} already exists at {address:?}, updating it is free");
+            return Ok(AttoTokens::zero());
+        }
+
+        let cost_map = self
+            .get_store_quotes(std::iter::once(address.xorname()))
+            .await?;
+        let total_cost = AttoTokens::from_atto(
+            cost_map
+                .values()
+                .map(|quote| quote.2.cost.as_atto())
+                .sum::<Amount>(),
+        );
+
+        Ok(total_cost)
+    }
+
+    /// Uploads a signed Scratchpad to the network, returning the cost paid.
+    ///
+    /// A new Scratchpad is paid for with the `wallet`, `None` updates an existing one for free.
+    /// With `verify_exact`, the upload only succeeds if the network ends up holding this exact Scratchpad,
+    /// which detects concurrent writers that got another Scratchpad stored with the same counter.
+    pub(crate) async fn scratchpad_upload(
+        &self,
+        scratch: Scratchpad,
+        wallet: Option<&EvmWallet>,
+        verify_exact: bool,
+    ) -> Result<AttoTokens, ScratchpadError> {
+        let mut total_cost = AttoTokens::zero();
+        let scratch_address = scratch.network_address();
+        let scratch_key = scratch_address.to_record_key();
+
+        info!("Writing scratchpad at {scratch_address:?}",);
+
+        let record = if let Some(wallet) = wallet {
+            let scratch_xor = scratch.address().xorname();
+            let (payment_proofs, _) = self
+                .pay(std::iter::once(scratch_xor), wallet)
+                .await
+                .inspect_err(|err| {
+                    error!("Failed to pay for new scratchpad at addr: {scratch_address:?} : {err}");
+                })?;
+
+            let proof = match payment_proofs.values().next() {
+                Some(proof) => proof,
+                None => return Err(ScratchpadError::PaymentUnexpectedlyInvalid(scratch_address)),
+            };
+            total_cost = proof.quote.cost;
+
+            Record {
+                key: scratch_key,
+                value: try_serialize_record(&(proof, scratch), RecordKind::ScratchpadWithPayment)
+                    .map_err(|_| {
+                        ScratchpadError::Serialization(
+                            "Failed to serialize scratchpad with payment".to_string(),
+                        )
+                    })?
+                    .to_vec(),
+                publisher: None,
+                expires: None,
+            }
+        } else {
+            Record {
+                key: scratch_key,
+                value: try_serialize_record(&scratch, RecordKind::Scratchpad)
+                    .map_err(|_| {
+                        ScratchpadError::Serialization("Failed to serialize scratchpad".to_string())
+                    })?
+                    .to_vec(),
+                publisher: None,
+                expires: None,
+            }
+        };
+
+        let target_record = verify_exact.then(|| record.clone());
+        let put_cfg = PutRecordCfg {
+            put_quorum: Quorum::Majority,
+            retry_strategy: Some(RetryStrategy::Balanced),
+            use_put_record_to: None,
+            verification: Some((
+                VerificationKind::Network,
+                GetRecordCfg {
+                    get_quorum: Quorum::Majority,
+                    retry_strategy: None,
+                    target_record,
+                    expected_holders: HashSet::new(),
+                    is_register: false,
+                },
+            )),
+        };
+
+        debug!("Put record - scratchpad at {scratch_address:?} to the network");
+        self.network
+            .put_record(record, &put_cfg)
+            .await
+            .inspect_err(|err| {
+                error!(
+                    "Failed to put scratchpad {scratch_address:?} to the network with err: {err:?}"
+                )
+            })?;
+
+        Ok(total_cost)
+    }
+}
+
+/// Signs the data into the Scratchpad, encrypting it for the owner if asked to
+fn sign_scratchpad(scratch: &mut Scratchpad, data: Bytes, sk: &ScratchpadSecretKey, encrypt: bool) {
+    let _ = if encrypt {
+        scratch.update_and_sign(data, sk)
+    } else {
+        scratch.update_and_sign_unencrypted(data, sk)
+    };
+    debug_assert!(scratch.is_valid(), "Must be valid after being signed. This is a bug, please report it by opening an issue on our github");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scratchpad_address_matches_derived_key() {
+        let owner = ScratchpadSecretKey::random();
+        let scratchpad_sk = derive_scratchpad_key(&owner, "my app");
+
+        assert_eq!(
+            scratchpad_address(&owner.public_key(), "my app"),
+            ScratchpadAddress::new(scratchpad_sk.public_key())
+        );
+        assert_ne!(
+            scratchpad_address(&owner.public_key(), "my app"),
+            scratchpad_address(&owner.public_key(), "another app")
+        );
+    }
+}
@@ -6,24 +6,27 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+pub mod index;
 pub mod key;
 pub mod user_data;
 
-pub use key::{derive_vault_key, VaultSecretKey};
+pub use index::{
+    VaultContentLocation, VaultContentVersion, VaultIndex, VAULT_INDEX_CONTENT_IDENTIFIER,
+};
+pub use key::{
+    derive_vault_content_key, derive_vault_index_key, derive_vault_key, VaultKeyError,
+    VaultSecretKey,
+};
 pub use user_data::UserData;
-use xor_name::XorName;
 
-use crate::client::data::PutError;
+use crate::client::data::{GetError, PutError};
+use crate::client::scratchpad::ScratchpadError;
 use crate::client::Client;
-use libp2p::kad::{Quorum, Record};
 use sn_evm::{Amount, AttoTokens, EvmWallet};
-use sn_networking::{GetRecordCfg, GetRecordError, NetworkError, PutRecordCfg, VerificationKind};
-use sn_protocol::storage::{
-    try_serialize_record, RecordKind, RetryStrategy, Scratchpad, ScratchpadAddress,
-};
+use sn_networking::{GetRecordError, NetworkError, MAX_PACKET_SIZE};
+use sn_protocol::storage::{try_deserialize_record, Scratchpad, ScratchpadAddress};
 use sn_protocol::Bytes;
-use sn_protocol::{storage::try_deserialize_record, NetworkAddress};
-use std::collections::HashSet;
+use std::future::Future;
 use std::hash::{DefaultHasher, Hash, Hasher};
 use tracing::info;
 
@@ -35,14 +38,64 @@ pub enum VaultError {
     Bls(#[from] bls::Error),
     #[error("Scratchpad found at {0:?} was not a valid record.")]
     CouldNotDeserializeVaultScratchPad(ScratchpadAddress),
+    #[error("Scratchpad at {address:?} holds content type {found} instead of {expected}")]
+    ContentTypeMismatch {
+        address: ScratchpadAddress,
+        expected: VaultContentType,
+        found: VaultContentType,
+    },
+    #[error("Scratchpad at {0:?} is not owned by this vault")]
+    UnknownScratchpadOwner(ScratchpadAddress),
+    #[error("Serialization error: {0}")]
+    Serialization(String),
+    #[error("Cost error: {0}")]
+    Cost(#[from] CostError),
+    #[error("Get error: {0}")]
+    Get(#[from] GetError),
     #[error("Protocol: {0}")]
     Protocol(#[from] sn_protocol::Error),
     #[error("Network: {0}")]
     Network(#[from] NetworkError),
+    #[error("Scratchpad: {0}")]
+    Scratchpad(ScratchpadError),
+    #[error("Vault content is at version {current:?} instead of the expected {expected:?}")]
+    VersionConflict {
+        expected: Option<VaultContentVersion>,
+        current: Option<VaultContentVersion>,
+    },
     #[error("Vault not found")]
     Missing,
 }
 
+impl From<ScratchpadError> for VaultError {
+    fn from(err: ScratchpadError) -> Self {
+        match err {
+            ScratchpadError::Missing => VaultError::Missing,
+            ScratchpadError::CouldNotDeserialize(address) => {
+                VaultError::CouldNotDeserializeVaultScratchPad(address)
+            }
+            ScratchpadError::Network(err) => VaultError::Network(err),
+            err => VaultError::Scratchpad(err),
+        }
+    }
+}
+
+/// Condition under which a vault write is carried out
+enum WriteCondition {
+    /// Overwrite whatever is stored in the vault
+    Unconditional,
+    /// Only write if the content type is at the given version, `None` meaning it is not in the vault yet
+    ExpectVersion(Option<VaultContentVersion>),
+}
+
+/// Max size of the content stored directly in a vault Scratchpad.
+/// Leaves room for the encryption and record overheads within [`MAX_PACKET_SIZE`].
+/// Bigger content is stored as self-encrypted private data, pointed at by the vault index.
+pub const MAX_VAULT_SCRATCHPAD_CONTENT_SIZE: usize = MAX_PACKET_SIZE - 64 * 1024;
+
+/// Number of times the vault index is written when it is concurrently updated by other writers.
+const MAX_VAULT_INDEX_WRITE_ATTEMPTS: usize = 3;
+
 /// The content type of the vault data
 /// The number is used to determine the type of the contents of the bytes contained in a vault
 /// Custom apps can use this to store their own custom types of data in vaults
@@ -58,101 +111,170 @@ pub fn app_name_to_vault_content_type<T: Hash>(s: T) -> VaultContentType {
 }
 
 impl Client {
-    /// Retrieves and returns a decrypted vault if one exists.
+    /// Retrieves and returns the decrypted data stored at the root of a vault, if any.
     /// Returns the content type of the bytes in the vault
+    ///
+    /// Only vaults created before each content type got its own Scratchpad hold data at their root.
+    /// Use [`Client::fetch_and_decrypt_vault_content`] to read a specific content type from any vault.
     pub async fn fetch_and_decrypt_vault(
         &self,
         secret_key: &VaultSecretKey,
     ) -> Result<(Bytes, VaultContentType), VaultError> {
         info!("Fetching and decrypting vault");
-        let pad = self.get_vault_from_network(secret_key).await?;
+        let pad = self
+            .scratchpad_get(&ScratchpadAddress::new(secret_key.public_key()))
+            .await?;
 
         let data = pad.decrypt_data(secret_key)?;
         Ok((data, pad.data_encoding()))
     }
 
-    /// Gets the vault Scratchpad from a provided client public key
-    async fn get_vault_from_network(
+    /// Retrieves and returns the decrypted data of one content type in the vault
+    pub async fn fetch_and_decrypt_vault_content(
         &self,
         secret_key: &VaultSecretKey,
-    ) -> Result<Scratchpad, VaultError> {
-        let client_pk = secret_key.public_key();
-
-        let scratch_address = ScratchpadAddress::new(client_pk);
-        let network_address = NetworkAddress::from_scratchpad_address(scratch_address);
-        info!("Fetching vault from network at {network_address:?}",);
-        let scratch_key = network_address.to_record_key();
-
-        let get_cfg = GetRecordCfg {
-            get_quorum: Quorum::Majority,
-            retry_strategy: None,
-            target_record: None,
-            expected_holders: HashSet::new(),
-            is_register: false,
-        };
+        content_type: VaultContentType,
+    ) -> Result<Bytes, VaultError> {
+        let (data, _) = self
+            .fetch_and_decrypt_vault_content_with_version(secret_key, content_type)
+            .await?;
+        Ok(data)
+    }
 
-        let pad = match self
-            .network
-            .get_record_from_network(scratch_key.clone(), &get_cfg)
-            .await
-        {
-            Ok(record) => {
-                debug!("Got scratchpad for {scratch_key:?}");
-                try_deserialize_record::<Scratchpad>(&record)
-                    .map_err(|_| VaultError::CouldNotDeserializeVaultScratchPad(scratch_address))?
-            }
-            Err(NetworkError::GetRecordError(GetRecordError::SplitRecord { result_map })) => {
-                debug!("Got multiple scratchpads for {scratch_key:?}");
-                let mut pads = result_map
-                    .values()
-                    .map(|(record, _)| try_deserialize_record::<Scratchpad>(record))
-                    .collect::<Result<Vec<_>, _>>()
-                    .map_err(|_| VaultError::CouldNotDeserializeVaultScratchPad(scratch_address))?;
-
-                // take the latest versions
-                pads.sort_by_key(|s| s.count());
-                let max_version = pads.last().map(|p| p.count()).unwrap_or_else(|| {
-                    error!("Got empty scratchpad vector for {scratch_key:?}");
-                    u64::MAX
-                });
-                let latest_pads: Vec<_> = pads
-                    .into_iter()
-                    .filter(|s| s.count() == max_version)
-                    .collect();
-
-                // make sure we only have one of latest version
-                let pad = match &latest_pads[..] {
-                    [one] => one,
-                    [multi, ..] => {
-                        error!("Got multiple conflicting scratchpads for {scratch_key:?} with the latest version, returning the first one");
-                        multi
-                    }
-                    [] => {
-                        error!("Got empty scratchpad vector for {scratch_key:?}");
-                        return Err(VaultError::Missing);
-                    }
-                };
-                pad.to_owned()
+    /// Retrieves and returns the decrypted data of one content type in the vault, along with its version
+    /// The version can be passed to [`Client::write_bytes_to_vault_if_version`] to avoid overwriting concurrent updates
+    pub async fn fetch_and_decrypt_vault_content_with_version(
+        &self,
+        secret_key: &VaultSecretKey,
+        content_type: VaultContentType,
+    ) -> Result<(Bytes, VaultContentVersion), VaultError> {
+        info!("Fetching and decrypting vault content type {content_type}");
+        let (index, _) = self.get_vault_index(secret_key).await?;
+        let address = match index.get(content_type) {
+            Some(VaultContentLocation::Scratchpad(address)) => *address,
+            Some(VaultContentLocation::PrivateData {
+                data_access,
+                version,
+            }) => {
+                debug!("Vault content type {content_type} is stored as private data");
+                let data = self.private_data_get(data_access.clone()).await?;
+                return Ok((data, VaultContentVersion::PrivateData(*version)));
             }
-            Err(e) => {
-                warn!("Failed to fetch vault {network_address:?} from network: {e}");
-                return Err(e)?;
+            None => {
+                debug!("Vault content type {content_type} is not in the index, looking for its own Scratchpad");
+                derived_vault_content_address(secret_key, content_type)
             }
         };
+        let content_sk = vault_content_secret_key(secret_key, content_type, &address)?;
+
+        let pad = self.scratchpad_get(&address).await?;
+        if pad.data_encoding() != content_type {
+            return Err(VaultError::ContentTypeMismatch {
+                address,
+                expected: content_type,
+                found: pad.data_encoding(),
+            });
+        }
+
+        let data = pad.decrypt_data(&content_sk)?;
+        Ok((data, VaultContentVersion::Scratchpad(pad.count())))
+    }
 
-        Ok(pad)
+    /// Lists the content types stored in the vault
+    pub async fn list_vault_content_types(
+        &self,
+        secret_key: &VaultSecretKey,
+    ) -> Result<Vec<VaultContentType>, VaultError> {
+        let (index, _) = self.get_vault_index(secret_key).await?;
+        Ok(index.content_types())
+    }
+
+    /// Retrieves the [`VaultIndex`] listing the content types stored in the vault
+    pub async fn fetch_vault_index(
+        &self,
+        secret_key: &VaultSecretKey,
+    ) -> Result<VaultIndex, VaultError> {
+        let (index, _) = self.get_vault_index(secret_key).await?;
+        Ok(index)
+    }
+
+    /// Gets the vault index, along with the index Scratchpad if one exists on the network.
+    ///
+    /// Vaults created before each content type got its own Scratchpad have no index Scratchpad,
+    /// their single content type lives at the root Scratchpad instead. For those, an index pointing
+    /// at the root Scratchpad is returned so they keep working.
+    async fn get_vault_index(
+        &self,
+        secret_key: &VaultSecretKey,
+    ) -> Result<(VaultIndex, Option<Scratchpad>), VaultError> {
+        let index_sk = derive_vault_index_key(secret_key);
+        let index_address = ScratchpadAddress::new(index_sk.public_key());
+
+        match self.scratchpad_get_if_exists(&index_address).await? {
+            Some(pad) => {
+                if pad.data_encoding() != *VAULT_INDEX_CONTENT_IDENTIFIER {
+                    return Err(VaultError::ContentTypeMismatch {
+                        address: index_address,
+                        expected: *VAULT_INDEX_CONTENT_IDENTIFIER,
+                        found: pad.data_encoding(),
+                    });
+                }
+                let bytes = pad.decrypt_data(&index_sk)?;
+                let index = VaultIndex::from_bytes(bytes).map_err(|e| {
+                    VaultError::Serialization(format!("Failed to deserialize vault index: {e}"))
+                })?;
+                Ok((index, Some(pad)))
+            }
+            None => {
+                debug!("No vault index found at {index_address:?}, looking for a legacy vault");
+                let mut index = VaultIndex::new();
+                let root_address = ScratchpadAddress::new(secret_key.public_key());
+                if let Some(root_pad) = self.scratchpad_get_if_exists(&root_address).await? {
+                    let _ = index.insert(
+                        root_pad.data_encoding(),
+                        VaultContentLocation::Scratchpad(root_address),
+                    );
+                }
+                Ok((index, None))
+            }
+        }
     }
 
-    /// Get the cost of creating a new vault
-    pub async fn vault_cost(&self, owner: &VaultSecretKey) -> Result<AttoTokens, CostError> {
-        info!("Getting cost for vault");
-        let client_pk = owner.public_key();
-        let content_type = Default::default();
-        let scratch = Scratchpad::new(client_pk, content_type);
-        let vault_xor = scratch.network_address().as_xorname().unwrap_or_default();
+    /// Get the cost of storing a content type in the vault
+    /// Only the Scratchpads that do not exist yet are paid for, as modifying an existing Scratchpad is free,
+    /// so this returns zero if the vault already holds that content type in a Scratchpad
+    pub async fn vault_cost(
+        &self,
+        owner: &VaultSecretKey,
+        content_type: VaultContentType,
+    ) -> Result<AttoTokens, VaultError> {
+        info!("Getting cost for vault content type {content_type}");
+        let (index, index_pad) = self.get_vault_index(owner).await?;
+        let content_address = match index.get(content_type) {
+            Some(VaultContentLocation::Scratchpad(address)) => *address,
+            _ => derived_vault_content_address(owner, content_type),
+        };
+
+        let mut xor_names = vec![];
+        if self
+            .scratchpad_get_if_exists(&content_address)
+            .await?
+            .is_none()
+        {
+            xor_names.push(content_address.xorname());
+        }
+        let index_needs_update =
+            index.get(content_type) != Some(&VaultContentLocation::Scratchpad(content_address));
+        if index_pad.is_none() && index_needs_update {
+            let index_pk = derive_vault_index_key(owner).public_key();
+            xor_names.push(ScratchpadAddress::new(index_pk).xorname());
+        }
+        if xor_names.is_empty() {
+            return Ok(AttoTokens::zero());
+        }
 
         // NB TODO: vault should be priced differently from other data
-        let cost_map = self.get_store_quotes(std::iter::once(vault_xor)).await?;
+        let cost_map = self.get_store_quotes(xor_names.into_iter()).await?;
         let total_cost = AttoTokens::from_atto(
             cost_map
                 .values()
@@ -165,9 +287,14 @@ impl Client {
 
     /// Put data into the client's VaultPacket
     ///
-    /// Pays for a new VaultPacket if none yet created for the client.
+    /// Each content type is stored in its own Scratchpad, listed in the vault index.
+    /// Pays for a new Scratchpad (and the index update) if the vault doesn't hold that content type yet.
+    /// Content bigger than [`MAX_VAULT_SCRATCHPAD_CONTENT_SIZE`] is self-encrypted and stored as private data instead,
+    /// only the chunks that are not already on the network are paid for and uploaded.
     /// Provide the bytes to be written to the vault and the content type of those bytes.
     /// It is recommended to use the hash of the app name or unique identifier as the content type.
+    ///
+    /// This overwrites any concurrent update of the same content type, see [`Client::write_bytes_to_vault_if_version`] to avoid that.
     pub async fn write_bytes_to_vault(
         &self,
         data: Bytes,
@@ -175,26 +302,275 @@ impl Client {
         secret_key: &VaultSecretKey,
         content_type: VaultContentType,
     ) -> Result<AttoTokens, PutError> {
-        let mut total_cost = AttoTokens::zero();
-        let client_pk = secret_key.public_key();
+        self.write_vault_content(
+            data,
+            wallet,
+            secret_key,
+            content_type,
+            WriteCondition::Unconditional,
+        )
+        .await
+    }
+
+    /// Put data into the client's VaultPacket, only if the content type is still at the expected version.
+    ///
+    /// The version is the one returned by [`Client::fetch_and_decrypt_vault_content_with_version`],
+    /// use `None` to only write a content type that is not in the vault yet.
+    /// Fails with [`VaultError::VersionConflict`] if the network is already ahead of the expected version,
+    /// or if another writer updated the content type from the same version concurrently, in which case the caller should fetch the latest content, merge it and try again.
+    pub async fn write_bytes_to_vault_if_version(
+        &self,
+        data: Bytes,
+        wallet: &EvmWallet,
+        secret_key: &VaultSecretKey,
+        content_type: VaultContentType,
+        expected_version: Option<VaultContentVersion>,
+    ) -> Result<AttoTokens, PutError> {
+        self.write_vault_content(
+            data,
+            wallet,
+            secret_key,
+            content_type,
+            WriteCondition::ExpectVersion(expected_version),
+        )
+        .await
+    }
+
+    async fn write_vault_content(
+        &self,
+        data: Bytes,
+        wallet: &EvmWallet,
+        secret_key: &VaultSecretKey,
+        content_type: VaultContentType,
+        condition: WriteCondition,
+    ) -> Result<AttoTokens, PutError> {
+        let (index, index_pad, current_pad, current_version) = self
+            .get_vault_content_state(secret_key, content_type)
+            .await?;
+
+        let expected = match condition {
+            WriteCondition::Unconditional => None,
+            WriteCondition::ExpectVersion(expected) => {
+                if expected != current_version {
+                    warn!("Vault content type {content_type} is at version {current_version:?}, expected {expected:?}");
+                    return Err(VaultError::VersionConflict {
+                        expected,
+                        current: current_version,
+                    }
+                    .into());
+                }
+                Some(expected)
+            }
+        };
+
+        // the index entry the write is based on, which must not change until the index is updated
+        let based_on = expected.map(|_| index.get(content_type).cloned());
+        let result = self
+            .write_vault_content_at_version(
+                index,
+                index_pad,
+                current_pad,
+                data,
+                wallet,
+                secret_key,
+                content_type,
+                based_on,
+            )
+            .await;
+
+        match (result, expected) {
+            // a concurrent writer updated the content type first, report it against the content type
+            (Err(PutError::Vault(VaultError::VersionConflict { .. })), Some(expected)) => {
+                let (.., current) = self
+                    .get_vault_content_state(secret_key, content_type)
+                    .await?;
+                warn!("Vault content type {content_type} was concurrently updated to version {current:?}, expected {expected:?}");
+                Err(VaultError::VersionConflict { expected, current }.into())
+            }
+            (result, _) => result,
+        }
+    }
+
+    /// Gets the vault index, the index Scratchpad and the Scratchpad of the content type if any,
+    /// along with the current version of the content type.
+    async fn get_vault_content_state(
+        &self,
+        secret_key: &VaultSecretKey,
+        content_type: VaultContentType,
+    ) -> Result<
+        (
+            VaultIndex,
+            Option<Scratchpad>,
+            Option<Scratchpad>,
+            Option<VaultContentVersion>,
+        ),
+        VaultError,
+    > {
+        let (index, index_pad) = self.get_vault_index(secret_key).await?;
+
+        // a content type missing from the index may still have its own Scratchpad,
+        // if the index update of the write that created it got lost
+        let current_pad = match index.get(content_type) {
+            Some(VaultContentLocation::Scratchpad(address)) => {
+                self.scratchpad_get_if_exists(address).await?
+            }
+            Some(VaultContentLocation::PrivateData { .. }) => None,
+            None => {
+                let address = derived_vault_content_address(secret_key, content_type);
+                self.scratchpad_get_if_exists(&address).await?
+            }
+        };
+        let current_version = match index.get(content_type) {
+            Some(VaultContentLocation::PrivateData { version, .. }) => {
+                Some(VaultContentVersion::PrivateData(*version))
+            }
+            _ => current_pad
+                .as_ref()
+                .map(|pad| VaultContentVersion::Scratchpad(pad.count())),
+        };
 
-        let pad_res = self.get_vault_from_network(secret_key).await;
-        let mut is_new = true;
+        Ok((index, index_pad, current_pad, current_version))
+    }
+
+    /// Writes the content type and updates its location in the index if needed.
+    /// With `based_on`, the index entry the write is based on, a Scratchpad of a concurrent writer found
+    /// on the network instead of ours, or a concurrent update of the index entry, fails the write with a [`VaultError::VersionConflict`].
+    /// The index is always written with compare-and-swap, so concurrent writers of other content types don't lose their entries.
+    /// Content stored as private data is only uploaded once, the index update being retried on its own.
+    #[allow(clippy::too_many_arguments)]
+    async fn write_vault_content_at_version(
+        &self,
+        index: VaultIndex,
+        index_pad: Option<Scratchpad>,
+        current_pad: Option<Scratchpad>,
+        data: Bytes,
+        wallet: &EvmWallet,
+        secret_key: &VaultSecretKey,
+        content_type: VaultContentType,
+        based_on: Option<Option<VaultContentLocation>>,
+    ) -> Result<AttoTokens, PutError> {
+        let verify_exact = based_on.is_some();
+        let (location, mut total_cost) = if data.len() > MAX_VAULT_SCRATCHPAD_CONTENT_SIZE {
+            info!(
+                "Vault content of {} bytes is too big for a Scratchpad, storing it as private data",
+                data.len()
+            );
+            let (data_access, cost) = self.private_data_put_with_cost(data, wallet).await?;
+            let version = match index.get(content_type) {
+                Some(VaultContentLocation::PrivateData { version, .. }) => version + 1,
+                _ => 0,
+            };
+            (
+                VaultContentLocation::PrivateData {
+                    data_access,
+                    version,
+                },
+                cost,
+            )
+        } else {
+            let (address, existing_pad) = match index.get(content_type) {
+                Some(VaultContentLocation::Scratchpad(address)) => (*address, current_pad),
+                None => (
+                    derived_vault_content_address(secret_key, content_type),
+                    current_pad,
+                ),
+                Some(VaultContentLocation::PrivateData { .. }) => {
+                    // the Scratchpad may still exist if the content was stored as private data since
+                    let address = derived_vault_content_address(secret_key, content_type);
+                    (address, self.scratchpad_get_if_exists(&address).await?)
+                }
+            };
+            let content_sk = vault_content_secret_key(secret_key, content_type, &address)?;
+
+            let cost = self
+                .write_vault_scratchpad(
+                    existing_pad,
+                    data,
+                    wallet,
+                    &content_sk,
+                    content_type,
+                    verify_exact,
+                )
+                .await?;
+            (VaultContentLocation::Scratchpad(address), cost)
+        };
 
-        let mut scratch = if let Ok(existing_data) = pad_res {
-            info!("Scratchpad already exists, returning existing data");
+        if index.get(content_type) != Some(&location) {
+            info!("Updating location of content type {content_type} in the vault index");
+            let index_cost = update_vault_index_entry(
+                (index, index_pad),
+                content_type,
+                location,
+                based_on,
+                || self.get_vault_index(secret_key),
+                |index, index_pad| self.write_vault_index(index, index_pad, wallet, secret_key),
+            )
+            .await?;
+            total_cost =
+                AttoTokens::from_atto(total_cost.as_atto().saturating_add(index_cost.as_atto()));
+        }
 
+        Ok(total_cost)
+    }
+
+    /// Writes the vault index to its Scratchpad, paying for it if it does not exist yet.
+    /// Fails with a [`VaultError::VersionConflict`] if another writer updated the index concurrently.
+    async fn write_vault_index(
+        &self,
+        index: VaultIndex,
+        index_pad: Option<Scratchpad>,
+        wallet: &EvmWallet,
+        secret_key: &VaultSecretKey,
+    ) -> Result<AttoTokens, PutError> {
+        let index_bytes = index.to_bytes().map_err(|e| {
+            PutError::Serialization(format!("Failed to serialize vault index: {e}"))
+        })?;
+        self.write_vault_scratchpad(
+            index_pad,
+            index_bytes,
+            wallet,
+            &derive_vault_index_key(secret_key),
+            *VAULT_INDEX_CONTENT_IDENTIFIER,
+            true,
+        )
+        .await
+    }
+
+    /// Signs the data into the Scratchpad owned by `secret_key` and uploads it.
+    /// Pays for the Scratchpad if it does not exist yet.
+    /// With `verify_exact`, the write only succeeds if the network ends up holding this exact Scratchpad,
+    /// a concurrent writer that got another Scratchpad stored failing it with a [`VaultError::VersionConflict`].
+    async fn write_vault_scratchpad(
+        &self,
+        existing_pad: Option<Scratchpad>,
+        data: Bytes,
+        wallet: &EvmWallet,
+        secret_key: &VaultSecretKey,
+        content_type: VaultContentType,
+        verify_exact: bool,
+    ) -> Result<AttoTokens, PutError> {
+        let client_pk = secret_key.public_key();
+        let is_new = existing_pad.is_none();
+
+        let mut scratch = if let Some(existing_data) = existing_pad {
             info!(
                 "scratch already exists, is version {:?}",
                 existing_data.count()
             );
 
-            is_new = false;
-
             if existing_data.owner() != &client_pk {
                 return Err(PutError::VaultBadOwner);
             }
 
+            if existing_data.data_encoding() != content_type {
+                return Err(VaultError::ContentTypeMismatch {
+                    address: *existing_data.address(),
+                    expected: content_type,
+                    found: existing_data.data_encoding(),
+                }
+                .into());
+            }
+
             existing_data
         } else {
             trace!("new scratchpad creation");
@@ -204,80 +580,285 @@ impl Client {
         let _ = scratch.update_and_sign(data, secret_key);
         debug_assert!(scratch.is_valid(), "Must be valid after being signed. This is a bug, please report it by opening an issue on our github");
 
-        let scratch_address = scratch.network_address();
-        let scratch_key = scratch_address.to_record_key();
-
-        info!("Writing to vault at {scratch_address:?}",);
+        info!("Writing to vault at {:?}", scratch.address());
+        let wallet = is_new.then_some(wallet);
+        let written = verify_exact.then(|| scratch.clone());
+        match self.scratchpad_upload(scratch, wallet, verify_exact).await {
+            Ok(cost) => Ok(cost),
+            Err(ScratchpadError::Network(err)) => match written {
+                Some(written) => Err(self.concurrent_write_or(written, err).await),
+                None => Err(ScratchpadError::Network(err).into()),
+            },
+            Err(err) => Err(err.into()),
+        }
+    }
 
-        let record = if is_new {
-            let scratch_xor = [&scratch_address]
-                .iter()
-                .filter_map(|f| f.as_xorname())
-                .collect::<Vec<XorName>>();
-            let (payment_proofs, _) = self
-                .pay(scratch_xor.iter().cloned(), wallet)
+    /// Checks whether the verification of the written Scratchpad failed because a concurrent writer
+    /// got another Scratchpad stored with the same or a higher counter, returning a [`VaultError::VersionConflict`] if so,
+    /// and the original error otherwise.
+    async fn concurrent_write_or(&self, written: Scratchpad, err: NetworkError) -> PutError {
+        let found = match &err {
+            NetworkError::GetRecordError(GetRecordError::RecordDoesNotMatch(record)) => {
+                try_deserialize_record::<Scratchpad>(record).ok()
+            }
+            _ => self
+                .scratchpad_get_if_exists(written.address())
                 .await
-                .inspect_err(|err| {
-                    error!("Failed to pay for new vault at addr: {scratch_address:?} : {err}");
-                })?;
+                .ok()
+                .flatten(),
+        };
 
-            let proof = match payment_proofs.values().next() {
-                Some(proof) => proof,
-                None => return Err(PutError::PaymentUnexpectedlyInvalid(scratch_address)),
-            };
-            total_cost = proof.quote.cost;
-
-            Record {
-                key: scratch_key,
-                value: try_serialize_record(&(proof, scratch), RecordKind::ScratchpadWithPayment)
-                    .map_err(|_| {
-                        PutError::Serialization(
-                            "Failed to serialize scratchpad with payment".to_string(),
-                        )
-                    })?
-                    .to_vec(),
-                publisher: None,
-                expires: None,
+        match found {
+            Some(found) if found != written && found.count() >= written.count() => {
+                warn!(
+                    "Scratchpad at {:?} is at version {} from another writer, we wrote version {}",
+                    written.address(),
+                    found.count(),
+                    written.count()
+                );
+                VaultError::VersionConflict {
+                    expected: written
+                        .count()
+                        .checked_sub(1)
+                        .map(VaultContentVersion::Scratchpad),
+                    current: Some(VaultContentVersion::Scratchpad(found.count())),
+                }
+                .into()
             }
-        } else {
-            Record {
-                key: scratch_key,
-                value: try_serialize_record(&scratch, RecordKind::Scratchpad)
-                    .map_err(|_| {
-                        PutError::Serialization("Failed to serialize scratchpad".to_string())
-                    })?
-                    .to_vec(),
-                publisher: None,
-                expires: None,
+            _ => ScratchpadError::Network(err).into(),
+        }
+    }
+}
+
+/// Sets the location of a content type in the vault index, starting from the `latest` index read,
+/// and retrying from the index `read` again when another writer updated it in between.
+///
+/// `write` must only store the index if no one else did since it was read, failing with a
+/// [`VaultError::VersionConflict`] otherwise. With `based_on`, the index entry the content was written from,
+/// a concurrent update of that entry fails the update with a [`VaultError::VersionConflict`] instead of being overwritten.
+async fn update_vault_index_entry<S, R, RF, W, WF>(
+    latest: (VaultIndex, S),
+    content_type: VaultContentType,
+    location: VaultContentLocation,
+    based_on: Option<Option<VaultContentLocation>>,
+    mut read: R,
+    mut write: W,
+) -> Result<AttoTokens, PutError>
+where
+    R: FnMut() -> RF,
+    RF: Future<Output = Result<(VaultIndex, S), VaultError>>,
+    W: FnMut(VaultIndex, S) -> WF,
+    WF: Future<Output = Result<AttoTokens, PutError>>,
+{
+    let (mut index, mut state) = latest;
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        if let Some(based_on) = &based_on {
+            if index.get(content_type) != based_on.as_ref() {
+                warn!("Vault index entry of content type {content_type} was concurrently updated");
+                return Err(VaultError::VersionConflict {
+                    expected: None,
+                    current: None,
+                }
+                .into());
             }
-        };
+        }
+        if index.get(content_type) == Some(&location) {
+            return Ok(AttoTokens::zero());
+        }
+
+        let _ = index.insert(content_type, location.clone());
+        match write(index, state).await {
+            Err(PutError::Vault(VaultError::VersionConflict { .. }))
+                if attempts < MAX_VAULT_INDEX_WRITE_ATTEMPTS =>
+            {
+                info!("Vault index was concurrently updated, retrying the update of content type {content_type}");
+                (index, state) = read().await?;
+            }
+            result => return result,
+        }
+    }
+}
 
-        let put_cfg = PutRecordCfg {
-            put_quorum: Quorum::Majority,
-            retry_strategy: Some(RetryStrategy::Balanced),
-            use_put_record_to: None,
-            verification: Some((
-                VerificationKind::Network,
-                GetRecordCfg {
-                    get_quorum: Quorum::Majority,
-                    retry_strategy: None,
-                    target_record: None,
-                    expected_holders: HashSet::new(),
-                    is_register: false,
-                },
-            )),
-        };
+/// The address of the Scratchpad a content type gets in a vault created with an index.
+fn derived_vault_content_address(
+    secret_key: &VaultSecretKey,
+    content_type: VaultContentType,
+) -> ScratchpadAddress {
+    ScratchpadAddress::new(derive_vault_content_key(secret_key, content_type).public_key())
+}
 
-        debug!("Put record - scratchpad at {scratch_address:?} to the network");
-        self.network
-            .put_record(record, &put_cfg)
-            .await
-            .inspect_err(|err| {
-                error!(
-                    "Failed to put scratchpad {scratch_address:?} to the network with err: {err:?}"
-                )
-            })?;
+/// Returns the key owning the Scratchpad of a content type in the vault.
+/// That is the vault key itself for legacy vaults storing their content at the root Scratchpad,
+/// and the derived content key otherwise.
+fn vault_content_secret_key(
+    secret_key: &VaultSecretKey,
+    content_type: VaultContentType,
+    address: &ScratchpadAddress,
+) -> Result<VaultSecretKey, VaultError> {
+    if *address == ScratchpadAddress::new(secret_key.public_key()) {
+        return Ok(secret_key.clone());
+    }
 
-        Ok(total_cost)
+    let content_sk = derive_vault_content_key(secret_key, content_type);
+    if *address == ScratchpadAddress::new(content_sk.public_key()) {
+        Ok(content_sk)
+    } else {
+        Err(VaultError::UnknownScratchpadOwner(*address))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use std::cell::RefCell;
+
+    /// An index stored with a counter, only written if still at the counter it was read at,
+    /// like the index Scratchpad written with `verify_exact`.
+    struct IndexStore {
+        index: VaultIndex,
+        counter: u64,
+        writes: usize,
+    }
+
+    impl IndexStore {
+        fn read(&self) -> Result<(VaultIndex, u64), VaultError> {
+            Ok((self.index.clone(), self.counter))
+        }
+
+        fn write(&mut self, index: VaultIndex, counter: u64) -> Result<AttoTokens, PutError> {
+            self.writes += 1;
+            if counter != self.counter {
+                return Err(VaultError::VersionConflict {
+                    expected: Some(VaultContentVersion::Scratchpad(counter)),
+                    current: Some(VaultContentVersion::Scratchpad(self.counter)),
+                }
+                .into());
+            }
+            self.index = index;
+            self.counter += 1;
+            Ok(AttoTokens::zero())
+        }
+
+        /// Another writer sets the location of a content type in between.
+        fn concurrent_write(&mut self, content_type: VaultContentType) {
+            let _ = self.index.insert(content_type, random_location());
+            self.counter += 1;
+        }
+    }
+
+    fn random_location() -> VaultContentLocation {
+        VaultContentLocation::Scratchpad(ScratchpadAddress::new(
+            bls::SecretKey::random().public_key(),
+        ))
+    }
+
+    /// Updates the entry of the content type in the store, `racing_writes` being called before each write.
+    fn update_entry(
+        store: &RefCell<IndexStore>,
+        content_type: VaultContentType,
+        location: VaultContentLocation,
+        based_on: Option<Option<VaultContentLocation>>,
+        mut racing_writes: impl FnMut(&mut IndexStore),
+    ) -> Result<AttoTokens, PutError> {
+        let latest = store.borrow().read().expect("Failed to read the index");
+        block_on(update_vault_index_entry(
+            latest,
+            content_type,
+            location,
+            based_on,
+            || futures::future::ready(store.borrow().read()),
+            |index, counter| {
+                let mut store = store.borrow_mut();
+                racing_writes(&mut store);
+                futures::future::ready(store.write(index, counter))
+            },
+        ))
+    }
+
+    #[test]
+    fn index_updates_retry_over_concurrent_updates_of_other_content_types() {
+        let store = RefCell::new(IndexStore {
+            index: VaultIndex::new(),
+            counter: 0,
+            writes: 0,
+        });
+        let location = random_location();
+
+        let mut racing = 1;
+        let result = update_entry(&store, 1, location.clone(), Some(None), |store| {
+            if racing < MAX_VAULT_INDEX_WRITE_ATTEMPTS as u64 {
+                racing += 1;
+                store.concurrent_write(racing);
+            }
+        });
+
+        assert!(result.is_ok(), "The update should succeed, got {result:?}");
+        let store = store.into_inner();
+        assert_eq!(store.writes, MAX_VAULT_INDEX_WRITE_ATTEMPTS);
+        assert_eq!(store.index.get(1), Some(&location));
+        // the entries of the concurrent writers are kept
+        assert_eq!(
+            store.index.content_types(),
+            (1..=MAX_VAULT_INDEX_WRITE_ATTEMPTS as u64).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn index_updates_give_up_when_always_raced() {
+        let store = RefCell::new(IndexStore {
+            index: VaultIndex::new(),
+            counter: 0,
+            writes: 0,
+        });
+
+        let result = update_entry(&store, 1, random_location(), None, |store| {
+            store.concurrent_write(2)
+        });
+
+        assert!(matches!(
+            result,
+            Err(PutError::Vault(VaultError::VersionConflict { .. }))
+        ));
+        let store = store.into_inner();
+        assert_eq!(store.writes, MAX_VAULT_INDEX_WRITE_ATTEMPTS);
+        assert_eq!(store.index.get(1), None);
+    }
+
+    #[test]
+    fn conditional_index_updates_conflict_over_concurrent_updates_of_the_same_content_type() {
+        let previous = random_location();
+        let mut index = VaultIndex::new();
+        let _ = index.insert(1, previous.clone());
+        let store = RefCell::new(IndexStore {
+            index,
+            counter: 0,
+            writes: 0,
+        });
+
+        let mut raced = false;
+        let result = update_entry(
+            &store,
+            1,
+            random_location(),
+            Some(Some(previous.clone())),
+            |store| {
+                if !raced {
+                    raced = true;
+                    store.concurrent_write(1);
+                }
+            },
+        );
+
+        assert!(matches!(
+            result,
+            Err(PutError::Vault(VaultError::VersionConflict { .. }))
+        ));
+        let store = store.into_inner();
+        // the update is not retried over the concurrent writer's entry
+        assert_eq!(store.writes, 1);
+        assert_ne!(store.index.get(1), Some(&previous));
     }
 }
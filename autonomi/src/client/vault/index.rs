@@ -0,0 +1,120 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use std::collections::BTreeMap;
+
+use crate::client::data_private::PrivateDataAccess;
+use crate::client::vault::{app_name_to_vault_content_type, VaultContentType};
+use serde::{Deserialize, Serialize};
+use sn_protocol::storage::ScratchpadAddress;
+use sn_protocol::Bytes;
+use std::sync::LazyLock;
+
+/// Vault content type for the VaultIndex
+pub static VAULT_INDEX_CONTENT_IDENTIFIER: LazyLock<VaultContentType> =
+    LazyLock::new(|| app_name_to_vault_content_type("VaultIndex"));
+
+/// Where the content of one content type of a vault is stored
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum VaultContentLocation {
+    /// The content is stored directly in its own Scratchpad
+    Scratchpad(ScratchpadAddress),
+    /// The content was too big to fit in a Scratchpad and is stored as self-encrypted private data
+    PrivateData {
+        /// Access to the self-encrypted content
+        data_access: PrivateDataAccess,
+        /// Incremented every time the content is written, independently of the other entries of the index
+        version: u64,
+    },
+}
+
+/// Version of one content type of a vault, used to detect concurrent updates
+/// It is the counter of the Scratchpad holding the content type, or the version of its index entry when stored as private data
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum VaultContentVersion {
+    /// Counter of the Scratchpad holding the content
+    Scratchpad(u64),
+    /// Version of the index entry pointing at the content stored as private data
+    PrivateData(u64),
+}
+
+/// The VaultIndex lists the content types stored in a vault, along with the location of each of them
+/// Every content type has its own Scratchpad, so apps sharing the same vault key don't overwrite each other's data
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct VaultIndex {
+    /// The location of each content type in the vault
+    pub entries: BTreeMap<VaultContentType, VaultContentLocation>,
+}
+
+impl VaultIndex {
+    /// Create a new empty VaultIndex
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the location of a content type
+    pub fn get(&self, content_type: VaultContentType) -> Option<&VaultContentLocation> {
+        self.entries.get(&content_type)
+    }
+
+    /// Add a content type. Returning `Option::Some` with the old location if the content type was already in the index.
+    pub fn insert(
+        &mut self,
+        content_type: VaultContentType,
+        location: VaultContentLocation,
+    ) -> Option<VaultContentLocation> {
+        self.entries.insert(content_type, location)
+    }
+
+    /// List the content types in the index
+    pub fn content_types(&self) -> Vec<VaultContentType> {
+        self.entries.keys().copied().collect()
+    }
+
+    /// To bytes
+    pub fn to_bytes(&self) -> Result<Bytes, rmp_serde::encode::Error> {
+        let bytes = rmp_serde::to_vec(&self)?;
+        Ok(Bytes::from(bytes))
+    }
+
+    /// From bytes
+    pub fn from_bytes(bytes: Bytes) -> Result<Self, rmp_serde::decode::Error> {
+        let index = rmp_serde::from_slice(&bytes)?;
+        Ok(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vault_index_bytes_roundtrip() {
+        let mut index = VaultIndex::new();
+        let address = ScratchpadAddress::new(bls::SecretKey::random().public_key());
+        let data_access = PrivateDataAccess::from_hex("00ff").expect("Failed to decode hex");
+
+        assert!(index
+            .insert(1, VaultContentLocation::Scratchpad(address))
+            .is_none());
+        assert!(index
+            .insert(
+                2,
+                VaultContentLocation::PrivateData {
+                    data_access,
+                    version: 3,
+                }
+            )
+            .is_none());
+
+        let bytes = index.to_bytes().expect("Failed to serialize index");
+        let index2 = VaultIndex::from_bytes(bytes).expect("Failed to deserialize index");
+        assert_eq!(index, index2);
+        assert_eq!(index2.content_types(), vec![1, 2]);
+    }
+}
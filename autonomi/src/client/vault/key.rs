@@ -6,6 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use crate::client::vault::VaultContentType;
 use blst::min_pk::SecretKey as BlstSecretKey;
 use sha2::{Digest, Sha256};
 
@@ -27,6 +28,9 @@ pub enum VaultKeyError {
 /// Message used to generate the vault secret key from the EVM secret key
 const VAULT_SECRET_KEY_SEED: &[u8] = b"Massive Array of Internet Disks Secure Access For Everyone";
 
+/// Derivation index of the key owning the vault index Scratchpad
+const VAULT_INDEX_DERIVATION_INDEX: &[u8] = b"vault_index";
+
 /// Derives the vault secret key from the EVM secret key hex string
 /// The EVM secret key is used to sign a message and the signature is hashed to derive the vault secret key
 /// Being able to derive the vault secret key from the EVM secret key allows users to only keep track of one key: the EVM secret key
@@ -39,14 +43,28 @@ pub fn derive_vault_key(evm_sk_hex: &str) -> Result<VaultSecretKey, VaultKeyErro
     Ok(vault_sk)
 }
 
+/// Derives the secret key owning the vault index Scratchpad from the vault secret key
+pub fn derive_vault_index_key(vault_sk: &VaultSecretKey) -> VaultSecretKey {
+    vault_sk.derive_child(VAULT_INDEX_DERIVATION_INDEX)
+}
+
+/// Derives the secret key owning the Scratchpad that holds one content type of the vault
+/// Each content type lives in its own Scratchpad so that apps sharing a vault don't overwrite each other's data
+pub fn derive_vault_content_key(
+    vault_sk: &VaultSecretKey,
+    content_type: VaultContentType,
+) -> VaultSecretKey {
+    vault_sk.derive_child(&content_type.to_be_bytes())
+}
+
 /// Convert a blst secret key to a blsttc secret key and pray that endianness is the same
-fn blst_to_blsttc(sk: &BlstSecretKey) -> Result<bls::SecretKey, VaultKeyError> {
+pub(crate) fn blst_to_blsttc(sk: &BlstSecretKey) -> Result<bls::SecretKey, VaultKeyError> {
     let sk_bytes = sk.to_bytes();
     let sk = bls::SecretKey::from_bytes(sk_bytes).map_err(VaultKeyError::BlsConversionError)?;
     Ok(sk)
 }
 
-fn derive_secret_key_from_seed(seed: &[u8]) -> Result<BlstSecretKey, VaultKeyError> {
+pub(crate) fn derive_secret_key_from_seed(seed: &[u8]) -> Result<BlstSecretKey, VaultKeyError> {
     let mut hasher = Sha256::new();
     hasher.update(seed);
     let hashed_seed = hasher.finalize();
@@ -54,3 +72,27 @@ fn derive_secret_key_from_seed(seed: &[u8]) -> Result<BlstSecretKey, VaultKeyErr
         BlstSecretKey::key_gen(&hashed_seed, &[]).map_err(|_| VaultKeyError::KeyGenerationError)?;
     Ok(sk)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vault_keys_are_distinct() {
+        let vault_sk = VaultSecretKey::random();
+        let index_sk = derive_vault_index_key(&vault_sk);
+        let content_a = derive_vault_content_key(&vault_sk, 1);
+        let content_b = derive_vault_content_key(&vault_sk, 2);
+
+        assert_ne!(vault_sk.public_key(), index_sk.public_key());
+        assert_ne!(vault_sk.public_key(), content_a.public_key());
+        assert_ne!(index_sk.public_key(), content_a.public_key());
+        assert_ne!(content_a.public_key(), content_b.public_key());
+
+        // derivation is deterministic
+        assert_eq!(
+            content_a.public_key(),
+            derive_vault_content_key(&vault_sk, 1).public_key()
+        );
+    }
+}
@@ -7,6 +7,7 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use std::collections::HashMap;
+use std::hash::Hash;
 
 use crate::client::archive::ArchiveAddr;
 use crate::client::archive_private::PrivateArchiveAccess;
@@ -14,7 +15,9 @@ use crate::client::data::GetError;
 use crate::client::data::PutError;
 use crate::client::registers::RegisterAddress;
 use crate::client::vault::VaultError;
-use crate::client::vault::{app_name_to_vault_content_type, VaultContentType, VaultSecretKey};
+use crate::client::vault::{
+    app_name_to_vault_content_type, VaultContentType, VaultContentVersion, VaultSecretKey,
+};
 use crate::client::Client;
 use serde::{Deserialize, Serialize};
 use sn_evm::AttoTokens;
@@ -80,6 +83,33 @@ impl UserData {
         self.file_archives.remove(&archive)
     }
 
+    /// Three-way merge of two diverging UserData sharing a common `base`, usually the last synced version.
+    ///
+    /// Entries added or renamed on either side are kept, entries removed on either side are dropped.
+    /// When both sides changed the same entry differently, `ours` wins.
+    pub fn merge(base: &UserData, ours: &UserData, theirs: &UserData) -> UserData {
+        let register_sk = if ours.register_sk == base.register_sk {
+            theirs.register_sk.clone()
+        } else {
+            ours.register_sk.clone()
+        };
+
+        UserData {
+            register_sk,
+            registers: merge_maps(&base.registers, &ours.registers, &theirs.registers),
+            file_archives: merge_maps(
+                &base.file_archives,
+                &ours.file_archives,
+                &theirs.file_archives,
+            ),
+            private_file_archives: merge_maps(
+                &base.private_file_archives,
+                &ours.private_file_archives,
+                &theirs.private_file_archives,
+            ),
+        }
+    }
+
     /// To bytes
     pub fn to_bytes(&self) -> Result<Bytes, rmp_serde::encode::Error> {
         let bytes = rmp_serde::to_vec(&self)?;
@@ -93,19 +123,60 @@ impl UserData {
     }
 }
 
+/// Three-way merge of the entries of a map, see [`UserData::merge`]
+fn merge_maps<K: Hash + Eq + Clone>(
+    base: &HashMap<K, String>,
+    ours: &HashMap<K, String>,
+    theirs: &HashMap<K, String>,
+) -> HashMap<K, String> {
+    let mut merged = HashMap::new();
+    // keys of theirs already in ours are handled with ours
+    let their_only = theirs.iter().filter(|(key, _)| !ours.contains_key(*key));
+    for (key, our_name) in ours {
+        let merged_name = match (base.get(key), theirs.get(key)) {
+            // present on both sides, take the side that changed the name
+            (Some(base_name), Some(their_name)) if our_name == base_name => their_name,
+            (_, Some(_)) => our_name,
+            // removed on their side
+            (Some(_), None) => continue,
+            // added on our side
+            (None, None) => our_name,
+        };
+        let _ = merged.insert(key.clone(), merged_name.clone());
+    }
+    for (key, their_name) in their_only {
+        // removed on our side, or added on theirs
+        if !base.contains_key(key) {
+            let _ = merged.insert(key.clone(), their_name.clone());
+        }
+    }
+    merged
+}
+
 impl Client {
     /// Get the user data from the vault
     pub async fn get_user_data_from_vault(
         &self,
         secret_key: &VaultSecretKey,
     ) -> Result<UserData, UserDataVaultGetError> {
-        let (bytes, content_type) = self.fetch_and_decrypt_vault(secret_key).await?;
+        let (user_data, _) = self
+            .get_user_data_from_vault_with_version(secret_key)
+            .await?;
+        Ok(user_data)
+    }
 
-        if content_type != *USER_DATA_VAULT_CONTENT_IDENTIFIER {
-            return Err(UserDataVaultGetError::UnsupportedVaultContentType(
-                content_type,
-            ));
-        }
+    /// Get the user data from the vault, along with its version
+    /// The version can be passed to [`Client::put_user_data_to_vault_if_version`] to avoid overwriting concurrent updates
+    pub async fn get_user_data_from_vault_with_version(
+        &self,
+        secret_key: &VaultSecretKey,
+    ) -> Result<(UserData, VaultContentVersion), UserDataVaultGetError> {
+        let (bytes, version) = self
+            .fetch_and_decrypt_vault_content_with_version(
+                secret_key,
+                *USER_DATA_VAULT_CONTENT_IDENTIFIER,
+            )
+            .await?;
 
         let vault = UserData::from_bytes(bytes).map_err(|e| {
             UserDataVaultGetError::Serialization(format!(
@@ -113,7 +184,7 @@ impl Client {
             ))
         })?;
 
-        Ok(vault)
+        Ok((vault, version))
     }
 
     /// Put the user data to the vault
@@ -137,4 +208,77 @@ impl Client {
             .await?;
         Ok(total_cost)
     }
+
+    /// Put the user data to the vault, only if the user data in the vault is still at the expected version
+    /// Use `None` to only put the user data if the vault doesn't hold any yet.
+    /// Returns the total cost of the put operation
+    pub async fn put_user_data_to_vault_if_version(
+        &self,
+        secret_key: &VaultSecretKey,
+        wallet: &EvmWallet,
+        user_data: UserData,
+        expected_version: Option<VaultContentVersion>,
+    ) -> Result<AttoTokens, PutError> {
+        let bytes = user_data
+            .to_bytes()
+            .map_err(|e| PutError::Serialization(format!("Failed to serialize user data: {e}")))?;
+        let total_cost = self
+            .write_bytes_to_vault_if_version(
+                bytes,
+                wallet,
+                secret_key,
+                *USER_DATA_VAULT_CONTENT_IDENTIFIER,
+                expected_version,
+            )
+            .await?;
+        Ok(total_cost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xor_name::XorName;
+
+    #[test]
+    fn test_user_data_three_way_merge() {
+        let mut rng = rand::thread_rng();
+        let kept = XorName::random(&mut rng);
+        let removed_by_us = XorName::random(&mut rng);
+        let removed_by_them = XorName::random(&mut rng);
+        let renamed_by_them = XorName::random(&mut rng);
+        let added_by_us = XorName::random(&mut rng);
+        let added_by_them = XorName::random(&mut rng);
+
+        let mut base = UserData::new();
+        for archive in [kept, removed_by_us, removed_by_them, renamed_by_them] {
+            let _ = base.add_file_archive_with_name(archive, "base".to_string());
+        }
+
+        let mut ours = base.clone();
+        let _ = ours.remove_file_archive(removed_by_us);
+        let _ = ours.add_file_archive_with_name(added_by_us, "ours".to_string());
+
+        let mut theirs = base.clone();
+        let _ = theirs.remove_file_archive(removed_by_them);
+        let _ = theirs.add_file_archive_with_name(renamed_by_them, "theirs".to_string());
+        let _ = theirs.add_file_archive_with_name(added_by_them, "theirs".to_string());
+        theirs.register_sk = Some("their key".to_string());
+
+        let merged = UserData::merge(&base, &ours, &theirs);
+
+        let expected: HashMap<_, _> = [
+            (kept, "base".to_string()),
+            (renamed_by_them, "theirs".to_string()),
+            (added_by_us, "ours".to_string()),
+            (added_by_them, "theirs".to_string()),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(merged.file_archives, expected);
+        assert_eq!(merged.register_sk, Some("their key".to_string()));
+
+        // merging is symmetric when there are no conflicting changes
+        assert_eq!(merged, UserData::merge(&base, &theirs, &ours));
+    }
 }
@@ -99,8 +99,12 @@ async fn file_into_vault() -> Result<()> {
     // now assert over the stored account packet
     let new_client = Client::connect(&[]).await?;
 
-    let (ap, got_version) = new_client.fetch_and_decrypt_vault(&client_sk).await?;
-    assert_eq!(set_version, got_version);
+    let content_types = new_client.list_vault_content_types(&client_sk).await?;
+    assert_eq!(content_types, vec![set_version]);
+
+    let ap = new_client
+        .fetch_and_decrypt_vault_content(&client_sk, set_version)
+        .await?;
     let ap_archive_fetched = autonomi::client::archive::Archive::from_bytes(ap)?;
 
     assert_eq!(
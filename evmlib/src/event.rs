@@ -25,8 +25,8 @@ pub enum Error {
 }
 
 /// Struct for the ChunkPaymentEvent emitted by the ChunkPayments smart contract.
-#[derive(Debug)]
-pub(crate) struct ChunkPaymentEvent {
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChunkPaymentEvent {
     pub rewards_address: Address,
     pub amount: U256,
     pub quote_hash: Hash,
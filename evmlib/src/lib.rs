@@ -7,7 +7,7 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::common::{Address, QuoteHash, TxHash, U256};
-use crate::transaction::verify_data_payment;
+use crate::transaction::{get_data_payments, verify_data_payment, DataPayments};
 use alloy::primitives::address;
 use alloy::transports::http::reqwest;
 use serde::{Deserialize, Serialize};
@@ -21,7 +21,7 @@ extern crate tracing;
 pub mod common;
 pub mod contract;
 pub mod cryptography;
-pub(crate) mod event;
+pub mod event;
 #[cfg(feature = "external-signer")]
 pub mod external_signer;
 pub mod testnet;
@@ -152,4 +152,12 @@ impl Network {
         )
         .await
     }
+
+    /// Get the data payments made by a confirmed transaction, to verify several quotes at once.
+    pub async fn get_data_payments(
+        &self,
+        tx_hash: TxHash,
+    ) -> Result<DataPayments, transaction::Error> {
+        get_data_payments(self, tx_hash).await
+    }
 }
@@ -157,11 +157,87 @@ pub async fn verify_data_payment(
     Err(Error::EventProofNotFound)
 }
 
+/// The data payments made by a confirmed transaction, fetched once to verify all the quotes
+/// it paid for without further on-chain lookups.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DataPayments {
+    /// The timestamp of the block the transaction was included in.
+    pub block_timestamp: u64,
+    /// The payments emitted by the transaction.
+    pub payments: Vec<ChunkPaymentEvent>,
+}
+
+impl DataPayments {
+    /// Verify the transaction paid `reward_addr` at least `amount` for `quote_hash`,
+    /// before the quote expired.
+    pub fn verify(
+        &self,
+        quote_hash: QuoteHash,
+        reward_addr: Address,
+        amount: U256,
+        quote_expiration_timestamp_in_secs: u64,
+    ) -> Result<(), Error> {
+        // Check if payment was done within the quote expiration timeframe.
+        if quote_expiration_timestamp_in_secs < self.block_timestamp {
+            return Err(Error::QuoteExpired);
+        }
+
+        let paid = self.payments.iter().any(|event| {
+            event.quote_hash == quote_hash
+                && event.rewards_address == reward_addr
+                && event.amount >= amount
+        });
+        if paid {
+            Ok(())
+        } else {
+            Err(Error::EventProofNotFound)
+        }
+    }
+}
+
+/// Get the data payments made by a confirmed transaction, out of its receipt.
+pub async fn get_data_payments(network: &Network, tx_hash: TxHash) -> Result<DataPayments, Error> {
+    debug!("Getting data payments for tx_hash: {tx_hash:?}");
+    let transaction = get_transaction_receipt_by_hash(network, tx_hash)
+        .await?
+        .ok_or(Error::TransactionNotFound)?;
+
+    // If the status is True, it means the tx is confirmed.
+    if !transaction.status() {
+        error!("Transaction {tx_hash:?} is not confirmed");
+        return Err(Error::TransactionUnconfirmed);
+    }
+
+    let block_number = transaction
+        .block_number
+        .ok_or(Error::TransactionNotInBlock)
+        .inspect_err(|_| error!("Transaction {tx_hash:?} has not been included in a block yet"))?;
+
+    let block = get_block_by_number(network, block_number)
+        .await?
+        .ok_or(Error::BlockNotFound)?;
+
+    let payments = transaction
+        .inner
+        .logs()
+        .iter()
+        .filter(|log| log.address() == *network.data_payments_address())
+        .filter_map(|log| ChunkPaymentEvent::try_from(log.clone()).ok())
+        .collect();
+
+    Ok(DataPayments {
+        block_timestamp: block.header.timestamp,
+        payments,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use crate::common::{Address, U256};
+    use crate::event::ChunkPaymentEvent;
     use crate::transaction::{
-        get_data_payment_event, get_transaction_receipt_by_hash, verify_data_payment,
+        get_data_payment_event, get_transaction_receipt_by_hash, verify_data_payment, DataPayments,
+        Error,
     };
     use crate::Network;
     use alloy::hex::FromHex;
@@ -217,4 +293,34 @@ mod tests {
 
         assert!(result.is_ok(), "Error: {:?}", result.err());
     }
+
+    #[test]
+    fn test_data_payments_verify() {
+        let reward_address = Address::repeat_byte(1);
+        let quote_hash = b256!("EBD943C38C0422901D4CF22E677DD95F2591CA8D6EBFEA8BAF1BFE9FF5506ECE"); // DevSkim: ignore DS173237
+        let data_payments = DataPayments {
+            block_timestamp: 100,
+            payments: vec![ChunkPaymentEvent {
+                rewards_address: reward_address,
+                amount: U256::from(10),
+                quote_hash,
+            }],
+        };
+
+        assert!(data_payments
+            .verify(quote_hash, reward_address, U256::from(10), 100)
+            .is_ok());
+        assert!(matches!(
+            data_payments.verify(quote_hash, reward_address, U256::from(10), 99),
+            Err(Error::QuoteExpired)
+        ));
+        assert!(matches!(
+            data_payments.verify(quote_hash, reward_address, U256::from(11), 100),
+            Err(Error::EventProofNotFound)
+        ));
+        assert!(matches!(
+            data_payments.verify(quote_hash, Address::repeat_byte(2), U256::from(1), 100),
+            Err(Error::EventProofNotFound)
+        ));
+    }
 }
@@ -6,7 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use color_eyre::eyre::Result;
 use futures::StreamExt;
 use libp2p::autonat::NatStatus;
@@ -14,6 +14,7 @@ use libp2p::core::{multiaddr::Protocol, Multiaddr};
 use libp2p::swarm::SwarmEvent;
 use libp2p::{noise, tcp, yamux};
 use std::collections::HashSet;
+use std::fmt;
 use std::net::Ipv4Addr;
 use std::time::Duration;
 use tracing::{debug, info, warn};
@@ -29,8 +30,54 @@ mod behaviour;
 const CONFIDENCE_MAX: usize = 2;
 const RETRY_INTERVAL: Duration = Duration::from_secs(10);
 
+/// The transports the NAT status can be detected for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum TransportKind {
+    Tcp,
+    Quic,
+}
+
+impl fmt::Display for TransportKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransportKind::Tcp => write!(f, "TCP"),
+            TransportKind::Quic => write!(f, "QUIC"),
+        }
+    }
+}
+
+impl TransportKind {
+    /// The address to listen on for this transport.
+    fn listen_addr(&self, port: u16) -> Multiaddr {
+        let addr = Multiaddr::empty().with(Protocol::Ip4(Ipv4Addr::UNSPECIFIED));
+        match self {
+            TransportKind::Tcp => addr.with(Protocol::Tcp(port)),
+            TransportKind::Quic => addr.with(Protocol::Udp(port)).with(Protocol::QuicV1),
+        }
+    }
+
+    /// The server address for this transport, the servers listening on the same port for all of them.
+    fn server_addr(&self, addr: &Multiaddr) -> Multiaddr {
+        let mut output = Multiaddr::empty();
+        for protocol in addr.iter() {
+            match (self, protocol) {
+                (TransportKind::Tcp, Protocol::Udp(port)) => output.push(Protocol::Tcp(port)),
+                (TransportKind::Tcp, Protocol::QuicV1) => {}
+                (TransportKind::Quic, Protocol::Tcp(port)) => {
+                    output.push(Protocol::Udp(port));
+                    output.push(Protocol::QuicV1);
+                }
+                (_, protocol) => output.push(protocol),
+            }
+        }
+        output
+    }
+}
+
 /// A tool to detect NAT status of the machine. It can be run in server mode or client mode.
-/// The program returns with the following exit codes based on the network status:
+/// As a client, the NAT status is detected for each transport, reporting the ones reachable.
+/// The program returns with the following exit codes based on the network status, on the best
+/// of the transports:
 /// - 10: Public NAT
 /// - 11: Public under UPnP
 /// - 12: Private or Unknown NAT
@@ -49,6 +96,7 @@ struct Opt {
     ///
     /// A multiaddr looks like `/ip4/1.2.3.4/tcp/1200/tcp` where `1.2.3.4` is the IP and `1200` is the port.
     /// Alternatively, the address can be written as `1.2.3.4:1200`.
+    /// The servers are dialed on the same port over each transport, e.g. `/ip4/1.2.3.4/udp/1200/quic-v1` for QUIC.
     ///
     /// This argument can be provided multiple times to connect to multiple peers.
     #[clap(name = "SERVER", value_name = "multiaddr", value_delimiter = ',', value_parser = parse_peer_addr)]
@@ -58,6 +106,10 @@ struct Opt {
     #[clap(long, short, default_value_t = false)]
     no_upnp: bool,
 
+    /// The transports to detect the NAT status for, or to listen on as a server.
+    #[clap(long, value_enum, value_delimiter = ',', default_values_t = [TransportKind::Tcp, TransportKind::Quic])]
+    transport: Vec<TransportKind>,
+
     #[command(flatten)]
     verbose: clap_verbosity_flag::Verbosity,
 
@@ -116,13 +168,55 @@ async fn main() -> Result<()> {
         registry.with(filter).try_init()
     };
 
-    let mut builder = AppBuilder::new()
-        .servers(opt.server_addr)
-        .upnp(false)
-        .port(opt.port);
+    // Without servers, we are in server mode and run until stopped.
+    if opt.server_addr.is_empty() {
+        let _status = AppBuilder::new()
+            .transports(opt.transport)
+            .port(opt.port)
+            .build()?
+            .run()
+            .await;
+        return Ok(());
+    }
+
+    let mut reachable_transports = vec![];
+    // The error codes are used by other programs, caution when changing them.
+    let mut exit_code = 12;
+    for transport in opt.transport {
+        info!("Detecting the NAT status over {transport}");
+        let servers = opt
+            .server_addr
+            .iter()
+            .map(|addr| transport.server_addr(addr))
+            .collect();
+        let builder = AppBuilder::new()
+            .servers(servers)
+            .transports(vec![transport])
+            .upnp(false)
+            .port(opt.port);
+
+        match detect_nat_status(builder, opt.no_upnp).await? {
+            (NatStatus::Public(addr), running_with_upnp) => {
+                info!(%addr, "NAT is public over {transport}{}", if running_with_upnp { " (with UPnP)" } else { "" });
+                reachable_transports.push(transport.to_string());
+                exit_code = exit_code.min(if running_with_upnp { 11 } else { 10 });
+            }
+            (NatStatus::Private, _) => info!("NAT is private over {transport}"),
+            (NatStatus::Unknown, _) => info!("NAT status is unknown over {transport}"),
+        }
+    }
 
-    // Run the program twice, to first detect NAT status without UPnP,
-    // and then with UPnP enabled. (Unless `--no-upnp` was given.)
+    if reachable_transports.is_empty() {
+        info!("No transport is reachable");
+    } else {
+        info!("Reachable transports: {}", reachable_transports.join(", "));
+    }
+    std::process::exit(exit_code);
+}
+
+/// Run the program twice, to first detect NAT status without UPnP, and then with UPnP enabled.
+/// (Unless `no_upnp` is set.) Returns the status and whether UPnP was enabled.
+async fn detect_nat_status(mut builder: AppBuilder, no_upnp: bool) -> Result<(NatStatus, bool)> {
     let mut running_with_upnp = false;
     loop {
         let status = builder
@@ -132,31 +226,14 @@ async fn main() -> Result<()> {
             .await;
 
         match status {
-            NatStatus::Public(addr) => {
-                info!(%addr, "NAT is public{}", if running_with_upnp { " (with UPnP)" } else { "" });
-                if running_with_upnp {
-                    // The error codes are used by other programs, caution when changing them.
-                    std::process::exit(11);
-                } else {
-                    std::process::exit(10);
-                }
-            }
-            NatStatus::Private => {
-                // Unless `--no-upnp` is set, rerun the program with UPnP enabled.
-                if !opt.no_upnp && !running_with_upnp {
-                    warn!("NAT is private, rerunning program with UPnP enabled in 2 seconds...");
-                    tokio::time::sleep(Duration::from_secs(2)).await;
-                    builder = builder.upnp(true);
-                    running_with_upnp = true;
-                } else {
-                    info!("NAT is private");
-                    std::process::exit(12);
-                }
-            }
-            NatStatus::Unknown => {
-                info!("NAT status is unknown");
-                std::process::exit(12);
+            // Unless `--no-upnp` is set, rerun the program with UPnP enabled.
+            NatStatus::Private if !no_upnp && !running_with_upnp => {
+                warn!("NAT is private, rerunning program with UPnP enabled in 2 seconds...");
+                tokio::time::sleep(Duration::from_secs(2)).await;
+                builder = builder.upnp(true);
+                running_with_upnp = true;
             }
+            status => return Ok((status, running_with_upnp)),
         }
     }
 }
@@ -385,6 +462,7 @@ fn parse_peer_addr(addr: &str) -> Result<Multiaddr, &'static str> {
 struct AppBuilder {
     port: u16,
     servers: Vec<Multiaddr>,
+    transports: Vec<TransportKind>,
     upnp: bool,
 }
 
@@ -394,9 +472,15 @@ impl AppBuilder {
             port: 0,
             upnp: false,
             servers: vec![],
+            transports: vec![TransportKind::Tcp],
         }
     }
 
+    fn transports(mut self, transports: Vec<TransportKind>) -> Self {
+        self.transports = transports;
+        self
+    }
+
     fn port(mut self, port: u16) -> Self {
         self.port = port;
         self
@@ -424,6 +508,7 @@ impl AppBuilder {
                 noise::Config::new,
                 yamux::Config::default,
             )?
+            .with_quic()
             .with_behaviour(|key| Behaviour::new(key.public(), client_mode, self.upnp))?
             // Make it so that we retry just before idling out, to prevent quickly disconnecting/connecting
             // to the same server.
@@ -432,11 +517,9 @@ impl AppBuilder {
             })
             .build();
 
-        swarm.listen_on(
-            Multiaddr::empty()
-                .with(Protocol::Ip4(Ipv4Addr::UNSPECIFIED))
-                .with(Protocol::Tcp(self.port)),
-        )?;
+        for transport in &self.transports {
+            swarm.listen_on(transport.listen_addr(self.port))?;
+        }
 
         info!(
             peer_id=%swarm.local_peer_id(),
@@ -55,9 +55,17 @@ impl App {
         peers_args: PeersArgs,
         safenode_path: Option<PathBuf>,
         app_data_path: Option<PathBuf>,
+        upload_limit: Option<u64>,
+        download_limit: Option<u64>,
     ) -> Result<Self> {
         // Configurations
-        let app_data = AppData::load(app_data_path)?;
+        let mut app_data = AppData::load(app_data_path.clone())?;
+        // The bandwidth limits provided on the command line replace the saved ones.
+        if upload_limit.is_some() || download_limit.is_some() {
+            app_data.upload_limit = upload_limit.or(app_data.upload_limit);
+            app_data.download_limit = download_limit.or(app_data.download_limit);
+            app_data.save(app_data_path)?;
+        }
         let config = Config::new()?;
 
         // Tries to set the data dir path based on the storage mountpoint set by the user,
@@ -98,6 +106,8 @@ impl App {
             connection_mode,
             port_from: Some(port_from),
             port_to: Some(port_to),
+            upload_limit: app_data.upload_limit,
+            download_limit: app_data.download_limit,
         };
 
         let status = Status::new(status_config).await?;
@@ -108,6 +118,8 @@ impl App {
             connection_mode,
             Some(port_from),
             Some(port_to),
+            app_data.upload_limit,
+            app_data.download_limit,
         )
         .await?;
         let help = Help::new().await?;
@@ -131,6 +143,8 @@ impl App {
                 connection_mode: Some(connection_mode),
                 port_from: Some(port_from),
                 port_to: Some(port_to),
+                upload_limit: app_data.upload_limit,
+                download_limit: app_data.download_limit,
             },
             tick_rate,
             frame_rate,
@@ -354,7 +368,8 @@ mod tests {
         let mut output = Cursor::new(Vec::new());
 
         // Create and run the App, capturing its output
-        let app_result = App::new(60.0, 60.0, peers_args, None, Some(config_path)).await;
+        let app_result =
+            App::new(60.0, 60.0, peers_args, None, Some(config_path), None, None).await;
 
         match app_result {
             Ok(app) => {
@@ -415,7 +430,16 @@ mod tests {
         let mut output = Cursor::new(Vec::new());
 
         // Create and run the App, capturing its output
-        let app_result = App::new(60.0, 60.0, peers_args, None, Some(test_app_data_path)).await;
+        let app_result = App::new(
+            60.0,
+            60.0,
+            peers_args,
+            None,
+            Some(test_app_data_path),
+            None,
+            None,
+        )
+        .await;
 
         match app_result {
             Ok(app) => {
@@ -470,8 +494,16 @@ mod tests {
         let mut output = Cursor::new(Vec::new());
 
         // Create and run the App, capturing its output
-        let app_result =
-            App::new(60.0, 60.0, peers_args, None, Some(non_existent_config_path)).await;
+        let app_result = App::new(
+            60.0,
+            60.0,
+            peers_args,
+            None,
+            Some(non_existent_config_path),
+            None,
+            None,
+        )
+        .await;
 
         match app_result {
             Ok(app) => {
@@ -533,7 +565,8 @@ mod tests {
         let peers_args = PeersArgs::default();
 
         // Create and run the App, capturing its output
-        let app_result = App::new(60.0, 60.0, peers_args, None, Some(config_path)).await;
+        let app_result =
+            App::new(60.0, 60.0, peers_args, None, Some(config_path), None, None).await;
 
         // Could be that the mountpoint doesn't exists
         // or that the user doesn't have permissions to access it
@@ -574,7 +607,16 @@ mod tests {
         let peers_args = PeersArgs::default();
 
         // Create and run the App
-        let app_result = App::new(60.0, 60.0, peers_args, None, Some(test_app_data_path)).await;
+        let app_result = App::new(
+            60.0,
+            60.0,
+            peers_args,
+            None,
+            Some(test_app_data_path),
+            None,
+            None,
+        )
+        .await;
 
         match app_result {
             Ok(app) => {
@@ -601,4 +643,41 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_app_saves_the_bandwidth_limits_provided() -> Result<()> {
+        // Create a temporary directory for our test
+        let temp_dir = tempdir()?;
+        let test_app_data_path = temp_dir.path().join("test_app_data.json");
+
+        let custom_config = r#"
+        {
+            "discord_username": "test_user",
+            "nodes_to_start": 3,
+            "download_limit": 8000000
+        }
+        "#;
+        std::fs::write(&test_app_data_path, custom_config)?;
+
+        // Only the upload limit is provided, the saved download limit is kept
+        let app = App::new(
+            60.0,
+            60.0,
+            PeersArgs::default(),
+            None,
+            Some(test_app_data_path.clone()),
+            Some(2_000_000),
+            None,
+        )
+        .await?;
+        assert_eq!(app.app_data.upload_limit, Some(2_000_000));
+        assert_eq!(app.app_data.download_limit, Some(8_000_000));
+
+        // The limits are remembered for the next runs
+        let app_data = AppData::load(Some(test_app_data_path))?;
+        assert_eq!(app_data.upload_limit, Some(2_000_000));
+        assert_eq!(app_data.download_limit, Some(8_000_000));
+
+        Ok(())
+    }
 }
@@ -21,6 +21,7 @@ use node_launchpad::{
 #[cfg(target_os = "windows")]
 use sn_node_manager::config::is_running_as_root;
 use sn_peers_acquisition::PeersArgs;
+use sn_protocol::storage::StorageSize;
 use std::{env, path::PathBuf};
 use tokio::task::LocalSet;
 
@@ -54,6 +55,18 @@ pub struct Cli {
     #[command(flatten)]
     pub(crate) peers: PeersArgs,
 
+    /// Limit the bytes per second uploaded by all the nodes together, e.g. "4MB" or "512KiB".
+    ///
+    /// The limit is shared evenly between the nodes and remembered for the next runs.
+    #[clap(long)]
+    upload_limit: Option<StorageSize>,
+
+    /// Limit the bytes per second downloaded by all the nodes together, e.g. "4MB" or "512KiB".
+    ///
+    /// The limit is shared evenly between the nodes and remembered for the next runs.
+    #[clap(long)]
+    download_limit: Option<StorageSize>,
+
     /// Print the crate version.
     #[clap(long)]
     crate_version: bool,
@@ -102,6 +115,8 @@ async fn tokio_main() -> Result<()> {
         args.peers,
         args.safenode_path,
         None,
+        args.upload_limit.map(|limit| limit.as_bytes()),
+        args.download_limit.map(|limit| limit.as_bytes()),
     )
     .await?;
     app.run().await?;
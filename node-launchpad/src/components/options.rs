@@ -33,11 +33,14 @@ pub struct Options {
     pub port_edit: bool,
     pub port_from: Option<u32>,
     pub port_to: Option<u32>,
+    pub upload_limit: Option<u64>,
+    pub download_limit: Option<u64>,
     pub active: bool,
     pub action_tx: Option<UnboundedSender<Action>>,
 }
 
 impl Options {
+    #[expect(clippy::too_many_arguments)]
     pub async fn new(
         storage_mountpoint: PathBuf,
         storage_drive: String,
@@ -45,6 +48,8 @@ impl Options {
         connection_mode: ConnectionMode,
         port_from: Option<u32>,
         port_to: Option<u32>,
+        upload_limit: Option<u64>,
+        download_limit: Option<u64>,
     ) -> Result<Self> {
         Ok(Self {
             storage_mountpoint,
@@ -54,12 +59,23 @@ impl Options {
             port_edit: false,
             port_from,
             port_to,
+            upload_limit,
+            download_limit,
             active: false,
             action_tx: None,
         })
     }
 }
 
+/// Formats a bandwidth limit of all the nodes, in bytes per second
+fn format_rate(rate: Option<u64>) -> String {
+    match rate {
+        Some(rate) if rate >= 1_000_000 => format!("{:.1} MB/s", rate as f64 / 1e6),
+        Some(rate) => format!("{:.0} KB/s", rate as f64 / 1e3),
+        None => "Unlimited".to_string(),
+    }
+}
+
 impl Component for Options {
     fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
         if !self.active {
@@ -71,7 +87,7 @@ impl Component for Options {
             .constraints(
                 [
                     Constraint::Length(1),
-                    Constraint::Length(7),
+                    Constraint::Length(8),
                     Constraint::Length(3),
                     Constraint::Length(3),
                     Constraint::Length(3),
@@ -180,6 +196,33 @@ impl Component for Options {
                         .alignment(Alignment::Right),
                     ),
                 ]),
+                Row::new(vec![
+                    Cell::from(
+                        Line::from(vec![Span::styled(
+                            " Bandwidth Limit: ",
+                            Style::default().fg(LIGHT_PERIWINKLE),
+                        )])
+                        .alignment(Alignment::Left),
+                    ),
+                    Cell::from(
+                        Line::from(vec![if self.upload_limit.is_none()
+                            && self.download_limit.is_none()
+                        {
+                            Span::styled(" Unlimited ", Style::default().fg(COOL_GREY))
+                        } else {
+                            Span::styled(
+                                format!(
+                                    " Up {} / Down {} ",
+                                    format_rate(self.upload_limit),
+                                    format_rate(self.download_limit)
+                                ),
+                                Style::default().fg(VIVID_SKY_BLUE),
+                            )
+                        }])
+                        .alignment(Alignment::Left),
+                    ),
+                    Cell::from(Line::from(vec![])),
+                ]),
                 Row::new(vec![Line::from(vec![])]),
             ],
             &[
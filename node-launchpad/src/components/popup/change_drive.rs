@@ -23,10 +23,7 @@ use ratatui::{
 
 use crate::{
     action::{Action, OptionsActions},
-    components::{
-        popup::manage_nodes::{GB, GB_PER_NODE},
-        Component,
-    },
+    components::{popup::manage_nodes::NODE_STORAGE_CAPACITY, Component},
     config::get_launchpad_nodes_data_dir_path,
     mode::{InputMode, Scene},
     style::{
@@ -129,7 +126,7 @@ impl ChangeDrivePopup {
             .map(|(drive_name, mountpoint, space, available)| {
                 let size_str = format!("{:.2} GB", *space as f64 / 1e9);
                 let has_enough_space = *space as u128
-                    >= (GB_PER_NODE as u128 * GB as u128 * self.nodes_to_start as u128);
+                    >= (NODE_STORAGE_CAPACITY.as_bytes() as u128 * self.nodes_to_start as u128);
                 DriveItem {
                     name: drive_name.to_string(),
                     mountpoint: mountpoint.clone(),
@@ -13,6 +13,7 @@ use crate::system::get_available_space_b;
 use color_eyre::Result;
 use crossterm::event::{Event, KeyCode, KeyEvent};
 use ratatui::{prelude::*, widgets::*};
+use sn_protocol::storage::StorageSize;
 use tui_input::{backend::crossterm::EventHandler, Input};
 
 use crate::{
@@ -24,6 +25,8 @@ use crate::{
 use super::super::{utils::centered_rect_fixed, Component};
 
 pub const GB_PER_NODE: usize = 35;
+/// The storage capacity given to each node started by the launchpad, passed as `--max-storage`.
+pub const NODE_STORAGE_CAPACITY: StorageSize = StorageSize::from_gb(GB_PER_NODE as u64);
 pub const MB: usize = 1000 * 1000;
 pub const GB: usize = MB * 1000;
 pub const MAX_NODE_COUNT: usize = 50;
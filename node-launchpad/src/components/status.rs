@@ -103,6 +103,10 @@ pub struct Status<'a> {
     port_from: Option<u32>,
     // Port to
     port_to: Option<u32>,
+    // The max bytes per second uploaded by all the nodes together
+    upload_limit: Option<u64>,
+    // The max bytes per second downloaded by all the nodes together
+    download_limit: Option<u64>,
     error_popup: Option<ErrorPopup>,
 }
 
@@ -122,6 +126,8 @@ pub struct StatusConfig {
     pub connection_mode: ConnectionMode,
     pub port_from: Option<u32>,
     pub port_to: Option<u32>,
+    pub upload_limit: Option<u64>,
+    pub download_limit: Option<u64>,
 }
 
 impl Status<'_> {
@@ -145,6 +151,8 @@ impl Status<'_> {
             connection_mode: config.connection_mode,
             port_from: config.port_from,
             port_to: config.port_to,
+            upload_limit: config.upload_limit,
+            download_limit: config.download_limit,
             error_popup: None,
         };
 
@@ -207,7 +215,7 @@ impl Status<'_> {
                         .iter()
                         .find(|s| s.service_name == node_item.service_name)
                     {
-                        item.attos = stats.forwarded_rewards;
+                        item.attos = stats.rewards_earned;
                         item.memory = stats.memory_usage_mb;
                         item.mbps = format!(
                             "↓{:06.2} ↑{:06.2}",
@@ -559,6 +567,8 @@ impl Component for Status<'_> {
                         connection_mode: self.connection_mode,
                         port_range: Some(port_range),
                         rewards_address: self.rewards_address.clone(),
+                        upload_limit: self.upload_limit,
+                        download_limit: self.download_limit,
                     };
 
                     debug!("Calling maintain_n_running_nodes");
@@ -710,7 +720,7 @@ impl Component for Status<'_> {
 
         let total_attos_earned_and_wallet_row = Row::new(vec![
             Cell::new("Attos Earned".to_string()).fg(VIVID_SKY_BLUE),
-            Cell::new(self.node_stats.total_forwarded_rewards.to_string())
+            Cell::new(self.node_stats.total_rewards_earned.to_string())
                 .fg(VIVID_SKY_BLUE)
                 .bold(),
             Cell::new(Line::from(wallet_not_set).alignment(Alignment::Right)),
@@ -107,6 +107,10 @@ pub struct AppData {
     pub connection_mode: Option<ConnectionMode>,
     pub port_from: Option<u32>,
     pub port_to: Option<u32>,
+    /// The max bytes per second uploaded by all the nodes together
+    pub upload_limit: Option<u64>,
+    /// The max bytes per second downloaded by all the nodes together
+    pub download_limit: Option<u64>,
 }
 
 impl Default for AppData {
@@ -119,6 +123,8 @@ impl Default for AppData {
             connection_mode: None,
             port_from: None,
             port_to: None,
+            upload_limit: None,
+            download_limit: None,
         }
     }
 }
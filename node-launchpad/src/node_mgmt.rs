@@ -1,4 +1,5 @@
 use crate::action::{Action, StatusActions};
+use crate::components::popup::manage_nodes::NODE_STORAGE_CAPACITY;
 use crate::connection_mode::ConnectionMode;
 use color_eyre::eyre::{eyre, Error};
 use sn_evm::{EvmNetwork, RewardsAddress};
@@ -20,7 +21,7 @@ const NODE_ADD_MAX_RETRIES: u32 = 5;
 pub fn stop_nodes(services: Vec<String>, action_sender: UnboundedSender<Action>) {
     tokio::task::spawn_local(async move {
         if let Err(err) =
-            sn_node_manager::cmd::node::stop(vec![], services, VerbosityLevel::Minimal).await
+            sn_node_manager::cmd::node::stop(None, vec![], services, VerbosityLevel::Minimal).await
         {
             error!("Error while stopping services {err:?}");
             if let Err(err) =
@@ -52,6 +53,10 @@ pub struct MaintainNodesArgs {
     pub connection_mode: ConnectionMode,
     pub port_range: Option<PortRange>,
     pub rewards_address: String,
+    /// The max bytes per second uploaded by all the nodes together
+    pub upload_limit: Option<u64>,
+    /// The max bytes per second downloaded by all the nodes together
+    pub download_limit: Option<u64>,
 }
 
 /// Maintain the specified number of nodes
@@ -174,6 +179,8 @@ struct NodeConfig {
     peers_args: PeersArgs,
     safenode_path: Option<PathBuf>,
     rewards_address: String,
+    max_download_rate: Option<u64>,
+    max_upload_rate: Option<u64>,
 }
 
 /// Run the NAT detection process
@@ -237,9 +244,21 @@ fn prepare_node_config(args: &MaintainNodesArgs) -> NodeConfig {
         peers_args: args.peers_args.clone(),
         safenode_path: args.safenode_path.clone(),
         rewards_address: args.rewards_address.clone(),
+        max_download_rate: args
+            .download_limit
+            .map(|limit| limit_per_node(limit, args.count)),
+        max_upload_rate: args
+            .upload_limit
+            .map(|limit| limit_per_node(limit, args.count)),
     }
 }
 
+/// Share a bandwidth limit of all the nodes evenly between them. The share is given to the nodes
+/// added from now on, the nodes already running keep theirs rather than being restarted.
+fn limit_per_node(limit: u64, count: u16) -> u64 {
+    (limit / u64::from(count.max(1))).max(1)
+}
+
 /// Debug log the node config
 fn debug_log_config(config: &NodeConfig, args: &MaintainNodesArgs) {
     debug!("************ STARTING NODE MAINTENANCE ************");
@@ -259,6 +278,10 @@ fn debug_log_config(config: &NodeConfig, args: &MaintainNodesArgs) {
         " auto_set_nat_flags: {:?}, custom_ports: {:?}, upnp: {}, home_network: {}",
         config.auto_set_nat_flags, config.custom_ports, config.upnp, config.home_network
     );
+    debug!(
+        " max_download_rate: {:?}, max_upload_rate: {:?}",
+        config.max_download_rate, config.max_upload_rate
+    );
 }
 
 /// Get the currently used ports from the node registry
@@ -298,6 +321,10 @@ async fn scale_down_nodes(config: &NodeConfig, count: u16) {
         None,
         None,
         None,
+        Some(NODE_STORAGE_CAPACITY.as_bytes()),
+        None,
+        None,
+        None,
         None,
         None,
         None, // We don't care about the port, as we are scaling down
@@ -373,6 +400,10 @@ async fn add_nodes(
             None,
             None,
             None,
+            Some(NODE_STORAGE_CAPACITY.as_bytes()),
+            config.max_download_rate,
+            config.max_upload_rate,
+            None,
             None,
             None,
             port_range,
@@ -9,8 +9,11 @@
 use color_eyre::Result;
 use futures::StreamExt;
 use serde::{Deserialize, Serialize};
-use sn_service_management::{NodeServiceData, ServiceStatus};
-use std::{path::PathBuf, time::Instant};
+use sn_service_management::{
+    rpc::{RpcActions, RpcClient},
+    NodeServiceData, ServiceStatus,
+};
+use std::{net::SocketAddr, path::PathBuf, time::Instant};
 use tokio::sync::mpsc::UnboundedSender;
 
 use super::components::status::NODE_STAT_UPDATE_INTERVAL;
@@ -21,6 +24,7 @@ use crate::action::{Action, StatusActions};
 pub struct IndividualNodeStats {
     pub service_name: String,
     pub forwarded_rewards: u64,
+    pub rewards_earned: u64,
     pub memory_usage_mb: usize,
     pub bandwidth_inbound: usize,
     pub bandwidth_outbound: usize,
@@ -34,6 +38,7 @@ pub struct IndividualNodeStats {
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct NodeStats {
     pub total_forwarded_rewards: u64,
+    pub total_rewards_earned: u64,
     pub total_memory_usage_mb: usize,
     pub individual_stats: Vec<IndividualNodeStats>,
 }
@@ -41,6 +46,9 @@ pub struct NodeStats {
 impl NodeStats {
     fn merge(&mut self, other: &IndividualNodeStats) {
         self.total_forwarded_rewards += other.forwarded_rewards;
+        self.total_rewards_earned = self
+            .total_rewards_earned
+            .saturating_add(other.rewards_earned);
         self.total_memory_usage_mb += other.memory_usage_mb;
         self.individual_stats.push(other.clone()); // Store individual stats
     }
@@ -72,6 +80,7 @@ impl NodeStats {
                         Some((
                             node.service_name.clone(),
                             metrics_port,
+                            node.rpc_socket_addr,
                             node.data_dir_path.clone(),
                         ))
                     } else {
@@ -112,19 +121,21 @@ impl NodeStats {
     ///
     /// # Parameters
     ///
-    /// * `node_details`: A vector of tuples, each containing the service name, metrics port, and data directory path of a node.
+    /// * `node_details`: A vector of tuples, each containing the service name, metrics port, RPC address and data directory path of a node.
     /// * `action_sender`: An unbounded sender of `Action` instances used to send the aggregated node statistics.
     async fn fetch_all_node_stats_inner(
-        node_details: Vec<(String, u16, PathBuf)>,
+        node_details: Vec<(String, u16, SocketAddr, PathBuf)>,
         action_sender: UnboundedSender<Action>,
     ) {
         let mut stream = futures::stream::iter(node_details)
-            .map(|(service_name, metrics_port, data_dir)| async move {
-                (
-                    Self::fetch_stat_per_node(metrics_port, data_dir).await,
-                    service_name,
-                )
-            })
+            .map(
+                |(service_name, metrics_port, rpc_socket_addr, data_dir)| async move {
+                    (
+                        Self::fetch_stat_per_node(metrics_port, rpc_socket_addr, data_dir).await,
+                        service_name,
+                    )
+                },
+            )
             .buffer_unordered(5);
 
         let mut all_node_stats = NodeStats::default();
@@ -135,6 +146,7 @@ impl NodeStats {
                     let individual_stats = IndividualNodeStats {
                         service_name: service_name.clone(),
                         forwarded_rewards: stats.forwarded_rewards,
+                        rewards_earned: stats.rewards_earned,
                         memory_usage_mb: stats.memory_usage_mb,
                         bandwidth_inbound: stats.bandwidth_inbound,
                         bandwidth_outbound: stats.bandwidth_outbound,
@@ -161,6 +173,7 @@ impl NodeStats {
 
     async fn fetch_stat_per_node(
         metrics_port: u16,
+        rpc_socket_addr: SocketAddr,
         _data_dir: PathBuf,
     ) -> Result<IndividualNodeStats> {
         let now = Instant::now();
@@ -253,6 +266,19 @@ impl NodeStats {
                 }
             }
         }
+
+        // The rewards are obtained from the node's ledger, which persists across restarts
+        let mut rpc_client = RpcClient::from_socket_addr(rpc_socket_addr);
+        rpc_client.set_max_attempts(1);
+        match rpc_client.rewards_ledger(0, 0).await {
+            Ok(ledger) => {
+                stats.rewards_earned = ledger.total_earned.as_atto().try_into().unwrap_or(u64::MAX);
+            }
+            Err(err) => {
+                debug!("Failed to obtain the rewards ledger from {rpc_socket_addr:?}: {err:?}");
+            }
+        }
+
         trace!(
             "Fetched stats from metrics_port {metrics_port:?} in {:?}",
             now.elapsed()
@@ -53,9 +53,17 @@ pub struct QuotingMetrics {
     pub max_records: usize,
     /// number of times that got paid
     pub received_payment_count: usize,
-    /// the duration that node keeps connected to the network, measured in hours
+    /// the duration that node keeps connected to the network, measured in seconds
     /// TODO: take `restart` into accout
     pub live_time: u64,
+    /// the total size in bytes of the records stored.
+    /// Left out of the quotes for peers on an older protocol version, which then serialise,
+    /// sign and hash the same as before.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub close_records_size: Option<u64>,
+    /// the storage capacity in bytes configured, set along with `close_records_size`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_storage: Option<u64>,
 }
 
 impl QuotingMetrics {
@@ -66,6 +74,8 @@ impl QuotingMetrics {
             max_records: 0,
             received_payment_count: 0,
             live_time: 0,
+            close_records_size: None,
+            max_storage: None,
         }
     }
 }
@@ -373,4 +383,50 @@ mod tests {
         assert!(new_quote.historical_verify(&old_quote));
         assert!(old_quote.historical_verify(&new_quote));
     }
+
+    #[test]
+    fn quoting_metrics_without_storage_bytes_keep_the_legacy_wire_format() {
+        /// The QuotingMetrics of the peers quoting by record count only
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct LegacyQuotingMetrics {
+            close_records_stored: usize,
+            max_records: usize,
+            received_payment_count: usize,
+            live_time: u64,
+        }
+        let legacy = LegacyQuotingMetrics {
+            close_records_stored: 10,
+            max_records: 8192,
+            received_payment_count: 2,
+            live_time: 3600,
+        };
+        let metrics = QuotingMetrics {
+            close_records_stored: 10,
+            max_records: 8192,
+            received_payment_count: 2,
+            live_time: 3600,
+            close_records_size: None,
+            max_storage: None,
+        };
+
+        // same bytes, so the same signatures and quote hashes
+        let legacy_bytes = rmp_serde::to_vec(&legacy).expect("serialise legacy metrics");
+        assert_eq!(
+            rmp_serde::to_vec(&metrics).expect("serialise metrics"),
+            legacy_bytes
+        );
+        let deserialised: QuotingMetrics =
+            rmp_serde::from_slice(&legacy_bytes).expect("deserialise legacy metrics");
+        assert_eq!(deserialised, metrics);
+
+        let with_storage_bytes = QuotingMetrics {
+            close_records_size: Some(1024),
+            max_storage: Some(4096),
+            ..metrics
+        };
+        let bytes = rmp_serde::to_vec(&with_storage_bytes).expect("serialise metrics");
+        let deserialised: QuotingMetrics =
+            rmp_serde::from_slice(&bytes).expect("deserialise metrics");
+        assert_eq!(deserialised, with_storage_bytes);
+    }
 }
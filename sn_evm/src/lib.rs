@@ -14,8 +14,10 @@ pub use evmlib::common::Address as EvmAddress;
 pub use evmlib::common::QuotePayment;
 pub use evmlib::common::{QuoteHash, TxHash};
 pub use evmlib::cryptography;
+pub use evmlib::event::ChunkPaymentEvent;
 #[cfg(feature = "external-signer")]
 pub use evmlib::external_signer;
+pub use evmlib::transaction::{DataPayments, Error as EvmTransactionError};
 pub use evmlib::utils;
 pub use evmlib::utils::get_evm_network_from_env;
 pub use evmlib::utils::{DATA_PAYMENTS_ADDRESS, PAYMENT_TOKEN_ADDRESS, RPC_URL};
@@ -0,0 +1,374 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::target_arch::{Duration, Instant};
+#[cfg(not(target_arch = "wasm32"))]
+use futures::{AsyncRead, AsyncWrite, Future};
+#[cfg(not(target_arch = "wasm32"))]
+use libp2p::{
+    core::{
+        muxing::{StreamMuxer, StreamMuxerBox, StreamMuxerEvent, StreamMuxerExt, SubstreamBox},
+        transport,
+    },
+    PeerId, Transport as _,
+};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    io,
+    pin::Pin,
+    sync::Arc,
+    task::{ready, Context, Poll},
+};
+#[cfg(not(target_arch = "wasm32"))]
+use tokio::time::Sleep;
+
+/// The bytes a direction can transfer in a burst, as a multiple of its rate.
+const BURST_DURATION: Duration = Duration::from_secs(1);
+
+/// The share of its burst a direction has to have left, not to be considered congested.
+const CONGESTION_THRESHOLD: f64 = 0.25;
+
+/// A throttled stream waits for the bytes of this duration to be available at least,
+/// so it doesn't wake up for every few bytes.
+const MIN_WAIT: Duration = Duration::from_millis(50);
+
+/// The upload and download rate limits of a node, in bytes per second, shared by all its connections.
+/// A direction without a limit is not throttled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BandwidthLimits {
+    pub upload: Option<u64>,
+    pub download: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Direction {
+    Upload,
+    Download,
+}
+
+/// A token bucket refilled at the rate of the limit, holding up to `BURST_DURATION` worth of bytes.
+/// The bytes transferred concurrently by several streams can take it below zero, the streams then
+/// waiting for the debt to be paid back.
+#[derive(Debug)]
+struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: u64) -> Self {
+        let rate = rate as f64;
+        Self {
+            rate,
+            tokens: rate * BURST_DURATION.as_secs_f64(),
+            refilled_at: Instant::now(),
+        }
+    }
+
+    fn capacity(&self) -> f64 {
+        self.rate * BURST_DURATION.as_secs_f64()
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.saturating_duration_since(self.refilled_at);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.rate).min(self.capacity());
+        self.refilled_at = now;
+    }
+}
+
+#[derive(Debug)]
+struct DirectionState {
+    bucket: Option<Mutex<TokenBucket>>,
+    transferred: AtomicU64,
+}
+
+impl DirectionState {
+    fn new(limit: Option<u64>) -> Self {
+        Self {
+            bucket: limit
+                .filter(|rate| *rate > 0)
+                .map(|rate| Mutex::new(TokenBucket::new(rate))),
+            transferred: AtomicU64::new(0),
+        }
+    }
+
+    fn with_bucket<T>(&self, f: impl FnOnce(&mut TokenBucket) -> T) -> Option<T> {
+        let bucket = self.bucket.as_ref()?;
+        let mut bucket = bucket
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        bucket.refill();
+        Some(f(&mut bucket))
+    }
+}
+
+/// Enforces the `BandwidthLimits` of a node over the streams of all its connections,
+/// and keeps track of the bytes they transferred.
+#[derive(Debug)]
+pub(crate) struct BandwidthLimiter {
+    #[cfg(feature = "open-metrics")]
+    limits: BandwidthLimits,
+    upload: DirectionState,
+    download: DirectionState,
+}
+
+impl BandwidthLimiter {
+    pub(crate) fn new(limits: BandwidthLimits) -> Self {
+        Self {
+            #[cfg(feature = "open-metrics")]
+            limits,
+            upload: DirectionState::new(limits.upload),
+            download: DirectionState::new(limits.download),
+        }
+    }
+
+    #[cfg(feature = "open-metrics")]
+    pub(crate) fn limits(&self) -> BandwidthLimits {
+        self.limits
+    }
+
+    fn state(&self, direction: Direction) -> &DirectionState {
+        match direction {
+            Direction::Upload => &self.upload,
+            Direction::Download => &self.download,
+        }
+    }
+
+    /// Returns the bytes that can be transferred right now,
+    /// or how long to wait for enough bytes to be available.
+    pub(crate) fn available(&self, direction: Direction) -> Result<usize, Duration> {
+        self.state(direction)
+            .with_bucket(|bucket| {
+                if bucket.tokens >= 1.0 {
+                    Ok(bucket.tokens as usize)
+                } else {
+                    let wanted = bucket.rate * MIN_WAIT.as_secs_f64() - bucket.tokens;
+                    Err(Duration::from_secs_f64(wanted.max(1.0) / bucket.rate))
+                }
+            })
+            .unwrap_or(Ok(usize::MAX))
+    }
+
+    /// Records the bytes transferred, consuming them from the limit.
+    pub(crate) fn consume(&self, direction: Direction, bytes: usize) {
+        let state = self.state(direction);
+        let _ = state.with_bucket(|bucket| bucket.tokens -= bytes as f64);
+        let _ = state.transferred.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// The total bytes transferred in that direction.
+    #[cfg(any(feature = "open-metrics", test))]
+    pub(crate) fn transferred(&self, direction: Direction) -> u64 {
+        self.state(direction).transferred.load(Ordering::Relaxed)
+    }
+
+    /// Whether a limited direction has used most of its bandwidth,
+    /// in which case the low priority traffic shall be held back.
+    pub(crate) fn is_congested(&self) -> bool {
+        [&self.upload, &self.download].into_iter().any(|state| {
+            state
+                .with_bucket(|bucket| bucket.tokens < bucket.capacity() * CONGESTION_THRESHOLD)
+                .unwrap_or(false)
+        })
+    }
+}
+
+/// Throttles the streams of all the connections of the transport with the limiter.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn throttle_transport(
+    transport: transport::Boxed<(PeerId, StreamMuxerBox)>,
+    limiter: Arc<BandwidthLimiter>,
+) -> transport::Boxed<(PeerId, StreamMuxerBox)> {
+    transport
+        .map(move |(peer_id, muxer), _| {
+            let muxer = ThrottledMuxer {
+                inner: muxer,
+                limiter: Arc::clone(&limiter),
+            };
+            (peer_id, StreamMuxerBox::new(muxer))
+        })
+        .boxed()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+struct ThrottledMuxer {
+    inner: StreamMuxerBox,
+    limiter: Arc<BandwidthLimiter>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ThrottledMuxer {
+    fn throttle(&self, stream: SubstreamBox) -> ThrottledStream {
+        ThrottledStream {
+            inner: stream,
+            limiter: Arc::clone(&self.limiter),
+            read_delay: None,
+            write_delay: None,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl StreamMuxer for ThrottledMuxer {
+    type Substream = ThrottledStream;
+    type Error = io::Error;
+
+    fn poll_inbound(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Self::Substream, Self::Error>> {
+        let this = self.get_mut();
+        let stream = ready!(this.inner.poll_inbound_unpin(cx))?;
+        Poll::Ready(Ok(this.throttle(stream)))
+    }
+
+    fn poll_outbound(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Self::Substream, Self::Error>> {
+        let this = self.get_mut();
+        let stream = ready!(this.inner.poll_outbound_unpin(cx))?;
+        Poll::Ready(Ok(this.throttle(stream)))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.get_mut().inner.poll_close_unpin(cx)
+    }
+
+    fn poll(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<StreamMuxerEvent, Self::Error>> {
+        self.get_mut().inner.poll_unpin(cx)
+    }
+}
+
+/// A stream reading and writing no more bytes than its limiter allows, waiting for them otherwise.
+#[cfg(not(target_arch = "wasm32"))]
+struct ThrottledStream {
+    inner: SubstreamBox,
+    limiter: Arc<BandwidthLimiter>,
+    read_delay: Option<Pin<Box<Sleep>>>,
+    write_delay: Option<Pin<Box<Sleep>>>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ThrottledStream {
+    fn poll_available(&mut self, direction: Direction, cx: &mut Context<'_>) -> Poll<usize> {
+        let delay = match direction {
+            Direction::Upload => &mut self.write_delay,
+            Direction::Download => &mut self.read_delay,
+        };
+        loop {
+            if let Some(sleep) = delay.as_mut() {
+                ready!(sleep.as_mut().poll(cx));
+                *delay = None;
+            }
+            match self.limiter.available(direction) {
+                Ok(bytes) => return Poll::Ready(bytes),
+                Err(wait) => *delay = Some(Box::pin(tokio::time::sleep(wait))),
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl AsyncRead for ThrottledStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let available = ready!(this.poll_available(Direction::Download, cx));
+        let len = buf.len().min(available);
+        let read = ready!(Pin::new(&mut this.inner).poll_read(cx, &mut buf[..len]))?;
+        this.limiter.consume(Direction::Download, read);
+        Poll::Ready(Ok(read))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl AsyncWrite for ThrottledStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let available = ready!(this.poll_available(Direction::Upload, cx));
+        let len = buf.len().min(available);
+        let written = ready!(Pin::new(&mut this.inner).poll_write(cx, &buf[..len]))?;
+        this.limiter.consume(Direction::Upload, written);
+        Poll::Ready(Ok(written))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_close(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unlimited_directions_are_never_throttled() {
+        let limiter = BandwidthLimiter::new(BandwidthLimits::default());
+
+        limiter.consume(Direction::Upload, 10_000_000);
+        assert_eq!(limiter.available(Direction::Upload), Ok(usize::MAX));
+        assert_eq!(limiter.transferred(Direction::Upload), 10_000_000);
+        assert!(!limiter.is_congested());
+    }
+
+    #[test]
+    fn limited_direction_waits_once_its_burst_is_used() {
+        let limiter = BandwidthLimiter::new(BandwidthLimits {
+            upload: Some(1_000),
+            download: None,
+        });
+
+        let available = limiter.available(Direction::Upload).expect("a full burst");
+        assert!(available >= 999);
+        assert!(!limiter.is_congested());
+
+        limiter.consume(Direction::Upload, 2_000);
+        let wait = limiter
+            .available(Direction::Upload)
+            .expect_err("the burst got used");
+        // paying back the debt of 1_000 bytes, then the bytes of MIN_WAIT
+        assert!(wait >= Duration::from_millis(1_000));
+        assert!(wait <= Duration::from_millis(1_100));
+        assert!(limiter.is_congested());
+
+        assert_eq!(limiter.available(Direction::Download), Ok(usize::MAX));
+        assert_eq!(limiter.transferred(Direction::Upload), 2_000);
+    }
+
+    #[test]
+    fn zero_limit_means_unlimited() {
+        let limiter = BandwidthLimiter::new(BandwidthLimits {
+            upload: Some(0),
+            download: Some(0),
+        });
+
+        assert_eq!(limiter.available(Direction::Upload), Ok(usize::MAX));
+        assert_eq!(limiter.available(Direction::Download), Ok(usize::MAX));
+    }
+}
@@ -12,36 +12,48 @@ use crate::{
     error::{NetworkError, Result},
     event::TerminateNodeReason,
     log_markers::Marker,
-    multiaddr_pop_p2p, sort_peers_by_address_and_limit, GetRecordCfg, GetRecordError, MsgResponder,
-    NetworkEvent, CLOSE_GROUP_SIZE,
+    multiaddr_pop_p2p,
+    network_size::NetworkSizeEstimate,
+    record_store::RecordScrubStats,
+    reputation::{PeerReputation, ReputationEvent},
+    sort_peers_by_address_and_limit, GetRecordCfg, GetRecordError, MsgResponder, NetworkEvent,
+    CLOSE_GROUP_SIZE,
 };
 use libp2p::{
     kad::{
         store::{Error as StoreError, RecordStore},
-        KBucketDistance, Quorum, Record, RecordKey,
+        KBucketDistance, PeerRecord, QueryId, Quorum, Record, RecordKey,
     },
     Multiaddr, PeerId,
 };
 use sn_evm::{AttoTokens, PaymentQuote, QuotingMetrics};
 use sn_protocol::{
-    messages::{Cmd, Request, Response},
+    messages::{Cmd, ReplicationSummary, Request, Response},
     storage::{get_type_from_record, RecordType},
+    version::{
+        MIN_PROTOCOL_VERSION, REPLICATION_SUMMARY_PROTOCOL_VERSION,
+        STORAGE_BYTES_QUOTE_PROTOCOL_VERSION,
+    },
     NetworkAddress, PrettyPrintRecordKey,
 };
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     fmt::Debug,
     time::Duration,
 };
 use tokio::sync::oneshot;
 
-use crate::target_arch::Instant;
+use crate::target_arch::{Instant, SystemTime};
 
 const MAX_CONTINUOUS_HDD_WRITE_ERROR: usize = 5;
 
 // Shall be synced with `sn_node::PERIODIC_REPLICATION_INTERVAL_MAX_S`
 const REPLICATION_TIMEOUT: Duration = Duration::from_secs(45);
 
+/// How long a peer that can't handle replication summaries is sent the full replication list,
+/// before trying the summary again in case it got upgraded.
+const LEGACY_REPLICATION_PEER_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum NodeIssue {
     /// Data Replication failed
@@ -52,6 +64,10 @@ pub enum NodeIssue {
     BadQuoting,
     /// Peer failed to pass the chunk proof verification
     FailedChunkProofCheck,
+    /// Peer failed to prove it holds the records it is responsible for, during a storage audit
+    FailedStorageAudit,
+    /// Peer served a record that does not match the address it was requested for
+    InvalidRecordServed,
 }
 
 /// Commands to send to the Swarm
@@ -98,6 +114,8 @@ pub enum LocalSwarmCmd {
     /// GetLocalStoreCost for this node, also with the bad_node list close to the target
     GetLocalStoreCost {
         key: RecordKey,
+        /// The peer the quote is for, quoted by record count if on an older protocol version
+        peer: PeerId,
         sender: oneshot::Sender<(AttoTokens, QuotingMetrics, Vec<NetworkAddress>)>,
     },
     /// Notify the node received a payment.
@@ -142,6 +160,30 @@ pub enum LocalSwarmCmd {
     TriggerIntervalReplication,
     /// Triggers unrelevant record cleanup
     TriggerUnrelevantRecordCleanup,
+    /// Notify the outcome of a record store scrub batch
+    RecordStoreScrubbed {
+        checked: usize,
+        corrupt: Vec<(RecordKey, RecordType)>,
+        pass_completed: bool,
+    },
+    /// Notify the outcome of validating a copy fetched for a GetRecord query
+    GetRecordCopyValidated {
+        query_id: QueryId,
+        peer_record: PeerRecord,
+        result: Result<()>,
+    },
+    /// Get the outcome of the record store scrubs undertaken so far
+    GetRecordScrubStats {
+        sender: oneshot::Sender<RecordScrubStats>,
+    },
+    /// Get the reputations of the peers we interacted with
+    GetPeerReputations {
+        sender: oneshot::Sender<Vec<(PeerId, PeerReputation)>>,
+    },
+    /// Estimate the number of nodes in the network from our routing table
+    GetNetworkSizeEstimate {
+        sender: oneshot::Sender<NetworkSizeEstimate>,
+    },
 }
 
 /// Commands to send to the Swarm
@@ -295,6 +337,37 @@ impl Debug for LocalSwarmCmd {
             LocalSwarmCmd::TriggerUnrelevantRecordCleanup => {
                 write!(f, "LocalSwarmCmd::TriggerUnrelevantRecordCleanup")
             }
+            LocalSwarmCmd::RecordStoreScrubbed {
+                checked, corrupt, ..
+            } => {
+                write!(
+                    f,
+                    "LocalSwarmCmd::RecordStoreScrubbed {} corrupt among {checked} records",
+                    corrupt.len()
+                )
+            }
+            LocalSwarmCmd::GetRecordCopyValidated {
+                query_id,
+                peer_record,
+                result,
+            } => {
+                write!(
+                    f,
+                    "LocalSwarmCmd::GetRecordCopyValidated {{ query_id: {query_id:?}, key: {:?}, peer: {:?}, valid: {} }}",
+                    PrettyPrintRecordKey::from(&peer_record.record.key),
+                    peer_record.peer,
+                    result.is_ok()
+                )
+            }
+            LocalSwarmCmd::GetRecordScrubStats { .. } => {
+                write!(f, "LocalSwarmCmd::GetRecordScrubStats")
+            }
+            LocalSwarmCmd::GetPeerReputations { .. } => {
+                write!(f, "LocalSwarmCmd::GetPeerReputations")
+            }
+            LocalSwarmCmd::GetNetworkSizeEstimate { .. } => {
+                write!(f, "LocalSwarmCmd::GetNetworkSizeEstimate")
+            }
         }
     }
 }
@@ -500,6 +573,7 @@ impl SwarmDriver {
                     if let Request::Query(query) = req {
                         self.send_event(NetworkEvent::QueryRequestReceived {
                             query,
+                            peer,
                             channel: MsgResponder::FromSelf(sender),
                         });
                     } else {
@@ -507,6 +581,17 @@ impl SwarmDriver {
                         // we already hold this data if we do... so we can ignore
                         trace!("Replicate cmd to self received, ignoring");
                     }
+                } else if let Some(peer_version) =
+                    unsupported_peer_version(&req, self.peer_protocol_versions.get(&peer).copied())
+                {
+                    warn!("Not sending {req:?} to {peer:?}, which speaks the older protocol version {peer_version}");
+                    if let Some(sender) = sender {
+                        let _ = sender.send(Err(NetworkError::RequestUnsupportedByPeer {
+                            peer,
+                            peer_version,
+                            required: req.since_protocol_version(),
+                        }));
+                    }
                 } else {
                     let request_id = self
                         .swarm
@@ -515,6 +600,7 @@ impl SwarmDriver {
                         .send_request(&peer, req);
                     trace!("Sending request {request_id:?} to peer {peer:?}");
                     let _ = self.pending_requests.insert(request_id, sender);
+                    let _ = self.request_sent_at.insert(request_id, Instant::now());
 
                     trace!("Pending Requests now: {:?}", self.pending_requests.len());
                 }
@@ -562,14 +648,16 @@ impl SwarmDriver {
                 cmd_string = "TriggerIntervalReplication";
                 self.try_interval_replication()?;
             }
-            LocalSwarmCmd::GetLocalStoreCost { key, sender } => {
+            LocalSwarmCmd::GetLocalStoreCost { key, peer, sender } => {
                 cmd_string = "GetLocalStoreCost";
+                let quote_storage_bytes =
+                    quotes_storage_bytes(self.peer_protocol_versions.get(&peer).copied());
                 let (cost, quoting_metrics) = self
                     .swarm
                     .behaviour_mut()
                     .kademlia
                     .store_mut()
-                    .store_cost(&key);
+                    .store_cost(&key, quote_storage_bytes);
 
                 self.record_metrics(Marker::StoreCost {
                     cost: cost.as_atto(),
@@ -579,15 +667,9 @@ impl SwarmDriver {
                 // To avoid sending entire list to client, sending those that:
                 //     closer than the CLOSE_GROUP_SIZEth closest node to the target
                 let mut bad_nodes: Vec<_> = self
-                    .bad_nodes
-                    .iter()
-                    .filter_map(|(peer_id, (_issue_list, is_bad))| {
-                        if *is_bad {
-                            Some(NetworkAddress::from_peer(*peer_id))
-                        } else {
-                            None
-                        }
-                    })
+                    .peer_reputations
+                    .bad_peers(SystemTime::now())
+                    .map(NetworkAddress::from_peer)
                     .collect();
 
                 // List is ordered already, hence the last one is always the one wanted
@@ -812,25 +894,17 @@ impl SwarmDriver {
             }
             LocalSwarmCmd::IsPeerShunned { target, sender } => {
                 cmd_string = "IsPeerInTrouble";
-                let is_bad = if let Some(peer_id) = target.as_peer_id() {
-                    if let Some((_issues, is_bad)) = self.bad_nodes.get(&peer_id) {
-                        *is_bad
-                    } else {
-                        false
-                    }
-                } else {
-                    false
-                };
+                let is_bad = target.as_peer_id().is_some_and(|peer_id| {
+                    self.peer_reputations.is_bad(&peer_id, SystemTime::now())
+                });
                 let _ = sender.send(is_bad);
             }
             LocalSwarmCmd::QuoteVerification { quotes } => {
                 cmd_string = "QuoteVerification";
                 for (peer_id, quote) in quotes {
                     // Do nothing if already being bad
-                    if let Some((_issues, is_bad)) = self.bad_nodes.get(&peer_id) {
-                        if *is_bad {
-                            continue;
-                        }
+                    if self.peer_reputations.is_bad(&peer_id, SystemTime::now()) {
+                        continue;
                     }
                     self.verify_peer_quote(peer_id, quote);
                 }
@@ -856,6 +930,52 @@ impl SwarmDriver {
                     .store_mut()
                     .cleanup_unrelevant_records();
             }
+            LocalSwarmCmd::RecordStoreScrubbed {
+                checked,
+                corrupt,
+                pass_completed,
+            } => {
+                cmd_string = "RecordStoreScrubbed";
+                let removed = self
+                    .swarm
+                    .behaviour_mut()
+                    .kademlia
+                    .store_mut()
+                    .scrub_completed(checked, corrupt, pass_completed);
+                self.record_metrics(Marker::RecordStoreScrubbed {
+                    checked,
+                    corrupt: removed.len(),
+                });
+                if !removed.is_empty() {
+                    self.refetch_corrupt_records(removed);
+                }
+            }
+            LocalSwarmCmd::GetRecordCopyValidated {
+                query_id,
+                peer_record,
+                result,
+            } => {
+                cmd_string = "GetRecordCopyValidated";
+                self.handle_get_record_copy_validated(query_id, peer_record, result)?;
+            }
+            LocalSwarmCmd::GetRecordScrubStats { sender } => {
+                cmd_string = "GetRecordScrubStats";
+                let stats = self
+                    .swarm
+                    .behaviour_mut()
+                    .kademlia
+                    .store_mut()
+                    .scrub_stats();
+                let _ = sender.send(stats);
+            }
+            LocalSwarmCmd::GetPeerReputations { sender } => {
+                cmd_string = "GetPeerReputations";
+                let _ = sender.send(self.peer_reputations.snapshot(SystemTime::now()));
+            }
+            LocalSwarmCmd::GetNetworkSizeEstimate { sender } => {
+                cmd_string = "GetNetworkSizeEstimate";
+                let _ = sender.send(self.estimate_network_size());
+            }
         }
 
         self.log_handling(cmd_string.to_string(), start.elapsed());
@@ -863,55 +983,54 @@ impl SwarmDriver {
         Ok(())
     }
 
-    fn record_node_issue(&mut self, peer_id: PeerId, issue: NodeIssue) {
-        info!("Peer {peer_id:?} is reported as having issue {issue:?}");
-        let (issue_vec, is_bad) = self.bad_nodes.entry(peer_id).or_default();
-
-        let mut is_new_bad = false;
-        let mut bad_behaviour: String = "".to_string();
-
-        // If being considered as bad already, skip certain operations
-        if !(*is_bad) {
-            // Remove outdated entries
-            issue_vec.retain(|(_, timestamp)| timestamp.elapsed().as_secs() < 300);
-
-            // check if vec is already 10 long, if so, remove the oldest issue
-            // we only track 10 issues to avoid mem leaks
-            if issue_vec.len() == 10 {
-                issue_vec.remove(0);
-            }
-
-            // To avoid being too sensitive, only consider as a new issue
-            // when after certain while since the last one
-            let is_new_issue = if let Some((_issue, timestamp)) = issue_vec.last() {
-                timestamp.elapsed().as_secs() > 10
-            } else {
-                true
+    /// Re-fetch the records removed by the scrubber, from the closest peer to each of them.
+    /// The fetch falls back to the network if that peer doesn't hold the record either.
+    fn refetch_corrupt_records(&mut self, records: Vec<(RecordKey, RecordType)>) {
+        let peers = self.get_all_local_peers_excluding_self();
+        let all_keys = self
+            .swarm
+            .behaviour_mut()
+            .kademlia
+            .store_mut()
+            .record_addresses_ref()
+            .clone();
+
+        let mut keys_to_fetch = vec![];
+        for (key, record_type) in records {
+            let address = NetworkAddress::from_record_key(&key);
+            let Some(holder) = peers
+                .iter()
+                .min_by_key(|peer_id| address.distance(&NetworkAddress::from_peer(**peer_id)))
+            else {
+                warn!(
+                    "No peer to re-fetch the corrupt record {:?} from",
+                    PrettyPrintRecordKey::from(&key)
+                );
+                continue;
             };
 
-            if is_new_issue {
-                issue_vec.push((issue, Instant::now()));
-            }
+            keys_to_fetch.extend(self.replication_fetcher.add_keys(
+                *holder,
+                vec![(address, record_type)],
+                &all_keys,
+                &peers,
+            ));
+        }
 
-            // Only consider candidate as a bad node when:
-            //   accumulated THREE same kind issues within certain period
-            for (issue, _timestamp) in issue_vec.iter() {
-                let issue_counts = issue_vec
-                    .iter()
-                    .filter(|(i, _timestamp)| *issue == *i)
-                    .count();
-                if issue_counts >= 3 {
-                    *is_bad = true;
-                    is_new_bad = true;
-                    bad_behaviour = format!("{issue:?}");
-                    info!("Peer {peer_id:?} accumulated {issue_counts} times of issue {issue:?}. Consider it as a bad node now.");
-                    // Once a bad behaviour detected, no point to continue
-                    break;
-                }
-            }
+        if !keys_to_fetch.is_empty() {
+            self.send_event(NetworkEvent::KeysToFetchForReplication(keys_to_fetch));
         }
+    }
+
+    pub(crate) fn record_node_issue(&mut self, peer_id: PeerId, issue: NodeIssue) {
+        info!("Peer {peer_id:?} is reported as having issue {issue:?}");
+        let bad_behaviour = format!("{issue:?}");
+        let now = SystemTime::now();
+        let is_new_bad = self
+            .peer_reputations
+            .record(peer_id, ReputationEvent::Issue(issue), now);
 
-        if *is_bad {
+        if self.peer_reputations.is_bad(&peer_id, now) {
             warn!("Cleaning out bad_peer {peer_id:?}. Will be added to the blocklist after informing that peer.");
             if let Some(dead_peer) = self.swarm.behaviour_mut().kademlia.remove_peer(&peer_id) {
                 self.update_on_peer_removal(*dead_peer.node.key.preimage());
@@ -1047,38 +1166,241 @@ impl SwarmDriver {
             return Ok(());
         }
 
-        let all_records: Vec<_> = self
+        if self
             .swarm
             .behaviour_mut()
             .kademlia
             .store_mut()
             .record_addresses_ref()
-            .values()
-            .cloned()
-            .collect();
+            .is_empty()
+        {
+            return Ok(());
+        }
 
-        if !all_records.is_empty() {
+        for peer_id in replicate_targets.iter() {
+            let _ = self
+                .replication_targets
+                .insert(*peer_id, now + REPLICATION_TIMEOUT);
+        }
+
+        // Peers on an older version only understand the full replication list, as do the peers
+        // whose version is not known yet. The others are sent a summary of the keys within our
+        // range and then only the keys that differ, along with the list of the keys out of it.
+        self.legacy_replication_peers
+            .retain(|_peer_id, expiry| *expiry > now);
+        let (summary_targets, legacy_targets): (Vec<_>, Vec<_>) =
+            replicate_targets.into_iter().partition(|peer_id| {
+                !self.legacy_replication_peers.contains_key(peer_id)
+                    && self
+                        .peer_protocol_versions
+                        .get(peer_id)
+                        .is_some_and(|version| *version >= REPLICATION_SUMMARY_PROTOCOL_VERSION)
+            });
+
+        if !summary_targets.is_empty() {
+            let range = self.get_request_range().ilog2();
+            let all_records = self
+                .swarm
+                .behaviour_mut()
+                .kademlia
+                .store_mut()
+                .record_addresses_ref();
+            let summary = ReplicationSummary::new(&our_address, all_records.values(), range);
+            // the keys out of range can't be compared through the summary, so are still listed
+            let out_of_range_records: Vec<_> = all_records
+                .values()
+                .filter(|(addr, _)| !ReplicationSummary::is_in_range(&our_address, addr, range))
+                .cloned()
+                .collect();
             debug!(
-                "Sending a replication list of {} keys to {replicate_targets:?} ",
-                all_records.len()
+                "Sending a replication summary of {} keys in {} buckets to {summary_targets:?}",
+                summary.keys_count(),
+                summary.buckets.len()
             );
-            let request = Request::Cmd(Cmd::Replicate {
-                holder: NetworkAddress::from_peer(self.self_peer_id),
-                keys: all_records,
+            let (range, bucket_bits) = (summary.range, summary.bucket_bits);
+            let request = Request::Cmd(Cmd::ReplicateSummary {
+                holder: our_address.clone(),
+                summary,
             });
-            for peer_id in replicate_targets {
-                self.queue_network_swarm_cmd(NetworkSwarmCmd::SendRequest {
-                    req: request.clone(),
-                    peer: peer_id,
-                    sender: None,
-                });
-
+            if !out_of_range_records.is_empty() {
+                self.send_replication_list(out_of_range_records, summary_targets.clone());
+            }
+            for peer_id in summary_targets {
+                let request_id = self
+                    .swarm
+                    .behaviour_mut()
+                    .request_response
+                    .send_request(&peer_id, request.clone());
+                let _ = self.pending_requests.insert(request_id, None);
+                let _ = self.request_sent_at.insert(request_id, Instant::now());
                 let _ = self
-                    .replication_targets
-                    .insert(peer_id, now + REPLICATION_TIMEOUT);
+                    .pending_replication_summaries
+                    .insert(request_id, (range, bucket_bits));
             }
         }
 
+        if !legacy_targets.is_empty() {
+            let all_records = self
+                .swarm
+                .behaviour_mut()
+                .kademlia
+                .store_mut()
+                .record_addresses_ref()
+                .values()
+                .cloned()
+                .collect();
+            self.send_replication_list(all_records, legacy_targets);
+        }
+
         Ok(())
     }
+
+    /// Send the keys held to the peers through the legacy `Cmd::Replicate`
+    pub(crate) fn send_replication_list(
+        &mut self,
+        keys: Vec<(NetworkAddress, RecordType)>,
+        peers: Vec<PeerId>,
+    ) {
+        debug!(
+            "Sending a replication list of {} keys to {peers:?} ",
+            keys.len()
+        );
+        let request = Request::Cmd(Cmd::Replicate {
+            holder: NetworkAddress::from_peer(self.self_peer_id),
+            keys,
+        });
+        for peer_id in peers {
+            self.queue_network_swarm_cmd(NetworkSwarmCmd::SendRequest {
+                req: request.clone(),
+                peer: peer_id,
+                sender: None,
+            });
+        }
+    }
+
+    /// Send the keys of the buckets that a peer reported as differing from its own keys
+    pub(crate) fn send_replication_buckets(
+        &mut self,
+        peer_id: PeerId,
+        range: Option<u32>,
+        bucket_bits: u8,
+        buckets: Vec<u32>,
+    ) {
+        if buckets.is_empty() {
+            debug!("Replication keys are in sync with {peer_id:?}");
+            return;
+        }
+
+        let our_address = NetworkAddress::from_peer(self.self_peer_id);
+        let buckets: HashSet<_> = buckets.into_iter().collect();
+        let keys: Vec<_> = self
+            .swarm
+            .behaviour_mut()
+            .kademlia
+            .store_mut()
+            .record_addresses_ref()
+            .values()
+            .filter(|(addr, _)| {
+                ReplicationSummary::is_in_range(&our_address, addr, range)
+                    && buckets.contains(&ReplicationSummary::bucket_of(
+                        &our_address,
+                        addr,
+                        bucket_bits,
+                    ))
+            })
+            .cloned()
+            .collect();
+
+        if !keys.is_empty() {
+            debug!(
+                "{peer_id:?} reported {} differing replication buckets",
+                buckets.len()
+            );
+            self.send_replication_list(keys, vec![peer_id]);
+        }
+    }
+
+    /// Fall back to the full replication list for a peer that couldn't handle the replication summary
+    pub(crate) fn fallback_to_replication_list(&mut self, peer_id: PeerId) {
+        info!("{peer_id:?} doesn't support replication summaries, sending it the full replication list");
+        let _ = self
+            .legacy_replication_peers
+            .insert(peer_id, Instant::now() + LEGACY_REPLICATION_PEER_TIMEOUT);
+
+        let all_records: Vec<_> = self
+            .swarm
+            .behaviour_mut()
+            .kademlia
+            .store_mut()
+            .record_addresses_ref()
+            .values()
+            .cloned()
+            .collect();
+        if !all_records.is_empty() {
+            self.send_replication_list(all_records, vec![peer_id]);
+        }
+    }
+}
+
+/// The protocol version of a peer not supporting a request, which shall then not be sent it.
+/// The peers whose protocol version is not known yet are taken as speaking [`MIN_PROTOCOL_VERSION`].
+fn unsupported_peer_version(req: &Request, peer_version: Option<u16>) -> Option<u16> {
+    let peer_version = peer_version.unwrap_or(MIN_PROTOCOL_VERSION);
+    (!req.is_supported_by(peer_version)).then_some(peer_version)
+}
+
+/// Whether a peer is quoted the storage in bytes. The peers whose protocol version is not known yet,
+/// such as the ones not identified yet, are quoted by record count as legacy peers are.
+fn quotes_storage_bytes(peer_version: Option<u16>) -> bool {
+    peer_version.is_some_and(|version| version >= STORAGE_BYTES_QUOTE_PROTOCOL_VERSION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_peers_are_quoted_by_record_count() {
+        assert!(!quotes_storage_bytes(None));
+        assert!(!quotes_storage_bytes(Some(MIN_PROTOCOL_VERSION)));
+        assert!(!quotes_storage_bytes(Some(
+            STORAGE_BYTES_QUOTE_PROTOCOL_VERSION - 1
+        )));
+        assert!(quotes_storage_bytes(Some(
+            STORAGE_BYTES_QUOTE_PROTOCOL_VERSION
+        )));
+    }
+
+    #[test]
+    fn replication_summaries_are_not_sent_to_v1_or_unknown_peers() {
+        let holder = NetworkAddress::from_peer(PeerId::random());
+        let summary = ReplicationSummary::new(&holder, std::iter::empty(), None);
+        let request = Request::Cmd(Cmd::ReplicateSummary {
+            holder: holder.clone(),
+            summary,
+        });
+        assert_eq!(
+            unsupported_peer_version(&request, Some(MIN_PROTOCOL_VERSION)),
+            Some(MIN_PROTOCOL_VERSION)
+        );
+        assert_eq!(
+            unsupported_peer_version(&request, None),
+            Some(MIN_PROTOCOL_VERSION)
+        );
+        assert_eq!(
+            unsupported_peer_version(&request, Some(REPLICATION_SUMMARY_PROTOCOL_VERSION)),
+            None
+        );
+
+        // the messages of the first version are sent to all
+        let request = Request::Cmd(Cmd::Replicate {
+            holder,
+            keys: vec![],
+        });
+        assert_eq!(
+            unsupported_peer_version(&request, Some(MIN_PROTOCOL_VERSION)),
+            None
+        );
+        assert_eq!(unsupported_peer_version(&request, None), None);
+    }
 }
@@ -6,7 +6,11 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use crate::transport;
+#[cfg(feature = "simulation")]
+use crate::SimulatedEndpoint;
 use crate::{
+    bandwidth::{BandwidthLimiter, BandwidthLimits},
     bootstrap::{ContinuousBootstrap, BOOTSTRAP_INTERVAL},
     circular_vec::CircularVec,
     cmd::{LocalSwarmCmd, NetworkSwarmCmd},
@@ -14,29 +18,35 @@ use crate::{
     event::{NetworkEvent, NodeEvent},
     external_address::ExternalAddressManager,
     log_markers::Marker,
-    multiaddr_pop_p2p,
+    multiaddr_is_global, multiaddr_pop_p2p, multiaddr_strip_p2p,
     network_discovery::NetworkDiscovery,
-    record_store::{ClientRecordStore, NodeRecordStore, NodeRecordStoreConfig},
+    pricing::{default_pricing_strategy, PricingStrategy},
+    record_store::{
+        ClientRecordStore, NodeRecordStore, NodeRecordStoreConfig, DEFAULT_MAX_STORAGE_BYTES,
+        DEFAULT_RECORDS_CACHE_BYTES,
+    },
     record_store_api::UnifiedRecordStore,
-    relay_manager::RelayManager,
+    record_store_backend::{migrate_record_store, RecordStorageBackendKind},
+    relay_manager::{RelayManager, RelayServerLimits},
     replication_fetcher::ReplicationFetcher,
+    reputation::PeerReputations,
     sort_peers_by_distance_to,
-    target_arch::{interval, spawn, Instant},
+    target_arch::{interval, spawn, Instant, SystemTime},
     GetRecordError, Network, CLOSE_GROUP_SIZE,
 };
 #[cfg(feature = "open-metrics")]
 use crate::{
-    metrics::service::run_metrics_server, metrics::NetworkMetricsRecorder, MetricsRegistries,
+    metrics::service::run_metrics_server, metrics::NetworkMetricsRecorder,
+    records_cache::RecordsCacheMetrics, MetricsRegistries,
 };
-use crate::{transport, NodeIssue};
 use futures::future::Either;
 use futures::StreamExt;
+use libp2p::core::muxing::StreamMuxerBox;
 #[cfg(feature = "local")]
 use libp2p::mdns;
-use libp2p::{core::muxing::StreamMuxerBox, relay};
 use libp2p::{
     identity::Keypair,
-    kad::{self, QueryId, Quorum, Record, RecordKey, K_VALUE},
+    kad::{self, ProgressStep, QueryId, Quorum, Record, RecordKey, K_VALUE},
     multiaddr::Protocol,
     request_response::{self, Config as RequestResponseConfig, OutboundRequestId, ProtocolSupport},
     swarm::{
@@ -49,12 +59,14 @@ use libp2p::{kad::KBucketDistance, Transport as _};
 #[cfg(feature = "open-metrics")]
 use prometheus_client::metrics::info::Info;
 use sn_evm::PaymentQuote;
+#[cfg(not(target_arch = "wasm32"))]
+use sn_peers_acquisition::BootstrapCache;
 use sn_protocol::{
     messages::{ChunkProof, Nonce, Request, Response},
     storage::{try_deserialize_record, RetryStrategy},
     version::{
         get_key_version_str, IDENTIFY_CLIENT_VERSION_STR, IDENTIFY_NODE_VERSION_STR,
-        IDENTIFY_PROTOCOL_STR, REQ_RESPONSE_VERSION_STR,
+        IDENTIFY_PROTOCOL_STR, REQ_RESPONSE_VERSION_STRS,
     },
     NetworkAddress, PrettyPrintKBucketKey, PrettyPrintRecordKey,
 };
@@ -66,7 +78,8 @@ use std::{
     io::{Read, Write},
     net::SocketAddr,
     num::NonZeroUsize,
-    path::PathBuf,
+    path::{Path, PathBuf},
+    sync::Arc,
 };
 use tokio::sync::{mpsc, oneshot};
 use tokio::time::Duration;
@@ -80,6 +93,18 @@ pub(crate) const CLOSET_RECORD_CHECK_INTERVAL: Duration = Duration::from_secs(15
 /// Interval over which we query relay manager to check if we can make any more reservations.
 pub(crate) const RELAY_MANAGER_RESERVATION_INTERVAL: Duration = Duration::from_secs(30);
 
+/// Interval over which the next batch of records held is re-read from disk and verified.
+const RECORD_SCRUB_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Interval over which the forgiven peers are forgotten or unblocked, and the reputations persisted.
+const PEER_REPUTATIONS_MAINTENANCE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Interval over which the peers reached, or not, are merged into the bootstrap cache file.
+const BOOTSTRAP_CACHE_SYNC_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// The clients are often short lived, so sync their bootstrap cache more often.
+const CLIENT_BOOTSTRAP_CACHE_SYNC_INTERVAL: Duration = Duration::from_secs(30);
+
 // Number of range distances to keep in the circular buffer
 pub const GET_RANGE_STORAGE_LIMIT: usize = 100;
 
@@ -109,10 +134,6 @@ pub(crate) type PendingGetRecord = HashMap<
     ),
 >;
 
-/// 10 is the max number of issues per node we track to avoid mem leaks
-/// The boolean flag to indicate whether the node is considered as bad or not
-pub(crate) type BadNodes = BTreeMap<PeerId, (Vec<(NodeIssue, Instant)>, bool)>;
-
 /// What is the largest packet to send over the network.
 /// Records larger than this will be rejected.
 // TODO: revisit once cashnote_redemption is in
@@ -262,10 +283,22 @@ pub struct NetworkBuilder {
     is_behind_home_network: bool,
     keypair: Keypair,
     local: bool,
-    listen_addr: Option<SocketAddr>,
+    listen_addrs: Vec<SocketAddr>,
+    tcp: bool,
     request_timeout: Option<Duration>,
     concurrency_limit: Option<usize>,
     initial_peers: Vec<Multiaddr>,
+    record_store_backend: RecordStorageBackendKind,
+    migrate_record_store: bool,
+    max_storage_bytes: u64,
+    records_cache_bytes: usize,
+    pricing_strategy: Option<Arc<dyn PricingStrategy>>,
+    bandwidth_limits: BandwidthLimits,
+    relay_server_limits: RelayServerLimits,
+    #[cfg(not(target_arch = "wasm32"))]
+    bootstrap_cache: Option<BootstrapCache>,
+    #[cfg(feature = "simulation")]
+    simulated_endpoint: Option<SimulatedEndpoint>,
     #[cfg(feature = "open-metrics")]
     metrics_registries: Option<MetricsRegistries>,
     #[cfg(feature = "open-metrics")]
@@ -280,10 +313,22 @@ impl NetworkBuilder {
             is_behind_home_network: false,
             keypair,
             local,
-            listen_addr: None,
+            listen_addrs: vec![],
+            tcp: false,
             request_timeout: None,
             concurrency_limit: None,
             initial_peers: Default::default(),
+            record_store_backend: RecordStorageBackendKind::default(),
+            migrate_record_store: false,
+            max_storage_bytes: DEFAULT_MAX_STORAGE_BYTES,
+            records_cache_bytes: DEFAULT_RECORDS_CACHE_BYTES,
+            pricing_strategy: None,
+            bandwidth_limits: BandwidthLimits::default(),
+            relay_server_limits: RelayServerLimits::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            bootstrap_cache: None,
+            #[cfg(feature = "simulation")]
+            simulated_endpoint: None,
             #[cfg(feature = "open-metrics")]
             metrics_registries: None,
             #[cfg(feature = "open-metrics")]
@@ -293,12 +338,29 @@ impl NetworkBuilder {
         }
     }
 
+    /// Run over the memory transport of a simulated network rather than over the real transports.
+    /// A node then listens on the address of the endpoint only.
+    #[cfg(feature = "simulation")]
+    pub fn simulated_endpoint(&mut self, endpoint: SimulatedEndpoint) {
+        self.simulated_endpoint = Some(endpoint);
+    }
+
     pub fn is_behind_home_network(&mut self, enable: bool) {
         self.is_behind_home_network = enable;
     }
 
+    /// Adds an address for the node to listen on. Add one address per IP family to listen on
+    /// both IPv4 and IPv6, e.g. `0.0.0.0` and `::`.
     pub fn listen_addr(&mut self, listen_addr: SocketAddr) {
-        self.listen_addr = Some(listen_addr);
+        if !self.listen_addrs.contains(&listen_addr) {
+            self.listen_addrs.push(listen_addr);
+        }
+    }
+
+    /// Enable the TCP transport, with noise and yamux, as a fallback for the networks blocking UDP.
+    /// A node then listens on TCP alongside QUIC, on the same port.
+    pub fn tcp(&mut self, enable: bool) {
+        self.tcp = enable;
     }
 
     pub fn request_timeout(&mut self, request_timeout: Duration) {
@@ -313,6 +375,52 @@ impl NetworkBuilder {
         self.initial_peers = initial_peers;
     }
 
+    /// Set the storage engine used by the node to persist its records.
+    pub fn record_store_backend(&mut self, backend: RecordStorageBackendKind) {
+        self.record_store_backend = backend;
+    }
+
+    /// Move the records stored by the other storage engines into the selected one before starting the node.
+    pub fn migrate_record_store(&mut self, migrate: bool) {
+        self.migrate_record_store = migrate;
+    }
+
+    /// Set the storage capacity of the node, in bytes. Far records get pruned to stay under it.
+    pub fn max_storage_bytes(&mut self, max_storage_bytes: u64) {
+        self.max_storage_bytes = max_storage_bytes;
+    }
+
+    /// Set the bytes of the most popular records kept in memory, sparing them from being
+    /// re-read from disk on every request.
+    pub fn records_cache_bytes(&mut self, records_cache_bytes: usize) {
+        self.records_cache_bytes = records_cache_bytes;
+    }
+
+    /// Set the upload and download rate limits of the node. The replication traffic is held back
+    /// while the node uses most of its bandwidth, leaving the rest to the client requests.
+    pub fn bandwidth_limits(&mut self, bandwidth_limits: BandwidthLimits) {
+        self.bandwidth_limits = bandwidth_limits;
+    }
+
+    /// Set the resources the node lends to the peers behind a home network, by relaying their
+    /// connections.
+    pub fn relay_server_limits(&mut self, relay_server_limits: RelayServerLimits) {
+        self.relay_server_limits = relay_server_limits;
+    }
+
+    /// Set the bootstrap cache file the peers reached, or not, are recorded to, for the next
+    /// start to bootstrap from.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn bootstrap_cache(&mut self, bootstrap_cache: BootstrapCache) {
+        self.bootstrap_cache = Some(bootstrap_cache);
+    }
+
+    /// Set the pricing strategy used to compute and verify the store costs.
+    /// When unset, the `SigmoidPricing` set through `PRICING_STRATEGY_ENV` is used, or its default.
+    pub fn pricing_strategy(&mut self, pricing_strategy: Arc<dyn PricingStrategy>) {
+        self.pricing_strategy = Some(pricing_strategy);
+    }
+
     /// Set the registries used inside the metrics server.
     /// Configure the `metrics_server_port` to enable the metrics server.
     #[cfg(feature = "open-metrics")]
@@ -393,15 +501,38 @@ impl NetworkBuilder {
                     source: error,
                 });
             }
+
+            if self.migrate_record_store {
+                for from in [
+                    RecordStorageBackendKind::FlatFile,
+                    RecordStorageBackendKind::LogKv,
+                ] {
+                    migrate_record_store(&storage_dir_path, from, self.record_store_backend)
+                        .map_err(|source| NetworkError::FailedToOpenRecordStore {
+                            path: storage_dir_path.clone(),
+                            source,
+                        })?;
+                }
+            }
+
             NodeRecordStoreConfig {
                 max_value_bytes: MAX_PACKET_SIZE, // TODO, does this need to be _less_ than MAX_PACKET_SIZE
                 storage_dir: storage_dir_path,
                 historic_quote_dir: root_dir.clone(),
+                backend: self.record_store_backend,
+                max_storage_bytes: self.max_storage_bytes,
+                records_cache_bytes: self.records_cache_bytes,
                 ..Default::default()
             }
         };
 
-        let listen_addr = self.listen_addr;
+        let listen_addrs = self.listen_addrs.clone();
+        #[cfg(feature = "simulation")]
+        let simulated_listen_addr = self
+            .simulated_endpoint
+            .as_ref()
+            .map(SimulatedEndpoint::listen_addr);
+        let tcp = self.tcp;
         #[cfg(feature = "upnp")]
         let upnp = self.upnp;
 
@@ -414,27 +545,57 @@ impl NetworkBuilder {
             #[cfg(feature = "upnp")]
             upnp,
         )?;
+        swarm_driver.restore_peer_reputations(&root_dir);
 
-        // Listen on the provided address
-        let listen_socket_addr = listen_addr.ok_or(NetworkError::ListenAddressNotProvided)?;
+        #[cfg(feature = "simulation")]
+        if let Some(listen_addr) = simulated_listen_addr {
+            swarm_driver
+                .listen_on(listen_addr)
+                .expect("Multiaddr should be supported by our configured transports");
+            return Ok((network, events_receiver, swarm_driver));
+        }
 
-        // Listen on QUIC
-        let addr_quic = Multiaddr::from(listen_socket_addr.ip())
-            .with(Protocol::Udp(listen_socket_addr.port()))
-            .with(Protocol::QuicV1);
+        if listen_addrs.is_empty() {
+            return Err(NetworkError::ListenAddressNotProvided);
+        }
         swarm_driver
-            .listen_on(addr_quic)
-            .expect("Multiaddr should be supported by our configured transports");
-
-        // Listen on WebSocket
-        #[cfg(any(feature = "websockets", target_arch = "wasm32"))]
-        {
-            let addr_ws = Multiaddr::from(listen_socket_addr.ip())
-                .with(Protocol::Tcp(listen_socket_addr.port()))
-                .with(Protocol::Ws("/".into()));
+            .relay_manager
+            .set_local_transports(tcp, &listen_addrs);
+
+        // Listen on each of the provided addresses
+        for listen_socket_addr in listen_addrs {
+            // Listen on QUIC
+            let addr_quic = Multiaddr::from(listen_socket_addr.ip())
+                .with(Protocol::Udp(listen_socket_addr.port()))
+                .with(Protocol::QuicV1);
             swarm_driver
-                .listen_on(addr_ws)
+                .listen_on(addr_quic)
                 .expect("Multiaddr should be supported by our configured transports");
+
+            // Listen on TCP, the websocket listener holding the port if enabled
+            if tcp {
+                let tcp_port = if cfg!(feature = "websockets") {
+                    0
+                } else {
+                    listen_socket_addr.port()
+                };
+                let addr_tcp =
+                    Multiaddr::from(listen_socket_addr.ip()).with(Protocol::Tcp(tcp_port));
+                swarm_driver
+                    .listen_on(addr_tcp)
+                    .expect("Multiaddr should be supported by our configured transports");
+            }
+
+            // Listen on WebSocket
+            #[cfg(any(feature = "websockets", target_arch = "wasm32"))]
+            {
+                let addr_ws = Multiaddr::from(listen_socket_addr.ip())
+                    .with(Protocol::Tcp(listen_socket_addr.port()))
+                    .with(Protocol::Ws("/".into()));
+                swarm_driver
+                    .listen_on(addr_ws)
+                    .expect("Multiaddr should be supported by our configured transports");
+            }
         }
 
         Ok((network, events_receiver, swarm_driver))
@@ -494,9 +655,19 @@ impl NetworkBuilder {
 
         // ==== Transport ====
         #[cfg(feature = "open-metrics")]
-        let main_transport = transport::build_transport(&self.keypair, &mut metrics_registries);
+        let main_transport =
+            transport::build_transport(&self.keypair, self.tcp, &mut metrics_registries);
         #[cfg(not(feature = "open-metrics"))]
-        let main_transport = transport::build_transport(&self.keypair);
+        let main_transport = transport::build_transport(&self.keypair, self.tcp);
+        #[cfg(feature = "simulation")]
+        let main_transport = match &self.simulated_endpoint {
+            Some(endpoint) => endpoint.build_transport(&self.keypair),
+            None => main_transport,
+        };
+        let bandwidth_limiter = Arc::new(BandwidthLimiter::new(self.bandwidth_limits));
+        #[cfg(not(target_arch = "wasm32"))]
+        let main_transport =
+            crate::bandwidth::throttle_transport(main_transport, Arc::clone(&bandwidth_limiter));
         let transport = if !self.local {
             debug!("Preventing non-global dials");
             // Wrap upper in a transport that prevents dialing local addresses.
@@ -544,6 +715,7 @@ impl NetworkBuilder {
                 )]),
             );
 
+            metrics_recorder.record_bandwidth_usage(Arc::clone(&bandwidth_limiter));
             run_metrics_server(metrics_registries, port);
             Some(metrics_recorder)
         } else {
@@ -557,13 +729,14 @@ impl NetworkBuilder {
 
             info!(
                 "Building request response with {:?}",
-                REQ_RESPONSE_VERSION_STR.as_str()
+                REQ_RESPONSE_VERSION_STRS.as_slice()
             );
+            // The newest protocol comes first, so the highest version supported by both sides
+            // gets negotiated on each stream.
             request_response::cbor::Behaviour::new(
-                [(
-                    StreamProtocol::new(&REQ_RESPONSE_VERSION_STR),
-                    req_res_protocol,
-                )],
+                REQ_RESPONSE_VERSION_STRS
+                    .iter()
+                    .map(|protocol| (StreamProtocol::new(protocol), req_res_protocol.clone())),
                 cfg,
             )
         };
@@ -574,22 +747,33 @@ impl NetworkBuilder {
         let (local_swarm_cmd_sender, local_swarm_cmd_receiver) =
             mpsc::channel(NETWORKING_CHANNEL_SIZE);
 
+        let pricing_strategy = match &self.pricing_strategy {
+            Some(pricing_strategy) => Arc::clone(pricing_strategy),
+            None => default_pricing_strategy()?,
+        };
+
         // Kademlia Behaviour
         let kademlia = {
             match record_store_cfg {
-                Some(store_cfg) => {
+                Some(mut store_cfg) => {
+                    store_cfg.pricing_strategy = Arc::clone(&pricing_strategy);
                     let node_record_store = NodeRecordStore::with_config(
                         peer_id,
                         store_cfg,
                         network_event_sender.clone(),
                         local_swarm_cmd_sender.clone(),
-                    );
+                    )?;
                     #[cfg(feature = "open-metrics")]
                     let mut node_record_store = node_record_store;
                     #[cfg(feature = "open-metrics")]
                     if let Some(metrics_recorder) = &metrics_recorder {
                         node_record_store = node_record_store
-                            .set_record_count_metric(metrics_recorder.records_stored.clone());
+                            .set_record_count_metric(metrics_recorder.records_stored.clone())
+                            .set_records_cache_metrics(RecordsCacheMetrics {
+                                hits: metrics_recorder.records_cache_hits.clone(),
+                                misses: metrics_recorder.records_cache_misses.clone(),
+                                cached_bytes: metrics_recorder.records_cache_bytes.clone(),
+                            });
                     }
 
                     let store = UnifiedRecordStore::Node(node_record_store);
@@ -638,18 +822,8 @@ impl NetworkBuilder {
         }
         .into(); // Into `Toggle<T>`
 
-        let relay_server = {
-            let relay_server_cfg = relay::Config {
-                max_reservations: 128,             // Amount of peers we are relaying for
-                max_circuits: 1024, // The total amount of relayed connections at any given moment.
-                max_circuits_per_peer: 256, // Amount of relayed connections per peer (both dst and src)
-                circuit_src_rate_limiters: vec![], // No extra rate limiting for now
-                // We should at least be able to relay packets with chunks etc.
-                max_circuit_bytes: MAX_PACKET_SIZE as u64,
-                ..Default::default()
-            };
-            libp2p::relay::Behaviour::new(peer_id, relay_server_cfg)
-        };
+        let relay_server =
+            libp2p::relay::Behaviour::new(peer_id, self.relay_server_limits.to_config());
 
         let behaviour = NodeBehaviour {
             blocklist: libp2p::allow_block_list::Behaviour::default(),
@@ -674,7 +848,8 @@ impl NetworkBuilder {
         let swarm = Swarm::new(transport, behaviour, peer_id, swarm_config);
 
         let bootstrap = ContinuousBootstrap::new();
-        let replication_fetcher = ReplicationFetcher::new(peer_id, network_event_sender.clone());
+        let replication_fetcher =
+            ReplicationFetcher::new(peer_id, network_event_sender.clone(), bandwidth_limiter);
         let mut relay_manager = RelayManager::new(peer_id);
         if !is_client {
             relay_manager.enable_hole_punching(self.is_behind_home_network);
@@ -706,6 +881,7 @@ impl NetworkBuilder {
             pending_get_closest_peers: Default::default(),
             pending_requests: Default::default(),
             pending_get_record: Default::default(),
+            pending_record_validations: Default::default(),
             // We use 255 here which allows covering a network larger than 64k without any rotating.
             // This is based on the libp2p kad::kBuckets peers distribution.
             dialed_peers: CircularVec::new(255),
@@ -715,11 +891,17 @@ impl NetworkBuilder {
             handling_statistics: Default::default(),
             handled_times: 0,
             hard_disk_write_error: 0,
-            bad_nodes: Default::default(),
+            peer_reputations: Default::default(),
+            request_sent_at: Default::default(),
             quotes_history: Default::default(),
             replication_targets: Default::default(),
+            pending_replication_summaries: Default::default(),
+            legacy_replication_peers: Default::default(),
+            peer_protocol_versions: Default::default(),
             range_distances: VecDeque::with_capacity(GET_RANGE_STORAGE_LIMIT),
             first_contact_made: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            bootstrap_cache: self.bootstrap_cache,
         };
 
         let network = Network::new(
@@ -727,6 +909,7 @@ impl NetworkBuilder {
             local_swarm_cmd_sender,
             peer_id,
             self.keypair,
+            pricing_strategy,
         );
 
         Ok((network, network_event_receiver, swarm_driver))
@@ -801,6 +984,9 @@ pub struct SwarmDriver {
     pub(crate) pending_requests:
         HashMap<OutboundRequestId, Option<oneshot::Sender<Result<Response>>>>,
     pub(crate) pending_get_record: PendingGetRecord,
+    /// The number of fetched copies being validated off the event loop for each GetRecord query,
+    /// along with the step the query finished at, if it did before they all came back.
+    pub(crate) pending_record_validations: HashMap<QueryId, (usize, Option<ProgressStep>)>,
     /// A list of the most recent peers we have dialed ourselves. Old dialed peers are evicted once the vec fills up.
     pub(crate) dialed_peers: CircularVec<PeerId>,
     // A list of random `PeerId` candidates that falls into kbuckets,
@@ -814,9 +1000,18 @@ pub struct SwarmDriver {
     handling_statistics: BTreeMap<String, Vec<Duration>>,
     handled_times: usize,
     pub(crate) hard_disk_write_error: usize,
-    pub(crate) bad_nodes: BadNodes,
+    pub(crate) peer_reputations: PeerReputations,
+    /// When the pending requests were sent, to rate the peers on their response latency.
+    pub(crate) request_sent_at: HashMap<OutboundRequestId, Instant>,
     pub(crate) quotes_history: BTreeMap<PeerId, PaymentQuote>,
     pub(crate) replication_targets: BTreeMap<PeerId, Instant>,
+    /// Replication summaries sent and awaiting the differing buckets, with the range and bucket bits used.
+    pub(crate) pending_replication_summaries: HashMap<OutboundRequestId, (Option<u32>, u8)>,
+    /// Peers that failed to handle a replication summary, likely on an older version.
+    /// They are sent the full replication list until the entry expires.
+    pub(crate) legacy_replication_peers: BTreeMap<PeerId, Instant>,
+    /// The wire protocol version negotiated with the connected peers, through Identify.
+    pub(crate) peer_protocol_versions: HashMap<PeerId, u16>,
 
     // The recent range_distances calculated by the node
     // Each update is generated when there is a routing table change
@@ -824,6 +1019,9 @@ pub struct SwarmDriver {
     pub(crate) range_distances: VecDeque<KBucketDistance>,
     // have we found out initial peer
     pub(crate) first_contact_made: bool,
+    /// The peers reached, or not, pending to be merged into the bootstrap cache file.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) bootstrap_cache: Option<BootstrapCache>,
 }
 
 impl SwarmDriver {
@@ -838,6 +1036,13 @@ impl SwarmDriver {
         let mut bootstrap_interval = interval(BOOTSTRAP_INTERVAL);
         let mut set_farthest_record_interval = interval(CLOSET_RECORD_CHECK_INTERVAL);
         let mut relay_manager_reservation_interval = interval(RELAY_MANAGER_RESERVATION_INTERVAL);
+        let mut record_scrub_interval = interval(RECORD_SCRUB_INTERVAL);
+        let mut peer_reputations_interval = interval(PEER_REPUTATIONS_MAINTENANCE_INTERVAL);
+        let mut bootstrap_cache_sync_interval = interval(if self.is_client {
+            CLIENT_BOOTSTRAP_CACHE_SYNC_INTERVAL
+        } else {
+            BOOTSTRAP_CACHE_SYNC_INTERVAL
+        });
 
         loop {
             tokio::select! {
@@ -894,11 +1099,97 @@ impl SwarmDriver {
 
                     }
                 }
-                _ = relay_manager_reservation_interval.tick() => self.relay_manager.try_connecting_to_relay(&mut self.swarm, &self.bad_nodes),
+                _ = relay_manager_reservation_interval.tick() => self.relay_manager.try_connecting_to_relay(&mut self.swarm, &self.peer_reputations),
+                _ = record_scrub_interval.tick() => {
+                    if !self.is_client {
+                        self.swarm.behaviour_mut().kademlia.store_mut().scrub_next_batch();
+                    }
+                }
+                _ = peer_reputations_interval.tick() => self.maintain_peer_reputations(),
+                _ = bootstrap_cache_sync_interval.tick() => {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    self.sync_bootstrap_cache();
+                }
             }
         }
     }
 
+    /// Restores the reputations persisted under the root dir, blocking the peers still bad.
+    fn restore_peer_reputations(&mut self, root_dir: &Path) {
+        self.peer_reputations = PeerReputations::load(root_dir);
+        for peer_id in self.peer_reputations.bad_peers(SystemTime::now()) {
+            self.swarm.behaviour_mut().blocklist.block_peer(peer_id);
+        }
+    }
+
+    /// Forgets the peers whose score decayed back to neutral, unblocks the bad peers
+    /// that served their time and persists the reputations if they changed, off the event loop.
+    fn maintain_peer_reputations(&mut self) {
+        for peer_id in self.peer_reputations.prune(SystemTime::now()) {
+            info!("Peer {peer_id:?} is no longer considered as bad, unblocking it.");
+            self.swarm.behaviour_mut().blocklist.unblock_peer(peer_id);
+        }
+        let Some(snapshot) = self.peer_reputations.take_snapshot() else {
+            return;
+        };
+        let _handle = tokio::task::spawn_blocking(move || {
+            if let Err(err) = snapshot.write() {
+                warn!(
+                    "Failed to persist the peer reputations to {:?}: {err:?}",
+                    snapshot.path()
+                );
+            }
+        });
+    }
+
+    /// Records the addresses of a peer added to the routing table as reachable in the bootstrap
+    /// cache. The relayed and, unless running a local network, non-global addresses are skipped.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn record_reachable_peer(&mut self, peer: PeerId, addresses: Vec<Multiaddr>) {
+        let local = self.local;
+        let Some(bootstrap_cache) = self.bootstrap_cache.as_mut() else {
+            return;
+        };
+        for addr in addresses {
+            let is_relayed = addr.iter().any(|p| matches!(p, Protocol::P2pCircuit));
+            if is_relayed || (!local && !multiaddr_is_global(&addr)) {
+                continue;
+            }
+            bootstrap_cache.record_success(multiaddr_strip_p2p(&addr).with(Protocol::P2p(peer)));
+        }
+    }
+
+    /// Records the addresses a peer could not be dialed on in the bootstrap cache.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn record_unreachable_peer(&mut self, peer: PeerId, addresses: &[Multiaddr]) {
+        if let Some(bootstrap_cache) = self.bootstrap_cache.as_mut() {
+            for addr in addresses {
+                bootstrap_cache.record_failure(multiaddr_strip_p2p(addr).with(Protocol::P2p(peer)));
+            }
+        }
+    }
+
+    /// Merges the peers reached, or not, since the last sync into the bootstrap cache file, off
+    /// the event loop as it blocks on the file lock.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn sync_bootstrap_cache(&mut self) {
+        let Some(bootstrap_cache) = self.bootstrap_cache.as_mut() else {
+            return;
+        };
+        if !bootstrap_cache.has_pending_updates() {
+            return;
+        }
+        let mut updates = bootstrap_cache.take_pending_updates();
+        let _handle = tokio::task::spawn_blocking(move || {
+            if let Err(err) = updates.sync() {
+                warn!(
+                    "Failed to sync the bootstrap cache at {:?}: {err}",
+                    updates.path()
+                );
+            }
+        });
+    }
+
     // --------------------------------------------
     // ---------- Crate helpers -------------------
     // --------------------------------------------
@@ -123,6 +123,12 @@ pub enum NetworkError {
     #[error("Record not stored by nodes, it could be invalid, else you should retry: {0:?}")]
     RecordNotStoredByNodes(NetworkAddress),
 
+    #[error("Record fetched for {key:?} is invalid: {reason}")]
+    InvalidRecord {
+        key: PrettyPrintRecordKey<'static>,
+        reason: String,
+    },
+
     // The RecordKind that was obtained did not match with the expected one
     #[error("The RecordKind obtained from the Record did not match with the expected kind: {0}")]
     RecordKindMismatch(RecordKind),
@@ -153,6 +159,12 @@ pub enum NetworkError {
         source: std::io::Error,
     },
 
+    #[error("Could not open record store at: {path:?}, error: {source}")]
+    FailedToOpenRecordStore {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
     // ---------- Internal Network Errors
     #[error("Could not get enough peers ({required}) to satisfy the request, found {found}")]
     NotEnoughPeers { found: usize, required: usize },
@@ -188,6 +200,16 @@ pub enum NetworkError {
 
     #[error("Register already exists at this address")]
     RegisterAlreadyExists,
+
+    #[error("Invalid pricing strategy: {0}")]
+    InvalidPricingStrategy(String),
+
+    #[error("{peer:?} speaks protocol version {peer_version}, while the request is only supported since version {required}")]
+    RequestUnsupportedByPeer {
+        peer: PeerId,
+        peer_version: u16,
+        required: u16,
+    },
 }
 
 #[cfg(test)]
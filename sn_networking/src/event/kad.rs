@@ -7,8 +7,13 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::{
-    cmd::NetworkSwarmCmd, driver::PendingGetClosestType, get_quorum_value, target_arch::Instant,
-    GetRecordCfg, GetRecordError, NetworkError, Result, SwarmDriver, CLOSE_GROUP_SIZE,
+    cmd::{LocalSwarmCmd, NetworkSwarmCmd},
+    driver::PendingGetClosestType,
+    get_quorum_value,
+    record_validation::validate_fetched_record,
+    send_local_swarm_cmd,
+    target_arch::Instant,
+    GetRecordCfg, GetRecordError, NetworkError, NodeIssue, Result, SwarmDriver, CLOSE_GROUP_SIZE,
 };
 use itertools::Itertools;
 use libp2p::{
@@ -260,18 +265,26 @@ impl SwarmDriver {
                 peer,
                 is_new_peer,
                 old_peer,
+                addresses,
                 ..
             } => {
                 event_string = "kad_event::RoutingUpdated";
                 if is_new_peer {
                     self.update_on_peer_addition(peer);
                 }
+                #[cfg(not(target_arch = "wasm32"))]
+                self.record_reachable_peer(peer, addresses.into_vec());
+                #[cfg(target_arch = "wasm32")]
+                let _ = addresses;
 
                 if !self.first_contact_made {
                     // This should only happen once
                     self.first_contact_made = true;
                     info!("Performing the first bootstrap");
                     self.trigger_network_discovery();
+                    // record the first peer reached right away, in case we don't run for long
+                    #[cfg(not(target_arch = "wasm32"))]
+                    self.sync_bootstrap_cache();
                 }
 
                 info!("kad_event::RoutingUpdated {:?}: {peer:?}, is_new_peer: {is_new_peer:?} old_peer: {old_peer:?}", self.peers_in_rt);
@@ -355,6 +368,89 @@ impl SwarmDriver {
     //
     //
 
+    /// Hands a copy received for a GetRecord query over to be validated on a blocking thread, as
+    /// verifying the signatures of registers and spends would otherwise hold up the event loop.
+    /// The outcome comes back through `LocalSwarmCmd::GetRecordCopyValidated`.
+    fn accumulate_get_record_found(
+        &mut self,
+        query_id: QueryId,
+        peer_record: PeerRecord,
+    ) -> Result<()> {
+        let Some((requested_key, ..)) = self.pending_get_record.get(&query_id) else {
+            // return error if the entry cannot be found
+            return Err(NetworkError::ReceivedKademliaEventDropped {
+                query_id,
+                event: format!(
+                    "Accumulate Get Record of {:?}",
+                    PrettyPrintRecordKey::from(&peer_record.record.key)
+                ),
+            });
+        };
+        let requested_key = requested_key.clone();
+
+        let (validating, _) = self.pending_record_validations.entry(query_id).or_default();
+        *validating += 1;
+
+        let cmd_sender = self.local_cmd_sender.clone();
+        let _handle = tokio::task::spawn_blocking(move || {
+            let result = validate_fetched_record(&requested_key, &peer_record.record);
+            send_local_swarm_cmd(
+                cmd_sender,
+                LocalSwarmCmd::GetRecordCopyValidated {
+                    query_id,
+                    peer_record,
+                    result,
+                },
+            );
+        });
+
+        Ok(())
+    }
+
+    /// Accumulates a validated copy into the GetRecord query results, or drops an invalid one and
+    /// reports its holder, leaving the query to carry on with the remaining close peers.
+    /// A query that finished while its copies were being validated is completed once the last
+    /// of them is accumulated.
+    pub(crate) fn handle_get_record_copy_validated(
+        &mut self,
+        query_id: QueryId,
+        peer_record: PeerRecord,
+        result: Result<()>,
+    ) -> Result<()> {
+        let finished_step = match self.pending_record_validations.entry(query_id) {
+            Entry::Occupied(mut entry) => {
+                let (validating, _) = entry.get_mut();
+                *validating = validating.saturating_sub(1);
+                if *validating == 0 {
+                    entry.remove().1
+                } else {
+                    None
+                }
+            }
+            Entry::Vacant(_) => None,
+        };
+
+        let accumulated = match result {
+            Ok(()) => self.accumulate_valid_record(query_id, peer_record),
+            Err(err) => {
+                let peer_id = peer_record.peer.unwrap_or(self.self_peer_id);
+                warn!(
+                    "For record {:?} task {query_id:?}, discarding the copy from {peer_id:?}: {err}",
+                    PrettyPrintRecordKey::from(&peer_record.record.key)
+                );
+                if peer_id != self.self_peer_id {
+                    self.record_node_issue(peer_id, NodeIssue::InvalidRecordServed);
+                }
+                Ok(())
+            }
+        };
+
+        if let Some(step) = finished_step {
+            self.handle_get_record_finished(query_id, step)?;
+        }
+        accumulated
+    }
+
     /// Accumulates the GetRecord query results
     /// If we get enough responses (ie exceed GetRange) for a record with the same content hash:
     /// - we return the Record after comparing with the target record. This might return RecordDoesNotMatch if the
@@ -362,7 +458,7 @@ impl SwarmDriver {
     /// - if multiple content hashes are found, we return a SplitRecord Error
     ///   And then we stop the kad query as we are done here.
     ///   We do not need to wait for GetRange to be exceeded here and should return early.
-    fn accumulate_get_record_found(
+    fn accumulate_valid_record(
         &mut self,
         query_id: QueryId,
         peer_record: PeerRecord,
@@ -493,6 +589,14 @@ impl SwarmDriver {
     /// NotEnoughCopies if there is only a single content hash version.
     /// SplitRecord if there are multiple content hash versions.
     fn handle_get_record_finished(&mut self, query_id: QueryId, step: ProgressStep) -> Result<()> {
+        if let Some((validating, finished_step)) =
+            self.pending_record_validations.get_mut(&query_id)
+        {
+            debug!("Getting record task {query_id:?} completed, waiting on {validating} copies being validated before returning.");
+            *finished_step = Some(step);
+            return Ok(());
+        }
+
         // return error if the entry cannot be found
         if let Some((r_key, senders, result_map, cfg)) = self.pending_get_record.remove(&query_id) {
             let num_of_versions = result_map.len();
@@ -651,6 +755,9 @@ impl SwarmDriver {
         _stats: QueryStats,
         _step: ProgressStep,
     ) -> Result<()> {
+        // the copies still being validated have no query to be accumulated into any longer
+        let _ = self.pending_record_validations.remove(&query_id);
+
         match &get_record_err {
             kad::GetRecordError::NotFound { .. } | kad::GetRecordError::QuorumFailed { .. } => {
                 // return error if the entry cannot be found
@@ -747,3 +854,94 @@ impl SwarmDriver {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NetworkBuilder;
+    use bytes::Bytes;
+    use libp2p::identity::Keypair;
+    use sn_protocol::storage::{try_serialize_record, Chunk, RecordKind};
+    use std::time::SystemTime;
+
+    #[tokio::test]
+    async fn invalid_copies_are_discarded_and_their_peer_reported() -> eyre::Result<()> {
+        let (_network, _events, mut driver) =
+            NetworkBuilder::new(Keypair::generate_ed25519(), false).build_client()?;
+
+        let chunk = Chunk::new(Bytes::from_static(b"requested chunk"));
+        let key = chunk.network_address().to_record_key();
+        let cfg = GetRecordCfg {
+            get_quorum: Quorum::One,
+            retry_strategy: None,
+            target_record: None,
+            expected_holders: Default::default(),
+            is_register: false,
+        };
+        let query_id = driver
+            .swarm
+            .behaviour_mut()
+            .kademlia
+            .get_record(key.clone());
+        let _ = driver
+            .pending_get_record
+            .insert(query_id, (key.clone(), vec![], Default::default(), cfg));
+
+        // A peer serving other content under the requested key.
+        let forged = Chunk::new(Bytes::from_static(b"forged chunk"));
+        let forged_record = Record::new(
+            key.clone(),
+            try_serialize_record(&forged, RecordKind::Chunk)?.to_vec(),
+        );
+        let forger = PeerId::random();
+        let forged_copy = PeerRecord {
+            peer: Some(forger),
+            record: forged_record,
+        };
+        driver.accumulate_get_record_found(query_id, forged_copy.clone())?;
+        assert_eq!(driver.pending_record_validations[&query_id].0, 1);
+        driver.handle_get_record_copy_validated(
+            query_id,
+            forged_copy.clone(),
+            validate_fetched_record(&key, &forged_copy.record),
+        )?;
+        assert!(!driver.pending_record_validations.contains_key(&query_id));
+
+        let (_, _, result_map, _) = &driver.pending_get_record[&query_id];
+        assert!(result_map.is_empty(), "The forged copy should be discarded");
+        assert!(driver.peer_reputations.score(&forger, SystemTime::now()) < 0.0);
+
+        let record = Record::new(
+            key.clone(),
+            try_serialize_record(&chunk, RecordKind::Chunk)?.to_vec(),
+        );
+        let holder = PeerId::random();
+        let copy = PeerRecord {
+            peer: Some(holder),
+            record,
+        };
+        driver.accumulate_get_record_found(query_id, copy.clone())?;
+        let (_, _, result_map, _) = &driver.pending_get_record[&query_id];
+        assert!(
+            result_map.is_empty(),
+            "The copy should only be accumulated once validated"
+        );
+        driver.handle_get_record_copy_validated(
+            query_id,
+            copy.clone(),
+            validate_fetched_record(&key, &copy.record),
+        )?;
+
+        let (_, _, result_map, _) = &driver.pending_get_record[&query_id];
+        assert_eq!(result_map.len(), 1);
+        assert!(result_map
+            .values()
+            .all(|(_, peers)| peers == &HashSet::from([holder])));
+        assert_eq!(
+            driver.peer_reputations.score(&holder, SystemTime::now()),
+            0.0
+        );
+
+        Ok(())
+    }
+}
@@ -10,13 +10,17 @@ mod kad;
 mod request_response;
 mod swarm;
 
-use crate::{driver::SwarmDriver, error::Result};
+use crate::{
+    driver::SwarmDriver,
+    error::Result,
+    network_size::{self, NetworkSizeEstimate},
+};
 use core::fmt;
 use custom_debug::Debug as CustomDebug;
 #[cfg(feature = "local")]
 use libp2p::mdns;
 use libp2p::{
-    kad::{Record, RecordKey, K_VALUE},
+    kad::{KBucketKey, Record, RecordKey, K_VALUE},
     request_response::ResponseChannel as PeerResponseChannel,
     Multiaddr, PeerId,
 };
@@ -114,6 +118,8 @@ pub enum NetworkEvent {
     QueryRequestReceived {
         /// Query
         query: Query,
+        /// The peer the query came from
+        peer: PeerId,
         /// The channel to send the `Response` through
         channel: MsgResponder,
     },
@@ -305,19 +311,10 @@ impl SwarmDriver {
         let mut index = 0;
         let mut total_peers = 0;
 
-        let mut peers_in_non_full_buckets = 0;
-        let mut num_of_full_buckets = 0;
-
         for kbucket in self.swarm.behaviour_mut().kademlia.kbuckets() {
             let range = kbucket.range();
             let num_entires = kbucket.num_entries();
 
-            if num_entires >= K_VALUE.get() {
-                num_of_full_buckets += 1;
-            } else {
-                peers_in_non_full_buckets += num_entires;
-            }
-
             total_peers += num_entires;
             if let Some(distance) = range.0.ilog2() {
                 kbucket_table_stats.push((index, num_entires, distance));
@@ -328,13 +325,12 @@ impl SwarmDriver {
             index += 1;
         }
 
-        let estimated_network_size =
-            Self::estimate_network_size(peers_in_non_full_buckets, num_of_full_buckets);
+        let estimated_network_size = self.estimate_network_size().estimated_size;
         #[cfg(feature = "open-metrics")]
         if let Some(metrics_recorder) = &self.metrics_recorder {
             let _ = metrics_recorder
                 .estimated_network_size
-                .set(estimated_network_size as i64);
+                .set(estimated_network_size.try_into().unwrap_or(i64::MAX));
         }
 
         // Just to warn if our tracking goes out of sync with libp2p. Can happen if someone forgets to call
@@ -352,11 +348,23 @@ impl SwarmDriver {
         println!("Estimated network size: {estimated_network_size:?}");
     }
 
-    /// Estimate the number of nodes in the network
-    fn estimate_network_size(
-        peers_in_non_full_buckets: usize,
-        num_of_full_buckets: usize,
-    ) -> usize {
-        (peers_in_non_full_buckets + 1) * (2_usize.pow(num_of_full_buckets as u32))
+    /// Estimate the number of nodes in the network, from the density of our k-buckets and the
+    /// distances to our closest peers.
+    pub(crate) fn estimate_network_size(&mut self) -> NetworkSizeEstimate {
+        let self_key = KBucketKey::from(self.self_peer_id);
+        let kademlia = &mut self.swarm.behaviour_mut().kademlia;
+        let buckets = kademlia
+            .kbuckets()
+            .filter_map(|kbucket| {
+                let ilog2 = kbucket.range().0.ilog2()?;
+                Some((ilog2, kbucket.num_entries()))
+            })
+            .collect::<Vec<_>>();
+        let closest_peers = kademlia
+            .get_closest_local_peers(&self_key)
+            .take(K_VALUE.get())
+            .collect::<Vec<_>>();
+
+        network_size::estimate_network_size(&self_key, &buckets, &closest_peers)
     }
 }
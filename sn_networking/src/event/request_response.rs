@@ -7,17 +7,18 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::{
-    cmd::NetworkSwarmCmd, log_markers::Marker, sort_peers_by_address_and_limit, MsgResponder,
-    NetworkError, NetworkEvent, SwarmDriver, CLOSE_GROUP_SIZE,
+    cmd::NetworkSwarmCmd, log_markers::Marker, reputation::ReputationEvent,
+    sort_peers_by_address_and_limit, target_arch::SystemTime, MsgResponder, NetworkError,
+    NetworkEvent, SwarmDriver, CLOSE_GROUP_SIZE,
 };
 use libp2p::{
     kad::RecordKey,
-    request_response::{self, Message},
+    request_response::{self, Message, OutboundFailure, OutboundRequestId},
     PeerId,
 };
 use rand::{rngs::OsRng, Rng};
 use sn_protocol::{
-    messages::{CmdResponse, Request, Response},
+    messages::{CmdResponse, ReplicationSummary, Request, Response},
     storage::RecordType,
     NetworkAddress,
 };
@@ -54,6 +55,20 @@ impl SwarmDriver {
 
                             self.add_keys_to_replication_fetcher(holder, keys);
                         }
+                        Request::Cmd(sn_protocol::messages::Cmd::ReplicateSummary {
+                            holder,
+                            summary,
+                        }) => {
+                            let buckets = self.mismatched_replication_buckets(holder, &summary);
+                            let response = Response::Cmd(
+                                sn_protocol::messages::CmdResponse::ReplicateSummary(Ok(buckets)),
+                            );
+
+                            self.queue_network_swarm_cmd(NetworkSwarmCmd::SendResponse {
+                                resp: response,
+                                channel: MsgResponder::FromPeer(channel),
+                            });
+                        }
                         Request::Cmd(sn_protocol::messages::Cmd::QuoteVerification {
                             quotes,
                             ..
@@ -114,6 +129,7 @@ impl SwarmDriver {
                         Request::Query(query) => {
                             self.send_event(NetworkEvent::QueryRequestReceived {
                                 query,
+                                peer,
                                 channel: MsgResponder::FromPeer(channel),
                             })
                         }
@@ -124,6 +140,22 @@ impl SwarmDriver {
                     response,
                 } => {
                     debug!("Got response {request_id:?} from peer {peer:?}, res: {response}.");
+                    self.rate_request_outcome(peer, request_id, true);
+                    if let Some((range, bucket_bits)) =
+                        self.pending_replication_summaries.remove(&request_id)
+                    {
+                        let _ = self.pending_requests.remove(&request_id);
+                        match response {
+                            Response::Cmd(CmdResponse::ReplicateSummary(Ok(buckets))) => {
+                                self.send_replication_buckets(peer, range, bucket_bits, buckets);
+                            }
+                            other => {
+                                warn!("Unexpected response to a replication summary from {peer:?}: {other:?}");
+                            }
+                        }
+                        return Ok(());
+                    }
+
                     if let Some(sender) = self.pending_requests.remove(&request_id) {
                         // The sender will be provided if the caller (Requester) is awaiting for a response
                         // at the call site.
@@ -158,6 +190,22 @@ impl SwarmDriver {
                 error,
                 peer,
             } => {
+                self.rate_request_outcome(peer, request_id, false);
+                if self
+                    .pending_replication_summaries
+                    .remove(&request_id)
+                    .is_some()
+                {
+                    let _ = self.pending_requests.remove(&request_id);
+                    // Peers on an older version don't support the summary, other failures are transient
+                    if matches!(error, OutboundFailure::UnsupportedProtocols) {
+                        self.fallback_to_replication_list(peer);
+                    } else {
+                        warn!("Failed to send the replication summary to {peer:?}: {error:?}");
+                    }
+                    return Ok(());
+                }
+
                 if let Some(sender) = self.pending_requests.remove(&request_id) {
                     match sender {
                         Some(sender) => {
@@ -189,6 +237,68 @@ impl SwarmDriver {
         Ok(())
     }
 
+    /// Rates the peer on whether, and how fast, it answered one of our requests.
+    fn rate_request_outcome(
+        &mut self,
+        peer: PeerId,
+        request_id: OutboundRequestId,
+        succeeded: bool,
+    ) {
+        let Some(sent_at) = self.request_sent_at.remove(&request_id) else {
+            return;
+        };
+        let event = if succeeded {
+            ReputationEvent::RequestSucceeded(sent_at.elapsed())
+        } else {
+            ReputationEvent::RequestFailed
+        };
+        let _ = self.peer_reputations.record(peer, event, SystemTime::now());
+    }
+
+    /// Compare a replication summary against our keys, returning the buckets we need the keys of.
+    ///
+    /// Summaries from peers we don't accept replication from get an empty answer,
+    /// so the holder doesn't follow up with any keys.
+    fn mismatched_replication_buckets(
+        &mut self,
+        sender: NetworkAddress,
+        summary: &ReplicationSummary,
+    ) -> Vec<u32> {
+        let peers = self.get_all_local_peers_excluding_self();
+        let Some(holder) = sender.as_peer_id() else {
+            warn!("Replication summary sender is not a peer_id {sender:?}");
+            return vec![];
+        };
+
+        if !peers.contains(&holder) || holder == self.self_peer_id {
+            trace!("Holder {holder:?} is self or not in replication range.");
+            return vec![];
+        }
+
+        let all_keys = self
+            .swarm
+            .behaviour_mut()
+            .kademlia
+            .store_mut()
+            .record_addresses_ref();
+
+        let buckets = summary.mismatched_buckets(&sender, all_keys.values());
+        debug!(
+            "Received replication summary from {holder:?} of {} keys, {} of its {} buckets differ from ours",
+            summary.keys_count(),
+            buckets.len(),
+            summary.buckets.len()
+        );
+
+        // Without differing buckets, no replication list follows to trigger the holder verification
+        if buckets.is_empty() && summary.keys_count() > 1 && OsRng.gen_bool(0.1) {
+            let all_keys = all_keys.clone();
+            self.spawn_replication_holder_verification(sender, peers, all_keys);
+        }
+
+        buckets
+    }
+
     fn add_keys_to_replication_fetcher(
         &mut self,
         sender: NetworkAddress,
@@ -241,61 +351,74 @@ impl SwarmDriver {
             self.send_event(NetworkEvent::KeysToFetchForReplication(keys_to_fetch));
         }
 
-        let event_sender = self.event_sender.clone();
         if more_than_one_key && OsRng.gen_bool(0.1) {
-            let _handle = tokio::spawn(async move {
-                // Only run 10% of the time
-                let keys_to_verify =
-                    Self::select_verification_data_candidates(&peers, &all_keys, &sender);
-
-                if keys_to_verify.is_empty() {
-                    debug!("No valid candidate to be checked against peer {holder:?}");
-                } else if let Err(error) = event_sender
-                    .send(NetworkEvent::ChunkProofVerification {
-                        peer_id: holder,
-                        keys_to_verify,
-                    })
-                    .await
-                {
-                    error!("SwarmDriver failed to send event: {}", error);
-                }
+            self.spawn_replication_holder_verification(sender, peers, all_keys);
+        }
+    }
 
-                // In additon to verify the sender, we also verify a random close node.
-                // This is to avoid malicious node escaping the check by never send a replication_list.
-                // With further reduced probability of 1% (5% * 20%)
-                let close_group_peers = sort_peers_by_address_and_limit(
-                    &peers,
-                    &NetworkAddress::from_peer(our_peer_id),
-                    CLOSE_GROUP_SIZE,
-                )
-                .unwrap_or_default();
-
-                loop {
-                    let index: usize = OsRng.gen_range(0..close_group_peers.len());
-                    let candidate_peer_id = *close_group_peers[index];
-                    let candidate = NetworkAddress::from_peer(*close_group_peers[index]);
-                    if sender != candidate {
-                        let keys_to_verify = Self::select_verification_data_candidates(
-                            &peers, &all_keys, &candidate,
-                        );
-
-                        if keys_to_verify.is_empty() {
-                            debug!("No valid candidate to be checked against peer {candidate:?}");
-                        } else if let Err(error) = event_sender
-                            .send(NetworkEvent::ChunkProofVerification {
-                                peer_id: candidate_peer_id,
-                                keys_to_verify,
-                            })
-                            .await
-                        {
-                            error!("SwarmDriver failed to send event: {}", error);
-                        }
+    /// Verify the holder of a replication list, and a random close node, do hold the chunks they should
+    fn spawn_replication_holder_verification(
+        &self,
+        sender: NetworkAddress,
+        peers: Vec<PeerId>,
+        all_keys: HashMap<RecordKey, (NetworkAddress, RecordType)>,
+    ) {
+        let Some(holder) = sender.as_peer_id() else {
+            return;
+        };
+        let our_peer_id = self.self_peer_id;
+        let event_sender = self.event_sender.clone();
+        let _handle = tokio::spawn(async move {
+            // Only run 10% of the time
+            let keys_to_verify =
+                Self::select_verification_data_candidates(&peers, &all_keys, &sender);
+
+            if keys_to_verify.is_empty() {
+                debug!("No valid candidate to be checked against peer {holder:?}");
+            } else if let Err(error) = event_sender
+                .send(NetworkEvent::ChunkProofVerification {
+                    peer_id: holder,
+                    keys_to_verify,
+                })
+                .await
+            {
+                error!("SwarmDriver failed to send event: {}", error);
+            }
+
+            // In additon to verify the sender, we also verify a random close node.
+            // This is to avoid malicious node escaping the check by never send a replication_list.
+            // With further reduced probability of 1% (5% * 20%)
+            let close_group_peers = sort_peers_by_address_and_limit(
+                &peers,
+                &NetworkAddress::from_peer(our_peer_id),
+                CLOSE_GROUP_SIZE,
+            )
+            .unwrap_or_default();
 
-                        break;
+            loop {
+                let index: usize = OsRng.gen_range(0..close_group_peers.len());
+                let candidate_peer_id = *close_group_peers[index];
+                let candidate = NetworkAddress::from_peer(*close_group_peers[index]);
+                if sender != candidate {
+                    let keys_to_verify =
+                        Self::select_verification_data_candidates(&peers, &all_keys, &candidate);
+
+                    if keys_to_verify.is_empty() {
+                        debug!("No valid candidate to be checked against peer {candidate:?}");
+                    } else if let Err(error) = event_sender
+                        .send(NetworkEvent::ChunkProofVerification {
+                            peer_id: candidate_peer_id,
+                            keys_to_verify,
+                        })
+                        .await
+                    {
+                        error!("SwarmDriver failed to send event: {}", error);
                     }
+
+                    break;
                 }
-            });
-        }
+            }
+        });
     }
 
     /// Check among all chunk type records that we have, select those close to the peer,
@@ -7,8 +7,12 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::{
-    event::NodeEvent, multiaddr_is_global, multiaddr_strip_p2p, relay_manager::is_a_relayed_peer,
-    target_arch::Instant, NetworkEvent, Result, SwarmDriver,
+    event::NodeEvent,
+    log_markers::Marker,
+    multiaddr_is_global, multiaddr_strip_p2p,
+    relay_manager::is_a_relayed_peer,
+    target_arch::{Instant, SystemTime},
+    NetworkEvent, Result, SwarmDriver,
 };
 #[cfg(feature = "local")]
 use libp2p::mdns;
@@ -23,7 +27,9 @@ use libp2p::{
     },
     Multiaddr, PeerId, TransportError,
 };
-use sn_protocol::version::{IDENTIFY_NODE_VERSION_STR, IDENTIFY_PROTOCOL_STR};
+use sn_protocol::version::{
+    is_node_agent_version, negotiate_protocol_version, IDENTIFY_PROTOCOL_STR,
+};
 use std::collections::HashSet;
 use tokio::time::Duration;
 
@@ -64,6 +70,7 @@ impl SwarmDriver {
                 {
                     self.relay_manager
                         .on_successful_reservation_by_client(&relay_peer_id, &mut self.swarm);
+                    self.record_relay_metrics();
                 }
             }
             #[cfg(feature = "upnp")]
@@ -105,6 +112,7 @@ impl SwarmDriver {
                     }
                     _ => {}
                 }
+                self.record_relay_metrics();
             }
             SwarmEvent::Behaviour(NodeEvent::Identify(iden)) => {
                 // Record the Identify event for metrics if the feature is enabled.
@@ -122,8 +130,12 @@ impl SwarmDriver {
                     } => {
                         debug!(conn_id=%connection_id, %peer_id, ?info, "identify: received info");
 
-                        if info.protocol_version != IDENTIFY_PROTOCOL_STR.to_string() {
-                            warn!(?info.protocol_version, "identify: {peer_id:?} does not have the same protocol. Our IDENTIFY_PROTOCOL_STR: {:?}", IDENTIFY_PROTOCOL_STR.as_str());
+                        let Some(protocol_version) = negotiate_protocol_version(
+                            &info.protocol_version,
+                            &info.agent_version,
+                            info.protocols.iter().map(AsRef::as_ref),
+                        ) else {
+                            warn!(?info.protocol_version, "identify: {peer_id:?} does not support any of our protocol versions. Our IDENTIFY_PROTOCOL_STR: {:?}", IDENTIFY_PROTOCOL_STR.as_str());
 
                             self.send_event(NetworkEvent::PeerWithUnsupportedProtocol {
                                 our_protocol: IDENTIFY_PROTOCOL_STR.to_string(),
@@ -139,10 +151,14 @@ impl SwarmDriver {
                             }
 
                             return Ok(());
-                        }
+                        };
+                        debug!("identify: negotiated protocol version {protocol_version} with {peer_id:?}");
+                        let _ = self
+                            .peer_protocol_versions
+                            .insert(peer_id, protocol_version);
 
                         // if client, return.
-                        if info.agent_version != IDENTIFY_NODE_VERSION_STR.to_string() {
+                        if !is_node_agent_version(&info.agent_version) {
                             return Ok(());
                         }
 
@@ -200,9 +216,9 @@ impl SwarmDriver {
                                         .iter()
                                         .any(|entry| entry.node.key.preimage() == &peer_id);
 
-                                    // If the bucket contains any of a bootstrap node,
-                                    // consider the bucket is not full and dial back
-                                    // so that the bootstrap nodes can be replaced.
+                                    // If the bucket contains any of a bootstrap node, or a peer
+                                    // with a poorer reputation, consider the bucket is not full
+                                    // and dial back so that they can be replaced.
                                     if is_bucket_full {
                                         if let Some(peers) = self.bootstrap_peers.get(&ilog2) {
                                             if kbucket.iter().any(|entry| {
@@ -211,6 +227,16 @@ impl SwarmDriver {
                                                 is_bucket_full = false;
                                             }
                                         }
+                                        let now = SystemTime::now();
+                                        let score = self.peer_reputations.score(&peer_id, now);
+                                        if kbucket.iter().any(|entry| {
+                                            let entry_peer = entry.node.key.preimage();
+                                            self.peer_reputations.is_disliked(entry_peer, now)
+                                                && self.peer_reputations.score(entry_peer, now)
+                                                    < score
+                                        }) {
+                                            is_bucket_full = false;
+                                        }
                                     }
 
                                     (is_bucket_full, already_present_in_rt, ilog2)
@@ -246,7 +272,7 @@ impl SwarmDriver {
                         // If we are not local, we care only for peers that we dialed and thus are reachable.
                         if !self.local && has_dialed {
                             // A bad node cannot establish a connection with us. So we can add it to the RT directly.
-                            self.remove_bootstrap_from_full(peer_id);
+                            self.make_room_in_full_bucket(peer_id);
 
                             // Avoid have `direct link format` addrs co-exists with `relay` addr
                             if has_relayed {
@@ -256,7 +282,12 @@ impl SwarmDriver {
                             }
                         }
 
-                        if self.local || has_dialed {
+                        if self
+                            .peer_reputations
+                            .is_disliked(&peer_id, SystemTime::now())
+                        {
+                            debug!(%peer_id, "identify: not adding a peer with a poor reputation to the routing table");
+                        } else if self.local || has_dialed {
                             // If we are not local, we care only for peers that we dialed and thus are reachable.
                             debug!(%peer_id, ?addrs, "identify: attempting to add addresses to routing table");
 
@@ -316,6 +347,18 @@ impl SwarmDriver {
                     address.push(Protocol::P2p(local_peer_id));
                 }
 
+                // Our plain TCP listen port, not the one of a relay or a websocket
+                if !address
+                    .iter()
+                    .any(|p| matches!(p, Protocol::Ws(_) | Protocol::P2pCircuit))
+                {
+                    if let Some(Protocol::Tcp(port)) =
+                        address.iter().find(|p| matches!(p, Protocol::Tcp(_)))
+                    {
+                        self.external_address_manager.set_tcp_listen_port(port);
+                    }
+                }
+
                 // Trigger server mode if we're not a client and we should not add our own address if we're behind
                 // home network.
                 if !self.is_client && !self.is_behind_home_network {
@@ -341,8 +384,13 @@ impl SwarmDriver {
             } => {
                 event_string = "listener closed";
                 info!("Listener {listener_id:?} with add {addresses:?} has been closed for {reason:?}");
-                self.relay_manager
-                    .on_listener_closed(&listener_id, &mut self.swarm);
+                if let Some(relay) = self
+                    .relay_manager
+                    .on_listener_closed(&listener_id, &mut self.swarm)
+                {
+                    self.record_metrics(Marker::RelayReservationFailed { relay: &relay });
+                }
+                self.record_relay_metrics();
             }
             SwarmEvent::IncomingConnection {
                 connection_id,
@@ -383,6 +431,10 @@ impl SwarmDriver {
                 event_string = "ConnectionClosed";
                 debug!(%peer_id, ?connection_id, ?cause, num_established, "ConnectionClosed: {}", endpoint_str(&endpoint));
                 let _ = self.live_connected_peers.remove(&connection_id);
+                if num_established == 0 {
+                    // the version gets negotiated again on reconnection, the peer may have upgraded
+                    let _ = self.peer_protocol_versions.remove(&peer_id);
+                }
                 self.record_connection_metrics();
             }
             SwarmEvent::OutgoingConnectionError {
@@ -502,6 +554,9 @@ impl SwarmDriver {
                     }
                 };
 
+                #[cfg(not(target_arch = "wasm32"))]
+                self.record_unreachable_peer(failed_peer_id, &failed_peer_addresses);
+
                 if should_clean_peer {
                     warn!("Serious issue with {failed_peer_id:?}. Clearing it out for now");
 
@@ -573,9 +628,11 @@ impl SwarmDriver {
         Ok(())
     }
 
-    // if target bucket is full, remove a bootstrap node if presents.
-    fn remove_bootstrap_from_full(&mut self, peer_id: PeerId) {
+    // if target bucket is full, remove a bootstrap node if presents,
+    // else the peer with the poorest reputation if it is disliked and worse than the new peer.
+    fn make_room_in_full_bucket(&mut self, peer_id: PeerId) {
         let mut shall_removed = None;
+        let now = SystemTime::now();
 
         if let Some(kbucket) = self.swarm.behaviour_mut().kademlia.kbucket(peer_id) {
             if kbucket.num_entries() >= K_VALUE.into() {
@@ -587,15 +644,28 @@ impl SwarmDriver {
                         }
                     }
                 }
+                if shall_removed.is_none() {
+                    let score = self.peer_reputations.score(&peer_id, now);
+                    shall_removed = kbucket
+                        .iter()
+                        .map(|peer_entry| *peer_entry.node.key.preimage())
+                        .filter(|entry_peer| self.peer_reputations.is_disliked(entry_peer, now))
+                        .map(|entry_peer| {
+                            (self.peer_reputations.score(&entry_peer, now), entry_peer)
+                        })
+                        .filter(|(entry_score, _)| *entry_score < score)
+                        .min_by(|(a, _), (b, _)| a.total_cmp(b))
+                        .map(|(_, entry_peer)| entry_peer);
+                }
             }
         }
-        if let Some(to_be_removed_bootstrap) = shall_removed {
-            info!("Bootstrap node {to_be_removed_bootstrap:?} to be replaced by peer {peer_id:?}");
+        if let Some(to_be_removed) = shall_removed {
+            info!("Peer {to_be_removed:?}, a bootstrap node or with a poor reputation, to be replaced by peer {peer_id:?}");
             let entry = self
                 .swarm
                 .behaviour_mut()
                 .kademlia
-                .remove_peer(&to_be_removed_bootstrap);
+                .remove_peer(&to_be_removed);
             if let Some(removed_peer) = entry {
                 self.update_on_peer_removal(*removed_peer.node.key.preimage());
             }
@@ -666,6 +736,19 @@ impl SwarmDriver {
                 .set(self.swarm.connected_peers().count() as i64);
         }
     }
+
+    /// Record the metrics on update of the relay reservations.
+    fn record_relay_metrics(&self) {
+        #[cfg(feature = "open-metrics")]
+        if let Some(metrics) = &self.metrics_recorder {
+            metrics
+                .relay_reservations_hosted
+                .set(self.relay_manager.reservations_hosted() as i64);
+            metrics
+                .relay_reservations_held
+                .set(self.relay_manager.reservations_held() as i64);
+        }
+    }
 }
 
 /// Helper function to print formatted connection role info.
@@ -18,13 +18,22 @@ const MAX_CANDIDATES: usize = 50;
 
 /// Manages the external addresses of a Public node. For a relayed node, the RelayManager should deal with
 /// adding and removing external addresses. We don't manage "local" addresses here.
+///
+/// A dual-stack node has an IPv4 and an IPv6 external address, so the addresses are confirmed and the IP
+/// switches are tracked per IP family.
 // TODO:
 // 1. if the max candidate is reached, kick out the oldest candidate sorted by # of reports
 #[derive(Debug)]
 pub struct ExternalAddressManager {
     /// All the external addresses of the node
     address_states: Vec<ExternalAddressState>,
-    current_ip_address: Option<IpAddr>,
+    /// The current IPv4 address of the node
+    current_ipv4_address: Option<IpAddr>,
+    /// The current IPv6 address of the node
+    current_ipv6_address: Option<IpAddr>,
+    /// The port we listen on for TCP, if enabled. The port observed by our peers on our TCP
+    /// connections is ephemeral, so our TCP external addresses are crafted with this one.
+    tcp_listen_port: Option<u16>,
     /// The peer id of the node
     peer_id: PeerId,
 }
@@ -33,7 +42,9 @@ impl ExternalAddressManager {
     pub fn new(peer_id: PeerId) -> Self {
         Self {
             address_states: Vec::new(),
-            current_ip_address: None,
+            current_ipv4_address: None,
+            current_ipv6_address: None,
+            tcp_listen_port: None,
             peer_id,
         }
     }
@@ -52,10 +63,32 @@ impl ExternalAddressManager {
             .collect()
     }
 
+    /// Get the current IP address of the same family as `ip_address`
+    fn current_ip_address(&self, ip_address: &IpAddr) -> Option<IpAddr> {
+        if ip_address.is_ipv4() {
+            self.current_ipv4_address
+        } else {
+            self.current_ipv6_address
+        }
+    }
+
+    fn set_current_ip_address(&mut self, ip_address: IpAddr) {
+        if ip_address.is_ipv4() {
+            self.current_ipv4_address = Some(ip_address);
+        } else {
+            self.current_ipv6_address = Some(ip_address);
+        }
+    }
+
+    /// Set the port we listen on for TCP, enabling our TCP external addresses.
+    pub fn set_tcp_listen_port(&mut self, port: u16) {
+        self.tcp_listen_port = Some(port);
+    }
+
     /// Add an external address candidate to the manager.
     /// If the address has been reported often enough, it is confirmed and added to the swarm.
     /// If a new IP address has been reported often enough, then we switch to the new IP address and discard the old
-    /// external addresses.
+    /// external addresses of the same IP family.
     pub fn add_external_address_candidate(
         &mut self,
         address: Multiaddr,
@@ -71,6 +104,10 @@ impl ExternalAddressManager {
             return;
         };
 
+        // the current IP address of the same family as the reported address
+        let current_ip_address =
+            multiaddr_get_ip(&address).and_then(|ip_address| self.current_ip_address(&ip_address));
+
         if let Some(state) = self
             .address_states
             .iter_mut()
@@ -86,7 +123,7 @@ impl ExternalAddressManager {
                 } => {
                     if *num_reports >= MAX_REPORTS_BEFORE_CONFIRMATION {
                         // if the IP address of our confirmed address is the same as the new address, then add it
-                        let confirmed = if let Some(current_ip_address) = self.current_ip_address {
+                        let confirmed = if let Some(current_ip_address) = current_ip_address {
                             current_ip_address == *ip_address
                         } else {
                             true
@@ -118,7 +155,7 @@ impl ExternalAddressManager {
         }
         // check if we need to update to new ip.
         // TODO: Need to observe this
-        if let Some(current_ip_address) = self.current_ip_address {
+        if let Some(current_ip_address) = current_ip_address {
             let mut new_ip_map = HashMap::new();
 
             for state in &self.address_states {
@@ -128,7 +165,8 @@ impl ExternalAddressManager {
                     ..
                 } = state
                 {
-                    if current_ip_address != *ip_address
+                    if current_ip_address.is_ipv4() == ip_address.is_ipv4()
+                        && current_ip_address != *ip_address
                         && *num_reports >= MAX_REPORTS_BEFORE_CONFIRMATION
                     {
                         *new_ip_map.entry(ip_address).or_insert(0) += 1;
@@ -175,8 +213,8 @@ impl ExternalAddressManager {
     }
 
     /// Adds a non-local listen-addr to the swarm and the manager.
-    /// If the IP address of the listen-addr is different from the current IP address, then we directly
-    /// switch to the new IP address.
+    /// If the IP address of the listen-addr is different from the current IP address of the same family,
+    /// then we directly switch to the new IP address.
     pub fn add_listen_addr_as_external_address(
         &mut self,
         listen_addr: Multiaddr,
@@ -197,7 +235,7 @@ impl ExternalAddressManager {
             return;
         };
 
-        if let Some(current_ip_address) = self.current_ip_address {
+        if let Some(current_ip_address) = self.current_ip_address(&ip_address) {
             if current_ip_address != ip_address {
                 // add as candidate with MAX_REPORTS to be confirmed inside switch_to_new_ip
                 self.address_states.push(ExternalAddressState::Candidate {
@@ -245,13 +283,13 @@ impl ExternalAddressManager {
         swarm.add_external_address(address);
     }
 
-    /// Switch to a new IP address. The old external addresses are removed and the new ones are added.
-    /// The new IP address is set as the current IP address.
+    /// Switch to a new IP address. The old external addresses of the same IP family are removed and the new ones
+    /// are added. The new IP address is set as the current IP address of its family.
     fn switch_to_new_ip(&mut self, new_ip: IpAddr, swarm: &mut Swarm<NodeBehaviour>) {
         info!("Switching to new IpAddr: {new_ip}");
-        self.current_ip_address = Some(new_ip);
+        self.set_current_ip_address(new_ip);
 
-        // remove all the old confirmed addresses with different ip
+        // remove all the old confirmed addresses of the same family with different ip
         let mut removed_addresses = Vec::new();
         for state in &mut self.address_states {
             if let ExternalAddressState::Confirmed {
@@ -260,7 +298,7 @@ impl ExternalAddressManager {
                 ..
             } = state
             {
-                if *ip_address != new_ip {
+                if ip_address.is_ipv4() == new_ip.is_ipv4() && *ip_address != new_ip {
                     removed_addresses.push(address.clone());
                     swarm.remove_external_address(address);
                 }
@@ -268,8 +306,12 @@ impl ExternalAddressManager {
         }
         info!("Removed addresses due to change of IP: {removed_addresses:?}");
 
-        self.address_states
-            .retain(|state| !matches!(state, ExternalAddressState::Confirmed { .. }));
+        self.address_states.retain(|state| match state {
+            ExternalAddressState::Confirmed { ip_address, .. } => {
+                ip_address.is_ipv4() != new_ip.is_ipv4()
+            }
+            ExternalAddressState::Candidate { .. } => true,
+        });
 
         // add the new confirmed addresses with new ip
         for state in &mut self.address_states {
@@ -293,19 +335,33 @@ impl ExternalAddressManager {
         Self::print_swarm_state(swarm);
     }
 
-    /// Craft a proper address to avoid any ill formed addresses
+    /// Craft a proper address to avoid any ill formed addresses.
+    /// The QUIC addresses keep their port, while the TCP ones get our TCP listen port.
     fn craft_external_address(&self, given_address: &Multiaddr) -> Option<Multiaddr> {
         let mut output_address = Multiaddr::empty();
 
         let ip = given_address
             .iter()
-            .find(|protocol| matches!(protocol, Protocol::Ip4(_)))?;
+            .find(|protocol| matches!(protocol, Protocol::Ip4(_) | Protocol::Ip6(_)))?;
         output_address.push(ip);
-        let port = given_address
+        let is_websocket = given_address
             .iter()
-            .find(|protocol| matches!(protocol, Protocol::Udp(_)))?;
-        output_address.push(port);
-        output_address.push(Protocol::QuicV1);
+            .any(|protocol| matches!(protocol, Protocol::Ws(_) | Protocol::Wss(_)));
+        if let Some(port) = given_address
+            .iter()
+            .find(|protocol| matches!(protocol, Protocol::Udp(_)))
+        {
+            output_address.push(port);
+            output_address.push(Protocol::QuicV1);
+        } else if given_address
+            .iter()
+            .any(|protocol| matches!(protocol, Protocol::Tcp(_)))
+            && !is_websocket
+        {
+            output_address.push(Protocol::Tcp(self.tcp_listen_port?));
+        } else {
+            return None;
+        }
 
         output_address.push(Protocol::P2p(self.peer_id));
         Some(output_address)
@@ -360,3 +416,65 @@ impl ExternalAddressState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn external_addresses_are_crafted_for_both_transports() {
+        let peer_id = PeerId::random();
+        let mut manager = ExternalAddressManager::new(peer_id);
+        let craft = |manager: &ExternalAddressManager, addr: &str| {
+            manager.craft_external_address(&addr.parse().expect("valid multiaddr"))
+        };
+        let expected = |addr: &str| {
+            Some(
+                addr.parse::<Multiaddr>()
+                    .expect("valid multiaddr")
+                    .with(Protocol::P2p(peer_id)),
+            )
+        };
+
+        assert_eq!(
+            craft(&manager, "/ip4/1.2.3.4/udp/1200/quic-v1"),
+            expected("/ip4/1.2.3.4/udp/1200/quic-v1")
+        );
+        // the TCP addresses are only crafted once we listen on TCP
+        assert_eq!(craft(&manager, "/ip4/1.2.3.4/tcp/50123"), None);
+
+        manager.set_tcp_listen_port(1200);
+        // the port observed by our peers is ephemeral, ours is used instead
+        assert_eq!(
+            craft(&manager, "/ip4/1.2.3.4/tcp/50123"),
+            expected("/ip4/1.2.3.4/tcp/1200")
+        );
+        assert_eq!(craft(&manager, "/ip4/1.2.3.4/tcp/1200/ws"), None);
+        assert_eq!(
+            craft(&manager, "/ip6/2001:4860::1/udp/1200/quic-v1"),
+            expected("/ip6/2001:4860::1/udp/1200/quic-v1")
+        );
+        assert_eq!(
+            craft(&manager, "/ip6/2001:4860::1/tcp/50123"),
+            expected("/ip6/2001:4860::1/tcp/1200")
+        );
+    }
+
+    #[test]
+    fn current_ip_addresses_are_tracked_per_family() {
+        let mut manager = ExternalAddressManager::new(PeerId::random());
+        let ipv4: IpAddr = "1.2.3.4".parse().expect("valid ip");
+        let ipv6: IpAddr = "2001:4860::1".parse().expect("valid ip");
+
+        manager.set_current_ip_address(ipv4);
+        manager.set_current_ip_address(ipv6);
+        assert_eq!(
+            manager.current_ip_address(&"5.6.7.8".parse().expect("valid ip")),
+            Some(ipv4)
+        );
+        assert_eq!(
+            manager.current_ip_address(&"2001:4860::2".parse().expect("valid ip")),
+            Some(ipv6)
+        );
+    }
+}
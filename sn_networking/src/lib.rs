@@ -9,6 +9,7 @@
 #[macro_use]
 extern crate tracing;
 
+mod bandwidth;
 mod bootstrap;
 mod circular_vec;
 mod cmd;
@@ -20,10 +21,18 @@ mod log_markers;
 #[cfg(feature = "open-metrics")]
 mod metrics;
 mod network_discovery;
+mod network_size;
+mod pricing;
 mod record_store;
 mod record_store_api;
+mod record_store_backend;
+mod record_validation;
+mod records_cache;
 mod relay_manager;
 mod replication_fetcher;
+mod reputation;
+#[cfg(feature = "simulation")]
+mod simulation;
 mod spends;
 pub mod target_arch;
 mod transfers;
@@ -35,17 +44,31 @@ use xor_name::XorName;
 
 // re-export arch dependent deps for use in the crate, or above
 pub use self::{
+    bandwidth::BandwidthLimits,
     cmd::{NodeIssue, SwarmLocalState},
     driver::{
         GetRecordCfg, NetworkBuilder, PutRecordCfg, SwarmDriver, VerificationKind, MAX_PACKET_SIZE,
     },
     error::{GetRecordError, NetworkError},
     event::{MsgResponder, NetworkEvent},
-    record_store::{calculate_cost_for_records, NodeRecordStore},
+    network_size::NetworkSizeEstimate,
+    pricing::{PricingStrategy, SigmoidPricing, PRICING_STRATEGY_ENV},
+    record_store::{
+        calculate_cost_for_records, NodeRecordStore, RecordScrubStats, DEFAULT_MAX_STORAGE_BYTES,
+        DEFAULT_RECORDS_CACHE_BYTES,
+    },
+    record_store_backend::{
+        migrate_record_store, migrate_records, FlatFileBackend, LogKvBackend, RecordStorageBackend,
+        RecordStorageBackendKind,
+    },
+    relay_manager::RelayServerLimits,
+    reputation::PeerReputation,
     transfers::{get_raw_signed_spends_from_record, get_signed_spend_from_record},
 };
 #[cfg(feature = "open-metrics")]
 pub use metrics::service::MetricsRegistries;
+#[cfg(feature = "simulation")]
+pub use simulation::{SimulatedEndpoint, SimulatedNetwork};
 pub use target_arch::{interval, sleep, spawn, Instant, Interval};
 
 use self::{cmd::NetworkSwarmCmd, error::Result};
@@ -224,6 +247,7 @@ struct NetworkInner {
     local_swarm_cmd_sender: mpsc::Sender<LocalSwarmCmd>,
     peer_id: PeerId,
     keypair: Keypair,
+    pricing_strategy: Arc<dyn PricingStrategy>,
 }
 
 impl Network {
@@ -232,6 +256,7 @@ impl Network {
         local_swarm_cmd_sender: mpsc::Sender<LocalSwarmCmd>,
         peer_id: PeerId,
         keypair: Keypair,
+        pricing_strategy: Arc<dyn PricingStrategy>,
     ) -> Self {
         Self {
             inner: Arc::new(NetworkInner {
@@ -239,6 +264,7 @@ impl Network {
                 local_swarm_cmd_sender,
                 peer_id,
                 keypair,
+                pricing_strategy,
             }),
         }
     }
@@ -253,6 +279,11 @@ impl Network {
         &self.inner.keypair
     }
 
+    /// Returns the pricing strategy of the network, to compute and verify the store costs.
+    pub fn pricing_strategy(&self) -> &Arc<dyn PricingStrategy> {
+        &self.inner.pricing_strategy
+    }
+
     /// Get the sender to send a `NetworkSwarmCmd` to the underlying `Swarm`.
     pub(crate) fn network_swarm_cmd_sender(&self) -> &mpsc::Sender<NetworkSwarmCmd> {
         &self.inner.network_swarm_cmd_sender
@@ -422,6 +453,33 @@ impl Network {
             .map_err(|_e| NetworkError::InternalMsgChannelDropped)
     }
 
+    /// Returns the outcome of the scrubs of the local record store undertaken so far
+    pub async fn get_record_scrub_stats(&self) -> Result<RecordScrubStats> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_local_swarm_cmd(LocalSwarmCmd::GetRecordScrubStats { sender });
+        receiver
+            .await
+            .map_err(|_e| NetworkError::InternalMsgChannelDropped)
+    }
+
+    /// Returns an estimate of the number of nodes in the network, made from our routing table
+    pub async fn get_network_size_estimate(&self) -> Result<NetworkSizeEstimate> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_local_swarm_cmd(LocalSwarmCmd::GetNetworkSizeEstimate { sender });
+        receiver
+            .await
+            .map_err(|_e| NetworkError::InternalMsgChannelDropped)
+    }
+
+    /// Returns the reputations of the peers we interacted with
+    pub async fn get_peer_reputations(&self) -> Result<Vec<(PeerId, PeerReputation)>> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_local_swarm_cmd(LocalSwarmCmd::GetPeerReputations { sender });
+        receiver
+            .await
+            .map_err(|_e| NetworkError::InternalMsgChannelDropped)
+    }
+
     /// Returns all the PeerId from all the KBuckets from our local Routing Table
     /// Excludes our own PeerId.
     pub async fn get_all_local_peers_excluding_self(&self) -> Result<Vec<PeerId>> {
@@ -557,11 +615,14 @@ impl Network {
                 }) => {
                     // Check the quote itself is valid.
                     if quote.cost
-                        != AttoTokens::from_u64(calculate_cost_for_records(
-                            quote.quoting_metrics.close_records_stored,
-                        ))
+                        != AttoTokens::from_u64(
+                            self.pricing_strategy().store_cost(&quote.quoting_metrics),
+                        )
                     {
                         warn!("Received invalid quote from {peer_address:?}, {quote:?}");
+                        if let Some(peer_id) = peer_address.as_peer_id() {
+                            self.record_node_issues(peer_id, NodeIssue::BadQuoting);
+                        }
                         continue;
                     }
 
@@ -591,10 +652,44 @@ impl Network {
         }
 
         filter_out_bad_nodes(&mut all_costs, record_address);
+        self.filter_out_disliked_nodes(&mut all_costs).await;
 
         get_fees_from_store_cost_responses(all_costs)
     }
 
+    /// Drops the quotes of the peers with a poor reputation with us,
+    /// unless that leaves no quote to pick from.
+    async fn filter_out_disliked_nodes(
+        &self,
+        all_costs: &mut Vec<(NetworkAddress, RewardsAddress, PaymentQuote)>,
+    ) {
+        let disliked: HashSet<PeerId> = match self.get_peer_reputations().await {
+            Ok(reputations) => reputations
+                .into_iter()
+                .filter(|(_, reputation)| reputation.is_disliked())
+                .map(|(peer_id, _)| peer_id)
+                .collect(),
+            Err(err) => {
+                warn!("Failed to get the peer reputations to filter the quotes: {err:?}");
+                return;
+            }
+        };
+        let is_liked = |peer_addr: &NetworkAddress| {
+            peer_addr
+                .as_peer_id()
+                .is_none_or(|peer_id| !disliked.contains(&peer_id))
+        };
+        if all_costs.iter().any(|(peer_addr, ..)| is_liked(peer_addr)) {
+            all_costs.retain(|(peer_addr, ..)| {
+                let liked = is_liked(peer_addr);
+                if !liked {
+                    info!("Ignoring the quote of {peer_addr:?}, which has a poor reputation");
+                }
+                liked
+            });
+        }
+    }
+
     /// Get register from network.
     /// Due to the nature of the p2p network, it's not guaranteed there is only one version
     /// exists in the network all the time.
@@ -868,9 +963,10 @@ impl Network {
     pub async fn get_local_storecost(
         &self,
         key: RecordKey,
+        peer: PeerId,
     ) -> Result<(AttoTokens, QuotingMetrics, Vec<NetworkAddress>)> {
         let (sender, receiver) = oneshot::channel();
-        self.send_local_swarm_cmd(LocalSwarmCmd::GetLocalStoreCost { key, sender });
+        self.send_local_swarm_cmd(LocalSwarmCmd::GetLocalStoreCost { key, peer, sender });
 
         receiver
             .await
@@ -1405,6 +1501,22 @@ pub fn multiaddr_is_global(multiaddr: &Multiaddr) -> bool {
                 | ip.is_documentation()
                 | ip.is_broadcast()
         }
+        Protocol::Ip6(ip) => {
+            // IPv4-mapped addresses are global as per their IPv4 address.
+            if let Some(ipv4) = ip.to_ipv4_mapped() {
+                return !multiaddr_is_global(&Multiaddr::from(ipv4));
+            }
+            // Based on the nightly `is_global` method (`Ipv6Addrs::is_global`), only using what is available in stable.
+            // Unique local (fc00::/7), unicast link local (fe80::/10) and documentation (2001:db8::/32) are checked
+            // by hand.
+            let segments = ip.segments();
+            ip.is_unspecified()
+                | ip.is_loopback()
+                | ip.is_multicast()
+                | (segments[0] & 0xfe00 == 0xfc00)
+                | (segments[0] & 0xffc0 == 0xfe80)
+                | (segments[0] == 0x2001 && segments[1] == 0xdb8)
+        }
         _ => false,
     })
 }
@@ -1503,6 +1615,34 @@ mod tests {
     use super::*;
     use sn_evm::PaymentQuote;
 
+    #[test]
+    fn multiaddr_is_global_for_both_transports_and_ip_families() {
+        for (addr, is_global) in [
+            ("/ip4/1.2.3.4/udp/1200/quic-v1", true),
+            ("/ip4/1.2.3.4/tcp/1200", true),
+            (
+                "/ip4/1.2.3.4/tcp/1200/p2p/12D3KooWRi6wF7yxWLuPSNskXc6kQ5cJ6eaymeMbCRdTnMesPgFx",
+                true,
+            ),
+            ("/ip4/192.168.1.2/udp/1200/quic-v1", false),
+            ("/ip4/192.168.1.2/tcp/1200", false),
+            ("/ip4/127.0.0.1/tcp/1200", false),
+            ("/ip6/2001:4860::1/udp/1200/quic-v1", true),
+            ("/ip6/2001:4860::1/tcp/1200", true),
+            ("/ip6/::ffff:1.2.3.4/tcp/1200", true),
+            ("/ip6/::/udp/1200/quic-v1", false),
+            ("/ip6/::1/udp/1200/quic-v1", false),
+            ("/ip6/fd00::1/udp/1200/quic-v1", false),
+            ("/ip6/fe80::1/tcp/1200", false),
+            ("/ip6/2001:db8::1/tcp/1200", false),
+            ("/ip6/ff02::1/tcp/1200", false),
+            ("/ip6/::ffff:192.168.1.2/tcp/1200", false),
+        ] {
+            let addr: Multiaddr = addr.parse().expect("valid multiaddr");
+            assert_eq!(multiaddr_is_global(&addr), is_global, "{addr}");
+        }
+    }
+
     #[test]
     fn test_get_fee_from_store_cost_responses() -> Result<()> {
         // for a vec of different costs of CLOSE_GROUP size
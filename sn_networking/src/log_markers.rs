@@ -29,6 +29,10 @@ pub enum Marker<'a> {
     PeerConsideredAsBad { bad_peer: &'a PeerId },
     /// We have been flagged as a bad node by a peer.
     FlaggedAsBadNode { flagged_by: &'a PeerId },
+    /// A batch of records got re-read from disk and verified, the corrupt ones got removed
+    RecordStoreScrubbed { checked: usize, corrupt: usize },
+    /// We failed to make a reservation with a relay, while behind a home network
+    RelayReservationFailed { relay: &'a PeerId },
 }
 
 impl<'a> Marker<'a> {
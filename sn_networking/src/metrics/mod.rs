@@ -13,7 +13,11 @@ pub mod service;
 mod upnp;
 
 use crate::MetricsRegistries;
-use crate::{log_markers::Marker, target_arch::sleep};
+use crate::{
+    bandwidth::{BandwidthLimiter, Direction},
+    log_markers::Marker,
+    target_arch::sleep,
+};
 use bad_node::{BadNodeMetrics, BadNodeMetricsMsg, TimeFrame};
 use libp2p::{
     metrics::{Metrics as Libp2pMetrics, Recorder},
@@ -23,6 +27,7 @@ use prometheus_client::{
     metrics::family::Family,
     metrics::{counter::Counter, gauge::Gauge},
 };
+use std::sync::Arc;
 use sysinfo::{Pid, ProcessRefreshKind, System};
 use tokio::time::Duration;
 
@@ -44,14 +49,22 @@ pub(crate) struct NetworkMetricsRecorder {
     pub(crate) open_connections: Gauge,
     pub(crate) peers_in_routing_table: Gauge,
     pub(crate) records_stored: Gauge,
+    pub(crate) records_cache_hits: Counter,
+    pub(crate) records_cache_misses: Counter,
+    pub(crate) records_cache_bytes: Gauge,
 
     // store cost
     store_cost: Gauge,
     relevant_records: Gauge,
-    max_records: Gauge,
+    relevant_records_size: Gauge,
+    max_storage: Gauge,
     received_payment_count: Gauge,
     live_time: Gauge,
 
+    // record store scrubber
+    records_scrubbed: Counter,
+    corrupt_records_found: Counter,
+
     // bad node metrics
     bad_peers_count: Counter,
     shunned_count: Counter,
@@ -66,6 +79,20 @@ pub(crate) struct NetworkMetricsRecorder {
     process_memory_used_mb: Gauge,
     process_cpu_usage_percentage: Gauge,
 
+    // bandwidth
+    upload_bytes_per_second: Gauge,
+    download_bytes_per_second: Gauge,
+    upload_limit_bytes_per_second: Gauge,
+    download_limit_bytes_per_second: Gauge,
+
+    // relay
+    pub(crate) relay_reservations_hosted: Gauge,
+    pub(crate) relay_reservations_held: Gauge,
+    relay_reservations_denied: Counter,
+    relay_circuits_hosted: Gauge,
+    relay_circuits_denied: Counter,
+    relay_reservation_failures: Counter,
+
     // helpers
     bad_nodes_notifier: tokio::sync::mpsc::Sender<BadNodeMetricsMsg>,
 }
@@ -86,6 +113,25 @@ impl NetworkMetricsRecorder {
             records_stored.clone(),
         );
 
+        let records_cache_hits = Counter::default();
+        sub_registry.register(
+            "records_cache_hits",
+            "The number of locally held records served from the in-memory cache",
+            records_cache_hits.clone(),
+        );
+        let records_cache_misses = Counter::default();
+        sub_registry.register(
+            "records_cache_misses",
+            "The number of locally held records that had to be read from disk",
+            records_cache_misses.clone(),
+        );
+        let records_cache_bytes = Gauge::default();
+        sub_registry.register(
+            "records_cache_bytes",
+            "The size in bytes of the records held by the in-memory cache",
+            records_cache_bytes.clone(),
+        );
+
         let connected_peers = Gauge::default();
         sub_registry.register(
             "connected_peers",
@@ -149,6 +195,68 @@ impl NetworkMetricsRecorder {
             process_cpu_usage_percentage.clone(),
         );
 
+        let upload_bytes_per_second = Gauge::default();
+        sub_registry.register(
+            "upload_bytes_per_second",
+            "The bytes per second uploaded by the node, averaged over the last 15 seconds",
+            upload_bytes_per_second.clone(),
+        );
+        let download_bytes_per_second = Gauge::default();
+        sub_registry.register(
+            "download_bytes_per_second",
+            "The bytes per second downloaded by the node, averaged over the last 15 seconds",
+            download_bytes_per_second.clone(),
+        );
+        let upload_limit_bytes_per_second = Gauge::default();
+        sub_registry.register(
+            "upload_limit_bytes_per_second",
+            "The upload rate limit of the node. 0 if unlimited",
+            upload_limit_bytes_per_second.clone(),
+        );
+        let download_limit_bytes_per_second = Gauge::default();
+        sub_registry.register(
+            "download_limit_bytes_per_second",
+            "The download rate limit of the node. 0 if unlimited",
+            download_limit_bytes_per_second.clone(),
+        );
+
+        let relay_reservations_hosted = Gauge::default();
+        sub_registry.register(
+            "relay_reservations_hosted",
+            "The number of peers behind a home network we hold a relay reservation for",
+            relay_reservations_hosted.clone(),
+        );
+        let relay_reservations_held = Gauge::default();
+        sub_registry.register(
+            "relay_reservations_held",
+            "The number of relays we hold a reservation with, while behind a home network",
+            relay_reservations_held.clone(),
+        );
+        let relay_reservations_denied = Counter::default();
+        sub_registry.register(
+            "relay_reservations_denied",
+            "The number of relay reservations we denied, having reached our relay limits",
+            relay_reservations_denied.clone(),
+        );
+        let relay_circuits_hosted = Gauge::default();
+        sub_registry.register(
+            "relay_circuits_hosted",
+            "The number of connections we are currently relaying",
+            relay_circuits_hosted.clone(),
+        );
+        let relay_circuits_denied = Counter::default();
+        sub_registry.register(
+            "relay_circuits_denied",
+            "The number of connections we refused to relay, having reached our relay limits",
+            relay_circuits_denied.clone(),
+        );
+        let relay_reservation_failures = Counter::default();
+        sub_registry.register(
+            "relay_reservation_failures",
+            "The number of reservations we failed to make with a relay, while behind a home network",
+            relay_reservation_failures.clone(),
+        );
+
         // store cost
         let store_cost = Gauge::default();
         sub_registry.register(
@@ -162,11 +270,17 @@ impl NetworkMetricsRecorder {
             "The number of records that we're responsible for. This is used to calculate the store cost",
             relevant_records.clone(),
         );
-        let max_records = Gauge::default();
+        let relevant_records_size = Gauge::default();
+        sub_registry.register(
+            "relevant_records_bytes",
+            "The size in bytes of the records that we're responsible for. This is used to calculate the store cost",
+            relevant_records_size.clone(),
+        );
+        let max_storage = Gauge::default();
         sub_registry.register(
-            "max_records",
-            "The maximum number of records that we can store. This is used to calculate the store cost",
-            max_records.clone(),
+            "max_storage_bytes",
+            "The storage capacity in bytes. This is used to calculate the store cost",
+            max_storage.clone(),
         );
         let received_payment_count = Gauge::default();
         sub_registry.register(
@@ -181,6 +295,19 @@ impl NetworkMetricsRecorder {
             live_time.clone(),
         );
 
+        let records_scrubbed = Counter::default();
+        sub_registry.register(
+            "records_scrubbed",
+            "The number of records re-read from disk and verified by the record store scrubber",
+            records_scrubbed.clone(),
+        );
+        let corrupt_records_found = Counter::default();
+        sub_registry.register(
+            "corrupt_records_found",
+            "The number of corrupt records found by the record store scrubber, which got removed and re-fetched",
+            corrupt_records_found.clone(),
+        );
+
         let shunned_by_close_group = Gauge::default();
         sub_registry.register(
             "shunned_by_close_group",
@@ -218,16 +345,23 @@ impl NetworkMetricsRecorder {
             upnp_events,
 
             records_stored,
+            records_cache_hits,
+            records_cache_misses,
+            records_cache_bytes,
             estimated_network_size,
             connected_peers,
             open_connections,
             peers_in_routing_table,
             store_cost,
             relevant_records,
-            max_records,
+            relevant_records_size,
+            max_storage,
             received_payment_count,
             live_time,
 
+            records_scrubbed,
+            corrupt_records_found,
+
             bad_peers_count,
             shunned_count_across_time_frames,
             shunned_count,
@@ -237,6 +371,18 @@ impl NetworkMetricsRecorder {
             process_memory_used_mb,
             process_cpu_usage_percentage,
 
+            upload_bytes_per_second,
+            download_bytes_per_second,
+            upload_limit_bytes_per_second,
+            download_limit_bytes_per_second,
+
+            relay_reservations_hosted,
+            relay_reservations_held,
+            relay_reservations_denied,
+            relay_circuits_hosted,
+            relay_circuits_denied,
+            relay_reservation_failures,
+
             bad_nodes_notifier,
         };
 
@@ -273,6 +419,36 @@ impl NetworkMetricsRecorder {
         });
     }
 
+    // Records the bandwidth limits of the node, and spawns a task to record its bandwidth usage
+    pub(crate) fn record_bandwidth_usage(&self, limiter: Arc<BandwidthLimiter>) {
+        let limits = limiter.limits();
+        let _ = self
+            .upload_limit_bytes_per_second
+            .set(limits.upload.unwrap_or(0).try_into().unwrap_or(i64::MAX));
+        let _ = self
+            .download_limit_bytes_per_second
+            .set(limits.download.unwrap_or(0).try_into().unwrap_or(i64::MAX));
+
+        let upload_bytes_per_second = self.upload_bytes_per_second.clone();
+        let download_bytes_per_second = self.download_bytes_per_second.clone();
+        tokio::spawn(async move {
+            let mut uploaded = limiter.transferred(Direction::Upload);
+            let mut downloaded = limiter.transferred(Direction::Download);
+            loop {
+                sleep(UPDATE_INTERVAL).await;
+
+                let now_uploaded = limiter.transferred(Direction::Upload);
+                let now_downloaded = limiter.transferred(Direction::Download);
+                let _ = upload_bytes_per_second
+                    .set(((now_uploaded - uploaded) / UPDATE_INTERVAL.as_secs()) as i64);
+                let _ = download_bytes_per_second
+                    .set(((now_downloaded - downloaded) / UPDATE_INTERVAL.as_secs()) as i64);
+                uploaded = now_uploaded;
+                downloaded = now_downloaded;
+            }
+        });
+    }
+
     // Records the metric
     pub(crate) fn record_from_marker(&self, log_marker: Marker) {
         match log_marker {
@@ -306,12 +482,28 @@ impl NetworkMetricsRecorder {
                 let _ = self
                     .relevant_records
                     .set(quoting_metrics.close_records_stored as i64);
-                let _ = self.max_records.set(quoting_metrics.max_records as i64);
+                if let Some(close_records_size) = quoting_metrics.close_records_size {
+                    let _ = self
+                        .relevant_records_size
+                        .set(close_records_size.try_into().unwrap_or(i64::MAX));
+                }
+                if let Some(max_storage) = quoting_metrics.max_storage {
+                    let _ = self
+                        .max_storage
+                        .set(max_storage.try_into().unwrap_or(i64::MAX));
+                }
                 let _ = self
                     .received_payment_count
                     .set(quoting_metrics.received_payment_count as i64);
                 let _ = self.live_time.set(quoting_metrics.live_time as i64);
             }
+            Marker::RecordStoreScrubbed { checked, corrupt } => {
+                let _ = self.records_scrubbed.inc_by(checked as u64);
+                let _ = self.corrupt_records_found.inc_by(corrupt as u64);
+            }
+            Marker::RelayReservationFailed { .. } => {
+                let _ = self.relay_reservation_failures.inc();
+            }
             _ => {}
         }
     }
@@ -338,6 +530,21 @@ impl Recorder<libp2p::kad::Event> for NetworkMetricsRecorder {
 
 impl Recorder<libp2p::relay::Event> for NetworkMetricsRecorder {
     fn record(&self, event: &libp2p::relay::Event) {
+        match event {
+            libp2p::relay::Event::ReservationReqDenied { .. } => {
+                let _ = self.relay_reservations_denied.inc();
+            }
+            libp2p::relay::Event::CircuitReqAccepted { .. } => {
+                let _ = self.relay_circuits_hosted.inc();
+            }
+            libp2p::relay::Event::CircuitClosed { .. } => {
+                let _ = self.relay_circuits_hosted.dec();
+            }
+            libp2p::relay::Event::CircuitReqDenied { .. } => {
+                let _ = self.relay_circuits_denied.inc();
+            }
+            _ => {}
+        }
         self.libp2p_metrics.record(event)
     }
 }
@@ -0,0 +1,203 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use libp2p::{
+    kad::{KBucketKey, K_VALUE},
+    PeerId,
+};
+use sn_protocol::CLOSE_GROUP_SIZE;
+
+/// The bits of the XOR address space.
+const ADDRESS_SPACE_BITS: u32 = 256;
+
+/// An estimate of the number of nodes in the network, made from the peers in our routing table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetworkSizeEstimate {
+    /// The best estimate, combining the ones below.
+    pub estimated_size: u64,
+    /// Estimated from the density of the peers in our k-buckets.
+    pub kbucket_estimate: u64,
+    /// Estimated from the distances to our closest peers. `None` until we know enough of them.
+    pub close_group_estimate: Option<u64>,
+    /// The peers in our routing table.
+    pub peers_in_routing_table: usize,
+}
+
+/// Estimates the number of nodes in the network.
+///
+/// `buckets` holds the `ilog2` distance and the number of entries of each non empty k-bucket, and
+/// `closest_peers` our closest peers sorted by increasing distance to `self_key`.
+///
+/// The two estimates are combined on a log scale, as each is only ever right to a factor or so.
+/// While none of our k-buckets is full, we know every peer of the network, so the k-bucket
+/// count is used alone.
+pub(crate) fn estimate_network_size(
+    self_key: &KBucketKey<PeerId>,
+    buckets: &[(u32, usize)],
+    closest_peers: &[KBucketKey<PeerId>],
+) -> NetworkSizeEstimate {
+    let kbucket_estimate = kbucket_density_estimate(buckets);
+    let close_group_estimate = close_group_distance_estimate(self_key, closest_peers);
+    let knows_all_peers = buckets.iter().all(|(_, entries)| *entries < K_VALUE.get());
+    let estimated_size = match close_group_estimate {
+        Some(close_group_estimate) if !knows_all_peers => {
+            (((kbucket_estimate as f64).ln() + (close_group_estimate as f64).ln()) / 2.0)
+                .exp()
+                .round() as u64
+        }
+        _ => kbucket_estimate,
+    };
+
+    NetworkSizeEstimate {
+        estimated_size,
+        kbucket_estimate,
+        close_group_estimate,
+        peers_in_routing_table: buckets.iter().map(|(_, entries)| entries).sum(),
+    }
+}
+
+/// The buckets closer to us than the first full one hold every peer within their range of the
+/// address space, so the network holds as many peers again in each range of the same size.
+fn kbucket_density_estimate(buckets: &[(u32, usize)]) -> u64 {
+    let covered_bits = buckets
+        .iter()
+        .filter(|(_, entries)| *entries >= K_VALUE.get())
+        .map(|(ilog2, _)| *ilog2)
+        .min()
+        .unwrap_or(ADDRESS_SPACE_BITS);
+    let peers_in_covered_range: usize = buckets
+        .iter()
+        .filter(|(ilog2, _)| *ilog2 < covered_bits)
+        .map(|(_, entries)| entries)
+        .sum();
+
+    // Count ourselves in too.
+    ((peers_in_covered_range + 1) as f64 * 2_f64.powi((ADDRESS_SPACE_BITS - covered_bits) as i32))
+        as u64
+}
+
+/// With the nodes spread evenly over the address space, the `i`th closest peer is expected at
+/// `i` times the address space divided by the size of the network. The spacing is fitted over
+/// our closest peers, by least squares.
+fn close_group_distance_estimate(
+    self_key: &KBucketKey<PeerId>,
+    closest_peers: &[KBucketKey<PeerId>],
+) -> Option<u64> {
+    if closest_peers.len() < CLOSE_GROUP_SIZE {
+        return None;
+    }
+
+    let (weighted_distances, squared_ranks) =
+        closest_peers.iter().take(K_VALUE.get()).zip(1_u32..).fold(
+            (0.0, 0.0),
+            |(weighted_distances, squared_ranks), (peer, rank)| {
+                let rank = rank as f64;
+                (
+                    weighted_distances + rank * distance_as_f64(self_key, peer),
+                    squared_ranks + rank * rank,
+                )
+            },
+        );
+    let spacing = weighted_distances / squared_ranks;
+    if spacing <= 0.0 {
+        return None;
+    }
+
+    Some((2_f64.powi(ADDRESS_SPACE_BITS as i32) / spacing) as u64)
+}
+
+/// The XOR distance between two keys, with the precision of a `f64`. `KBucketDistance` only gives
+/// away its `ilog2`, which is too coarse to tell a network of 2000 nodes from one of 3000.
+fn distance_as_f64(a: &KBucketKey<PeerId>, b: &KBucketKey<PeerId>) -> f64 {
+    a.hashed_bytes()
+        .iter()
+        .zip(b.hashed_bytes())
+        .fold(0.0, |distance, (a, b)| distance * 256.0 + (a ^ b) as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+
+    struct RoutingTable {
+        self_key: KBucketKey<PeerId>,
+        buckets: Vec<(u32, usize)>,
+        closest_peers: Vec<KBucketKey<PeerId>>,
+    }
+
+    impl RoutingTable {
+        fn estimate(&self) -> NetworkSizeEstimate {
+            estimate_network_size(&self.self_key, &self.buckets, &self.closest_peers)
+        }
+    }
+
+    /// Builds the k-buckets and the closest peers we would see of a network of `size` random nodes.
+    fn routing_table_of(size: usize) -> RoutingTable {
+        let self_key = KBucketKey::from(PeerId::random());
+        let mut peers = (0..size)
+            .map(|_| KBucketKey::from(PeerId::random()))
+            .collect_vec();
+        peers.sort_by_key(|peer| self_key.distance(peer));
+
+        let buckets = peers
+            .iter()
+            .filter_map(|peer| self_key.distance(peer).ilog2())
+            .counts()
+            .into_iter()
+            // A bucket holds at most K_VALUE peers.
+            .map(|(ilog2, count)| (ilog2, count.min(K_VALUE.get())))
+            .sorted()
+            .collect_vec();
+        let closest_peers = peers.into_iter().take(K_VALUE.get()).collect_vec();
+        RoutingTable {
+            self_key,
+            buckets,
+            closest_peers,
+        }
+    }
+
+    #[test]
+    fn small_networks_are_counted_exactly() {
+        let estimate = routing_table_of(10).estimate();
+
+        assert_eq!(estimate.peers_in_routing_table, 10);
+        // None of the buckets are full, so all the nodes are known.
+        assert_eq!(estimate.kbucket_estimate, 11);
+        assert!(estimate.close_group_estimate.is_some());
+        assert_eq!(estimate.estimated_size, 11);
+    }
+
+    #[test]
+    fn too_few_peers_give_no_close_group_estimate() {
+        let estimate = routing_table_of(CLOSE_GROUP_SIZE - 1).estimate();
+
+        assert_eq!(estimate.close_group_estimate, None);
+        assert_eq!(estimate.estimated_size, estimate.kbucket_estimate);
+    }
+
+    #[test]
+    fn large_networks_are_estimated_to_the_right_magnitude() {
+        let size = 20_000;
+        // Averaged over a few nodes, as any single view can be off by a factor of a few.
+        let log_errors = (0..10)
+            .map(|_| {
+                let estimate = routing_table_of(size).estimate();
+                assert!(estimate.close_group_estimate.is_some());
+                (estimate.estimated_size as f64 / size as f64).ln()
+            })
+            .collect_vec();
+        let mean_log_error = log_errors.iter().sum::<f64>() / log_errors.len() as f64;
+
+        assert!(
+            mean_log_error.abs() < 2_f64.ln(),
+            "estimates are off by a factor of {:.2}",
+            mean_log_error.exp()
+        );
+    }
+}
@@ -0,0 +1,239 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::error::{NetworkError, Result};
+use serde::{Deserialize, Serialize};
+use sn_evm::QuotingMetrics;
+use std::{fmt::Debug, sync::Arc};
+
+/// Environment variable holding the JSON encoded `SigmoidPricing` parameters used by a network.
+///
+/// All the nodes and clients of a network must use the same parameters,
+/// as the quotes are verified by recomputing their cost from the quoting metrics.
+/// It is hence read by the `NetworkBuilder` of the nodes and clients alike, rather than set per node.
+pub const PRICING_STRATEGY_ENV: &str = "SN_PRICING_STRATEGY";
+
+/// Max store cost for a chunk.
+pub(crate) const MAX_STORE_COST: u64 = 1_000_000;
+
+// Min store cost for a chunk.
+pub(crate) const MIN_STORE_COST: u64 = 1;
+
+/// Computes the cost to store a record out of the `QuotingMetrics` of a node.
+///
+/// The cost must be a pure function of the metrics, so anyone can verify a quote.
+pub trait PricingStrategy: Debug + Send + Sync {
+    fn store_cost(&self, quoting_metrics: &QuotingMetrics) -> u64;
+}
+
+/// The pricing strategy used by default: a sigmoid over the share of the storage capacity used,
+/// giving a cheap price while the node has room and an exponential one as it fills up.
+///
+/// The price can optionally be discounted for long lived nodes, and raised for the nodes
+/// that got paid the most, to spread the payments across the network.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SigmoidPricing {
+    /// The cost of a full node.
+    pub max_cost: u64,
+    /// The cost of an empty node.
+    pub min_cost: u64,
+    /// How sharply the cost rises around the midpoint.
+    pub steepness: f64,
+    /// The share of the storage capacity used at which the cost is half the max.
+    pub midpoint: f64,
+    /// The weight of the node's live time, in hours, in discounting the cost.
+    /// Zero disables the discount.
+    pub live_time_weight: f64,
+    /// The weight of the number of payments received in raising the cost.
+    /// Zero disables the raise.
+    pub payment_history_weight: f64,
+}
+
+impl Default for SigmoidPricing {
+    fn default() -> Self {
+        Self {
+            max_cost: MAX_STORE_COST,
+            min_cost: MIN_STORE_COST,
+            steepness: 30.0,
+            midpoint: 0.5,
+            live_time_weight: 0.0,
+            payment_history_weight: 0.0,
+        }
+    }
+}
+
+impl SigmoidPricing {
+    /// The pricing set through `PRICING_STRATEGY_ENV`, or the default one when unset.
+    pub fn from_env() -> Result<Self> {
+        match std::env::var(PRICING_STRATEGY_ENV) {
+            Ok(json) => {
+                let pricing = Self::from_json(&json)?;
+                info!("Using the pricing strategy set through {PRICING_STRATEGY_ENV}: {pricing:?}");
+                Ok(pricing)
+            }
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    /// Parse the JSON encoded pricing parameters, the parameters left out taking their default value,
+    /// e.g. `{"steepness": 20.0}`.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let pricing: Self = serde_json::from_str(json).map_err(|err| {
+            NetworkError::InvalidPricingStrategy(format!(
+                "Failed to parse the pricing strategy: {err}"
+            ))
+        })?;
+        pricing.validate()?;
+        Ok(pricing)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.min_cost > self.max_cost {
+            return Err(NetworkError::InvalidPricingStrategy(format!(
+                "min_cost {} is above max_cost {}",
+                self.min_cost, self.max_cost
+            )));
+        }
+        for (name, value) in [
+            ("steepness", self.steepness),
+            ("midpoint", self.midpoint),
+            ("live_time_weight", self.live_time_weight),
+            ("payment_history_weight", self.payment_history_weight),
+        ] {
+            if !value.is_finite() || value < 0.0 {
+                return Err(NetworkError::InvalidPricingStrategy(format!(
+                    "{name} must be a positive number, got {value}"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl PricingStrategy for SigmoidPricing {
+    // The storage used is in bytes, or in records for the quotes of peers on an older protocol version.
+    // A node not declaring any capacity is considered full.
+    fn store_cost(&self, quoting_metrics: &QuotingMetrics) -> u64 {
+        let used_ratio = match (
+            quoting_metrics.close_records_size,
+            quoting_metrics.max_storage,
+        ) {
+            (Some(records_size), Some(max_storage)) if max_storage > 0 => {
+                records_size as f64 / max_storage as f64
+            }
+            (None, None) if quoting_metrics.max_records > 0 => {
+                quoting_metrics.close_records_stored as f64 / quoting_metrics.max_records as f64
+            }
+            _ => return self.max_cost,
+        };
+        let sigmoid = 1.0 / (1.0 + (-self.steepness * (used_ratio - self.midpoint)).exp());
+        let mut cost = sigmoid * self.max_cost as f64;
+
+        // Give extra reward (lower the quoting price to gain a better chance) to long lived nodes.
+        if self.live_time_weight > 0.0 {
+            let live_hours = quoting_metrics.live_time as f64 / 3600.0;
+            cost /= 1.0 + self.live_time_weight * (1.0 + live_hours).log2();
+        }
+        if self.payment_history_weight > 0.0 {
+            let payments = quoting_metrics.received_payment_count as f64;
+            cost *= 1.0 + self.payment_history_weight * (1.0 + payments).log2();
+        }
+
+        // Deploy lower and upper cap safe_guards to the store_cost
+        (cost as u64).clamp(self.min_cost, self.max_cost)
+    }
+}
+
+/// The pricing strategy used when none is set explicitly.
+pub fn default_pricing_strategy() -> Result<Arc<dyn PricingStrategy>> {
+    Ok(Arc::new(SigmoidPricing::from_env()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(used_percent: u64, live_time: u64, received_payment_count: usize) -> QuotingMetrics {
+        QuotingMetrics {
+            close_records_stored: 0,
+            max_records: 0,
+            received_payment_count,
+            live_time,
+            close_records_size: Some(used_percent * 1_000),
+            max_storage: Some(100_000),
+        }
+    }
+
+    #[test]
+    fn live_time_and_payment_history_are_only_used_when_enabled() {
+        let default = SigmoidPricing::default();
+        let base = default.store_cost(&metrics(50, 0, 0));
+        assert_eq!(base, MAX_STORE_COST / 2);
+        assert_eq!(default.store_cost(&metrics(50, 24 * 3600, 100)), base);
+
+        let live_time = SigmoidPricing {
+            live_time_weight: 0.5,
+            ..Default::default()
+        };
+        assert_eq!(live_time.store_cost(&metrics(50, 0, 0)), base);
+        assert!(live_time.store_cost(&metrics(50, 24 * 3600, 0)) < base);
+
+        let payment_history = SigmoidPricing {
+            payment_history_weight: 0.5,
+            ..Default::default()
+        };
+        assert_eq!(payment_history.store_cost(&metrics(50, 0, 0)), base);
+        assert!(payment_history.store_cost(&metrics(50, 0, 100)) > base);
+        assert_eq!(
+            payment_history.store_cost(&metrics(100, 0, 1_000_000)),
+            MAX_STORE_COST
+        );
+    }
+
+    #[test]
+    fn quotes_by_record_count_are_priced_on_the_records_stored() {
+        let default = SigmoidPricing::default();
+        let by_count = |close_records_stored| QuotingMetrics {
+            close_records_stored,
+            max_records: 100,
+            ..Default::default()
+        };
+        assert_eq!(
+            default.store_cost(&by_count(50)),
+            default.store_cost(&metrics(50, 0, 0))
+        );
+        assert_eq!(
+            default.store_cost(&QuotingMetrics::default()),
+            MAX_STORE_COST
+        );
+    }
+
+    #[test]
+    fn pricing_parameters_are_parsed_and_validated() -> eyre::Result<()> {
+        let pricing = SigmoidPricing::from_json(r#"{"max_cost": 5000, "live_time_weight": 0.1}"#)?;
+        assert_eq!(
+            pricing,
+            SigmoidPricing {
+                max_cost: 5000,
+                live_time_weight: 0.1,
+                ..Default::default()
+            }
+        );
+        assert!(pricing.validate().is_ok());
+
+        let invalid = SigmoidPricing {
+            min_cost: 10,
+            max_cost: 1,
+            ..Default::default()
+        };
+        assert!(invalid.validate().is_err());
+        assert!(SigmoidPricing::from_json(r#"{"min_cost": 10, "max_cost": 1}"#).is_err());
+        Ok(())
+    }
+}
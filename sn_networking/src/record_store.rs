@@ -9,6 +9,12 @@
 
 use crate::cmd::LocalSwarmCmd;
 use crate::driver::MAX_PACKET_SIZE;
+use crate::error::NetworkError;
+use crate::pricing::{PricingStrategy, SigmoidPricing};
+use crate::record_store_backend::{RecordStorageBackend, RecordStorageBackendKind};
+use crate::records_cache::RecordsCache;
+#[cfg(feature = "open-metrics")]
+use crate::records_cache::RecordsCacheMetrics;
 use crate::send_local_swarm_cmd;
 use crate::target_arch::{spawn, Instant};
 use crate::{event::NetworkEvent, log_markers::Marker};
@@ -17,7 +23,6 @@ use aes_gcm_siv::{
     Aes256GcmSiv, Nonce,
 };
 
-use itertools::Itertools;
 use libp2p::{
     identity::PeerId,
     kad::{
@@ -32,7 +37,7 @@ use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use serde::{Deserialize, Serialize};
 use sn_evm::{AttoTokens, QuotingMetrics};
 use sn_protocol::{
-    storage::{RecordHeader, RecordKind, RecordType},
+    storage::{try_deserialize_record, Chunk, RecordHeader, RecordKind, RecordType, Scratchpad},
     NetworkAddress, PrettyPrintRecordKey,
 };
 use std::collections::VecDeque;
@@ -41,32 +46,43 @@ use std::{
     collections::{HashMap, HashSet},
     fs,
     path::{Path, PathBuf},
+    sync::Arc,
     time::SystemTime,
     vec,
 };
 use tokio::sync::mpsc;
-use walkdir::{DirEntry, WalkDir};
 use xor_name::XorName;
 
-// A spend record is at the size of 4KB roughly.
-// Given chunk record is maxed at size of 4MB.
-// During Beta phase, it's almost one spend per chunk,
-// which makes the average record size is around 2MB.
-// Given we are targeting node size to be 32GB,
-// this shall allow around 16K records.
-const MAX_RECORDS_COUNT: usize = 16 * 1024;
+/// The default storage capacity of a node, in bytes.
+/// Chunk records are maxed at 4MB, this allows for around 8K of them.
+pub const DEFAULT_MAX_STORAGE_BYTES: u64 = 32 * 1024 * 1024 * 1024;
+
+/// The size of a chunk record of the max size, used to express the storage capacity as a number
+/// of records for the peers on an older protocol version.
+const MAX_CHUNK_RECORD_SIZE: u64 = 4 * 1024 * 1024;
+
+/// The default bytes of records cached in memory, as many as 25 records of the max size.
+pub const DEFAULT_RECORDS_CACHE_BYTES: usize = 25 * MAX_PACKET_SIZE;
 
-/// The maximum number of records to cache in memory.
-const MAX_RECORDS_CACHE_SIZE: usize = 25;
+/// The max number of records re-read from disk and verified in a single scrub batch.
+const RECORD_SCRUB_BATCH_SIZE: usize = 50;
+
+/// The max number of bytes re-read from disk in a single scrub batch, to not hog the disk.
+/// A batch always holds at least a record, whatever its size.
+const RECORD_SCRUB_BATCH_BYTES: u64 = 16 * 1024 * 1024;
 
 /// File name of the recorded historical quoting metrics.
 const HISTORICAL_QUOTING_METRICS_FILENAME: &str = "historic_quoting_metrics";
 
-/// Max store cost for a chunk.
-const MAX_STORE_COST: u64 = 1_000_000;
+/// Bytes added to a record value by its encryption, the AES-GCM-SIV authentication tag.
+const RECORD_ENCRYPTION_OVERHEAD: u64 = 16;
+
+/// Key of the entry stored next to the records to detect they were encrypted with a different key.
+/// It can't clash with a record key, those are 32 bytes long.
+const ENCRYPTION_CANARY_KEY: &[u8] = b"record_store_encryption_canary";
 
-// Min store cost for a chunk.
-const MIN_STORE_COST: u64 = 1;
+/// Value of the encryption canary entry, before encryption.
+const ENCRYPTION_CANARY_VALUE: &[u8] = b"record_store_encryption_canary_value";
 
 /// A `RecordStore` that stores records on disk.
 pub struct NodeRecordStore {
@@ -78,11 +94,12 @@ pub struct NodeRecordStore {
     config: NodeRecordStoreConfig,
     /// A set of keys, each corresponding to a data `Record` stored on disk.
     records: HashMap<Key, (NetworkAddress, RecordType)>,
-    /// FIFO simple cache of records to reduce read times
-    records_cache: VecDeque<Record>,
-    /// A map from record keys to their indices in the cache
-    /// allowing for more efficient cache management
-    records_cache_map: HashMap<Key, usize>,
+    /// The size in bytes of each record stored, or being written, to disk.
+    record_sizes: HashMap<Key, u64>,
+    /// The sum of `record_sizes`.
+    stored_bytes: u64,
+    /// Cache of the popular records to reduce read times
+    records_cache: RecordsCache,
     /// Send network events to the node layer.
     network_event_sender: mpsc::Sender<NetworkEvent>,
     /// Send cmds to the network layer. Used to interact with self in an async fashion.
@@ -103,6 +120,15 @@ pub struct NodeRecordStore {
     timestamp: SystemTime,
     /// Farthest record to self
     farthest_record: Option<(Key, Distance)>,
+    /// The storage engine persisting the records
+    backend: Arc<dyn RecordStorageBackend>,
+    /// The records yet to be scrubbed in the current pass over the store
+    scrub_queue: VecDeque<Key>,
+    /// The keys of the scrub batch being verified, a single one is undertaken at a time.
+    /// The keys written since the batch got read are dropped, their verification being stale.
+    scrub_batch: Option<HashSet<Key>>,
+    /// The outcome of the scrubs undertaken since the store got opened
+    scrub_stats: RecordScrubStats,
 }
 
 /// Configuration for a `DiskBackedRecordStore`.
@@ -113,12 +139,16 @@ pub struct NodeRecordStoreConfig {
     /// The directory where the historic quote to be stored
     /// (normally to be the parent dir of the storage_dir)
     pub historic_quote_dir: PathBuf,
-    /// The maximum number of records.
-    pub max_records: usize,
+    /// The storage capacity, in bytes. Far records get pruned to stay under it.
+    pub max_storage_bytes: u64,
     /// The maximum size of record values, in bytes.
     pub max_value_bytes: usize,
-    /// The maximum number of records to cache in memory.
-    pub records_cache_size: usize,
+    /// The maximum bytes of records to cache in memory.
+    pub records_cache_bytes: usize,
+    /// The storage engine used to persist the records.
+    pub backend: RecordStorageBackendKind,
+    /// The pricing strategy computing the store cost out of the quoting metrics.
+    pub pricing_strategy: Arc<dyn PricingStrategy>,
 }
 
 impl Default for NodeRecordStoreConfig {
@@ -127,13 +157,26 @@ impl Default for NodeRecordStoreConfig {
         Self {
             storage_dir: historic_quote_dir.clone(),
             historic_quote_dir,
-            max_records: MAX_RECORDS_COUNT,
+            max_storage_bytes: DEFAULT_MAX_STORAGE_BYTES,
             max_value_bytes: MAX_PACKET_SIZE,
-            records_cache_size: MAX_RECORDS_CACHE_SIZE,
+            records_cache_bytes: DEFAULT_RECORDS_CACHE_BYTES,
+            backend: RecordStorageBackendKind::default(),
+            pricing_strategy: Arc::new(SigmoidPricing::default()),
         }
     }
 }
 
+/// The outcome of re-reading and verifying the records held by a `NodeRecordStore`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RecordScrubStats {
+    /// The number of records re-read from disk and verified
+    pub records_checked: u64,
+    /// The number of records found corrupt, which got removed and are re-fetched
+    pub corrupt_records: u64,
+    /// The number of complete passes over the records held
+    pub passes_completed: u64,
+}
+
 /// Generate an encryption nonce for a given record key and nonce_starter bytes.
 fn generate_nonce_for_record(nonce_starter: &[u8; 4], key: &Key) -> Nonce {
     let mut nonce_bytes = nonce_starter.to_vec();
@@ -151,74 +194,131 @@ struct HistoricQuotingMetrics {
 }
 
 impl NodeRecordStore {
-    /// If a directory for our node already exists, repopulate the records from the files in the dir
+    /// If the backend already holds records, repopulate the index of records, and their sizes, from it
+    ///
+    /// Backends that keep the record types in their index are loaded without reading any record.
+    /// The other records are read to work out their type, and removed if they can't be decrypted.
     fn update_records_from_an_existing_store(
-        config: &NodeRecordStoreConfig,
+        backend: &Arc<dyn RecordStorageBackend>,
         encryption_details: &(Aes256GcmSiv, [u8; 4]),
-    ) -> HashMap<Key, (NetworkAddress, RecordType)> {
-        let process_entry = |entry: &DirEntry| -> _ {
-            let path = entry.path();
-            if path.is_file() {
-                debug!("Existing record found: {path:?}");
-                // if we've got a file, lets try and read it
-                let filename = match path.file_name().and_then(|n| n.to_str()) {
-                    Some(file_name) => file_name,
-                    None => {
-                        // warn and remove this file as it's not a valid record
-                        warn!(
-                            "Found a file in the storage dir that is not a valid record: {:?}",
-                            path
-                        );
-                        if let Err(e) = fs::remove_file(path) {
-                            warn!(
-                                "Failed to remove invalid record file from storage dir: {:?}",
-                                e
-                            );
+    ) -> (
+        HashMap<Key, (NetworkAddress, RecordType)>,
+        HashMap<Key, u64>,
+    ) {
+        info!(
+            "Attempting to repopulate records from existing {} store...",
+            backend.kind()
+        );
+        Self::check_encryption_canary(backend, encryption_details);
+
+        let canary_key = Key::from(ENCRYPTION_CANARY_KEY.to_vec());
+        let stored_keys = match backend.keys() {
+            Ok(keys) => keys,
+            Err(err) => {
+                error!("Failed to list the records of the existing store: {err:?}");
+                return (HashMap::new(), HashMap::new());
+            }
+        };
+
+        let process_entry = |(key, record_type): &(Key, Option<RecordType>)| -> _ {
+            if *key == canary_key {
+                return None;
+            }
+            let record_type = match record_type {
+                Some(record_type) => record_type.clone(),
+                None => {
+                    let record = match backend.read(key) {
+                        Ok(Some(bytes)) => {
+                            Self::get_record_from_bytes(bytes, key, encryption_details)
+                        }
+                        Ok(None) => None,
+                        Err(err) => {
+                            error!("Error while reading record {key:?}: {err:?}");
+                            None
+                        }
+                    };
+                    let Some(record) = record else {
+                        warn!("Removing unreadable record {key:?} from the existing store");
+                        if let Err(err) = backend.remove(key) {
+                            warn!("Failed to remove unreadable record {key:?}: {err:?}");
                         }
                         return None;
-                    }
-                };
-                // get the record key from the filename
-                let key = Self::get_data_from_filename(filename)?;
-                let record = match fs::read(path) {
-                    Ok(bytes) => {
-                        // and the stored record
-                        Self::get_record_from_bytes(bytes, &key, encryption_details)?
-                    }
-                    Err(err) => {
-                        error!("Error while reading file. filename: {filename}, error: {err:?}");
-                        return None;
-                    }
-                };
+                    };
 
-                let record_type = match RecordHeader::is_record_of_type_chunk(&record) {
-                    Ok(true) => RecordType::Chunk,
-                    Ok(false) => {
-                        let xorname_hash = XorName::from_content(&record.value);
-                        RecordType::NonChunk(xorname_hash)
-                    }
-                    Err(error) => {
-                        warn!("Failed to parse record type from record: {:?}", error);
-                        return None;
+                    match RecordHeader::is_record_of_type_chunk(&record) {
+                        Ok(true) => RecordType::Chunk,
+                        Ok(false) => {
+                            let xorname_hash = XorName::from_content(&record.value);
+                            RecordType::NonChunk(xorname_hash)
+                        }
+                        Err(error) => {
+                            warn!("Failed to parse record type from record: {:?}", error);
+                            return None;
+                        }
                     }
-                };
+                }
+            };
 
-                let address = NetworkAddress::from_record_key(&key);
-                info!("Existing record loaded: {path:?}");
-                return Some((key, (address, record_type)));
-            }
-            None
+            // track the same plaintext size as `put_verified` does
+            let size = match backend.value_size(key) {
+                Ok(size) => Self::plaintext_size(size.unwrap_or_default()),
+                Err(err) => {
+                    warn!("Failed to get the size of record {key:?}: {err:?}");
+                    0
+                }
+            };
+
+            let address = NetworkAddress::from_record_key(key);
+            info!(
+                "Existing record loaded: {:?}",
+                PrettyPrintRecordKey::from(key)
+            );
+            Some((key.clone(), (address, record_type), size))
         };
 
-        info!("Attempting to repopulate records from existing store...");
-        let records = WalkDir::new(&config.storage_dir)
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .collect_vec()
-            .par_iter()
-            .filter_map(process_entry)
-            .collect();
-        records
+        let loaded: Vec<_> = stored_keys.par_iter().filter_map(process_entry).collect();
+
+        let mut records = HashMap::with_capacity(loaded.len());
+        let mut record_sizes = HashMap::with_capacity(loaded.len());
+        for (key, record, size) in loaded {
+            let _ = record_sizes.insert(key.clone(), size);
+            let _ = records.insert(key, record);
+        }
+        (records, record_sizes)
+    }
+
+    /// Records encrypted with another key can't be read back, as the key is generated at startup.
+    /// An entry encrypted with the current key is kept in the backend to detect that in one read,
+    /// all the records are dropped if it can't be decrypted.
+    fn check_encryption_canary(
+        backend: &Arc<dyn RecordStorageBackend>,
+        encryption_details: &(Aes256GcmSiv, [u8; 4]),
+    ) {
+        let canary_key = Key::from(ENCRYPTION_CANARY_KEY.to_vec());
+        match backend.read(&canary_key) {
+            Ok(Some(bytes)) => {
+                let decrypted = Self::get_record_from_bytes(bytes, &canary_key, encryption_details);
+                if decrypted.map(|record| record.value.clone())
+                    != Some(ENCRYPTION_CANARY_VALUE.to_vec())
+                {
+                    warn!(
+                        "Existing records were encrypted with a different key, clearing the store"
+                    );
+                    if let Err(err) = backend.clear() {
+                        error!("Failed to clear the existing store: {err:?}");
+                    }
+                }
+            }
+            Ok(None) => {}
+            Err(err) => error!("Failed to read the encryption canary: {err:?}"),
+        }
+
+        let canary = Record::new(canary_key.clone(), ENCRYPTION_CANARY_VALUE.to_vec());
+        if let Some(bytes) = Self::prepare_record_bytes(canary, encryption_details.clone()) {
+            if let Err(err) = backend.write(&canary_key, &bytes, None) {
+                error!("Failed to write the encryption canary: {err:?}");
+            }
+        }
     }
 
     /// If quote_metrics file already exists, using the existing parameters.
@@ -259,7 +359,14 @@ impl NodeRecordStore {
         config: NodeRecordStoreConfig,
         network_event_sender: mpsc::Sender<NetworkEvent>,
         swarm_cmd_sender: mpsc::Sender<LocalSwarmCmd>,
-    ) -> Self {
+    ) -> std::result::Result<Self, NetworkError> {
+        let backend = config.backend.open(&config.storage_dir).map_err(|source| {
+            NetworkError::FailedToOpenRecordStore {
+                path: config.storage_dir.clone(),
+                source,
+            }
+        })?;
+
         let key = Aes256GcmSiv::generate_key(&mut OsRng);
         let cipher = Aes256GcmSiv::new(&key);
         let mut nonce_starter = [0u8; 4];
@@ -280,16 +387,19 @@ impl NodeRecordStore {
             (0, SystemTime::now())
         };
 
-        let records = Self::update_records_from_an_existing_store(&config, &encryption_details);
+        let (records, record_sizes) =
+            Self::update_records_from_an_existing_store(&backend, &encryption_details);
+        let stored_bytes = record_sizes.values().sum();
 
-        let cache_size = config.records_cache_size;
+        let records_cache = RecordsCache::new(config.records_cache_bytes);
         let mut record_store = NodeRecordStore {
             local_key: KBucketKey::from(local_id),
             local_address: NetworkAddress::from_peer(local_id),
             config,
             records,
-            records_cache: VecDeque::with_capacity(cache_size),
-            records_cache_map: HashMap::with_capacity(cache_size),
+            record_sizes,
+            stored_bytes,
+            records_cache,
             network_event_sender,
             local_swarm_cmd_sender: swarm_cmd_sender,
             responsible_distance_range: None,
@@ -299,13 +409,24 @@ impl NodeRecordStore {
             encryption_details,
             timestamp,
             farthest_record: None,
+            backend,
+            scrub_queue: VecDeque::new(),
+            scrub_batch: None,
+            scrub_stats: RecordScrubStats::default(),
         };
 
         record_store.farthest_record = record_store.calculate_farthest();
 
         record_store.flush_historic_quoting_metrics();
 
-        record_store
+        Ok(record_store)
+    }
+
+    /// Set the metrics reporting how well the records cache serves the records
+    #[cfg(feature = "open-metrics")]
+    pub(crate) fn set_records_cache_metrics(mut self, metrics: RecordsCacheMetrics) -> Self {
+        self.records_cache.set_metrics(metrics);
+        self
     }
 
     /// Set the record_count_metric to report the number of records stored to the metrics server
@@ -320,17 +441,6 @@ impl NodeRecordStore {
         hex::encode(key.as_ref())
     }
 
-    // Converts a Hex string back into a Key.
-    fn get_data_from_filename(hex_str: &str) -> Option<Key> {
-        match hex::decode(hex_str) {
-            Ok(bytes) => Some(Key::from(bytes)),
-            Err(error) => {
-                error!("Error decoding hex string: {:?}", error);
-                None
-            }
-        }
-    }
-
     /// Upon read perform any data transformations required to return a `Record`.
     fn get_record_from_bytes<'a>(
         bytes: Vec<u8>,
@@ -367,16 +477,14 @@ impl NodeRecordStore {
     fn read_from_disk<'a>(
         encryption_details: &(Aes256GcmSiv, [u8; 4]),
         key: &Key,
-        storage_dir: &Path,
+        backend: &Arc<dyn RecordStorageBackend>,
     ) -> Option<Cow<'a, Record>> {
         let start = Instant::now();
         let filename = Self::generate_filename(key);
 
-        let file_path = storage_dir.join(&filename);
-
         // we should only be reading if we know the record is written to disk properly
-        match fs::read(file_path) {
-            Ok(bytes) => {
+        match backend.read(key) {
+            Ok(Some(bytes)) => {
                 // vdash metric (if modified please notify at https://github.com/happybeing/vdash/issues):
                 info!(
                     "Retrieved record from disk! filename: {filename} after {:?}",
@@ -385,6 +493,10 @@ impl NodeRecordStore {
 
                 Self::get_record_from_bytes(bytes, key, encryption_details)
             }
+            Ok(None) => {
+                error!("Record missing from disk. filename: {filename}");
+                None
+            }
             Err(err) => {
                 error!("Error while reading file. filename: {filename}, error: {err:?}");
                 None
@@ -419,24 +531,32 @@ impl NodeRecordStore {
     }
 
     /// Prune the records in the store to ensure that we free up space
-    /// for the incoming record.
+    /// for the incoming record of `incoming_record_size` bytes.
     /// Returns Ok if the record can be stored because it is closer to the local peer
     /// or we are not full.
     ///
     /// Err MaxRecords if we cannot store as it's farther than the farthest data we have
-    fn prune_records_if_needed(&mut self, incoming_record_key: &Key) -> Result<()> {
-        // we're not full, so we don't need to prune
-        if self.records.len() < self.config.max_records {
-            return Ok(());
-        }
+    fn prune_records_if_needed(
+        &mut self,
+        incoming_record_key: &Key,
+        incoming_record_size: u64,
+    ) -> Result<()> {
+        let incoming_record_distance = self
+            .local_address
+            .distance(&NetworkAddress::from_record_key(incoming_record_key));
+
+        while self.stored_bytes_with(incoming_record_key, incoming_record_size)
+            > self.config.max_storage_bytes
+        {
+            // records still being written are not pruned, their bytes stay reserved
+            let Some((farthest_record, farthest_record_distance)) = self.farthest_record.clone()
+            else {
+                warn!("Only records being written are left, no space can be freed up for the incoming record");
+                return Err(Error::MaxRecords);
+            };
 
-        if let Some((farthest_record, farthest_record_distance)) = self.farthest_record.clone() {
             // if the incoming record is farther than the farthest record, we can't store it
-            if farthest_record_distance
-                < self
-                    .local_address
-                    .distance(&NetworkAddress::from_record_key(incoming_record_key))
-            {
+            if farthest_record_distance < incoming_record_distance {
                 return Err(Error::MaxRecords);
             }
 
@@ -450,13 +570,34 @@ impl NodeRecordStore {
         Ok(())
     }
 
-    // When the accumulated record copies exceeds the `expotional pricing point` (max_records * 0.6)
+    /// The bytes stored once the record `key` of `size` bytes is written, replacing any previous value
+    fn stored_bytes_with(&self, key: &Key, size: u64) -> u64 {
+        let existing_size = self.record_sizes.get(key).copied().unwrap_or_default();
+        self.stored_bytes - existing_size + size
+    }
+
+    /// Track the size of a record stored, or being written, to disk
+    fn track_record_size(&mut self, key: Key, size: u64) {
+        if let Some(existing_size) = self.record_sizes.insert(key, size) {
+            self.stored_bytes -= existing_size;
+        }
+        self.stored_bytes += size;
+    }
+
+    /// Stop tracking the size of a removed record
+    fn untrack_record_size(&mut self, key: &Key) {
+        if let Some(size) = self.record_sizes.remove(key) {
+            self.stored_bytes -= size;
+        }
+    }
+
+    // When the accumulated record copies exceeds the `expotional pricing point` (max_storage_bytes * 0.6)
     // those `out of range` records shall be cleaned up.
     // This is to avoid `over-quoting` during restart, when RT is not fully populated,
     // result in mis-calculation of relevant records.
     pub fn cleanup_unrelevant_records(&mut self) {
         let accumulated_records = self.records.len();
-        if accumulated_records < MAX_RECORDS_COUNT * 6 / 10 {
+        if self.stored_bytes < self.config.max_storage_bytes / 10 * 6 {
             return;
         }
 
@@ -490,6 +631,171 @@ impl NodeRecordStore {
         info!("Cleaned up {} unrelevant records, among the original {accumulated_records} accumulated_records",
             removed_keys.len());
     }
+
+    /// Re-read the next batch of records from disk, to detect the ones that got corrupted since
+    /// they were written. The records are scrubbed in turn, a batch at a time, so the whole store
+    /// gets checked over time without hogging the disk.
+    ///
+    /// The batch is verified off thread, the corrupt records are reported back through
+    /// `LocalSwarmCmd::RecordStoreScrubbed`.
+    pub(crate) fn scrub_next_batch(&mut self) {
+        if self.scrub_batch.is_some() {
+            debug!("The previous scrub batch is still being verified");
+            return;
+        }
+
+        if self.scrub_queue.is_empty() {
+            self.scrub_queue.extend(self.records.keys().cloned());
+        }
+
+        let mut batch = Vec::new();
+        let mut batch_bytes = 0;
+        while batch.len() < RECORD_SCRUB_BATCH_SIZE {
+            let Some(key) = self.scrub_queue.pop_front() else {
+                break;
+            };
+            // Records removed since the pass started are skipped
+            let Some((_, record_type)) = self.records.get(&key) else {
+                continue;
+            };
+            let size = self.record_sizes.get(&key).copied().unwrap_or_default();
+            if !batch.is_empty() && batch_bytes + size > RECORD_SCRUB_BATCH_BYTES {
+                self.scrub_queue.push_front(key);
+                break;
+            }
+            batch.push((key, record_type.clone()));
+            batch_bytes += size;
+        }
+
+        if batch.is_empty() {
+            return;
+        }
+        let pass_completed = self.scrub_queue.is_empty();
+        self.scrub_batch = Some(batch.iter().map(|(key, _)| key.clone()).collect());
+
+        let encryption_details = self.encryption_details.clone();
+        let backend = Arc::clone(&self.backend);
+        let cmd_sender = self.local_swarm_cmd_sender.clone();
+        let _handle = tokio::task::spawn_blocking(move || {
+            let mut checked = 0;
+            let mut corrupt = Vec::new();
+            for (key, record_type) in batch {
+                // A record that couldn't be read is checked again in the next pass
+                match Self::verify_stored_record(&encryption_details, &key, &record_type, &backend)
+                {
+                    Some(true) => checked += 1,
+                    Some(false) => {
+                        checked += 1;
+                        corrupt.push((key, record_type));
+                    }
+                    None => {}
+                }
+            }
+
+            send_local_swarm_cmd(
+                cmd_sender,
+                LocalSwarmCmd::RecordStoreScrubbed {
+                    checked,
+                    corrupt,
+                    pass_completed,
+                },
+            );
+        });
+    }
+
+    /// Re-read a record from disk, checking it decrypts and its content matches its key.
+    /// Returns `None` if the record couldn't be read, which says nothing of its integrity.
+    fn verify_stored_record(
+        encryption_details: &(Aes256GcmSiv, [u8; 4]),
+        key: &Key,
+        record_type: &RecordType,
+        backend: &Arc<dyn RecordStorageBackend>,
+    ) -> Option<bool> {
+        let pretty_key = PrettyPrintRecordKey::from(key);
+        let record = match backend.read(key) {
+            Ok(Some(bytes)) => Self::get_record_from_bytes(bytes, key, encryption_details),
+            Ok(None) => {
+                warn!("Scrubbed record {pretty_key:?} is missing from disk");
+                None
+            }
+            Err(err) => {
+                warn!("Failed to read scrubbed record {pretty_key:?}, retrying in the next scrub pass: {err:?}");
+                return None;
+            }
+        };
+        let Some(record) = record else {
+            return Some(false);
+        };
+
+        let is_intact = match record_type {
+            // The xorname of a chunk is the hash of its content
+            RecordType::Chunk => {
+                matches!(
+                    RecordHeader::from_record(&record),
+                    Ok(RecordHeader {
+                        kind: RecordKind::Chunk
+                    })
+                ) && try_deserialize_record::<Chunk>(&record)
+                    .is_ok_and(|chunk| chunk.network_address().to_record_key() == *key)
+            }
+            // The content of a scratchpad changes, only its address can be checked
+            RecordType::Scratchpad => try_deserialize_record::<Scratchpad>(&record)
+                .is_ok_and(|scratchpad| scratchpad.network_address().to_record_key() == *key),
+            RecordType::NonChunk(content_hash) => {
+                XorName::from_content(&record.value) == *content_hash
+            }
+        };
+        if !is_intact {
+            warn!("Scrubbed record {pretty_key:?} of {record_type:?} doesn't match its key");
+        }
+        Some(is_intact)
+    }
+
+    /// Remove the corrupt records found by a scrub batch, returning the ones removed, to be
+    /// re-fetched from the network.
+    ///
+    /// A record written while being scrubbed is kept, even if of the same type as a scratchpad
+    /// overwritten with a new version, it is scrubbed again in the next pass.
+    pub(crate) fn scrub_completed(
+        &mut self,
+        checked: usize,
+        corrupt: Vec<(Key, RecordType)>,
+        pass_completed: bool,
+    ) -> Vec<(Key, RecordType)> {
+        let not_written_since = self.scrub_batch.take().unwrap_or_default();
+
+        let removed: Vec<_> = corrupt
+            .into_iter()
+            .filter(|(key, record_type)| {
+                not_written_since.contains(key)
+                    && matches!(self.records.get(key), Some((_, current_type)) if current_type == record_type)
+            })
+            .collect();
+        for (key, _) in removed.iter() {
+            error!(
+                "Removing corrupt record {:?} found by the scrubber",
+                PrettyPrintRecordKey::from(key)
+            );
+            self.remove(key);
+        }
+
+        self.scrub_stats.records_checked += checked as u64;
+        self.scrub_stats.corrupt_records += removed.len() as u64;
+        if pass_completed {
+            self.scrub_stats.passes_completed += 1;
+            info!(
+                "Completed a scrub pass over the record store: {:?}",
+                self.scrub_stats
+            );
+        }
+
+        removed
+    }
+
+    /// The outcome of the scrubs undertaken since the store got opened
+    pub(crate) fn scrub_stats(&self) -> RecordScrubStats {
+        self.scrub_stats
+    }
 }
 
 impl NodeRecordStore {
@@ -531,6 +837,14 @@ impl NodeRecordStore {
         }
     }
 
+    /// The size of a record value from the size of its bytes as stored
+    fn plaintext_size(stored_size: u64) -> u64 {
+        if !cfg!(feature = "encrypt-records") {
+            return stored_size;
+        }
+        stored_size.saturating_sub(RECORD_ENCRYPTION_OVERHEAD)
+    }
+
     /// Prepare record bytes for storage
     /// If feats are enabled, this will eg, encrypt the record for storage
     fn prepare_record_bytes(
@@ -566,40 +880,24 @@ impl NodeRecordStore {
         let record_key = PrettyPrintRecordKey::from(&r.key).into_owned();
         debug!("PUTting a verified Record: {record_key:?}");
 
-        // if the cache already has this record in it (eg, a conflicting spend)
-        // remove it from the cache
-        // self.records_cache.retain(|record| record.key != r.key);
-        // Remove from cache if it already exists
-        if let Some(&index) = self.records_cache_map.get(key) {
-            if let Some(existing_record) = self.records_cache.remove(index) {
-                if existing_record.value == r.value {
-                    // we actually just want to keep what we have, and can assume it's been stored properly.
-
-                    // so we put it back in the cache
-                    self.records_cache.insert(index, existing_record);
-                    // and exit early.
-                    return Ok(());
-                }
-            }
-            self.update_cache_indices(index);
+        // If the cache already has this very record in it, we can assume it's been stored properly.
+        // Otherwise (eg, a conflicting spend) the cached version gets replaced.
+        if self.records_cache.contains(key, &r.value) {
+            return Ok(());
         }
 
-        // Store in the FIFO records cache, removing the oldest if needed
-        if self.records_cache.len() >= self.config.records_cache_size {
-            if let Some(old_record) = self.records_cache.pop_front() {
-                self.records_cache_map.remove(&old_record.key);
-            }
+        let record_size = r.value.len() as u64;
+        self.prune_records_if_needed(key, record_size)?;
+        self.track_record_size(key.clone(), record_size);
+        // the scrub verifying the previous version of the record is stale
+        if let Some(scrub_batch) = self.scrub_batch.as_mut() {
+            let _ = scrub_batch.remove(key);
         }
 
-        // Push the new record to the back of the cache
-        self.records_cache.push_back(r.clone());
-        self.records_cache_map
-            .insert(key.clone(), self.records_cache.len() - 1);
-
-        self.prune_records_if_needed(key)?;
+        self.records_cache.insert(r.clone());
 
         let filename = Self::generate_filename(key);
-        let file_path = self.config.storage_dir.join(&filename);
+        let backend = Arc::clone(&self.backend);
 
         #[cfg(feature = "open-metrics")]
         if let Some(metric) = &self.record_count_metric {
@@ -610,10 +908,11 @@ impl NodeRecordStore {
         let cloned_cmd_sender = self.local_swarm_cmd_sender.clone();
 
         let record_key2 = record_key.clone();
-        spawn(async move {
+        // the backend syncs the write to disk, keep it off the runtime workers
+        let _handle = tokio::task::spawn_blocking(move || {
             let key = r.key.clone();
             if let Some(bytes) = Self::prepare_record_bytes(r, encryption_details) {
-                let cmd = match fs::write(&file_path, bytes) {
+                let cmd = match backend.write(&key, &bytes, Some(&record_type)) {
                     Ok(_) => {
                         // vdash metric (if modified please notify at https://github.com/happybeing/vdash/issues):
                         info!("Wrote record {record_key2:?} to disk! filename: {filename}");
@@ -635,18 +934,15 @@ impl NodeRecordStore {
         Ok(())
     }
 
-    /// Update the cache indices after removing an element
-    fn update_cache_indices(&mut self, start_index: usize) {
-        for index in start_index..self.records_cache.len() {
-            if let Some(record) = self.records_cache.get(index) {
-                self.records_cache_map.insert(record.key.clone(), index);
-            }
-        }
-    }
-
     /// Calculate the cost to store data for our current store state
-    pub(crate) fn store_cost(&self, key: &Key) -> (AttoTokens, QuotingMetrics) {
+    /// Without `quote_storage_bytes`, the storage is quoted by record count only, for the peers on an older protocol version.
+    pub(crate) fn store_cost(
+        &self,
+        key: &Key,
+        quote_storage_bytes: bool,
+    ) -> (AttoTokens, QuotingMetrics) {
         let records_stored = self.records.len();
+        let records_size = self.records_size(self.records.keys());
         let record_keys_as_hashset: HashSet<&Key> = self.records.keys().collect();
 
         let live_time = if let Ok(elapsed) = self.timestamp.elapsed() {
@@ -657,24 +953,38 @@ impl NodeRecordStore {
 
         let mut quoting_metrics = QuotingMetrics {
             close_records_stored: records_stored,
-            max_records: self.config.max_records,
+            max_records: (self.config.max_storage_bytes / MAX_CHUNK_RECORD_SIZE) as usize,
             received_payment_count: self.received_payment_count,
             live_time,
+            close_records_size: Some(records_size),
+            max_storage: Some(self.config.max_storage_bytes),
         };
 
         if let Some(distance_range) = self.responsible_distance_range {
+            let relevant_records_size = self.records_size(
+                record_keys_as_hashset
+                    .iter()
+                    .copied()
+                    .filter(|key| self.is_within_distance_range(key, distance_range)),
+            );
             let relevant_records =
                 self.get_records_within_distance_range(record_keys_as_hashset, distance_range);
 
             quoting_metrics.close_records_stored = relevant_records;
+            quoting_metrics.close_records_size = Some(relevant_records_size);
         } else {
             info!("Basing cost of _total_ records stored.");
         };
 
+        if !quote_storage_bytes {
+            quoting_metrics.close_records_size = None;
+            quoting_metrics.max_storage = None;
+        }
+
         let cost = if self.contains(key) {
             0
         } else {
-            calculate_cost_for_records(quoting_metrics.close_records_stored)
+            self.config.pricing_strategy.store_cost(&quoting_metrics)
         };
         // vdash metric (if modified please notify at https://github.com/happybeing/vdash/issues):
         info!("Cost is now {cost:?} for quoting_metrics {quoting_metrics:?}");
@@ -702,16 +1012,23 @@ impl NodeRecordStore {
 
         let relevant_records_len = records
             .iter()
-            .filter(|key| {
-                let kbucket_key = KBucketKey::new(key.to_vec());
-                distance_range >= self.local_key.distance(&kbucket_key)
-            })
+            .filter(|key| self.is_within_distance_range(key, distance_range))
             .count();
 
         Marker::CloseRecordsLen(relevant_records_len).log();
         relevant_records_len
     }
 
+    fn is_within_distance_range(&self, key: &Key, distance_range: Distance) -> bool {
+        let kbucket_key = KBucketKey::new(key.to_vec());
+        distance_range >= self.local_key.distance(&kbucket_key)
+    }
+
+    /// The total size in bytes of the given records
+    fn records_size<'a>(&self, keys: impl Iterator<Item = &'a Key>) -> u64 {
+        keys.filter_map(|key| self.record_sizes.get(key)).sum()
+    }
+
     /// Setup the distance range.
     pub(crate) fn set_responsible_distance_range(&mut self, farthest_distance: Distance) {
         self.responsible_distance_range = Some(farthest_distance);
@@ -728,10 +1045,9 @@ impl RecordStore for NodeRecordStore {
         // ignored if we don't have the record locally.
         let key = PrettyPrintRecordKey::from(k);
 
-        let cached_record = self.records_cache.iter().find(|r| r.key == *k);
-        // first return from FIFO cache if existing there
-        if let Some(record) = cached_record {
-            return Some(Cow::Borrowed(record));
+        // first return from the cache if existing there
+        if let Some(record) = self.records_cache.get(k) {
+            return Some(Cow::Owned(record));
         }
 
         if !self.records.contains_key(k) {
@@ -741,7 +1057,9 @@ impl RecordStore for NodeRecordStore {
 
         debug!("GET request for Record key: {key}");
 
-        Self::read_from_disk(&self.encryption_details, k, &self.config.storage_dir)
+        let record = Self::read_from_disk(&self.encryption_details, k, &self.backend)?;
+        self.records_cache.admit_read(&record);
+        Some(record)
     }
 
     fn put(&mut self, record: Record) -> Result<()> {
@@ -808,7 +1126,8 @@ impl RecordStore for NodeRecordStore {
 
     fn remove(&mut self, k: &Key) {
         let _ = self.records.remove(k);
-        self.records_cache.retain(|r| r.key != *k);
+        self.untrack_record_size(k);
+        self.records_cache.remove(k);
 
         #[cfg(feature = "open-metrics")]
         if let Some(metric) = &self.record_count_metric {
@@ -822,16 +1141,15 @@ impl RecordStore for NodeRecordStore {
         }
 
         let filename = Self::generate_filename(k);
-        let file_path = self.config.storage_dir.join(&filename);
+        let backend = Arc::clone(&self.backend);
+        let key = k.clone();
 
-        let _handle = spawn(async move {
-            match fs::remove_file(file_path) {
-                Ok(_) => {
-                    info!("Removed record from disk! filename: {filename}");
-                }
-                Err(err) => {
-                    error!("Error while removing file. filename: {filename}, error: {err:?}");
-                }
+        let _handle = tokio::task::spawn_blocking(move || match backend.remove(&key) {
+            Ok(_) => {
+                info!("Removed record from disk! filename: {filename}");
+            }
+            Err(err) => {
+                error!("Error while removing file. filename: {filename}, error: {err:?}");
             }
         });
     }
@@ -920,25 +1238,9 @@ impl RecordStore for ClientRecordStore {
     fn remove_provider(&mut self, _key: &Key, _provider: &PeerId) {}
 }
 
-// Using a linear growth function tweaked by `max_records`,
-// and gives an exponential pricing curve when storage reaches high.
-// and give extra reward (lower the quoting price to gain a better chance) to long lived nodes.
-pub fn calculate_cost_for_records(records_stored: usize) -> u64 {
-    use std::cmp::{max, min};
-
-    let max_records = MAX_RECORDS_COUNT;
-
-    let ori_cost = positive_input_0_1_sigmoid(records_stored as f64 / max_records as f64)
-        * MAX_STORE_COST as f64;
-
-    // Deploy a lower cap safe_guard to the store_cost
-    let charge = max(MIN_STORE_COST, ori_cost as u64);
-    // Deploy an upper cap safe_guard to the store_cost
-    min(MAX_STORE_COST, charge)
-}
-
-fn positive_input_0_1_sigmoid(x: f64) -> f64 {
-    1.0 / (1.0 + (-30.0 * (x - 0.5)).exp())
+/// The cost to store a record, as computed by the default `SigmoidPricing`.
+pub fn calculate_cost_for_records(quoting_metrics: &QuotingMetrics) -> u64 {
+    SigmoidPricing::default().store_cost(quoting_metrics)
 }
 
 #[expect(trivial_casts)]
@@ -946,9 +1248,11 @@ fn positive_input_0_1_sigmoid(x: f64) -> f64 {
 mod tests {
 
     use crate::get_fees_from_store_cost_responses;
+    use crate::pricing::{MAX_STORE_COST, MIN_STORE_COST};
 
     use super::*;
     use bls::SecretKey;
+    use itertools::Itertools;
     use xor_name::XorName;
 
     use bytes::Bytes;
@@ -1001,51 +1305,59 @@ mod tests {
         }
     }
 
+    fn quoting_metrics_at_percent(percent: u64) -> QuotingMetrics {
+        QuotingMetrics {
+            close_records_size: Some(DEFAULT_MAX_STORAGE_BYTES * percent / 100),
+            max_storage: Some(DEFAULT_MAX_STORAGE_BYTES),
+            ..Default::default()
+        }
+    }
+
     #[test]
     fn test_calculate_max_cost_for_records() {
-        let sut = calculate_cost_for_records(MAX_RECORDS_COUNT + 1);
+        let quoting_metrics = quoting_metrics_at_percent(100);
+        let quoting_metrics = QuotingMetrics {
+            close_records_size: quoting_metrics.close_records_size.map(|size| size + 1),
+            ..quoting_metrics
+        };
+        let sut = calculate_cost_for_records(&quoting_metrics);
         assert_eq!(sut, MAX_STORE_COST - 1);
     }
 
     #[test]
     fn test_calculate_50_percent_cost_for_records() {
-        let percent = MAX_RECORDS_COUNT * 50 / 100;
-        let sut = calculate_cost_for_records(percent);
+        let sut = calculate_cost_for_records(&quoting_metrics_at_percent(50));
 
         // at this point we should be at max cost
         assert_eq!(sut, 500000);
     }
     #[test]
     fn test_calculate_60_percent_cost_for_records() {
-        let percent = MAX_RECORDS_COUNT * 60 / 100;
-        let sut = calculate_cost_for_records(percent);
+        let sut = calculate_cost_for_records(&quoting_metrics_at_percent(60));
 
         // at this point we should be at max cost
-        assert_eq!(sut, 952541);
+        assert_eq!(sut, 952574);
     }
 
     #[test]
     fn test_calculate_65_percent_cost_for_records() {
-        let percent = MAX_RECORDS_COUNT * 65 / 100;
-        let sut = calculate_cost_for_records(percent);
+        let sut = calculate_cost_for_records(&quoting_metrics_at_percent(65));
 
         // at this point we should be at max cost
-        assert_eq!(sut, 989001);
+        assert_eq!(sut, 989013);
     }
 
     #[test]
     fn test_calculate_70_percent_cost_for_records() {
-        let percent = MAX_RECORDS_COUNT * 70 / 100;
-        let sut = calculate_cost_for_records(percent);
+        let sut = calculate_cost_for_records(&quoting_metrics_at_percent(70));
 
         // at this point we should be at max cost
-        assert_eq!(sut, 997523);
+        assert_eq!(sut, 997527);
     }
 
     #[test]
     fn test_calculate_80_percent_cost_for_records() {
-        let percent = MAX_RECORDS_COUNT * 80 / 100;
-        let sut = calculate_cost_for_records(percent);
+        let sut = calculate_cost_for_records(&quoting_metrics_at_percent(80));
 
         // at this point we should be at max cost
         assert_eq!(sut, 999876);
@@ -1053,18 +1365,23 @@ mod tests {
 
     #[test]
     fn test_calculate_90_percent_cost_for_records() {
-        let percent = MAX_RECORDS_COUNT * 90 / 100;
-        let sut = calculate_cost_for_records(percent);
+        let sut = calculate_cost_for_records(&quoting_metrics_at_percent(90));
         // at this point we should be at max cost
         assert_eq!(sut, 999993);
     }
 
     #[test]
     fn test_calculate_min_cost_for_records() {
-        let sut = calculate_cost_for_records(0);
+        let sut = calculate_cost_for_records(&quoting_metrics_at_percent(0));
         assert_eq!(sut, MIN_STORE_COST);
     }
 
+    #[test]
+    fn test_calculate_cost_without_capacity_is_max_cost() {
+        let sut = calculate_cost_for_records(&QuotingMetrics::default());
+        assert_eq!(sut, MAX_STORE_COST);
+    }
+
     #[test]
     fn put_get_remove_record() {
         fn prop(r: ArbitraryRecord) {
@@ -1088,15 +1405,16 @@ mod tests {
             Default::default(),
             network_event_sender,
             swarm_cmd_sender,
-        );
+        )
+        .expect("Failed to open record store");
 
-        let store_cost_before = store.store_cost(&r.key);
+        let store_cost_before = store.store_cost(&r.key, true);
         // An initial unverified put should not write to disk
         assert!(store.put(r.clone()).is_ok());
         assert!(store.get(&r.key).is_none());
         // Store cost should not change if no PUT has been added
         assert_eq!(
-            store.store_cost(&r.key).0,
+            store.store_cost(&r.key, true).0,
             store_cost_before.0,
             "store cost should not change over unverified put"
         );
@@ -1167,7 +1485,8 @@ mod tests {
             store_config,
             network_event_sender,
             swarm_cmd_sender,
-        );
+        )
+        .expect("Failed to open record store");
 
         // Create a chunk
         let chunk_data = Bytes::from_static(b"Test chunk data");
@@ -1231,7 +1550,8 @@ mod tests {
             store_config,
             network_event_sender,
             swarm_cmd_sender,
-        );
+        )
+        .expect("Failed to open record store");
 
         // Create a scratchpad
         let unencrypted_scratchpad_data = Bytes::from_static(b"Test scratchpad data");
@@ -1296,6 +1616,156 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn scrubbing_removes_corrupt_records() -> eyre::Result<()> {
+        let storage_dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        fs::create_dir_all(&storage_dir)?;
+        let store_config = NodeRecordStoreConfig {
+            storage_dir,
+            ..Default::default()
+        };
+        let (network_event_sender, _) = mpsc::channel(1);
+        let (swarm_cmd_sender, mut swarm_cmd_receiver) = mpsc::channel(10);
+        let mut store = NodeRecordStore::with_config(
+            PeerId::random(),
+            store_config,
+            network_event_sender,
+            swarm_cmd_sender,
+        )?;
+
+        let mut keys = vec![];
+        for i in 0..3u8 {
+            let chunk = Chunk::new(Bytes::from(vec![i; 100]));
+            let record = Record::new(
+                chunk.network_address().to_record_key(),
+                try_serialize_record(&chunk, RecordKind::Chunk)?.to_vec(),
+            );
+            keys.push(record.key.clone());
+            store.put_verified(record, RecordType::Chunk)?;
+
+            match swarm_cmd_receiver.recv().await {
+                Some(LocalSwarmCmd::AddLocalRecordAsStored { key, record_type }) => {
+                    store.mark_as_stored(key, record_type)
+                }
+                other => panic!("Unexpected cmd while storing a record: {other:?}"),
+            }
+        }
+
+        // Flip a byte of one of the chunks on disk, as bit rot would
+        let corrupt_key = keys[0].clone();
+        let mut bytes = store
+            .backend
+            .read(&corrupt_key)?
+            .expect("the record to be written");
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        store
+            .backend
+            .write(&corrupt_key, &bytes, Some(&RecordType::Chunk))?;
+
+        store.scrub_next_batch();
+        let (checked, corrupt, pass_completed) = match swarm_cmd_receiver.recv().await {
+            Some(LocalSwarmCmd::RecordStoreScrubbed {
+                checked,
+                corrupt,
+                pass_completed,
+            }) => (checked, corrupt, pass_completed),
+            other => panic!("Unexpected cmd while scrubbing: {other:?}"),
+        };
+        assert_eq!(checked, keys.len());
+        assert!(pass_completed);
+        assert_eq!(corrupt, vec![(corrupt_key.clone(), RecordType::Chunk)]);
+
+        let removed = store.scrub_completed(checked, corrupt, pass_completed);
+        assert_eq!(removed, vec![(corrupt_key.clone(), RecordType::Chunk)]);
+        assert!(!store.contains(&corrupt_key));
+        assert!(keys[1..].iter().all(|key| store.contains(key)));
+        assert_eq!(
+            store.scrub_stats(),
+            RecordScrubStats {
+                records_checked: 3,
+                corrupt_records: 1,
+                passes_completed: 1,
+            }
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn scrubbing_keeps_records_overwritten_meanwhile() -> eyre::Result<()> {
+        let storage_dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        fs::create_dir_all(&storage_dir)?;
+        let store_config = NodeRecordStoreConfig {
+            storage_dir,
+            ..Default::default()
+        };
+        let (network_event_sender, _) = mpsc::channel(1);
+        let (swarm_cmd_sender, mut swarm_cmd_receiver) = mpsc::channel(10);
+        let mut store = NodeRecordStore::with_config(
+            PeerId::random(),
+            store_config,
+            network_event_sender,
+            swarm_cmd_sender,
+        )?;
+
+        let owner_sk = SecretKey::random();
+        let mut scratchpad = Scratchpad::new(owner_sk.public_key(), 0);
+        let _ = scratchpad.update_and_sign(Bytes::from_static(b"first version"), &owner_sk);
+        let key = NetworkAddress::ScratchpadAddress(*scratchpad.address()).to_record_key();
+        let record = Record::new(
+            key.clone(),
+            try_serialize_record(&scratchpad, RecordKind::Scratchpad)?.to_vec(),
+        );
+        store.put_verified(record, RecordType::Scratchpad)?;
+        match swarm_cmd_receiver.recv().await {
+            Some(LocalSwarmCmd::AddLocalRecordAsStored { key, record_type }) => {
+                store.mark_as_stored(key, record_type)
+            }
+            other => panic!("Unexpected cmd while storing a record: {other:?}"),
+        }
+
+        let mut bytes = store.backend.read(&key)?.expect("the record to be written");
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        store
+            .backend
+            .write(&key, &bytes, Some(&RecordType::Scratchpad))?;
+
+        store.scrub_next_batch();
+        let (checked, corrupt, pass_completed) = match swarm_cmd_receiver.recv().await {
+            Some(LocalSwarmCmd::RecordStoreScrubbed {
+                checked,
+                corrupt,
+                pass_completed,
+            }) => (checked, corrupt, pass_completed),
+            other => panic!("Unexpected cmd while scrubbing: {other:?}"),
+        };
+        assert_eq!(corrupt, vec![(key.clone(), RecordType::Scratchpad)]);
+
+        // A new version of the scratchpad is written before the scrub outcome gets handled
+        let _ = scratchpad.update_and_sign(Bytes::from_static(b"second version"), &owner_sk);
+        let record = Record::new(
+            key.clone(),
+            try_serialize_record(&scratchpad, RecordKind::Scratchpad)?.to_vec(),
+        );
+        store.put_verified(record.clone(), RecordType::Scratchpad)?;
+        match swarm_cmd_receiver.recv().await {
+            Some(LocalSwarmCmd::AddLocalRecordAsStored { key, record_type }) => {
+                store.mark_as_stored(key, record_type)
+            }
+            other => panic!("Unexpected cmd while storing a record: {other:?}"),
+        }
+
+        let removed = store.scrub_completed(checked, corrupt, pass_completed);
+        assert!(removed.is_empty());
+        assert_eq!(
+            store.get(&key).map(|stored| stored.into_owned().value),
+            Some(record.value)
+        );
+        Ok(())
+    }
+
     #[tokio::test]
     async fn pruning_on_full() -> Result<()> {
         let max_iterations = 10;
@@ -1307,11 +1777,20 @@ mod tests {
         let storage_dir = temp_dir.join(unique_dir_name);
         fs::create_dir_all(&storage_dir).expect("Failed to create directory");
 
-        // Set the config::max_record to be 50, then generate 100 records
+        let generate_value = || match try_serialize_record(
+            &(0..50).map(|_| rand::random::<u8>()).collect::<Bytes>(),
+            RecordKind::Chunk,
+        ) {
+            Ok(value) => value.to_vec(),
+            Err(err) => panic!("Cannot generate record value {err:?}"),
+        };
+        let record_size = generate_value().len() as u64;
+
+        // Set the config::max_storage_bytes to fit 50 records, then generate 100 records
         // On storing the 51st to 100th record,
         // check there is an expected pruning behaviour got carried out.
         let store_config = NodeRecordStoreConfig {
-            max_records,
+            max_storage_bytes: max_records as u64 * record_size,
             storage_dir,
             ..Default::default()
         };
@@ -1324,7 +1803,8 @@ mod tests {
             store_config.clone(),
             network_event_sender,
             swarm_cmd_sender,
-        );
+        )
+        .expect("Failed to open record store");
         // keep track of everything ever stored, to check missing at the end are further away
         let mut stored_records_at_some_point: Vec<RecordKey> = vec![];
         let self_address = NetworkAddress::from_peer(self_id);
@@ -1336,13 +1816,7 @@ mod tests {
         for _ in 0..max_records * 2 {
             // println!("i: {i}");
             let record_key = NetworkAddress::from_peer(PeerId::random()).to_record_key();
-            let value = match try_serialize_record(
-                &(0..50).map(|_| rand::random::<u8>()).collect::<Bytes>(),
-                RecordKind::Chunk,
-            ) {
-                Ok(value) => value.to_vec(),
-                Err(err) => panic!("Cannot generate record value {err:?}"),
-            };
+            let value = generate_value();
             let record = Record {
                 key: record_key.clone(),
                 value,
@@ -1426,6 +1900,41 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn records_being_written_keep_their_space_reserved() -> eyre::Result<()> {
+        let value = try_serialize_record(&Bytes::from(vec![1; 50]), RecordKind::Chunk)?.to_vec();
+        let store_config = NodeRecordStoreConfig {
+            max_storage_bytes: 2 * value.len() as u64,
+            storage_dir: std::env::temp_dir().join(uuid::Uuid::new_v4().to_string()),
+            ..Default::default()
+        };
+        let (network_event_sender, _) = mpsc::channel(1);
+        let (swarm_cmd_sender, _) = mpsc::channel(1);
+        let mut store = NodeRecordStore::with_config(
+            PeerId::random(),
+            store_config,
+            network_event_sender,
+            swarm_cmd_sender,
+        )?;
+
+        let record = || Record {
+            key: NetworkAddress::from_peer(PeerId::random()).to_record_key(),
+            value: value.clone(),
+            publisher: None,
+            expires: None,
+        };
+
+        // none of the records is marked as stored yet, so none can be pruned
+        store.put_verified(record(), RecordType::Chunk)?;
+        store.put_verified(record(), RecordType::Chunk)?;
+        assert!(matches!(
+            store.put_verified(record(), RecordType::Chunk),
+            Err(Error::MaxRecords)
+        ));
+        assert!(store.stored_bytes <= 2 * value.len() as u64);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn get_records_within_bucket_range() -> eyre::Result<()> {
         let max_records = 50;
@@ -1436,7 +1945,6 @@ mod tests {
 
         // setup the store
         let store_config = NodeRecordStoreConfig {
-            max_records,
             storage_dir,
             ..Default::default()
         };
@@ -1448,7 +1956,8 @@ mod tests {
             store_config,
             network_event_sender,
             swarm_cmd_sender,
-        );
+        )
+        .expect("Failed to open record store");
 
         let mut stored_records: Vec<RecordKey> = vec![];
         let self_address = NetworkAddress::from_peer(self_id);
@@ -1532,7 +2041,8 @@ mod tests {
             store_config.clone(),
             network_event_sender.clone(),
             swarm_cmd_sender.clone(),
-        );
+        )
+        .expect("Failed to open record store");
 
         store.payment_received();
 
@@ -1544,7 +2054,8 @@ mod tests {
             store_config,
             network_event_sender,
             swarm_cmd_sender,
-        );
+        )
+        .expect("Failed to open record store");
 
         assert_eq!(1, new_store.received_payment_count);
         assert_eq!(store.timestamp, new_store.timestamp);
@@ -1552,208 +2063,331 @@ mod tests {
         Ok(())
     }
 
+    /// The average record size used by the payment simulation
+    const SIMULATED_RECORD_SIZE: u64 = 2 * 1024 * 1024;
+
+    fn simulated_quoting_metrics(peer: &PeerStats, hour: u64) -> QuotingMetrics {
+        let close_records_stored = peer.records_stored.load(Ordering::Relaxed);
+        QuotingMetrics {
+            close_records_stored,
+            max_records: (DEFAULT_MAX_STORAGE_BYTES / MAX_CHUNK_RECORD_SIZE) as usize,
+            received_payment_count: peer.payments_received.load(Ordering::Relaxed),
+            live_time: (peer.joined_hours_ago + hour) * 3600,
+            close_records_size: Some(close_records_stored as u64 * SIMULATED_RECORD_SIZE),
+            max_storage: Some(DEFAULT_MAX_STORAGE_BYTES),
+        }
+    }
+
     struct PeerStats {
         address: NetworkAddress,
         rewards_addr: RewardsAddress,
         records_stored: AtomicUsize,
         nanos_earned: AtomicU64,
         payments_received: AtomicUsize,
+        /// How long the peer has been in the network when the simulation starts
+        joined_hours_ago: u64,
     }
 
-    // takes a long time to run
-    #[ignore]
-    #[test]
-    fn address_distribution_sim() {
-        use rayon::prelude::*;
+    /// The parameters of a payment simulation.
+    struct PricingSimulation {
+        num_of_peers: usize,
+        num_of_chunks_per_hour: usize,
+        max_hours: u64,
+    }
+
+    /// The state of the network at the end of a simulated hour.
+    struct SimulatedHourReport {
+        empty_earned_nodes: usize,
+        min_earned: u64,
+        max_earned: u64,
+        min_store_cost: u64,
+        max_store_cost: u64,
+    }
 
+    impl Default for PricingSimulation {
         // as network saturates, we can see that peers all eventually earn similarly
-        let num_of_peers = 5_000;
-        let num_of_chunks_per_hour = 1_000_000;
-        let max_hours = 50;
-
-        //
-        let k = K_VALUE.get();
-
-        let replication_group_size = k / 3;
-
-        // Initialize peers with random addresses
-        let mut peers: Vec<PeerStats> = (0..num_of_peers)
-            .into_par_iter()
-            .map(|_| PeerStats {
-                address: NetworkAddress::from_peer(PeerId::random()),
-                records_stored: AtomicUsize::new(0),
-                nanos_earned: AtomicU64::new(0),
-                payments_received: AtomicUsize::new(0),
-                rewards_addr: dummy_address(),
-            })
-            .collect();
+        fn default() -> Self {
+            Self {
+                num_of_peers: 5_000,
+                num_of_chunks_per_hour: 1_000_000,
+                max_hours: 50,
+            }
+        }
+    }
 
-        let mut hour = 0;
-        let mut total_received_payment_count = 0;
+    impl PricingSimulation {
+        /// Simulate clients paying the cheapest node of the close group of random chunks,
+        /// with all the nodes quoting through `strategy`. Returns the report of every hour.
+        fn run(&self, strategy: &dyn PricingStrategy) -> Vec<SimulatedHourReport> {
+            use rayon::prelude::*;
 
-        let peers_len = peers.len();
+            let num_of_chunks_per_hour = self.num_of_chunks_per_hour;
 
-        // Generate a random sorting target address
-        let sorting_target_address =
-            NetworkAddress::from_chunk_address(ChunkAddress::new(XorName::default()));
+            //
+            let k = K_VALUE.get();
 
-        // Sort all peers based on their distance to the sorting target
-        peers.par_sort_by(|a, b| {
-            sorting_target_address
-                .distance(&a.address)
-                .cmp(&sorting_target_address.distance(&b.address))
-        });
+            let replication_group_size = k / 3;
 
-        loop {
-            // Parallel processing of chunks
-            let _chunk_results: Vec<_> = (0..num_of_chunks_per_hour)
+            // Initialize peers with random addresses, having joined the network at different times
+            let mut peers: Vec<PeerStats> = (0..self.num_of_peers)
                 .into_par_iter()
-                .map(|_| {
-                    // Generate a random chunk address
-                    let name = xor_name::rand::random();
-                    let chunk_address = NetworkAddress::from_chunk_address(ChunkAddress::new(name));
-
-                    let chunk_distance_to_sorting = sorting_target_address.distance(&chunk_address);
-                    // Binary search to find the insertion point for the chunk
-                    let partition_point = peers.partition_point(|peer| {
-                        sorting_target_address.distance(&peer.address) < chunk_distance_to_sorting
-                    });
-
-                    // Collect close_group_size closest peers
-                    let mut close_group = Vec::with_capacity(replication_group_size);
-                    let mut left = partition_point;
-                    let mut right = partition_point;
-
-                    while close_group.len() < replication_group_size
-                        && (left > 0 || right < peers_len)
-                    {
-                        if left > 0 {
-                            left -= 1;
-                            close_group.push(left);
-                        }
-                        if close_group.len() < replication_group_size && right < peers_len {
-                            close_group.push(right);
-                            right += 1;
-                        }
-                    }
+                .map(|i| PeerStats {
+                    address: NetworkAddress::from_peer(PeerId::random()),
+                    records_stored: AtomicUsize::new(0),
+                    nanos_earned: AtomicU64::new(0),
+                    payments_received: AtomicUsize::new(0),
+                    rewards_addr: dummy_address(),
+                    joined_hours_ago: (i % 100) as u64,
+                })
+                .collect();
 
-                    // Truncate to ensure we have exactly close_group_size peers
-                    close_group.truncate(replication_group_size);
+            let mut reports = vec![];
+            let mut total_received_payment_count = 0;
 
-                    // Find the cheapest payee among the close group
-                    let Ok((payee_index, cost)) = pick_cheapest_payee(&peers, &close_group) else {
-                        bail!("Failed to find a payee");
-                    };
+            let peers_len = peers.len();
 
-                    for &peer_index in &close_group {
-                        let peer = &peers[peer_index];
-                        peer.records_stored.fetch_add(1, Ordering::Relaxed);
+            // Generate a random sorting target address
+            let sorting_target_address =
+                NetworkAddress::from_chunk_address(ChunkAddress::new(XorName::default()));
 
-                        if peer_index == payee_index {
-                            peer.nanos_earned.fetch_add(
-                                cost.as_atto().try_into().unwrap_or(u64::MAX),
-                                Ordering::Relaxed,
-                            );
-                            peer.payments_received.fetch_add(1, Ordering::Relaxed);
+            // Sort all peers based on their distance to the sorting target
+            peers.par_sort_by(|a, b| {
+                sorting_target_address
+                    .distance(&a.address)
+                    .cmp(&sorting_target_address.distance(&b.address))
+            });
+
+            for hour in 0..self.max_hours {
+                // Parallel processing of chunks
+                let _chunk_results: Vec<_> = (0..num_of_chunks_per_hour)
+                    .into_par_iter()
+                    .map(|_| {
+                        // Generate a random chunk address
+                        let name = xor_name::rand::random();
+                        let chunk_address =
+                            NetworkAddress::from_chunk_address(ChunkAddress::new(name));
+
+                        let chunk_distance_to_sorting =
+                            sorting_target_address.distance(&chunk_address);
+                        // Binary search to find the insertion point for the chunk
+                        let partition_point = peers.partition_point(|peer| {
+                            sorting_target_address.distance(&peer.address)
+                                < chunk_distance_to_sorting
+                        });
+
+                        // Collect close_group_size closest peers
+                        let mut close_group = Vec::with_capacity(replication_group_size);
+                        let mut left = partition_point;
+                        let mut right = partition_point;
+
+                        while close_group.len() < replication_group_size
+                            && (left > 0 || right < peers_len)
+                        {
+                            if left > 0 {
+                                left -= 1;
+                                close_group.push(left);
+                            }
+                            if close_group.len() < replication_group_size && right < peers_len {
+                                close_group.push(right);
+                                right += 1;
+                            }
                         }
-                    }
 
-                    Ok(())
-                })
-                .collect();
+                        // Truncate to ensure we have exactly close_group_size peers
+                        close_group.truncate(replication_group_size);
+
+                        // Find the cheapest payee among the close group
+                        let Ok((payee_index, cost)) =
+                            pick_cheapest_payee(&peers, &close_group, strategy, hour)
+                        else {
+                            bail!("Failed to find a payee");
+                        };
+
+                        for &peer_index in &close_group {
+                            let peer = &peers[peer_index];
+                            peer.records_stored.fetch_add(1, Ordering::Relaxed);
+
+                            if peer_index == payee_index {
+                                peer.nanos_earned.fetch_add(
+                                    cost.as_atto().try_into().unwrap_or(u64::MAX),
+                                    Ordering::Relaxed,
+                                );
+                                peer.payments_received.fetch_add(1, Ordering::Relaxed);
+                            }
+                        }
 
-            // Parallel reduction to calculate statistics
-            let (
-                received_payment_count,
-                empty_earned_nodes,
-                min_earned,
-                max_earned,
-                min_store_cost,
-                max_store_cost,
-            ) = peers
-                .par_iter()
-                .map(|peer| {
-                    let cost =
-                        calculate_cost_for_records(peer.records_stored.load(Ordering::Relaxed));
-                    let earned = peer.nanos_earned.load(Ordering::Relaxed);
-                    (
-                        peer.payments_received.load(Ordering::Relaxed),
-                        if earned == 0 { 1 } else { 0 },
-                        earned,
-                        earned,
-                        cost,
-                        cost,
-                    )
-                })
-                .reduce(
-                    || (0, 0, u64::MAX, 0, u64::MAX, 0),
-                    |a, b| {
-                        let (
-                            a_received_payment_count,
-                            a_empty_earned_nodes,
-                            a_min_earned,
-                            a_max_earned,
-                            a_min_store_cost,
-                            a_max_store_cost,
-                        ) = a;
-                        let (
-                            b_received_payment_count,
-                            b_empty_earned_nodes,
-                            b_min_earned,
-                            b_max_earned,
-                            b_min_store_cost,
-                            b_max_store_cost,
-                        ) = b;
+                        Ok(())
+                    })
+                    .collect();
+
+                // Parallel reduction to calculate statistics
+                let (
+                    received_payment_count,
+                    empty_earned_nodes,
+                    min_earned,
+                    max_earned,
+                    min_store_cost,
+                    max_store_cost,
+                ) = peers
+                    .par_iter()
+                    .map(|peer| {
+                        let cost = strategy.store_cost(&simulated_quoting_metrics(peer, hour));
+                        let earned = peer.nanos_earned.load(Ordering::Relaxed);
                         (
-                            a_received_payment_count + b_received_payment_count,
-                            a_empty_earned_nodes + b_empty_earned_nodes,
-                            a_min_earned.min(b_min_earned),
-                            a_max_earned.max(b_max_earned),
-                            a_min_store_cost.min(b_min_store_cost),
-                            a_max_store_cost.max(b_max_store_cost),
+                            peer.payments_received.load(Ordering::Relaxed),
+                            if earned == 0 { 1 } else { 0 },
+                            earned,
+                            earned,
+                            cost,
+                            cost,
                         )
-                    },
-                );
+                    })
+                    .reduce(
+                        || (0, 0, u64::MAX, 0, u64::MAX, 0),
+                        |a, b| {
+                            let (
+                                a_received_payment_count,
+                                a_empty_earned_nodes,
+                                a_min_earned,
+                                a_max_earned,
+                                a_min_store_cost,
+                                a_max_store_cost,
+                            ) = a;
+                            let (
+                                b_received_payment_count,
+                                b_empty_earned_nodes,
+                                b_min_earned,
+                                b_max_earned,
+                                b_min_store_cost,
+                                b_max_store_cost,
+                            ) = b;
+                            (
+                                a_received_payment_count + b_received_payment_count,
+                                a_empty_earned_nodes + b_empty_earned_nodes,
+                                a_min_earned.min(b_min_earned),
+                                a_max_earned.max(b_max_earned),
+                                a_min_store_cost.min(b_min_store_cost),
+                                a_max_store_cost.max(b_max_store_cost),
+                            )
+                        },
+                    );
 
-            total_received_payment_count += num_of_chunks_per_hour;
-            assert_eq!(total_received_payment_count, received_payment_count);
+                total_received_payment_count += num_of_chunks_per_hour;
+                assert_eq!(total_received_payment_count, received_payment_count);
 
-            println!("After the completion of hour {hour} with {num_of_chunks_per_hour} chunks put, there are {empty_earned_nodes} nodes which earned nothing");
-            println!("\t\t with storecost variation of (min {min_store_cost} - max {max_store_cost}), and earned variation of (min {min_earned} - max {max_earned})");
+                println!("After the completion of hour {hour} with {num_of_chunks_per_hour} chunks put, there are {empty_earned_nodes} nodes which earned nothing");
+                println!("\t\t with storecost variation of (min {min_store_cost} - max {max_store_cost}), and earned variation of (min {min_earned} - max {max_earned})");
 
-            hour += 1;
+                reports.push(SimulatedHourReport {
+                    empty_earned_nodes,
+                    min_earned,
+                    max_earned,
+                    min_store_cost,
+                    max_store_cost,
+                });
+            }
 
-            // Check termination condition
-            if hour == max_hours {
-                let acceptable_percentage = 0.01; //%
+            reports
+        }
+    }
 
-                // Calculate acceptable empty nodes based on % of total nodes
-                let acceptable_empty_nodes =
-                    (num_of_peers as f64 * acceptable_percentage).ceil() as usize;
+    // takes a long time to run
+    #[ignore]
+    #[test]
+    fn address_distribution_sim() {
+        let simulation = PricingSimulation::default();
+        let reports = simulation.run(&SigmoidPricing::default());
+        let SimulatedHourReport {
+            empty_earned_nodes,
+            min_earned,
+            max_earned,
+            min_store_cost,
+            max_store_cost,
+        } = reports.last().expect("at least one hour simulated");
+
+        let acceptable_percentage = 0.01; //%
+
+        // Calculate acceptable empty nodes based on % of total nodes
+        let acceptable_empty_nodes =
+            (simulation.num_of_peers as f64 * acceptable_percentage).ceil() as usize;
+
+        // Assert conditions for termination
+        assert!(
+            *empty_earned_nodes <= acceptable_empty_nodes,
+            "More than {acceptable_percentage}% of nodes ({acceptable_empty_nodes}) still not earning: {empty_earned_nodes}"
+        );
+        assert!(
+            (max_store_cost / min_store_cost) < 1000000,
+            "store cost is not 'balanced', expected ratio max/min to be < 1000000, but was {}",
+            max_store_cost / min_store_cost
+        );
+        assert!(
+            (max_earned / min_earned) < 500000000,
+            "earning distribution is not balanced, expected to be < 500000000, but was {}",
+            max_earned / min_earned
+        );
+    }
 
-                // Assert conditions for termination
-                assert!(
-                    empty_earned_nodes <= acceptable_empty_nodes,
-                    "More than {acceptable_percentage}% of nodes ({acceptable_empty_nodes}) still not earning: {empty_earned_nodes}"
-                );
-                assert!(
-                    (max_store_cost / min_store_cost) < 1000000,
-                    "store cost is not 'balanced', expected ratio max/min to be < 1000000, but was {}",
-                    max_store_cost / min_store_cost
-                );
-                assert!(
-                    (max_earned / min_earned) < 500000000,
-                    "earning distribution is not balanced, expected to be < 500000000, but was {}",
-                    max_earned / min_earned
-                );
-                break;
-            }
+    // Compares the earning distributions of pricing strategies, along with the one set through
+    // `PRICING_STRATEGY_ENV` if any, e.g. run with
+    // `SN_PRICING_STRATEGY='{"steepness": 20.0}' cargo test --release -p sn_networking -- --ignored --nocapture pricing_strategies_sim`
+    // takes a long time to run
+    #[ignore]
+    #[test]
+    fn pricing_strategies_sim() -> eyre::Result<()> {
+        let mut strategies = vec![
+            ("default", SigmoidPricing::default()),
+            (
+                "live time discount",
+                SigmoidPricing {
+                    live_time_weight: 0.2,
+                    ..Default::default()
+                },
+            ),
+            (
+                "payment history raise",
+                SigmoidPricing {
+                    payment_history_weight: 0.2,
+                    ..Default::default()
+                },
+            ),
+        ];
+        if std::env::var(crate::PRICING_STRATEGY_ENV).is_ok() {
+            strategies.push(("from env", SigmoidPricing::from_env()?));
+        }
+
+        let simulation = PricingSimulation {
+            num_of_peers: 2_000,
+            num_of_chunks_per_hour: 200_000,
+            max_hours: 20,
+        };
+        let mut summaries = vec![];
+        for (name, strategy) in strategies {
+            println!("Simulating the {name} pricing strategy: {strategy:?}");
+            let reports = simulation.run(&strategy);
+            let last = reports.last().context("at least one hour simulated")?;
+            summaries.push(format!(
+                "{name}: {} nodes earned nothing, earned (min {} - max {}), storecost (min {} - max {})",
+                last.empty_earned_nodes,
+                last.min_earned,
+                last.max_earned,
+                last.min_store_cost,
+                last.max_store_cost
+            ));
+        }
+
+        println!("After {} hours:", simulation.max_hours);
+        for summary in summaries {
+            println!("\t{summary}");
         }
+        Ok(())
     }
 
     fn pick_cheapest_payee(
         peers: &[PeerStats],
         close_group: &[usize],
+        strategy: &dyn PricingStrategy,
+        hour: u64,
     ) -> eyre::Result<(usize, AttoTokens)> {
         let mut costs_vec = Vec::with_capacity(close_group.len());
         let mut address_to_index = BTreeMap::new();
@@ -1762,19 +2396,14 @@ mod tests {
             let peer = &peers[i];
             address_to_index.insert(peer.address.clone(), i);
 
-            let close_records_stored = peer.records_stored.load(Ordering::Relaxed);
-            let cost = AttoTokens::from(calculate_cost_for_records(close_records_stored));
+            let quoting_metrics = simulated_quoting_metrics(peer, hour);
+            let cost = AttoTokens::from(strategy.store_cost(&quoting_metrics));
 
             let quote = PaymentQuote {
                 content: XorName::default(), // unimportant for cost calc
                 cost,
                 timestamp: std::time::SystemTime::now(),
-                quoting_metrics: QuotingMetrics {
-                    close_records_stored: peer.records_stored.load(Ordering::Relaxed),
-                    max_records: MAX_RECORDS_COUNT,
-                    received_payment_count: 1, // unimportant for cost calc
-                    live_time: 0,              // unimportant for cost calc
-                },
+                quoting_metrics,
                 bad_nodes: vec![],
                 pub_key: bls::SecretKey::random().public_key().to_bytes().to_vec(),
                 signature: vec![],
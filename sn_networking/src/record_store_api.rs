@@ -7,7 +7,7 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 #![allow(clippy::mutable_key_type)] // for the Bytes in NetworkAddress
 
-use crate::record_store::{ClientRecordStore, NodeRecordStore};
+use crate::record_store::{ClientRecordStore, NodeRecordStore, RecordScrubStats};
 use libp2p::kad::{
     store::{RecordStore, Result},
     KBucketDistance, ProviderRecord, Record, RecordKey,
@@ -111,13 +111,17 @@ impl UnifiedRecordStore {
         }
     }
 
-    pub(crate) fn store_cost(&self, key: &RecordKey) -> (AttoTokens, QuotingMetrics) {
+    pub(crate) fn store_cost(
+        &self,
+        key: &RecordKey,
+        quote_storage_bytes: bool,
+    ) -> (AttoTokens, QuotingMetrics) {
         match self {
             Self::Client(_) => {
                 warn!("Calling store cost calculation at Client. This should not happen");
                 (AttoTokens::zero(), Default::default())
             }
-            Self::Node(store) => store.store_cost(key),
+            Self::Node(store) => store.store_cost(key, quote_storage_bytes),
         }
     }
 
@@ -167,4 +171,35 @@ impl UnifiedRecordStore {
             Self::Node(store) => store.cleanup_unrelevant_records(),
         }
     }
+
+    pub(crate) fn scrub_next_batch(&mut self) {
+        match self {
+            Self::Client(_store) => {
+                warn!("Calling scrub_next_batch at Client. This should not happen");
+            }
+            Self::Node(store) => store.scrub_next_batch(),
+        }
+    }
+
+    pub(crate) fn scrub_completed(
+        &mut self,
+        checked: usize,
+        corrupt: Vec<(RecordKey, RecordType)>,
+        pass_completed: bool,
+    ) -> Vec<(RecordKey, RecordType)> {
+        match self {
+            Self::Client(_store) => {
+                warn!("Calling scrub_completed at Client. This should not happen");
+                vec![]
+            }
+            Self::Node(store) => store.scrub_completed(checked, corrupt, pass_completed),
+        }
+    }
+
+    pub(crate) fn scrub_stats(&self) -> RecordScrubStats {
+        match self {
+            Self::Client(_store) => RecordScrubStats::default(),
+            Self::Node(store) => store.scrub_stats(),
+        }
+    }
 }
@@ -0,0 +1,96 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{RecordStorageBackend, RecordStorageBackendKind};
+use libp2p::kad::RecordKey as Key;
+use sn_protocol::storage::RecordType;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Stores each record in its own file, named after the hex encoded record key.
+///
+/// This backend has no index: the record types have to be worked out from the values when the store is reopened.
+#[derive(Debug)]
+pub struct FlatFileBackend {
+    storage_dir: PathBuf,
+}
+
+impl FlatFileBackend {
+    /// Open the backend, creating the storage dir if needed
+    pub fn open(storage_dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(storage_dir)?;
+        Ok(Self {
+            storage_dir: storage_dir.to_path_buf(),
+        })
+    }
+
+    fn file_path(&self, key: &Key) -> PathBuf {
+        self.storage_dir.join(hex::encode(key.as_ref()))
+    }
+}
+
+impl RecordStorageBackend for FlatFileBackend {
+    fn kind(&self) -> RecordStorageBackendKind {
+        RecordStorageBackendKind::FlatFile
+    }
+
+    fn write(&self, key: &Key, value: &[u8], _record_type: Option<&RecordType>) -> io::Result<()> {
+        fs::write(self.file_path(key), value)
+    }
+
+    fn read(&self, key: &Key) -> io::Result<Option<Vec<u8>>> {
+        match fs::read(self.file_path(key)) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn value_size(&self, key: &Key) -> io::Result<Option<u64>> {
+        match fs::metadata(self.file_path(key)) {
+            Ok(metadata) => Ok(Some(metadata.len())),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn remove(&self, key: &Key) -> io::Result<()> {
+        match fs::remove_file(self.file_path(key)) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+
+    fn keys(&self) -> io::Result<Vec<(Key, Option<RecordType>)>> {
+        let mut keys = vec![];
+        for entry in fs::read_dir(&self.storage_dir)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+            // files that are not named after a record key don't belong to this backend
+            match entry.file_name().to_str().map(hex::decode) {
+                Some(Ok(bytes)) => keys.push((Key::from(bytes), None)),
+                _ => debug!(
+                    "Ignoring file in the storage dir that is not a record: {:?}",
+                    entry.path()
+                ),
+            }
+        }
+        Ok(keys)
+    }
+
+    fn clear(&self) -> io::Result<()> {
+        for (key, _) in self.keys()? {
+            self.remove(&key)?;
+        }
+        Ok(())
+    }
+}
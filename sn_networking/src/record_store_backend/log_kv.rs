@@ -0,0 +1,996 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{RecordStorageBackend, RecordStorageBackendKind};
+use libp2p::kad::RecordKey as Key;
+use serde::{Deserialize, Serialize};
+use sn_protocol::storage::RecordType;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex, MutexGuard,
+    },
+    thread,
+};
+
+/// Sub directory of the storage dir holding the files of this backend
+const LOG_KV_DIR: &str = "log_kv";
+const LOG_FILENAME: &str = "records.log";
+const COMPACTED_LOG_FILENAME: &str = "records.log.compact";
+const CLEARED_LOG_FILENAME: &str = "records.log.clear";
+const INDEX_FILENAME: &str = "records.idx";
+const INDEX_TMP_FILENAME: &str = "records.idx.tmp";
+
+/// Magic bytes at the start of the log, followed by the generation of the log
+const LOG_MAGIC: &[u8; 8] = b"SNKVLOG1";
+const LOG_HEADER_LEN: u64 = 16;
+
+/// crc (4) + op (1) + key len (2) + meta len (4) + value len (4)
+const ENTRY_HEADER_LEN: usize = 15;
+const OP_PUT: u8 = 1;
+const OP_DELETE: u8 = 2;
+/// Fills the space of an entry whose write failed while later entries were being written
+const OP_PAD: u8 = 3;
+
+/// The index is written to disk every this many log entries, bounding the part of the log replayed at startup
+const INDEX_SNAPSHOT_INTERVAL: usize = 1024;
+
+/// The log is compacted once it holds more dead bytes than this, and more dead bytes than live ones
+const COMPACTION_MIN_DEAD_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Location of the latest value of a record in the log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexEntry {
+    entry_offset: u64,
+    entry_len: u64,
+    value_len: u32,
+    record_type: Option<RecordType>,
+}
+
+/// The index as written to disk, only valid for the log of the same generation and at least `log_len` long
+#[derive(Serialize, Deserialize)]
+struct IndexSnapshot {
+    generation: u64,
+    log_len: u64,
+    entries: Vec<(Vec<u8>, IndexEntry)>,
+}
+
+/// One entry read back from the log
+#[derive(Debug)]
+struct LogEntry {
+    op: u8,
+    key: Key,
+    record_type: Option<RecordType>,
+    value_len: u32,
+    /// The whole entry as written in the log
+    bytes: Vec<u8>,
+}
+
+/// An entry whose space is reserved in the log, but which is not in the index yet
+#[derive(Debug)]
+enum PendingWrite {
+    Writing(Key),
+    Written(LogEntry),
+    /// The write failed, leaving this many bytes to fill or truncate
+    Failed(u64),
+}
+
+#[derive(Debug)]
+struct LogState {
+    /// Shared with the readers and writers, which use it without holding the lock.
+    /// A compaction or a clear swaps in a new file, the readers still holding the previous one.
+    file: Arc<File>,
+    generation: u64,
+    /// End of the entries written and applied to the index
+    log_len: u64,
+    /// End of the space reserved by the writers, past `log_len` while entries are being written
+    reserved_len: u64,
+    /// The entries being written, by offset. They are applied to the index in log order once written.
+    pending: BTreeMap<u64, PendingWrite>,
+    /// Set while a compaction or a clear waits for the pending writes to swap the log, holding back new ones
+    swapping: bool,
+    index: HashMap<Key, IndexEntry>,
+    /// Bytes of the log used by the entries in the index
+    live_bytes: u64,
+    entries_since_snapshot: usize,
+    /// Sequence number of the last index snapshot taken
+    snapshot_seq: u64,
+}
+
+/// Stores the records in a single append-only log, with an index of the latest value of each key.
+///
+/// Every write is appended to the log and synced to disk before returning, so a record is either fully
+/// stored or not at all: an entry torn by a crash fails its checksum and is dropped when the log is reopened.
+/// Writers only take the lock to reserve the space of their entry and to apply it to the index, so
+/// concurrent writes are synced to disk in parallel, and applied to the index in log order.
+/// The index is periodically written next to the log, so that only the entries appended since have to be
+/// replayed at startup, and it holds the record types, so values never have to be read to reopen the store.
+/// Once most of the log is made of overwritten or removed values, it is compacted on a background thread
+/// by copying the live entries into a new log, without blocking reads and writes until the final swap.
+#[derive(Debug)]
+pub struct LogKvBackend {
+    log: Arc<RecordLog>,
+}
+
+#[derive(Debug)]
+struct RecordLog {
+    dir: PathBuf,
+    state: Mutex<LogState>,
+    /// Notified when pending writes are applied to the index, or when a log swap is done
+    state_changed: Condvar,
+    compacting: AtomicBool,
+    /// Serialises the index snapshot writes, holding the sequence number of the last one written
+    written_snapshot: Mutex<u64>,
+}
+
+impl LogKvBackend {
+    /// Open the backend under the storage dir, replaying the log written since the last index snapshot
+    pub fn open(storage_dir: &Path) -> io::Result<Self> {
+        let dir = storage_dir.join(LOG_KV_DIR);
+        fs::create_dir_all(&dir)?;
+
+        // a compaction or a clear interrupted before the swap leaves its partial log behind
+        for filename in [COMPACTED_LOG_FILENAME, CLEARED_LOG_FILENAME] {
+            let partial_path = dir.join(filename);
+            if partial_path.exists() {
+                warn!("Removing the log of an interrupted compaction or clear: {partial_path:?}");
+                fs::remove_file(&partial_path)?;
+            }
+        }
+
+        let log_path = dir.join(LOG_FILENAME);
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&log_path)?;
+        if file.metadata()?.len() < LOG_HEADER_LEN {
+            write_log_header(&file, rand::random())?;
+        }
+        let generation = read_log_header(&mut file)?;
+        let file_len = file.metadata()?.len();
+
+        let (mut index, replay_from) = match read_index_snapshot(&dir) {
+            Some(snapshot) if snapshot.generation == generation && snapshot.log_len <= file_len => {
+                let index = snapshot
+                    .entries
+                    .into_iter()
+                    .map(|(key, entry)| (Key::from(key), entry))
+                    .collect();
+                (index, snapshot.log_len)
+            }
+            _ => (HashMap::new(), LOG_HEADER_LEN),
+        };
+
+        let log_len = replay_log(&mut file, replay_from, &mut index)?;
+        if log_len < file_len {
+            warn!(
+                "Dropping {} bytes of torn or corrupted entries at the end of {log_path:?}",
+                file_len - log_len
+            );
+            file.set_len(log_len)?;
+            file.sync_all()?;
+        }
+        let live_bytes = index.values().map(|entry| entry.entry_len).sum();
+        info!(
+            "Opened record log {log_path:?} with {} records, replayed from offset {replay_from}",
+            index.len()
+        );
+
+        let log = RecordLog {
+            dir,
+            state: Mutex::new(LogState {
+                file: Arc::new(file),
+                generation,
+                log_len,
+                reserved_len: log_len,
+                pending: BTreeMap::new(),
+                swapping: false,
+                index,
+                live_bytes,
+                entries_since_snapshot: 0,
+                snapshot_seq: 0,
+            }),
+            state_changed: Condvar::new(),
+            compacting: AtomicBool::new(false),
+            written_snapshot: Mutex::new(0),
+        };
+        if replay_from < log_len {
+            let snapshot = log.lock_state()?.take_index_snapshot();
+            log.write_index_snapshot(snapshot)?;
+        }
+        Ok(Self { log: Arc::new(log) })
+    }
+
+    /// Rewrite the log with only the latest value of each record
+    pub fn compact(&self) -> io::Result<()> {
+        self.log.compact()
+    }
+
+    /// Append an entry to the log and sync it to disk.
+    /// Starts a compaction on a background thread once most of the log is dead.
+    fn append(&self, op: u8, key: &Key, meta: &[u8], value: &[u8]) -> io::Result<()> {
+        let bytes = encode_log_entry(op, key, meta, value)?;
+        let record_type = decode_meta(meta)?;
+        let entry_len = bytes.len() as u64;
+
+        // reserve the space of the entry, then write it and sync it without holding the lock
+        let (file, entry_offset) = {
+            let state = self.log.lock_state()?;
+            let mut state = self.log.wait_while(state, |state| state.swapping)?;
+            let entry_offset = state.reserved_len;
+            state.reserved_len += entry_len;
+            let _ = state
+                .pending
+                .insert(entry_offset, PendingWrite::Writing(key.clone()));
+            (Arc::clone(&state.file), entry_offset)
+        };
+        let written = write_all_at(&file, &bytes, entry_offset).and_then(|_| file.sync_data());
+
+        let (snapshot, needs_compaction) = {
+            let mut state = self.log.lock_state()?;
+            let pending_write = match written {
+                Ok(()) => PendingWrite::Written(LogEntry {
+                    op,
+                    key: key.clone(),
+                    record_type,
+                    value_len: value.len() as u32,
+                    bytes,
+                }),
+                Err(_) => PendingWrite::Failed(entry_len),
+            };
+            let _ = state.pending.insert(entry_offset, pending_write);
+            self.log.apply_written_entries(&mut state);
+
+            // the entry is applied once the ones before it in the log are written as well
+            let mut state = self
+                .log
+                .wait_while(state, |state| state.pending.contains_key(&entry_offset))?;
+            written?;
+
+            let snapshot = (state.entries_since_snapshot >= INDEX_SNAPSHOT_INTERVAL)
+                .then(|| state.take_index_snapshot());
+
+            let dead_bytes = state.log_len - LOG_HEADER_LEN - state.live_bytes;
+            (
+                snapshot,
+                dead_bytes > COMPACTION_MIN_DEAD_BYTES && dead_bytes > state.live_bytes,
+            )
+        };
+
+        if let Some(snapshot) = snapshot {
+            self.log.write_index_snapshot(snapshot)?;
+        }
+        if needs_compaction && !self.log.compacting.load(Ordering::SeqCst) {
+            let log = Arc::clone(&self.log);
+            let spawned = thread::Builder::new()
+                .name("record-log-compaction".to_string())
+                .spawn(move || {
+                    if let Err(err) = log.compact() {
+                        error!("Failed to compact record log: {err:?}");
+                    }
+                });
+            if let Err(err) = spawned {
+                error!("Failed to start the record log compaction: {err:?}");
+            }
+        }
+        Ok(())
+    }
+}
+
+impl LogState {
+    /// Copy the index to be written to disk, along with the sequence number of the snapshot
+    fn take_index_snapshot(&mut self) -> (u64, IndexSnapshot) {
+        self.entries_since_snapshot = 0;
+        self.snapshot_seq += 1;
+        let snapshot = IndexSnapshot {
+            generation: self.generation,
+            log_len: self.log_len,
+            entries: self
+                .index
+                .iter()
+                .map(|(key, entry)| (key.to_vec(), entry.clone()))
+                .collect(),
+        };
+        (self.snapshot_seq, snapshot)
+    }
+}
+
+impl RecordLog {
+    /// Apply the written entries at the start of the pending ones to the index, in log order
+    fn apply_written_entries(&self, state: &mut LogState) {
+        let mut applied = false;
+        while let Some(pending) = state.pending.first_entry() {
+            if matches!(pending.get(), PendingWrite::Writing(_)) {
+                break;
+            }
+            let entry_offset = *pending.key();
+            match pending.remove() {
+                PendingWrite::Written(entry) => {
+                    let key = entry.key.clone();
+                    let previous_live = state.index.get(&key).map(|entry| entry.entry_len);
+                    state.log_len = apply_log_entry(&mut state.index, entry, entry_offset);
+                    let current_live = state.index.get(&key).map(|entry| entry.entry_len);
+                    state.live_bytes = (state.live_bytes + current_live.unwrap_or(0))
+                        .saturating_sub(previous_live.unwrap_or(0));
+                    state.entries_since_snapshot += 1;
+                }
+                PendingWrite::Failed(entry_len) => {
+                    self.drop_failed_entry(state, entry_offset, entry_len);
+                }
+                PendingWrite::Writing(_) => unreachable!("checked above"),
+            }
+            applied = true;
+        }
+        if applied {
+            self.state_changed.notify_all();
+        }
+    }
+
+    /// Don't leave the partial entry of a failed write in front of the next ones: truncate it if it is
+    /// the last one, otherwise overwrite it with a padding entry skipped by the replay.
+    fn drop_failed_entry(&self, state: &mut LogState, entry_offset: u64, entry_len: u64) {
+        if entry_offset + entry_len == state.reserved_len {
+            state.reserved_len = entry_offset;
+            if let Err(err) = state.file.set_len(entry_offset) {
+                error!("Failed to truncate the failed record log entry at {entry_offset}: {err:?}");
+            }
+            return;
+        }
+
+        state.log_len = entry_offset + entry_len;
+        let padded = encode_log_entry(
+            OP_PAD,
+            &Key::from(Vec::new()),
+            &[],
+            &vec![0; entry_len as usize - ENTRY_HEADER_LEN],
+        )
+        .and_then(|bytes| write_all_at(&state.file, &bytes, entry_offset))
+        .and_then(|_| state.file.sync_data());
+        if let Err(err) = padded {
+            // the entries after it are lost on reopen, as the replay stops at the first invalid entry
+            error!("Failed to pad the failed record log entry at {entry_offset}: {err:?}");
+        }
+    }
+
+    /// Wait for the pending writes to be applied, holding back new ones, and swap in another log
+    fn swap_log<T>(&self, swap: impl FnOnce(&mut LogState) -> io::Result<T>) -> io::Result<T> {
+        let mut state = self.wait_while(self.lock_state()?, |state| state.swapping)?;
+        state.swapping = true;
+        let mut state = self.wait_while(state, |state| !state.pending.is_empty())?;
+        let swapped = swap(&mut state);
+        state.swapping = false;
+        self.state_changed.notify_all();
+        swapped
+    }
+
+    fn compact(&self) -> io::Result<()> {
+        if self.compacting.swap(true, Ordering::SeqCst) {
+            debug!("Record log compaction already in progress");
+            return Ok(());
+        }
+        let result = self.compact_log();
+        self.compacting.store(false, Ordering::SeqCst);
+        result
+    }
+
+    fn compact_log(&self) -> io::Result<()> {
+        let (live_entries, generation, copied_up_to) = {
+            let state = self.lock_state()?;
+            let mut live_entries: Vec<_> = state
+                .index
+                .iter()
+                .map(|(key, entry)| (key.clone(), entry.clone()))
+                .collect();
+            live_entries.sort_by_key(|(_, entry)| entry.entry_offset);
+            (live_entries, state.generation, state.log_len)
+        };
+        info!(
+            "Compacting record log, keeping {} records",
+            live_entries.len()
+        );
+
+        // copy the live entries without holding the lock, entries are never modified once written
+        let new_generation = generation.wrapping_add(1);
+        let compacted_path = self.dir.join(COMPACTED_LOG_FILENAME);
+        let mut compacted = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&compacted_path)?;
+        write_log_header(&compacted, new_generation)?;
+        let mut source = File::open(self.dir.join(LOG_FILENAME))?;
+        let mut new_index = HashMap::with_capacity(live_entries.len());
+        let mut new_len = LOG_HEADER_LEN;
+        for (key, entry) in live_entries {
+            let mut bytes = vec![0; entry.entry_len as usize];
+            let _ = source.seek(SeekFrom::Start(entry.entry_offset))?;
+            source.read_exact(&mut bytes)?;
+            compacted.write_all(&bytes)?;
+            let _ = new_index.insert(
+                key,
+                IndexEntry {
+                    entry_offset: new_len,
+                    ..entry
+                },
+            );
+            new_len += bytes.len() as u64;
+        }
+
+        // then bring in what was written meanwhile and swap the logs
+        let snapshot = self.swap_log(|state| {
+            if state.generation != generation {
+                info!("Record log was cleared during compaction, dropping the compacted log");
+                drop(compacted);
+                fs::remove_file(compacted_path)?;
+                return Ok(None);
+            }
+            let _ = source.seek(SeekFrom::Start(copied_up_to))?;
+            let mut reader = BufReader::new(source);
+            while let Some(entry) = read_log_entry(&mut reader)? {
+                compacted.write_all(&entry.bytes)?;
+                new_len = apply_log_entry(&mut new_index, entry, new_len);
+            }
+            compacted.sync_all()?;
+            fs::rename(&compacted_path, self.dir.join(LOG_FILENAME))?;
+            sync_dir(&self.dir)?;
+
+            let removed_bytes = state.log_len.saturating_sub(new_len);
+            state.file = Arc::new(compacted);
+            state.generation = new_generation;
+            state.log_len = new_len;
+            state.reserved_len = new_len;
+            state.live_bytes = new_index.values().map(|entry| entry.entry_len).sum();
+            state.index = new_index;
+            info!("Compacted record log, reclaimed {removed_bytes} bytes");
+            Ok(Some(state.take_index_snapshot()))
+        })?;
+        match snapshot {
+            Some(snapshot) => self.write_index_snapshot(snapshot),
+            None => Ok(()),
+        }
+    }
+
+    /// Wait on the state until the condition is false
+    fn wait_while<'a>(
+        &self,
+        state: MutexGuard<'a, LogState>,
+        condition: impl FnMut(&mut LogState) -> bool,
+    ) -> io::Result<MutexGuard<'a, LogState>> {
+        self.state_changed
+            .wait_while(state, condition)
+            .map_err(|_| io::Error::other("Record log lock poisoned"))
+    }
+
+    fn lock_state(&self) -> io::Result<MutexGuard<'_, LogState>> {
+        self.state
+            .lock()
+            .map_err(|_| io::Error::other("Record log lock poisoned"))
+    }
+
+    /// Write the index to disk, atomically replacing the previous snapshot.
+    /// A snapshot older than the one already written is skipped.
+    fn write_index_snapshot(&self, (seq, snapshot): (u64, IndexSnapshot)) -> io::Result<()> {
+        let mut written_seq = self
+            .written_snapshot
+            .lock()
+            .map_err(|_| io::Error::other("Record log index lock poisoned"))?;
+        if seq <= *written_seq {
+            return Ok(());
+        }
+
+        let bytes = rmp_serde::to_vec(&snapshot).map_err(io::Error::other)?;
+        let tmp_path = self.dir.join(INDEX_TMP_FILENAME);
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(&bytes)?;
+        file.sync_all()?;
+        fs::rename(tmp_path, self.dir.join(INDEX_FILENAME))?;
+        *written_seq = seq;
+        debug!(
+            "Wrote record log index with {} records",
+            snapshot.entries.len()
+        );
+        Ok(())
+    }
+}
+
+impl RecordStorageBackend for LogKvBackend {
+    fn kind(&self) -> RecordStorageBackendKind {
+        RecordStorageBackendKind::LogKv
+    }
+
+    fn write(&self, key: &Key, value: &[u8], record_type: Option<&RecordType>) -> io::Result<()> {
+        let meta = rmp_serde::to_vec(&record_type).map_err(io::Error::other)?;
+        self.append(OP_PUT, key, &meta, value)
+    }
+
+    fn read(&self, key: &Key) -> io::Result<Option<Vec<u8>>> {
+        let (file, entry) = {
+            let state = self.log.lock_state()?;
+            let Some(entry) = state.index.get(key).cloned() else {
+                return Ok(None);
+            };
+            (Arc::clone(&state.file), entry)
+        };
+        let mut bytes = vec![0; entry.entry_len as usize];
+        read_exact_at(&file, &mut bytes, entry.entry_offset)?;
+
+        if !entry_checksum_matches(&bytes) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Checksum mismatch for record {key:?} in the record log"),
+            ));
+        }
+        let value_start = bytes.len() - entry.value_len as usize;
+        Ok(Some(bytes.split_off(value_start)))
+    }
+
+    fn value_size(&self, key: &Key) -> io::Result<Option<u64>> {
+        let state = self.log.lock_state()?;
+        Ok(state.index.get(key).map(|entry| entry.value_len as u64))
+    }
+
+    fn remove(&self, key: &Key) -> io::Result<()> {
+        let held = {
+            let state = self.log.lock_state()?;
+            state.index.contains_key(key)
+                || state.pending.values().any(|pending| match pending {
+                    PendingWrite::Writing(pending_key) => pending_key == key,
+                    PendingWrite::Written(entry) => &entry.key == key,
+                    PendingWrite::Failed(_) => false,
+                })
+        };
+        if !held {
+            return Ok(());
+        }
+        self.append(OP_DELETE, key, &[], &[])
+    }
+
+    fn keys(&self) -> io::Result<Vec<(Key, Option<RecordType>)>> {
+        let state = self.log.lock_state()?;
+        Ok(state
+            .index
+            .iter()
+            .map(|(key, entry)| (key.clone(), entry.record_type.clone()))
+            .collect())
+    }
+
+    fn clear(&self) -> io::Result<()> {
+        let snapshot = self.log.swap_log(|state| {
+            let generation = state.generation.wrapping_add(1);
+
+            // swap in a new empty log rather than truncating the one the readers may still be reading
+            let cleared_path = self.log.dir.join(CLEARED_LOG_FILENAME);
+            let cleared = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(&cleared_path)?;
+            write_log_header(&cleared, generation)?;
+            fs::rename(&cleared_path, self.log.dir.join(LOG_FILENAME))?;
+            sync_dir(&self.log.dir)?;
+
+            state.file = Arc::new(cleared);
+            state.generation = generation;
+            state.log_len = LOG_HEADER_LEN;
+            state.reserved_len = LOG_HEADER_LEN;
+            state.index.clear();
+            state.live_bytes = 0;
+            Ok(state.take_index_snapshot())
+        })?;
+        self.log.write_index_snapshot(snapshot)
+    }
+}
+
+fn write_log_header(mut file: &File, generation: u64) -> io::Result<()> {
+    let _ = file.seek(SeekFrom::Start(0))?;
+    file.write_all(LOG_MAGIC)?;
+    file.write_all(&generation.to_le_bytes())?;
+    file.sync_all()
+}
+
+/// Returns the generation of the log
+fn read_log_header(file: &mut File) -> io::Result<u64> {
+    let _ = file.seek(SeekFrom::Start(0))?;
+    let mut header = [0; LOG_HEADER_LEN as usize];
+    file.read_exact(&mut header)?;
+    if &header[..LOG_MAGIC.len()] != LOG_MAGIC {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Not a record log, unexpected magic bytes",
+        ));
+    }
+    let mut generation = [0; 8];
+    generation.copy_from_slice(&header[LOG_MAGIC.len()..]);
+    Ok(u64::from_le_bytes(generation))
+}
+
+fn read_index_snapshot(dir: &Path) -> Option<IndexSnapshot> {
+    let bytes = fs::read(dir.join(INDEX_FILENAME)).ok()?;
+    match rmp_serde::from_slice(&bytes) {
+        Ok(snapshot) => Some(snapshot),
+        Err(err) => {
+            warn!("Ignoring unreadable record log index: {err:?}");
+            None
+        }
+    }
+}
+
+/// Apply the log entries from `offset` to the index, returns the offset of the end of the last valid entry
+fn replay_log(
+    file: &mut File,
+    offset: u64,
+    index: &mut HashMap<Key, IndexEntry>,
+) -> io::Result<u64> {
+    let _ = file.seek(SeekFrom::Start(offset))?;
+    let mut reader = BufReader::new(file);
+    let mut log_len = offset;
+    loop {
+        match read_log_entry(&mut reader) {
+            Ok(Some(entry)) => log_len = apply_log_entry(index, entry, log_len),
+            Ok(None) => break,
+            Err(err) if err.kind() == io::ErrorKind::InvalidData => {
+                warn!("Stopping record log replay at offset {log_len}: {err}");
+                break;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(log_len)
+}
+
+/// Update the index with an entry written at `offset`, returns the offset right after the entry
+fn apply_log_entry(index: &mut HashMap<Key, IndexEntry>, entry: LogEntry, offset: u64) -> u64 {
+    let entry_len = entry.bytes.len() as u64;
+    if entry.op == OP_PAD {
+        return offset + entry_len;
+    }
+    if entry.op == OP_PUT {
+        let _ = index.insert(
+            entry.key,
+            IndexEntry {
+                entry_offset: offset,
+                entry_len,
+                value_len: entry.value_len,
+                record_type: entry.record_type,
+            },
+        );
+    } else {
+        let _ = index.remove(&entry.key);
+    }
+    offset + entry_len
+}
+
+fn encode_log_entry(op: u8, key: &Key, meta: &[u8], value: &[u8]) -> io::Result<Vec<u8>> {
+    let key_len = u16::try_from(key.as_ref().len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Record key too long"))?;
+    let meta_len = u32::try_from(meta.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Record metadata too long"))?;
+    let value_len = u32::try_from(value.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Record value too long"))?;
+
+    let mut bytes =
+        Vec::with_capacity(ENTRY_HEADER_LEN + key.as_ref().len() + meta.len() + value.len());
+    bytes.extend_from_slice(&[0; 4]);
+    bytes.push(op);
+    bytes.extend_from_slice(&key_len.to_le_bytes());
+    bytes.extend_from_slice(&meta_len.to_le_bytes());
+    bytes.extend_from_slice(&value_len.to_le_bytes());
+    bytes.extend_from_slice(key.as_ref());
+    bytes.extend_from_slice(meta);
+    bytes.extend_from_slice(value);
+    let crc = crc32fast::hash(&bytes[4..]);
+    bytes[..4].copy_from_slice(&crc.to_le_bytes());
+    Ok(bytes)
+}
+
+fn entry_checksum_matches(bytes: &[u8]) -> bool {
+    bytes.len() >= ENTRY_HEADER_LEN && bytes[..4] == crc32fast::hash(&bytes[4..]).to_le_bytes()
+}
+
+fn decode_meta(meta: &[u8]) -> io::Result<Option<RecordType>> {
+    if meta.is_empty() {
+        return Ok(None);
+    }
+    rmp_serde::from_slice(meta).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// Read the next entry of the log.
+/// Returns `None` at the end of the log, and an `InvalidData` error for a torn or corrupted entry.
+fn read_log_entry(reader: &mut impl Read) -> io::Result<Option<LogEntry>> {
+    let mut header = [0; ENTRY_HEADER_LEN];
+    let read = read_up_to(reader, &mut header)?;
+    if read == 0 {
+        return Ok(None);
+    }
+    if read < ENTRY_HEADER_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Torn record log entry header",
+        ));
+    }
+
+    let op = header[4];
+    let key_len = u16::from_le_bytes([header[5], header[6]]) as usize;
+    let meta_len = u32::from_le_bytes([header[7], header[8], header[9], header[10]]) as usize;
+    let value_len = u32::from_le_bytes([header[11], header[12], header[13], header[14]]);
+    if op != OP_PUT && op != OP_DELETE && op != OP_PAD {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Unknown record log entry op {op}"),
+        ));
+    }
+
+    let body_len = key_len + meta_len + value_len as usize;
+    let mut bytes = Vec::with_capacity(ENTRY_HEADER_LEN + body_len);
+    bytes.extend_from_slice(&header);
+    bytes.resize(ENTRY_HEADER_LEN + body_len, 0);
+    if read_up_to(reader, &mut bytes[ENTRY_HEADER_LEN..])? < body_len {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Torn record log entry",
+        ));
+    }
+    if !entry_checksum_matches(&bytes) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Record log entry checksum mismatch",
+        ));
+    }
+
+    let key_end = ENTRY_HEADER_LEN + key_len;
+    let key = Key::from(bytes[ENTRY_HEADER_LEN..key_end].to_vec());
+    let record_type = decode_meta(&bytes[key_end..key_end + meta_len])?;
+    Ok(Some(LogEntry {
+        op,
+        key,
+        record_type,
+        value_len,
+        bytes,
+    }))
+}
+
+/// Like `read_exact`, but returns the number of bytes read when reaching the end of the input
+fn read_up_to(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match reader.read(&mut buf[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(read)
+}
+
+/// Read exactly `buf.len()` bytes at `offset`, without moving a cursor shared with other readers
+#[cfg(unix)]
+fn read_exact_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<()> {
+    std::os::unix::fs::FileExt::read_exact_at(file, buf, offset)
+}
+
+/// Read exactly `buf.len()` bytes at `offset`, without moving a cursor shared with other readers
+#[cfg(windows)]
+fn read_exact_at(file: &File, mut buf: &mut [u8], mut offset: u64) -> io::Result<()> {
+    use std::os::windows::fs::FileExt;
+    while !buf.is_empty() {
+        match file.seek_read(buf, offset) {
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => {
+                buf = &mut buf[n..];
+                offset += n as u64;
+            }
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+/// Write all of `buf` at `offset`
+#[cfg(unix)]
+fn write_all_at(file: &File, buf: &[u8], offset: u64) -> io::Result<()> {
+    std::os::unix::fs::FileExt::write_all_at(file, buf, offset)
+}
+
+/// Write all of `buf` at `offset`
+#[cfg(windows)]
+fn write_all_at(file: &File, mut buf: &[u8], mut offset: u64) -> io::Result<()> {
+    use std::os::windows::fs::FileExt;
+    while !buf.is_empty() {
+        match file.seek_write(buf, offset) {
+            Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(n) => {
+                buf = &buf[n..];
+                offset += n as u64;
+            }
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+/// Sync a directory so that a rename in it survives a crash
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    File::open(dir)?.sync_all()?;
+    #[cfg(not(unix))]
+    let _ = dir;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xor_name::XorName;
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "log_kv_backend_{}",
+            XorName::random(&mut rand::thread_rng())
+        ))
+    }
+
+    fn random_key() -> Key {
+        Key::new(&XorName::random(&mut rand::thread_rng()))
+    }
+
+    #[test]
+    fn write_read_remove_survive_reopen() -> eyre::Result<()> {
+        let dir = temp_dir();
+        let chunk_key = random_key();
+        let non_chunk_key = random_key();
+        let removed_key = random_key();
+        let non_chunk_type = RecordType::NonChunk(XorName::random(&mut rand::thread_rng()));
+
+        {
+            let backend = LogKvBackend::open(&dir)?;
+            backend.write(&chunk_key, b"chunk", Some(&RecordType::Chunk))?;
+            backend.write(&non_chunk_key, b"first", Some(&non_chunk_type))?;
+            backend.write(&non_chunk_key, b"second", Some(&non_chunk_type))?;
+            backend.write(&removed_key, b"removed", None)?;
+            backend.remove(&removed_key)?;
+
+            assert_eq!(backend.read(&non_chunk_key)?, Some(b"second".to_vec()));
+            assert_eq!(backend.read(&removed_key)?, None);
+        }
+
+        // no index snapshot was written, everything comes from replaying the log
+        let backend = LogKvBackend::open(&dir)?;
+        let mut keys = backend.keys()?;
+        keys.sort_by(|a, b| a.0.as_ref().cmp(b.0.as_ref()));
+        let mut expected = vec![
+            (chunk_key.clone(), Some(RecordType::Chunk)),
+            (non_chunk_key.clone(), Some(non_chunk_type)),
+        ];
+        expected.sort_by(|a, b| a.0.as_ref().cmp(b.0.as_ref()));
+        assert_eq!(keys, expected);
+        assert_eq!(backend.read(&chunk_key)?, Some(b"chunk".to_vec()));
+        assert_eq!(backend.read(&non_chunk_key)?, Some(b"second".to_vec()));
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn concurrent_writes_survive_reopen() -> eyre::Result<()> {
+        let dir = temp_dir();
+        let backend = Arc::new(LogKvBackend::open(&dir)?);
+        let keys: Vec<_> = (0..8).map(|_| random_key()).collect();
+        let writers: Vec<_> = keys
+            .iter()
+            .enumerate()
+            .map(|(i, key)| {
+                let backend = Arc::clone(&backend);
+                let key = key.clone();
+                thread::spawn(move || -> io::Result<()> {
+                    for round in 0..10u8 {
+                        backend.write(&key, &[i as u8, round], None)?;
+                        // every write is readable once it returns
+                        assert_eq!(backend.read(&key)?, Some(vec![i as u8, round]));
+                    }
+                    Ok(())
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().expect("writer thread panicked")?;
+        }
+        drop(backend);
+
+        let backend = LogKvBackend::open(&dir)?;
+        assert_eq!(backend.keys()?.len(), keys.len());
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(backend.read(key)?, Some(vec![i as u8, 9]));
+        }
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn torn_entry_is_dropped_on_reopen() -> eyre::Result<()> {
+        let dir = temp_dir();
+        let kept_key = random_key();
+        let torn_key = random_key();
+        {
+            let backend = LogKvBackend::open(&dir)?;
+            backend.write(&kept_key, &[1; 64], None)?;
+            backend.write(&torn_key, &[2; 64], None)?;
+        }
+
+        // simulate a crash in the middle of the last write
+        let log_path = dir.join(LOG_KV_DIR).join(LOG_FILENAME);
+        let log_len = fs::metadata(&log_path)?.len();
+        OpenOptions::new()
+            .write(true)
+            .open(&log_path)?
+            .set_len(log_len - 10)?;
+
+        let backend = LogKvBackend::open(&dir)?;
+        assert_eq!(backend.read(&kept_key)?, Some(vec![1; 64]));
+        assert_eq!(backend.read(&torn_key)?, None);
+
+        // the log is usable again after the torn entry
+        backend.write(&torn_key, &[3; 64], None)?;
+        drop(backend);
+        let backend = LogKvBackend::open(&dir)?;
+        assert_eq!(backend.read(&torn_key)?, Some(vec![3; 64]));
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn compaction_keeps_latest_values() -> eyre::Result<()> {
+        let dir = temp_dir();
+        let backend = LogKvBackend::open(&dir)?;
+        let keys: Vec<_> = (0..20).map(|_| random_key()).collect();
+        for round in 0..5u8 {
+            for key in keys.iter() {
+                backend.write(key, &[round; 1024], Some(&RecordType::Chunk))?;
+            }
+        }
+        for key in keys.iter().take(5) {
+            backend.remove(key)?;
+        }
+
+        let log_path = dir.join(LOG_KV_DIR).join(LOG_FILENAME);
+        let len_before = fs::metadata(&log_path)?.len();
+        backend.compact()?;
+        let len_after = fs::metadata(&log_path)?.len();
+        assert!(len_after < len_before / 4);
+
+        // writes after compaction land in the new log
+        let new_key = random_key();
+        backend.write(&new_key, b"new", None)?;
+        drop(backend);
+
+        let backend = LogKvBackend::open(&dir)?;
+        assert_eq!(backend.keys()?.len(), 16);
+        for key in keys.iter().take(5) {
+            assert_eq!(backend.read(key)?, None);
+        }
+        for key in keys.iter().skip(5) {
+            assert_eq!(backend.read(key)?, Some(vec![4; 1024]));
+        }
+        assert_eq!(backend.read(&new_key)?, Some(b"new".to_vec()));
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
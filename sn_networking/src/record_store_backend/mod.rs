@@ -0,0 +1,194 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Storage engines persisting the records of the `NodeRecordStore`.
+//!
+//! The `NodeRecordStore` keeps its index of records in memory and hands the (possibly encrypted)
+//! record bytes to a [`RecordStorageBackend`] to persist them. Two backends are available:
+//! - [`FlatFileBackend`]: one hex named file per record in the storage dir.
+//! - [`LogKvBackend`]: an append-only log with an index, written with fsync and compacted on a background thread.
+
+mod flat_file;
+mod log_kv;
+
+pub use flat_file::FlatFileBackend;
+pub use log_kv::LogKvBackend;
+
+use libp2p::kad::RecordKey as Key;
+use sn_protocol::storage::RecordType;
+use std::{
+    fmt::{self, Debug, Display},
+    io,
+    path::Path,
+    str::FromStr,
+    sync::Arc,
+};
+
+/// A storage engine for the records of the `NodeRecordStore`
+///
+/// The values handed to the backend are opaque bytes, already encrypted if record encryption is on.
+/// All the methods may be called concurrently from different threads.
+pub trait RecordStorageBackend: Debug + Send + Sync {
+    /// The kind of this backend
+    fn kind(&self) -> RecordStorageBackendKind;
+
+    /// Persist the value of a record, replacing any previous value for that key.
+    /// The record type is kept alongside the value if the backend has an index, so it doesn't have
+    /// to be worked out again from the value when the store is reopened.
+    fn write(&self, key: &Key, value: &[u8], record_type: Option<&RecordType>) -> io::Result<()>;
+
+    /// Read the value of a record, `None` if the backend holds no value for that key
+    fn read(&self, key: &Key) -> io::Result<Option<Vec<u8>>>;
+
+    /// The size in bytes of the value held for a record, `None` if the backend holds no value for that key
+    fn value_size(&self, key: &Key) -> io::Result<Option<u64>>;
+
+    /// Remove a record. Removing a key that is not held is not an error.
+    fn remove(&self, key: &Key) -> io::Result<()>;
+
+    /// List the keys held, along with their record type when the backend knows it without reading the value
+    fn keys(&self) -> io::Result<Vec<(Key, Option<RecordType>)>>;
+
+    /// Remove all the records
+    fn clear(&self) -> io::Result<()>;
+}
+
+/// The available [`RecordStorageBackend`]s
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum RecordStorageBackendKind {
+    /// One file per record, see [`FlatFileBackend`]
+    #[default]
+    FlatFile,
+    /// Append-only log with an index, see [`LogKvBackend`]
+    LogKv,
+}
+
+impl RecordStorageBackendKind {
+    /// Open the backend of this kind, storing its data under `storage_dir`
+    pub fn open(&self, storage_dir: &Path) -> io::Result<Arc<dyn RecordStorageBackend>> {
+        let backend: Arc<dyn RecordStorageBackend> = match self {
+            Self::FlatFile => Arc::new(FlatFileBackend::open(storage_dir)?),
+            Self::LogKv => Arc::new(LogKvBackend::open(storage_dir)?),
+        };
+        Ok(backend)
+    }
+}
+
+impl Display for RecordStorageBackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FlatFile => write!(f, "flat-file"),
+            Self::LogKv => write!(f, "log-kv"),
+        }
+    }
+}
+
+impl FromStr for RecordStorageBackendKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "flat-file" => Ok(Self::FlatFile),
+            "log-kv" => Ok(Self::LogKv),
+            other => Err(format!(
+                "Unknown record store backend {other:?}, expected one of: flat-file, log-kv"
+            )),
+        }
+    }
+}
+
+/// Copy every record of a backend into another one, returns the number of records copied.
+/// The source backend is left untouched.
+pub fn migrate_records(
+    from: &dyn RecordStorageBackend,
+    to: &dyn RecordStorageBackend,
+) -> io::Result<usize> {
+    let mut migrated = 0;
+    for (key, record_type) in from.keys()? {
+        match from.read(&key)? {
+            Some(value) => {
+                to.write(&key, &value, record_type.as_ref())?;
+                migrated += 1;
+            }
+            None => warn!("Record {key:?} listed by {} could not be read", from.kind()),
+        }
+    }
+    Ok(migrated)
+}
+
+/// Move all the records found in `storage_dir` from one backend to another.
+/// The records are only removed from the source backend once they are all written to the target.
+/// Returns the number of records moved.
+pub fn migrate_record_store(
+    storage_dir: &Path,
+    from: RecordStorageBackendKind,
+    to: RecordStorageBackendKind,
+) -> io::Result<usize> {
+    if from == to {
+        return Ok(0);
+    }
+    info!("Migrating record store at {storage_dir:?} from {from} to {to}");
+    let source = from.open(storage_dir)?;
+    let target = to.open(storage_dir)?;
+    let migrated = migrate_records(source.as_ref(), target.as_ref())?;
+    source.clear()?;
+    info!("Migrated {migrated} records from {from} to {to}");
+    Ok(migrated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xor_name::XorName;
+
+    #[test]
+    fn migrate_between_backends() -> eyre::Result<()> {
+        let temp_dir = std::env::temp_dir().join(format!(
+            "record_store_migration_{}",
+            XorName::random(&mut rand::thread_rng())
+        ));
+
+        let flat_file = RecordStorageBackendKind::FlatFile.open(&temp_dir)?;
+        let mut keys = vec![];
+        for i in 0..10u8 {
+            let key = Key::new(&XorName::random(&mut rand::thread_rng()));
+            flat_file.write(&key, &[i; 100], None)?;
+            keys.push(key);
+        }
+
+        let migrated = migrate_record_store(
+            &temp_dir,
+            RecordStorageBackendKind::FlatFile,
+            RecordStorageBackendKind::LogKv,
+        )?;
+        assert_eq!(migrated, keys.len());
+        assert!(flat_file.keys()?.is_empty());
+
+        let log_kv = RecordStorageBackendKind::LogKv.open(&temp_dir)?;
+        assert_eq!(log_kv.keys()?.len(), keys.len());
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(log_kv.read(key)?, Some(vec![i as u8; 100]));
+        }
+        drop(log_kv);
+
+        let migrated = migrate_record_store(
+            &temp_dir,
+            RecordStorageBackendKind::LogKv,
+            RecordStorageBackendKind::FlatFile,
+        )?;
+        assert_eq!(migrated, keys.len());
+        assert_eq!(flat_file.keys()?.len(), keys.len());
+        assert!(RecordStorageBackendKind::LogKv
+            .open(&temp_dir)?
+            .keys()?
+            .is_empty());
+
+        std::fs::remove_dir_all(temp_dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,239 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{NetworkError, Result};
+use libp2p::kad::{Record, RecordKey};
+use sn_protocol::{
+    storage::{try_deserialize_record, Chunk, RecordHeader, RecordKind, Scratchpad},
+    NetworkAddress, PrettyPrintRecordKey,
+};
+use sn_registers::SignedRegister;
+use sn_transfers::SignedSpend;
+
+/// Checks that a record fetched from a peer is a valid record for the key it was requested under.
+///
+/// - Chunks must hash to their address.
+/// - Registers must carry a valid owner signature and live at the requested address.
+/// - Scratchpads must be signed by the owner their address is derived from.
+/// - Spends must all be for the requested address and carry valid signatures.
+///
+/// Records with payment attached are only ever sent along with a PUT, so a peer
+/// returning one for a GET is serving something it should not have stored as is.
+pub(crate) fn validate_fetched_record(key: &RecordKey, record: &Record) -> Result<()> {
+    let invalid = |reason: String| NetworkError::InvalidRecord {
+        key: PrettyPrintRecordKey::from(key).into_owned(),
+        reason,
+    };
+
+    if record.key != *key {
+        return Err(invalid(format!(
+            "record is for a different key {:?}",
+            PrettyPrintRecordKey::from(&record.key)
+        )));
+    }
+
+    let header = RecordHeader::from_record(record)?;
+    let record_address = match header.kind {
+        RecordKind::Chunk => {
+            let chunk = try_deserialize_record::<Chunk>(record)?;
+            NetworkAddress::from_chunk_address(*chunk.address())
+        }
+        RecordKind::Register => {
+            let register = try_deserialize_record::<SignedRegister>(record)?;
+            register
+                .verify()
+                .map_err(|err| invalid(format!("register failed verification: {err}")))?;
+            NetworkAddress::from_register_address(*register.address())
+        }
+        RecordKind::Scratchpad => {
+            let scratchpad = try_deserialize_record::<Scratchpad>(record)?;
+            if !scratchpad.is_valid() {
+                return Err(invalid("scratchpad is not signed by its owner".to_string()));
+            }
+            scratchpad.network_address()
+        }
+        RecordKind::Spend => {
+            let spends = try_deserialize_record::<Vec<SignedSpend>>(record)?;
+            let Some(first) = spends.first() else {
+                return Err(invalid("record holds no spends".to_string()));
+            };
+            let address = first.address();
+            for spend in &spends {
+                if spend.address() != address {
+                    return Err(invalid(format!(
+                        "record holds spends for different addresses {address:?} and {:?}",
+                        spend.address()
+                    )));
+                }
+                spend
+                    .verify()
+                    .map_err(|err| invalid(format!("spend failed verification: {err}")))?;
+            }
+            NetworkAddress::from_spend_address(address)
+        }
+        kind @ (RecordKind::ChunkWithPayment
+        | RecordKind::RegisterWithPayment
+        | RecordKind::ScratchpadWithPayment) => {
+            return Err(invalid(format!("record kind {kind:?} is never served")));
+        }
+    };
+
+    if record_address.to_record_key() != *key {
+        return Err(invalid(format!(
+            "record content belongs at {record_address:?}"
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use sn_protocol::storage::try_serialize_record;
+    use sn_registers::{Permissions, Register};
+    use sn_transfers::{DerivedSecretKey, MainSecretKey, Spend, SpendReason};
+    use std::collections::{BTreeMap, BTreeSet};
+    use xor_name::XorName;
+
+    fn record_of<T: serde::Serialize>(key: RecordKey, data: &T, kind: RecordKind) -> Record {
+        Record::new(
+            key,
+            try_serialize_record(data, kind)
+                .expect("serialise record")
+                .to_vec(),
+        )
+    }
+
+    #[test]
+    fn chunk_must_hash_to_the_requested_key() {
+        let chunk = Chunk::new(Bytes::from_static(b"some chunk content"));
+        let key = chunk.network_address().to_record_key();
+        let record = record_of(key.clone(), &chunk, RecordKind::Chunk);
+        assert!(validate_fetched_record(&key, &record).is_ok());
+
+        // A peer serving other content under the same key.
+        let other = Chunk::new(Bytes::from_static(b"forged chunk content"));
+        let forged = record_of(key.clone(), &other, RecordKind::Chunk);
+        assert!(matches!(
+            validate_fetched_record(&key, &forged),
+            Err(NetworkError::InvalidRecord { .. })
+        ));
+    }
+
+    #[test]
+    fn scratchpad_must_be_signed_by_the_owner_of_its_address() {
+        let owner = bls::SecretKey::random();
+        let mut scratchpad = Scratchpad::new(owner.public_key(), 0);
+        let _ = scratchpad.update_and_sign_unencrypted(Bytes::from_static(b"data"), &owner);
+        let key = scratchpad.network_address().to_record_key();
+        let record = record_of(key.clone(), &scratchpad, RecordKind::Scratchpad);
+        assert!(validate_fetched_record(&key, &record).is_ok());
+
+        // Signed by someone else than the owner the address is derived from.
+        let intruder = bls::SecretKey::random();
+        let mut forged = Scratchpad::new(owner.public_key(), 0);
+        let _ = forged.update_and_sign_unencrypted(Bytes::from_static(b"evil"), &intruder);
+        let record = record_of(key.clone(), &forged, RecordKind::Scratchpad);
+        assert!(validate_fetched_record(&key, &record).is_err());
+
+        // A valid scratchpad of another owner served under this key.
+        let mut other = Scratchpad::new(intruder.public_key(), 0);
+        let _ = other.update_and_sign_unencrypted(Bytes::from_static(b"data"), &intruder);
+        let record = record_of(key.clone(), &other, RecordKind::Scratchpad);
+        assert!(validate_fetched_record(&key, &record).is_err());
+    }
+
+    #[test]
+    fn register_must_verify_and_live_at_the_requested_key() {
+        let owner = bls::SecretKey::random();
+        let new_register = || {
+            Register::new(
+                owner.public_key(),
+                XorName::random(&mut rand::thread_rng()),
+                Permissions::new_anyone_can_write(),
+            )
+        };
+        let base_register = new_register();
+        let signature = owner.sign(base_register.bytes().expect("register bytes"));
+        let register = SignedRegister::new(base_register, signature, BTreeSet::new());
+        let key = NetworkAddress::from_register_address(*register.address()).to_record_key();
+        let record = record_of(key.clone(), &register, RecordKind::Register);
+        assert!(validate_fetched_record(&key, &record).is_ok());
+
+        // A correctly signed register, but not the one that was asked for.
+        let other_key =
+            NetworkAddress::from_register_address(*new_register().address()).to_record_key();
+        let record = record_of(other_key.clone(), &register, RecordKind::Register);
+        assert!(validate_fetched_record(&other_key, &record).is_err());
+
+        // The owner signature does not cover this register.
+        let unsigned = SignedRegister::new(
+            new_register(),
+            bls::SecretKey::random().sign(b"something else"),
+            BTreeSet::new(),
+        );
+        let key = NetworkAddress::from_register_address(*unsigned.address()).to_record_key();
+        let record = record_of(key.clone(), &unsigned, RecordKind::Register);
+        assert!(validate_fetched_record(&key, &record).is_err());
+    }
+
+    #[test]
+    fn spends_must_verify_and_all_live_at_the_requested_key() {
+        let owner = MainSecretKey::random();
+        let mut rng = rand::thread_rng();
+        let new_key = |rng: &mut _| owner.random_derived_key(rng);
+        let spend_of = |derived_sk: &DerivedSecretKey, descendant: &DerivedSecretKey| {
+            let spend = Spend {
+                unique_pubkey: derived_sk.unique_pubkey(),
+                reason: SpendReason::default(),
+                ancestors: BTreeSet::new(),
+                descendants: BTreeMap::from([(descendant.unique_pubkey(), 1.into())]),
+                royalties: vec![],
+            };
+            SignedSpend::sign(spend, derived_sk)
+        };
+
+        let derived_sk = new_key(&mut rng);
+        let spend = spend_of(&derived_sk, &new_key(&mut rng));
+        let key = NetworkAddress::from_spend_address(spend.address()).to_record_key();
+        let record = record_of(key.clone(), &vec![spend.clone()], RecordKind::Spend);
+        assert!(validate_fetched_record(&key, &record).is_ok());
+
+        // A double spend is still served as is, for the client to see it.
+        let double_spend = spend_of(&derived_sk, &new_key(&mut rng));
+        let record = record_of(
+            key.clone(),
+            &vec![spend.clone(), double_spend],
+            RecordKind::Spend,
+        );
+        assert!(validate_fetched_record(&key, &record).is_ok());
+
+        let record = record_of(key.clone(), &Vec::<SignedSpend>::new(), RecordKind::Spend);
+        assert!(validate_fetched_record(&key, &record).is_err());
+
+        // Mixed with a spend of another address.
+        let other = spend_of(&new_key(&mut rng), &new_key(&mut rng));
+        let record = record_of(
+            key.clone(),
+            &vec![spend.clone(), other.clone()],
+            RecordKind::Spend,
+        );
+        assert!(validate_fetched_record(&key, &record).is_err());
+
+        // A valid spend, but not for the requested address.
+        let record = record_of(key.clone(), &vec![other], RecordKind::Spend);
+        assert!(validate_fetched_record(&key, &record).is_err());
+
+        // Not signed by the key being spent.
+        let forged = SignedSpend::sign(spend.spend.clone(), &new_key(&mut rng));
+        let record = record_of(key.clone(), &vec![forged], RecordKind::Spend);
+        assert!(validate_fetched_record(&key, &record).is_err());
+    }
+}
@@ -0,0 +1,314 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use libp2p::kad::{Record, RecordKey};
+#[cfg(feature = "open-metrics")]
+use prometheus_client::metrics::{counter::Counter, gauge::Gauge};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Mutex, MutexGuard},
+};
+
+/// The number of accesses after which all the access counts are halved,
+/// so the records popular a while ago make room for the ones popular now.
+const ACCESS_COUNT_HALVING_PERIOD: u64 = 10_000;
+
+/// The max number of records, not cached, whose access counts are tracked.
+const MAX_GHOST_ENTRIES: usize = 10_000;
+
+struct CachedRecord {
+    record: Record,
+    access_count: u64,
+    last_access: u64,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<RecordKey, CachedRecord>,
+    /// The cached records by (access count, last access), the first one being evicted first.
+    eviction_order: BTreeMap<(u64, u64), RecordKey>,
+    /// The access counts of the records read from disk but not cached.
+    ghosts: HashMap<RecordKey, u64>,
+    cached_bytes: usize,
+    clock: u64,
+}
+
+impl CacheState {
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        if self.clock % ACCESS_COUNT_HALVING_PERIOD == 0 {
+            self.halve_access_counts();
+        }
+        self.clock
+    }
+
+    fn halve_access_counts(&mut self) {
+        self.eviction_order.clear();
+        for (key, entry) in self.entries.iter_mut() {
+            entry.access_count /= 2;
+            let _ = self
+                .eviction_order
+                .insert((entry.access_count, entry.last_access), key.clone());
+        }
+        self.ghosts.retain(|_, access_count| {
+            *access_count /= 2;
+            *access_count > 0
+        });
+    }
+
+    fn insert(&mut self, record: Record, access_count: u64) {
+        let last_access = self.tick();
+        let key = record.key.clone();
+        self.cached_bytes += record.value.len();
+        let _ = self
+            .eviction_order
+            .insert((access_count, last_access), key.clone());
+        if let Some(replaced) = self.entries.insert(
+            key,
+            CachedRecord {
+                record,
+                access_count,
+                last_access,
+            },
+        ) {
+            self.forget(&replaced);
+        }
+    }
+
+    fn remove(&mut self, key: &RecordKey) -> Option<CachedRecord> {
+        let removed = self.entries.remove(key)?;
+        self.forget(&removed);
+        Some(removed)
+    }
+
+    fn forget(&mut self, entry: &CachedRecord) {
+        self.cached_bytes -= entry.record.value.len();
+        let _ = self
+            .eviction_order
+            .remove(&(entry.access_count, entry.last_access));
+    }
+
+    /// Evict the least popular records until `size` more bytes fit under `max_bytes`,
+    /// keeping track of their access counts in case they get popular again.
+    fn evict_for(&mut self, size: usize, max_bytes: usize) {
+        while self.cached_bytes + size > max_bytes {
+            let Some((_, key)) = self.eviction_order.pop_first() else {
+                break;
+            };
+            if let Some(evicted) = self.entries.remove(&key) {
+                self.cached_bytes -= evicted.record.value.len();
+                self.track_ghost(key, evicted.access_count);
+            }
+        }
+    }
+
+    fn track_ghost(&mut self, key: RecordKey, access_count: u64) {
+        if self.ghosts.len() >= MAX_GHOST_ENTRIES && !self.ghosts.contains_key(&key) {
+            self.halve_access_counts();
+            if self.ghosts.len() >= MAX_GHOST_ENTRIES {
+                return;
+            }
+        }
+        let _ = self.ghosts.insert(key, access_count);
+    }
+
+    /// The access count of the most popular record evicted to make room for `size` more bytes.
+    fn eviction_cost(&self, size: usize, max_bytes: usize) -> u64 {
+        let mut freed = 0;
+        let mut cost = 0;
+        for ((access_count, _), key) in self.eviction_order.iter() {
+            if self.cached_bytes - freed + size <= max_bytes {
+                break;
+            }
+            cost = *access_count;
+            freed += self
+                .entries
+                .get(key)
+                .map(|entry| entry.record.value.len())
+                .unwrap_or_default();
+        }
+        cost
+    }
+}
+
+/// The metrics reporting how well the cache serves the records.
+#[cfg(feature = "open-metrics")]
+pub(crate) struct RecordsCacheMetrics {
+    pub(crate) hits: Counter,
+    pub(crate) misses: Counter,
+    pub(crate) cached_bytes: Gauge,
+}
+
+/// An in-memory cache of records bounded by the bytes it holds, sparing the popular records
+/// from being re-read from disk and decrypted on every request.
+///
+/// The least frequently accessed record is evicted first, the least recently accessed one among
+/// the equally popular. A record written is always cached, while a record read from disk is
+/// only cached once accessed more often than the records it would evict, so a sweep over
+/// many records doesn't flush the popular ones. The access counts are halved periodically,
+/// aging past popularity out.
+pub(crate) struct RecordsCache {
+    max_bytes: usize,
+    state: Mutex<CacheState>,
+    #[cfg(feature = "open-metrics")]
+    metrics: Option<RecordsCacheMetrics>,
+}
+
+impl RecordsCache {
+    pub(crate) fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            state: Mutex::new(CacheState::default()),
+            #[cfg(feature = "open-metrics")]
+            metrics: None,
+        }
+    }
+
+    #[cfg(feature = "open-metrics")]
+    pub(crate) fn set_metrics(&mut self, metrics: RecordsCacheMetrics) {
+        self.metrics = Some(metrics);
+    }
+
+    fn state(&self) -> MutexGuard<'_, CacheState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Returns the cached record, counting an access to it.
+    pub(crate) fn get(&self, key: &RecordKey) -> Option<Record> {
+        let mut state = self.state();
+        let entry = state.remove(key)?;
+        let record = entry.record.clone();
+        state.insert(entry.record, entry.access_count + 1);
+
+        #[cfg(feature = "open-metrics")]
+        if let Some(metrics) = &self.metrics {
+            let _ = metrics.hits.inc();
+        }
+        Some(record)
+    }
+
+    /// Whether the record is cached with the provided value.
+    pub(crate) fn contains(&self, key: &RecordKey, value: &[u8]) -> bool {
+        self.state()
+            .entries
+            .get(key)
+            .is_some_and(|entry| entry.record.value == value)
+    }
+
+    /// Cache a record just written, evicting the least popular records to make room for it.
+    pub(crate) fn insert(&self, record: Record) {
+        let size = record.value.len();
+        let mut state = self.state();
+        let access_count = state
+            .remove(&record.key)
+            .map(|entry| entry.access_count)
+            .unwrap_or(1);
+        if size <= self.max_bytes {
+            state.evict_for(size, self.max_bytes);
+            state.insert(record, access_count);
+        }
+        self.report_cached_bytes(state.cached_bytes);
+    }
+
+    /// Counts an access to a record that had to be read from disk, caching it if it got more
+    /// popular than the records it would evict.
+    pub(crate) fn admit_read(&self, record: &Record) {
+        #[cfg(feature = "open-metrics")]
+        if let Some(metrics) = &self.metrics {
+            let _ = metrics.misses.inc();
+        }
+
+        let size = record.value.len();
+        if size > self.max_bytes {
+            return;
+        }
+
+        let mut state = self.state();
+        let _ = state.tick();
+        let access_count = state.ghosts.remove(&record.key).unwrap_or_default() + 1;
+        if state.eviction_cost(size, self.max_bytes) >= access_count {
+            state.track_ghost(record.key.clone(), access_count);
+            return;
+        }
+
+        state.evict_for(size, self.max_bytes);
+        state.insert(record.clone(), access_count);
+        self.report_cached_bytes(state.cached_bytes);
+    }
+
+    pub(crate) fn remove(&self, key: &RecordKey) {
+        let mut state = self.state();
+        let _ = state.remove(key);
+        let _ = state.ghosts.remove(key);
+        self.report_cached_bytes(state.cached_bytes);
+    }
+
+    #[cfg_attr(not(feature = "open-metrics"), allow(unused_variables))]
+    fn report_cached_bytes(&self, cached_bytes: usize) {
+        #[cfg(feature = "open-metrics")]
+        if let Some(metrics) = &self.metrics {
+            let _ = metrics.cached_bytes.set(cached_bytes as i64);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(id: u8, size: usize) -> Record {
+        Record::new(RecordKey::new(&[id]), vec![id; size])
+    }
+
+    #[test]
+    fn evicts_the_least_popular_records_to_stay_under_the_bytes_bound() {
+        let cache = RecordsCache::new(300);
+        for id in 0..3 {
+            cache.insert(record(id, 100));
+        }
+        // make the first and last records more popular than the second
+        assert!(cache.get(&record(0, 0).key).is_some());
+        assert!(cache.get(&record(2, 0).key).is_some());
+
+        cache.insert(record(3, 100));
+        assert!(cache.get(&record(1, 0).key).is_none());
+        for id in [0, 2, 3] {
+            assert!(cache.get(&record(id, 0).key).is_some());
+        }
+
+        // a single record larger than the cache is never cached
+        cache.insert(record(4, 301));
+        assert!(cache.get(&record(4, 0).key).is_none());
+        assert!(cache.state().cached_bytes <= 300);
+    }
+
+    #[test]
+    fn records_read_once_do_not_flush_the_popular_ones() {
+        let cache = RecordsCache::new(200);
+        for id in 0..2 {
+            cache.insert(record(id, 100));
+            assert!(cache.get(&record(id, 0).key).is_some());
+        }
+
+        // a sweep over many records read from disk once
+        for id in 10..50 {
+            cache.admit_read(&record(id, 100));
+        }
+        for id in 0..2 {
+            assert!(cache.get(&record(id, 0).key).is_some());
+        }
+
+        // a record getting read more often than the cached ones makes it in
+        for _ in 0..5 {
+            cache.admit_read(&record(60, 100));
+        }
+        assert!(cache.get(&record(60, 0).key).is_some());
+    }
+}
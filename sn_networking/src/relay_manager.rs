@@ -6,16 +6,116 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::driver::{BadNodes, NodeBehaviour};
+use crate::{
+    driver::{NodeBehaviour, MAX_PACKET_SIZE},
+    reputation::PeerReputations,
+    target_arch::{Duration, Instant, SystemTime},
+};
 use itertools::Itertools;
 use libp2p::{
-    core::transport::ListenerId, multiaddr::Protocol, Multiaddr, PeerId, StreamProtocol, Swarm,
+    core::transport::ListenerId, multiaddr::Protocol, relay, Multiaddr, PeerId, StreamProtocol,
+    Swarm,
+};
+use rand::seq::SliceRandom;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    net::SocketAddr,
 };
-use rand::Rng;
-use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 
 const MAX_CONCURRENT_RELAY_CONNECTIONS: usize = 4;
 const MAX_POTENTIAL_CANDIDATES: usize = 1000;
+/// The wait before retrying a relay that failed a reservation, doubled on each further failure.
+const RELAY_RETRY_BACKOFF: Duration = Duration::from_secs(60);
+const MAX_RELAY_RETRY_BACKOFF: Duration = Duration::from_secs(60 * 60);
+/// The score penalties of a failed reservation, and of a reservation lost once made.
+const RESERVATION_FAILURE_PENALTY: f64 = 20.0;
+const RESERVATION_LOSS_PENALTY: f64 = 10.0;
+/// The time a relay has to hold our reservations for to gain a point, up to `MAX_UPTIME_BONUS`.
+const UPTIME_PER_POINT: Duration = Duration::from_secs(10 * 60);
+const MAX_UPTIME_BONUS: f64 = 20.0;
+
+/// The resources a node lends to the peers behind a home network, by relaying their connections.
+#[derive(Debug, Clone, Copy)]
+pub struct RelayServerLimits {
+    /// The peers a reservation is held for at once.
+    pub max_reservations: usize,
+    /// The relayed connections at any given moment.
+    pub max_circuits: usize,
+    /// The bytes relayed over a circuit before it gets closed.
+    pub max_circuit_bytes: u64,
+    /// The time a circuit is kept open for.
+    pub max_circuit_duration: Duration,
+}
+
+impl Default for RelayServerLimits {
+    fn default() -> Self {
+        Self {
+            max_reservations: 128,
+            max_circuits: 1024,
+            // We should at least be able to relay packets with chunks etc.
+            max_circuit_bytes: MAX_PACKET_SIZE as u64,
+            max_circuit_duration: Duration::from_secs(2 * 60),
+        }
+    }
+}
+
+impl RelayServerLimits {
+    pub(crate) fn to_config(self) -> relay::Config {
+        relay::Config {
+            max_reservations: self.max_reservations, // Amount of peers we are relaying for
+            max_circuits: self.max_circuits, // The total amount of relayed connections at any given moment.
+            max_circuits_per_peer: self.max_circuits.min(256), // Amount of relayed connections per peer (both dst and src)
+            circuit_src_rate_limiters: vec![],                 // No extra rate limiting for now
+            max_circuit_bytes: self.max_circuit_bytes,
+            max_circuit_duration: self.max_circuit_duration,
+            ..Default::default()
+        }
+    }
+}
+
+/// What we observed of a relay server, to prefer the reliable ones.
+#[derive(Debug, Default)]
+struct RelayQuality {
+    reservation_failures: u32,
+    reservations_lost: u32,
+    /// The time our past reservations with the relay were held for.
+    held_for: Duration,
+    /// When our current reservation with the relay was made.
+    reserved_at: Option<Instant>,
+    /// The relay is not retried before this, after failing a reservation.
+    retry_after: Option<Instant>,
+}
+
+impl RelayQuality {
+    fn score(&self, reputation_score: f64) -> f64 {
+        let uptime_bonus =
+            (self.held_for.as_secs_f64() / UPTIME_PER_POINT.as_secs_f64()).min(MAX_UPTIME_BONUS);
+        reputation_score + uptime_bonus
+            - RESERVATION_FAILURE_PENALTY * self.reservation_failures as f64
+            - RESERVATION_LOSS_PENALTY * self.reservations_lost as f64
+    }
+
+    fn on_failure(&mut self, now: Instant) {
+        self.reservation_failures += 1;
+        let backoff = RELAY_RETRY_BACKOFF
+            .saturating_mul(2_u32.saturating_pow(self.reservation_failures - 1))
+            .min(MAX_RELAY_RETRY_BACKOFF);
+        self.retry_after = Some(now + backoff);
+    }
+
+    fn on_reservation(&mut self, now: Instant) {
+        self.reservation_failures = self.reservation_failures.saturating_sub(1);
+        self.reserved_at = Some(now);
+        self.retry_after = None;
+    }
+
+    fn on_loss(&mut self, now: Instant) {
+        self.reservations_lost += 1;
+        if let Some(reserved_at) = self.reserved_at.take() {
+            self.held_for += now.saturating_duration_since(reserved_at);
+        }
+    }
+}
 
 pub(crate) fn is_a_relayed_peer(addrs: &HashSet<Multiaddr>) -> bool {
     addrs
@@ -34,6 +134,11 @@ pub(crate) struct RelayManager {
     candidates: VecDeque<(PeerId, Multiaddr)>,
     waiting_for_reservation: BTreeMap<PeerId, Multiaddr>,
     connected_relays: BTreeMap<PeerId, Multiaddr>,
+    relay_quality: HashMap<PeerId, RelayQuality>,
+    /// The transports we can reach the relays over, as the ones we listen on.
+    tcp_enabled: bool,
+    listens_on_ipv4: bool,
+    listens_on_ipv6: bool,
 
     /// Tracker for the relayed listen addresses.
     relayed_listener_id_map: HashMap<ListenerId, PeerId>,
@@ -48,15 +153,38 @@ impl RelayManager {
             connected_relays: Default::default(),
             waiting_for_reservation: Default::default(),
             candidates: Default::default(),
+            relay_quality: Default::default(),
+            tcp_enabled: false,
+            listens_on_ipv4: true,
+            listens_on_ipv6: false,
             relayed_listener_id_map: Default::default(),
         }
     }
 
+    /// Only pick the relay addresses reachable over the transports and IP families we listen on.
+    pub(crate) fn set_local_transports(&mut self, tcp_enabled: bool, listen_addrs: &[SocketAddr]) {
+        self.tcp_enabled = tcp_enabled;
+        self.listens_on_ipv4 = listen_addrs.iter().any(SocketAddr::is_ipv4);
+        self.listens_on_ipv6 = listen_addrs.iter().any(SocketAddr::is_ipv6);
+    }
+
     pub(crate) fn enable_hole_punching(&mut self, enable: bool) {
         info!("Setting relay client mode to {enable:?}");
         self.enable_client = enable;
     }
 
+    /// The peers we hold a reservation for, as a relay server.
+    #[cfg(feature = "open-metrics")]
+    pub(crate) fn reservations_hosted(&self) -> usize {
+        self.reserved_by.len()
+    }
+
+    /// The relays we hold a reservation with, as a client.
+    #[cfg(feature = "open-metrics")]
+    pub(crate) fn reservations_held(&self) -> usize {
+        self.connected_relays.len()
+    }
+
     /// Should we keep this peer alive? Closing a connection to that peer would remove that server from the listen addr.
     pub(crate) fn keep_alive_peer(&self, peer_id: &PeerId) -> bool {
         self.connected_relays.contains_key(peer_id)
@@ -78,14 +206,13 @@ impl RelayManager {
         }
 
         if Self::does_it_support_relay_server_protocol(stream_protocols) {
-            // todo: collect and manage multiple addrs
-            if let Some(addr) = addrs.iter().next() {
-                // The calling place shall already checked whether the peer is `relayed`.
-                // Hence here can add the addr directly.
-                if let Some(relay_addr) = Self::craft_relay_address(addr, Some(*peer_id)) {
-                    debug!("Adding {peer_id:?} with {relay_addr:?} as a potential relay candidate");
-                    self.candidates.push_back((*peer_id, relay_addr));
-                }
+            // The calling place shall already checked whether the peer is `relayed`.
+            // Hence here can add the addr directly.
+            if let Some(relay_addr) = self.pick_relay_address(addrs, *peer_id) {
+                debug!("Adding {peer_id:?} with {relay_addr:?} as a potential relay candidate");
+                self.candidates.push_back((*peer_id, relay_addr));
+            } else {
+                debug!("Peer {peer_id:?} has no address we can reach it on as a relay");
             }
         } else {
             debug!("Peer {peer_id:?} does not support relay server protocol");
@@ -98,7 +225,7 @@ impl RelayManager {
     pub(crate) fn try_connecting_to_relay(
         &mut self,
         swarm: &mut Swarm<NodeBehaviour>,
-        bad_nodes: &BadNodes,
+        peer_reputations: &PeerReputations,
     ) {
         if !self.enable_client {
             return;
@@ -117,23 +244,9 @@ impl RelayManager {
             // todo: should we remove all our other `listen_addr`? And should we block from adding `add_external_address` if
             // we're behind nat?
 
-            // Pick a random candidate from the vector. Check if empty, or `gen_range` panics for empty range.
-            let index = if self.candidates.is_empty() {
-                debug!("No more relay candidates.");
-                break;
-            } else {
-                rand::thread_rng().gen_range(0..self.candidates.len())
-            };
-
-            if let Some((peer_id, relay_addr)) = self.candidates.remove(index) {
-                // skip if detected as a bad node
-                if let Some((_, is_bad)) = bad_nodes.get(&peer_id) {
-                    if *is_bad {
-                        debug!("Peer {peer_id:?} is considered as a bad node. Skipping it.");
-                        continue;
-                    }
-                }
-
+            if let Some((peer_id, relay_addr)) =
+                self.pick_candidate(peer_reputations, Instant::now())
+            {
                 if self.connected_relays.contains_key(&peer_id)
                     || self.waiting_for_reservation.contains_key(&peer_id)
                 {
@@ -159,6 +272,48 @@ impl RelayManager {
         }
     }
 
+    /// Removes and returns the candidate of the best quality, skipping the bad ones
+    /// and those waiting to be retried after a failure. Equal candidates are picked at random.
+    fn pick_candidate(
+        &mut self,
+        peer_reputations: &PeerReputations,
+        now: Instant,
+    ) -> Option<(PeerId, Multiaddr)> {
+        let system_now = SystemTime::now();
+        self.candidates
+            .retain(|(peer_id, _)| !peer_reputations.is_bad(peer_id, system_now));
+
+        let mut eligible = self
+            .candidates
+            .iter()
+            .enumerate()
+            .filter_map(|(index, (peer_id, _))| {
+                let quality = self.relay_quality.get(peer_id);
+                if quality
+                    .and_then(|quality| quality.retry_after)
+                    .is_some_and(|retry_after| retry_after > now)
+                {
+                    return None;
+                }
+                let reputation_score = peer_reputations.score(peer_id, system_now);
+                let score =
+                    quality.map_or(reputation_score, |quality| quality.score(reputation_score));
+                Some((index, score))
+            })
+            .collect_vec();
+        eligible.shuffle(&mut rand::thread_rng());
+
+        let (index, score) = eligible
+            .into_iter()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))?;
+        let candidate = self.candidates.remove(index)?;
+        debug!(
+            "Picked relay candidate {:?} with a score of {score:.1}",
+            candidate.0
+        );
+        Some(candidate)
+    }
+
     /// Update relay server state on incoming reservation from a client
     pub(crate) fn on_successful_reservation_by_server(&mut self, peer_id: PeerId) {
         self.reserved_by.insert(peer_id);
@@ -185,6 +340,10 @@ impl RelayManager {
         match self.waiting_for_reservation.remove(peer_id) {
             Some(addr) => {
                 info!("Successfully made reservation with {peer_id:?} on {addr:?}. Adding the addr to external address.");
+                self.relay_quality
+                    .entry(*peer_id)
+                    .or_default()
+                    .on_reservation(Instant::now());
                 swarm.add_external_address(addr.clone());
                 self.connected_relays.insert(*peer_id, addr);
             }
@@ -195,35 +354,52 @@ impl RelayManager {
     }
 
     /// Update client state if the reservation has been cancelled or if the relay has closed.
+    /// The relay is kept as a candidate, to be retried once the backoff of a failure elapsed.
+    /// Returns the relay if the listener was waiting on a reservation with it, which thus failed.
     pub(crate) fn on_listener_closed(
         &mut self,
         listener_id: &ListenerId,
         swarm: &mut Swarm<NodeBehaviour>,
-    ) {
-        let Some(peer_id) = self.relayed_listener_id_map.remove(listener_id) else {
-            return;
-        };
+    ) -> Option<PeerId> {
+        let peer_id = self.relayed_listener_id_map.remove(listener_id)?;
+        let now = Instant::now();
 
         if let Some(addr) = self.connected_relays.remove(&peer_id) {
             info!("Removing connected relay server as the listener has been closed: {peer_id:?}");
+            self.relay_quality.entry(peer_id).or_default().on_loss(now);
+            self.add_back_candidate(peer_id, addr.clone());
             info!("Removing external addr: {addr:?}");
             swarm.remove_external_address(&addr);
 
             // Even though we craft and store addrs in this format /ip4/198.51.100.0/tcp/55555/p2p/QmRelay/p2p-circuit/,
             // sometimes our PeerId is added at the end by the swarm?, which we want to remove as well i.e.,
             // /ip4/198.51.100.0/tcp/55555/p2p/QmRelay/p2p-circuit/p2p/QmSelf
-            let Ok(addr_with_self_peer_id) = addr.with_p2p(self.self_peer_id) else {
-                return;
-            };
-            info!("Removing external addr: {addr_with_self_peer_id:?}");
-            swarm.remove_external_address(&addr_with_self_peer_id);
+            if let Ok(addr_with_self_peer_id) = addr.with_p2p(self.self_peer_id) {
+                info!("Removing external addr: {addr_with_self_peer_id:?}");
+                swarm.remove_external_address(&addr_with_self_peer_id);
+            }
         }
         if let Some(addr) = self.waiting_for_reservation.remove(&peer_id) {
             info!("Removed peer form waiting_for_reservation as the listener has been closed {peer_id:?}: {addr:?}");
             debug!(
                 "waiting_for_reservation len: {:?}",
                 self.waiting_for_reservation.len()
-            )
+            );
+            self.relay_quality
+                .entry(peer_id)
+                .or_default()
+                .on_failure(now);
+            self.add_back_candidate(peer_id, addr);
+            return Some(peer_id);
+        }
+        None
+    }
+
+    fn add_back_candidate(&mut self, peer_id: PeerId, relay_addr: Multiaddr) {
+        if self.candidates.len() < MAX_POTENTIAL_CANDIDATES
+            && !self.candidates.iter().any(|(peer, _)| *peer == peer_id)
+        {
+            self.candidates.push_back((peer_id, relay_addr));
         }
     }
 
@@ -236,19 +412,48 @@ impl RelayManager {
         false
     }
 
+    /// The relay address of a peer, preferring its QUIC addresses over its TCP ones.
+    fn pick_relay_address(&self, addrs: &HashSet<Multiaddr>, peer_id: PeerId) -> Option<Multiaddr> {
+        addrs
+            .iter()
+            .filter_map(|addr| self.craft_relay_address(addr, Some(peer_id)))
+            .min_by_key(|relay_addr| {
+                !relay_addr
+                    .iter()
+                    .any(|protocol| matches!(protocol, Protocol::QuicV1))
+            })
+    }
+
     /// The listen addr should be something like /ip4/198.51.100.0/tcp/55555/p2p/QmRelay/p2p-circuit/
-    fn craft_relay_address(addr: &Multiaddr, peer_id: Option<PeerId>) -> Option<Multiaddr> {
+    /// None if the address is of an IP family we don't listen on, or over TCP without our TCP
+    /// transport enabled.
+    fn craft_relay_address(&self, addr: &Multiaddr, peer_id: Option<PeerId>) -> Option<Multiaddr> {
         let mut output_addr = Multiaddr::empty();
 
-        let ip = addr
-            .iter()
-            .find(|protocol| matches!(protocol, Protocol::Ip4(_)))?;
+        let ip = addr.iter().find(|protocol| match protocol {
+            Protocol::Ip4(_) => self.listens_on_ipv4,
+            Protocol::Ip6(_) => self.listens_on_ipv6,
+            _ => false,
+        })?;
         output_addr.push(ip);
-        let port = addr
+        if let Some(port) = addr
             .iter()
-            .find(|protocol| matches!(protocol, Protocol::Udp(_)))?;
-        output_addr.push(port);
-        output_addr.push(Protocol::QuicV1);
+            .find(|protocol| matches!(protocol, Protocol::Udp(_)))
+        {
+            output_addr.push(port);
+            output_addr.push(Protocol::QuicV1);
+        } else if self.tcp_enabled
+            && !addr
+                .iter()
+                .any(|protocol| matches!(protocol, Protocol::Ws(_)))
+        {
+            let port = addr
+                .iter()
+                .find(|protocol| matches!(protocol, Protocol::Tcp(_)))?;
+            output_addr.push(port);
+        } else {
+            return None;
+        }
 
         let peer_id = {
             if let Some(peer_id) = peer_id {
@@ -265,3 +470,150 @@ impl RelayManager {
         Some(output_addr)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{reputation::ReputationEvent, NodeIssue};
+
+    fn manager_with_candidates(count: usize) -> (RelayManager, Vec<PeerId>) {
+        let mut manager = RelayManager::new(PeerId::random());
+        let peers = (0..count).map(|_| PeerId::random()).collect_vec();
+        for peer in &peers {
+            let addr: Multiaddr = "/ip4/1.2.3.4/udp/1/quic-v1"
+                .parse()
+                .expect("valid multiaddr");
+            manager.candidates.push_back((*peer, addr));
+        }
+        (manager, peers)
+    }
+
+    #[test]
+    fn failed_relays_are_retried_after_an_increasing_backoff() {
+        let now = Instant::now();
+        let mut quality = RelayQuality::default();
+
+        quality.on_failure(now);
+        assert_eq!(quality.retry_after, Some(now + RELAY_RETRY_BACKOFF));
+        quality.on_failure(now);
+        assert_eq!(quality.retry_after, Some(now + RELAY_RETRY_BACKOFF * 2));
+        for _ in 0..20 {
+            quality.on_failure(now);
+        }
+        assert_eq!(quality.retry_after, Some(now + MAX_RELAY_RETRY_BACKOFF));
+
+        // A successful reservation lifts the backoff.
+        quality.on_reservation(now);
+        assert_eq!(quality.retry_after, None);
+    }
+
+    #[test]
+    fn relays_in_backoff_are_not_picked() {
+        let (mut manager, peers) = manager_with_candidates(2);
+        let reputations = PeerReputations::default();
+        let now = Instant::now();
+        manager
+            .relay_quality
+            .entry(peers[0])
+            .or_default()
+            .on_failure(now);
+
+        let picked = manager
+            .pick_candidate(&reputations, now)
+            .map(|(peer, _)| peer);
+        assert_eq!(picked, Some(peers[1]));
+        // The failed relay stays a candidate, but only once its backoff elapsed.
+        assert!(manager.pick_candidate(&reputations, now).is_none());
+        let picked = manager
+            .pick_candidate(&reputations, now + RELAY_RETRY_BACKOFF)
+            .map(|(peer, _)| peer);
+        assert_eq!(picked, Some(peers[0]));
+    }
+
+    #[test]
+    fn bad_peers_are_dropped_from_the_candidates() {
+        let (mut manager, peers) = manager_with_candidates(2);
+        let mut reputations = PeerReputations::default();
+        let mut now = SystemTime::now();
+        for issue in [
+            NodeIssue::BadQuoting,
+            NodeIssue::InvalidRecordServed,
+            NodeIssue::FailedChunkProofCheck,
+        ] {
+            let _ = reputations.record(peers[0], ReputationEvent::Issue(issue), now);
+            now += Duration::from_secs(30);
+        }
+        assert!(reputations.is_bad(&peers[0], SystemTime::now()));
+
+        let picked = manager
+            .pick_candidate(&reputations, Instant::now())
+            .map(|(peer, _)| peer);
+        assert_eq!(picked, Some(peers[1]));
+        assert!(manager.candidates.is_empty());
+    }
+
+    #[test]
+    fn relays_that_held_our_reservations_are_preferred() {
+        let (mut manager, peers) = manager_with_candidates(3);
+        let reputations = PeerReputations::default();
+        let now = Instant::now();
+        // One relay dropped our reservation right away, one held it for a few hours.
+        let quality = manager.relay_quality.entry(peers[0]).or_default();
+        quality.on_reservation(now);
+        quality.on_loss(now);
+        let quality = manager.relay_quality.entry(peers[1]).or_default();
+        quality.on_reservation(now);
+        quality.on_loss(now + Duration::from_secs(4 * 60 * 60));
+
+        let picked = (0..3)
+            .filter_map(|_| manager.pick_candidate(&reputations, now))
+            .map(|(peer, _)| peer)
+            .collect_vec();
+        assert_eq!(picked, [peers[1], peers[2], peers[0]]);
+    }
+
+    #[test]
+    fn relays_are_reached_over_the_transports_we_listen_on() {
+        let mut manager = RelayManager::new(PeerId::random());
+        let peer = PeerId::random();
+        let addrs: HashSet<Multiaddr> = [
+            "/ip6/2001:db8::1/udp/1/quic-v1",
+            "/ip4/1.2.3.4/tcp/2",
+            "/ip4/1.2.3.4/udp/3/quic-v1",
+        ]
+        .iter()
+        .map(|addr| addr.parse().expect("valid multiaddr"))
+        .collect();
+        let relay_addr = |addr: &str| -> Multiaddr {
+            format!("{addr}/p2p/{peer}/p2p-circuit")
+                .parse()
+                .expect("valid multiaddr")
+        };
+
+        // QUIC is preferred over TCP, of the IP family we listen on
+        let ipv4: SocketAddr = "0.0.0.0:0".parse().expect("valid socket addr");
+        manager.set_local_transports(true, &[ipv4]);
+        assert_eq!(
+            manager.pick_relay_address(&addrs, peer),
+            Some(relay_addr("/ip4/1.2.3.4/udp/3/quic-v1"))
+        );
+
+        let ipv6: SocketAddr = "[::]:0".parse().expect("valid socket addr");
+        manager.set_local_transports(true, &[ipv6]);
+        assert_eq!(
+            manager.pick_relay_address(&addrs, peer),
+            Some(relay_addr("/ip6/2001:db8::1/udp/1/quic-v1"))
+        );
+
+        // TCP is only used with our TCP transport enabled
+        let tcp_only: HashSet<Multiaddr> =
+            ["/ip4/1.2.3.4/tcp/2".parse().expect("valid multiaddr")].into();
+        manager.set_local_transports(false, &[ipv4]);
+        assert_eq!(manager.pick_relay_address(&tcp_only, peer), None);
+        manager.set_local_transports(true, &[ipv4]);
+        assert_eq!(
+            manager.pick_relay_address(&tcp_only, peer),
+            Some(relay_addr("/ip4/1.2.3.4/tcp/2"))
+        );
+    }
+}
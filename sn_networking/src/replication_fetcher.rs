@@ -9,14 +9,17 @@
 
 use crate::target_arch::spawn;
 use crate::CLOSE_GROUP_SIZE;
-use crate::{event::NetworkEvent, target_arch::Instant};
+use crate::{bandwidth::BandwidthLimiter, event::NetworkEvent, target_arch::Instant};
 use itertools::Itertools;
 use libp2p::{
     kad::{KBucketDistance as Distance, RecordKey, K_VALUE},
     PeerId,
 };
 use sn_protocol::{storage::RecordType, NetworkAddress, PrettyPrintRecordKey};
-use std::collections::{hash_map::Entry, BTreeSet, HashMap};
+use std::{
+    collections::{hash_map::Entry, BTreeSet, HashMap},
+    sync::Arc,
+};
 use tokio::{sync::mpsc, time::Duration};
 
 // Max parallel fetches that can be undertaken at the same time.
@@ -49,11 +52,18 @@ pub(crate) struct ReplicationFetcher {
     /// used when the node is full, but we still have "close" data coming in
     /// that is _not_ closer than our farthest max record
     farthest_acceptable_distance: Option<Distance>,
+    /// The fetches are held back while the node uses most of its bandwidth,
+    /// the replication having a lower priority than the client requests.
+    bandwidth_limiter: Arc<BandwidthLimiter>,
 }
 
 impl ReplicationFetcher {
     /// Instantiate a new replication fetcher with passed PeerId.
-    pub(crate) fn new(self_peer_id: PeerId, event_sender: mpsc::Sender<NetworkEvent>) -> Self {
+    pub(crate) fn new(
+        self_peer_id: PeerId,
+        event_sender: mpsc::Sender<NetworkEvent>,
+        bandwidth_limiter: Arc<BandwidthLimiter>,
+    ) -> Self {
         Self {
             self_peer_id,
             to_be_fetched: HashMap::new(),
@@ -61,6 +71,7 @@ impl ReplicationFetcher {
             event_sender,
             distance_range: None,
             farthest_acceptable_distance: None,
+            bandwidth_limiter,
         }
     }
 
@@ -279,6 +290,14 @@ impl ReplicationFetcher {
             return vec![];
         }
 
+        if self.bandwidth_limiter.is_congested() {
+            debug!(
+                "Bandwidth is congested, holding back the fetch of {} entries.",
+                self.to_be_fetched.len()
+            );
+            return vec![];
+        }
+
         if !self.to_be_fetched.is_empty() {
             debug!(
                 "Number of records still to be retrieved: {:?}",
@@ -428,10 +447,11 @@ impl ReplicationFetcher {
 #[cfg(test)]
 mod tests {
     use super::{ReplicationFetcher, FETCH_TIMEOUT, MAX_PARALLEL_FETCH};
+    use crate::bandwidth::{BandwidthLimiter, BandwidthLimits, Direction};
     use eyre::Result;
     use libp2p::{kad::RecordKey, PeerId};
     use sn_protocol::{storage::RecordType, NetworkAddress};
-    use std::{collections::HashMap, time::Duration};
+    use std::{collections::HashMap, sync::Arc, time::Duration};
     use tokio::{sync::mpsc, time::sleep};
 
     #[tokio::test]
@@ -439,7 +459,11 @@ mod tests {
         //random peer_id
         let peer_id = PeerId::random();
         let (event_sender, _event_receiver) = mpsc::channel(4);
-        let mut replication_fetcher = ReplicationFetcher::new(peer_id, event_sender);
+        let mut replication_fetcher = ReplicationFetcher::new(
+            peer_id,
+            event_sender,
+            Arc::new(BandwidthLimiter::new(BandwidthLimits::default())),
+        );
         let locally_stored_keys = HashMap::new();
 
         let mut incoming_keys = Vec::new();
@@ -494,13 +518,51 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn fetches_are_held_back_while_bandwidth_is_congested() -> Result<()> {
+        let peer_id = PeerId::random();
+        let (event_sender, _event_receiver) = mpsc::channel(4);
+        let bandwidth_limiter = Arc::new(BandwidthLimiter::new(BandwidthLimits {
+            upload: None,
+            download: Some(10_000),
+        }));
+        let mut replication_fetcher =
+            ReplicationFetcher::new(peer_id, event_sender, Arc::clone(&bandwidth_limiter));
+
+        // a client request used up the whole burst
+        bandwidth_limiter.consume(Direction::Download, 10_000);
+
+        // a single key is a fresh record, which is fetched straight away, hence using two
+        let incoming_keys = (0..2)
+            .map(|_| {
+                let random_data: Vec<u8> = (0..50).map(|_| rand::random::<u8>()).collect();
+                let key = NetworkAddress::from_record_key(&RecordKey::from(random_data));
+                (key, RecordType::Chunk)
+            })
+            .collect();
+        let keys_to_fetch =
+            replication_fetcher.add_keys(PeerId::random(), incoming_keys, &HashMap::new(), &[]);
+        assert!(keys_to_fetch.is_empty());
+
+        // the keys are fetched once a quarter of the burst got refilled
+        sleep(Duration::from_millis(300)).await;
+        let keys_to_fetch = replication_fetcher.next_keys_to_fetch();
+        assert_eq!(keys_to_fetch.len(), 2);
+
+        Ok(())
+    }
+
     #[test]
     fn verify_in_range_check() {
         //random peer_id
         let peer_id = PeerId::random();
         let self_address = NetworkAddress::from_peer(peer_id);
         let (event_sender, _event_receiver) = mpsc::channel(4);
-        let mut replication_fetcher = ReplicationFetcher::new(peer_id, event_sender);
+        let mut replication_fetcher = ReplicationFetcher::new(
+            peer_id,
+            event_sender,
+            Arc::new(BandwidthLimiter::new(BandwidthLimits::default())),
+        );
 
         // Set distance range
         // way to update this test
@@ -0,0 +1,525 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    target_arch::{Duration, SystemTime, UNIX_EPOCH},
+    NodeIssue,
+};
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// File under the node's root dir the reputations are persisted to.
+pub(crate) const PEER_REPUTATIONS_FILENAME: &str = "peer_reputations";
+/// The reputations are written to this file first, then renamed over the previous ones.
+const PEER_REPUTATIONS_TMP_FILENAME: &str = "peer_reputations.tmp";
+
+/// The time it takes for a score to be halved, i.e. for its history to be half forgiven.
+const SCORE_HALF_LIFE: Duration = Duration::from_secs(15 * 60);
+/// The scores are kept within `[-MAX_SCORE, MAX_SCORE]`.
+const MAX_SCORE: f64 = 100.0;
+/// A peer falling to this score is considered bad, and blocked.
+const BAD_SCORE: f64 = -MAX_SCORE;
+/// A peer at or below this score is avoided whenever another one can be used instead.
+const DISLIKED_SCORE: f64 = -50.0;
+/// The penalty of an issue proving the peer misbehaved. Three of those in a short while make a peer bad.
+const ISSUE_PENALTY: f64 = 40.0;
+/// The penalty of an issue that may just be caused by the peer being briefly unreachable.
+/// It takes a lot more of those in a short while to make a peer bad.
+const TRANSIENT_ISSUE_PENALTY: f64 = 15.0;
+/// Issues reported within this duration of the previous one count as the same issue,
+/// to not be too sensitive to a burst of reports about a single event.
+const ISSUE_DEDUP_PERIOD: Duration = Duration::from_secs(10);
+/// The penalty of a failed request. Those alone can't make a peer bad, as they are
+/// just as likely caused by the peer going offline than by it misbehaving.
+const REQUEST_FAILURE_PENALTY: f64 = 2.0;
+/// The reward of a request answered within `FAST_RESPONSE`, halved when within `SLOW_RESPONSE`.
+const REQUEST_SUCCESS_REWARD: f64 = 1.0;
+const FAST_RESPONSE: Duration = Duration::from_secs(1);
+const SLOW_RESPONSE: Duration = Duration::from_secs(5);
+/// How long a bad peer stays blocked, restarts included.
+const BAD_PEER_BLOCK_DURATION: Duration = Duration::from_secs(24 * 60 * 60);
+/// Scores decayed closer to neutral than this are forgotten.
+const NEGLIGIBLE_SCORE: f64 = 1.0;
+
+/// What we learnt about a peer from interacting with it.
+#[derive(Debug)]
+pub(crate) enum ReputationEvent {
+    /// The peer answered one of our requests, after the given latency.
+    RequestSucceeded(Duration),
+    /// One of our requests to the peer failed or timed out.
+    RequestFailed,
+    /// The peer misbehaved.
+    Issue(NodeIssue),
+}
+
+/// The standing of a peer with us, as accumulated from our interactions with it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PeerReputation {
+    /// From -100 to 100, 0 being a peer we know nothing about.
+    pub score: f64,
+    /// Whether the peer is considered bad, and thus blocked.
+    pub is_bad: bool,
+}
+
+impl PeerReputation {
+    /// Whether the peer shall be avoided whenever another one can be used instead.
+    pub(crate) fn is_disliked(&self) -> bool {
+        self.is_bad || self.score <= DISLIKED_SCORE
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PeerScore {
+    score: f64,
+    /// The seconds since the UNIX epoch at which `score` was last updated.
+    updated_at: u64,
+    last_issue_at: Option<u64>,
+    bad_since: Option<u64>,
+}
+
+impl PeerScore {
+    fn new(now: u64) -> Self {
+        Self {
+            score: 0.0,
+            updated_at: now,
+            last_issue_at: None,
+            bad_since: None,
+        }
+    }
+
+    fn decayed_score(&self, now: u64) -> f64 {
+        let elapsed = now.saturating_sub(self.updated_at) as f64;
+        self.score * 0.5_f64.powf(elapsed / SCORE_HALF_LIFE.as_secs_f64())
+    }
+
+    fn decay(&mut self, now: u64) {
+        self.score = self.decayed_score(now);
+        self.updated_at = now.max(self.updated_at);
+    }
+
+    fn is_bad(&self, now: u64) -> bool {
+        self.bad_since
+            .is_some_and(|since| now.saturating_sub(since) < BAD_PEER_BLOCK_DURATION.as_secs())
+    }
+}
+
+/// Keeps a decaying score for each of the peers we interacted with.
+///
+/// The scores drive which peers get blocked, which ones are preferred in the routing table,
+/// and which ones are picked for a quote. They can be persisted so the peers keep their
+/// standing across restarts.
+#[derive(Debug, Default)]
+pub(crate) struct PeerReputations {
+    peers: BTreeMap<PeerId, PeerScore>,
+    file_path: Option<PathBuf>,
+    /// Whether the reputations changed since they were last persisted.
+    dirty: bool,
+}
+
+/// The serialised reputations, to be persisted off the event loop.
+pub(crate) struct ReputationsSnapshot {
+    bytes: Vec<u8>,
+    file_path: PathBuf,
+}
+
+impl ReputationsSnapshot {
+    /// Writes the reputations, atomically replacing the ones persisted before.
+    pub(crate) fn write(&self) -> io::Result<()> {
+        let tmp_path = self.file_path.with_file_name(PEER_REPUTATIONS_TMP_FILENAME);
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(&self.bytes)?;
+        file.sync_all()?;
+        fs::rename(tmp_path, &self.file_path)
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.file_path
+    }
+}
+
+impl PeerReputations {
+    /// Restores the reputations persisted under the root dir, or starts afresh.
+    pub(crate) fn load(root_dir: &Path) -> Self {
+        let file_path = root_dir.join(PEER_REPUTATIONS_FILENAME);
+        let peers = std::fs::read(&file_path)
+            .ok()
+            .and_then(|bytes| {
+                rmp_serde::from_slice::<BTreeMap<String, PeerScore>>(&bytes)
+                    .inspect_err(|err| warn!("Failed to parse the peer reputations: {err:?}"))
+                    .ok()
+            })
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(peer, score)| Some((peer.parse().ok()?, score)))
+            .collect::<BTreeMap<_, _>>();
+        info!(
+            "Restored the reputation of {} peers from {file_path:?}",
+            peers.len()
+        );
+
+        Self {
+            peers,
+            file_path: Some(file_path),
+            dirty: false,
+        }
+    }
+
+    /// Takes a snapshot of the reputations to persist, if they were loaded from a file and
+    /// changed since the last snapshot.
+    pub(crate) fn take_snapshot(&mut self) -> Option<ReputationsSnapshot> {
+        let file_path = self.file_path.clone()?;
+        if !self.dirty {
+            return None;
+        }
+        let peers: BTreeMap<String, &PeerScore> = self
+            .peers
+            .iter()
+            .map(|(peer, score)| (peer.to_string(), score))
+            .collect();
+        match rmp_serde::to_vec(&peers) {
+            Ok(bytes) => {
+                self.dirty = false;
+                Some(ReputationsSnapshot { bytes, file_path })
+            }
+            Err(err) => {
+                warn!("Failed to serialise the peer reputations: {err:?}");
+                None
+            }
+        }
+    }
+
+    /// Updates the score of the peer with the event.
+    /// Returns true if that made the peer bad.
+    pub(crate) fn record(&mut self, peer: PeerId, event: ReputationEvent, now: SystemTime) -> bool {
+        let now = unix_secs(now);
+        let entry = self
+            .peers
+            .entry(peer)
+            .or_insert_with(|| PeerScore::new(now));
+        if entry.is_bad(now) {
+            return false;
+        }
+        entry.decay(now);
+        self.dirty = true;
+
+        match event {
+            ReputationEvent::RequestSucceeded(latency) => {
+                let reward = if latency <= FAST_RESPONSE {
+                    REQUEST_SUCCESS_REWARD
+                } else if latency <= SLOW_RESPONSE {
+                    REQUEST_SUCCESS_REWARD / 2.0
+                } else {
+                    0.0
+                };
+                entry.score = (entry.score + reward).min(MAX_SCORE);
+            }
+            ReputationEvent::RequestFailed => {
+                if entry.score > DISLIKED_SCORE {
+                    entry.score = (entry.score - REQUEST_FAILURE_PENALTY).max(DISLIKED_SCORE);
+                }
+            }
+            ReputationEvent::Issue(issue) => {
+                let is_repeat = entry
+                    .last_issue_at
+                    .is_some_and(|last| now.saturating_sub(last) < ISSUE_DEDUP_PERIOD.as_secs());
+                if is_repeat {
+                    debug!("Peer {peer:?} reported with {issue:?} again, too soon to count it");
+                    return false;
+                }
+                entry.last_issue_at = Some(now);
+                entry.score = (entry.score - issue_penalty(&issue)).max(-MAX_SCORE);
+                debug!(
+                    "Peer {peer:?} reported with {issue:?}, its score is now {:.1}",
+                    entry.score
+                );
+
+                if entry.score <= BAD_SCORE {
+                    info!("Peer {peer:?} got its score down to {:.1} with {issue:?}. Consider it as a bad node now.", entry.score);
+                    entry.bad_since = Some(now);
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// The current score of the peer, 0 if unknown.
+    pub(crate) fn score(&self, peer: &PeerId, now: SystemTime) -> f64 {
+        self.peers
+            .get(peer)
+            .map_or(0.0, |entry| entry.decayed_score(unix_secs(now)))
+    }
+
+    pub(crate) fn is_bad(&self, peer: &PeerId, now: SystemTime) -> bool {
+        self.peers
+            .get(peer)
+            .is_some_and(|entry| entry.is_bad(unix_secs(now)))
+    }
+
+    /// Whether the peer shall be avoided whenever another one can be used instead.
+    pub(crate) fn is_disliked(&self, peer: &PeerId, now: SystemTime) -> bool {
+        self.is_bad(peer, now) || self.score(peer, now) <= DISLIKED_SCORE
+    }
+
+    pub(crate) fn bad_peers(&self, now: SystemTime) -> impl Iterator<Item = PeerId> + '_ {
+        let now = unix_secs(now);
+        self.peers
+            .iter()
+            .filter(move |(_, entry)| entry.is_bad(now))
+            .map(|(peer, _)| *peer)
+    }
+
+    /// The reputations of all the peers we still remember.
+    pub(crate) fn snapshot(&self, now: SystemTime) -> Vec<(PeerId, PeerReputation)> {
+        let now = unix_secs(now);
+        self.peers
+            .iter()
+            .map(|(peer, entry)| {
+                let reputation = PeerReputation {
+                    score: entry.decayed_score(now),
+                    is_bad: entry.is_bad(now),
+                };
+                (*peer, reputation)
+            })
+            .collect()
+    }
+
+    /// Forgets the peers whose score decayed back to neutral.
+    /// Returns the bad peers served their time, which shall be unblocked.
+    pub(crate) fn prune(&mut self, now: SystemTime) -> Vec<PeerId> {
+        let now = unix_secs(now);
+        let known_peers = self.peers.len();
+        let mut unblocked = vec![];
+        self.peers.retain(|peer, entry| {
+            if entry.is_bad(now) {
+                return true;
+            }
+            if entry.bad_since.take().is_some() {
+                unblocked.push(*peer);
+            }
+            entry.decay(now);
+            entry.score.abs() >= NEGLIGIBLE_SCORE
+        });
+        if self.peers.len() != known_peers || !unblocked.is_empty() {
+            self.dirty = true;
+        }
+        unblocked
+    }
+}
+
+fn issue_penalty(issue: &NodeIssue) -> f64 {
+    match issue {
+        NodeIssue::ReplicationFailure | NodeIssue::CloseNodesShunning => TRANSIENT_ISSUE_PENALTY,
+        NodeIssue::BadQuoting
+        | NodeIssue::FailedChunkProofCheck
+        | NodeIssue::FailedStorageAudit
+        | NodeIssue::InvalidRecordServed => ISSUE_PENALTY,
+    }
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_700_000_000 + secs)
+    }
+
+    #[test]
+    fn three_issues_in_a_short_while_make_a_peer_bad() {
+        let mut reputations = PeerReputations::default();
+        let peer = PeerId::random();
+
+        assert!(!reputations.record(peer, ReputationEvent::Issue(NodeIssue::BadQuoting), at(0)));
+        // Reported again too soon, counted as the same issue.
+        assert!(!reputations.record(peer, ReputationEvent::Issue(NodeIssue::BadQuoting), at(5)));
+        assert!(!reputations.record(
+            peer,
+            ReputationEvent::Issue(NodeIssue::InvalidRecordServed),
+            at(30)
+        ));
+        assert!(reputations.is_disliked(&peer, at(30)));
+        assert!(!reputations.is_bad(&peer, at(30)));
+
+        assert!(reputations.record(
+            peer,
+            ReputationEvent::Issue(NodeIssue::FailedChunkProofCheck),
+            at(60)
+        ));
+        assert!(reputations.is_bad(&peer, at(60)));
+        assert_eq!(reputations.bad_peers(at(60)).collect::<Vec<_>>(), [peer]);
+    }
+
+    #[test]
+    fn mixed_transient_issues_do_not_make_a_peer_bad() {
+        let mut reputations = PeerReputations::default();
+        let peer = PeerId::random();
+
+        for (index, issue) in [
+            NodeIssue::ReplicationFailure,
+            NodeIssue::CloseNodesShunning,
+            NodeIssue::ReplicationFailure,
+            NodeIssue::CloseNodesShunning,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            assert!(!reputations.record(
+                peer,
+                ReputationEvent::Issue(issue),
+                at(index as u64 * 30)
+            ));
+        }
+        assert!(reputations.score(&peer, at(90)) > BAD_SCORE);
+        assert!(!reputations.is_bad(&peer, at(90)));
+    }
+
+    #[test]
+    fn issues_spread_over_time_are_forgiven() {
+        let mut reputations = PeerReputations::default();
+        let peer = PeerId::random();
+
+        for hour in 0..24 {
+            let _ = reputations.record(
+                peer,
+                ReputationEvent::Issue(NodeIssue::ReplicationFailure),
+                at(hour * 3600),
+            );
+        }
+        assert!(!reputations.is_bad(&peer, at(24 * 3600)));
+        assert!(reputations.score(&peer, at(24 * 3600)) > DISLIKED_SCORE);
+    }
+
+    #[test]
+    fn failed_requests_alone_do_not_make_a_peer_bad() {
+        let mut reputations = PeerReputations::default();
+        let peer = PeerId::random();
+
+        for secs in 0..1000 {
+            let _ = reputations.record(peer, ReputationEvent::RequestFailed, at(secs));
+        }
+        assert!(reputations.is_disliked(&peer, at(999)));
+        assert!(!reputations.is_bad(&peer, at(999)));
+    }
+
+    #[test]
+    fn responsive_peers_are_rewarded_more_than_slow_ones() {
+        let mut reputations = PeerReputations::default();
+        let fast = PeerId::random();
+        let slow = PeerId::random();
+        let unresponsive = PeerId::random();
+
+        for secs in 0..100 {
+            let _ = reputations.record(
+                fast,
+                ReputationEvent::RequestSucceeded(Duration::from_millis(200)),
+                at(secs),
+            );
+            let _ = reputations.record(
+                slow,
+                ReputationEvent::RequestSucceeded(Duration::from_secs(3)),
+                at(secs),
+            );
+            let _ = reputations.record(unresponsive, ReputationEvent::RequestFailed, at(secs));
+        }
+
+        let score = |reputations: &PeerReputations, peer| reputations.score(&peer, at(100));
+        assert!(score(&reputations, fast) > score(&reputations, slow));
+        assert!(score(&reputations, slow) > 0.0);
+        assert!(score(&reputations, unresponsive) < 0.0);
+        // A good history does not shield from misbehaving.
+        let _ = reputations.record(
+            fast,
+            ReputationEvent::Issue(NodeIssue::InvalidRecordServed),
+            at(100),
+        );
+        assert!(score(&reputations, fast) < 100.0 - ISSUE_PENALTY);
+    }
+
+    #[test]
+    fn bad_peers_are_unblocked_once_served_their_time() {
+        let mut reputations = PeerReputations::default();
+        let peer = PeerId::random();
+        let neutral = PeerId::random();
+        for minute in 0..3 {
+            let _ = reputations.record(
+                peer,
+                ReputationEvent::Issue(NodeIssue::BadQuoting),
+                at(minute * 60),
+            );
+        }
+        let _ = reputations.record(
+            neutral,
+            ReputationEvent::RequestSucceeded(Duration::ZERO),
+            at(0),
+        );
+        assert!(reputations.is_bad(&peer, at(120)));
+
+        assert!(reputations.prune(at(3600)).is_empty());
+        assert!(reputations.is_bad(&peer, at(3600)));
+        // The neutral peer decayed to nothing and got forgotten.
+        assert_eq!(reputations.snapshot(at(3600)).len(), 1);
+
+        let day = BAD_PEER_BLOCK_DURATION.as_secs();
+        assert_eq!(reputations.prune(at(120 + day)), vec![peer]);
+        assert!(!reputations.is_bad(&peer, at(120 + day)));
+    }
+
+    #[test]
+    fn reputations_survive_a_restart() {
+        let root_dir = std::env::temp_dir().join(format!("reputations_{}", PeerId::random()));
+        std::fs::create_dir_all(&root_dir).expect("create root dir");
+
+        let mut reputations = PeerReputations::load(&root_dir);
+        let bad = PeerId::random();
+        let good = PeerId::random();
+        for minute in 0..3 {
+            let _ = reputations.record(
+                bad,
+                ReputationEvent::Issue(NodeIssue::FailedStorageAudit),
+                at(minute * 60),
+            );
+            let _ = reputations.record(
+                good,
+                ReputationEvent::RequestSucceeded(Duration::ZERO),
+                at(minute * 60),
+            );
+        }
+        reputations
+            .take_snapshot()
+            .expect("changed reputations to persist")
+            .write()
+            .expect("write reputations");
+        assert!(
+            reputations.take_snapshot().is_none(),
+            "Unchanged reputations should not be persisted again"
+        );
+        assert!(!root_dir.join(PEER_REPUTATIONS_TMP_FILENAME).exists());
+
+        let restored = PeerReputations::load(&root_dir);
+        assert!(restored.is_bad(&bad, at(180)));
+        assert_eq!(
+            restored.score(&good, at(180)),
+            reputations.score(&good, at(180))
+        );
+
+        let _ = std::fs::remove_dir_all(root_dir);
+    }
+}
@@ -0,0 +1,409 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! An in-process network, over libp2p's memory transport, to run many nodes in a single test.
+//!
+//! The links between the endpoints of a [`SimulatedNetwork`] are subject to its conditions: latency,
+//! packet loss, partitions and endpoints cut off from the network, as their process would have stopped.
+//! The conditions of the links and the keypairs of the endpoints are drawn from the seed the network
+//! is created with, so the identities of a run are reproducible. The nodes draw their own randomness
+//! and are scheduled by the runtime, so what they do on the network is not.
+
+use futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, Future};
+use libp2p::{
+    core::{
+        muxing::StreamMuxerBox,
+        transport::{self, MemoryTransport},
+        upgrade, ConnectedPoint,
+    },
+    identity::Keypair,
+    multiaddr::Protocol,
+    noise, yamux, Multiaddr, PeerId, Transport as _,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+use tokio::{sync::Notify, time::Sleep};
+
+/// The memory ports are shared by the whole process, so each endpoint of each simulated network gets
+/// its own, away from the random ones picked when listening on `/memory/0`.
+static NEXT_MEMORY_PORT: AtomicU64 = AtomicU64::new(1 << 48);
+
+/// The conditions of the links between the endpoints.
+#[derive(Debug)]
+struct SimulationState {
+    rng: StdRng,
+    /// The min and max latency added to each write
+    latency: (Duration, Duration),
+    /// The probability of a write getting lost
+    packet_loss: f64,
+    /// The endpoint listening on each memory port
+    endpoints_by_port: HashMap<u64, usize>,
+    /// The endpoints cut off from the network
+    offline: HashSet<usize>,
+    /// The partition of each partitioned endpoint, the others being in partition 0
+    partitions: HashMap<usize, usize>,
+}
+
+impl SimulationState {
+    fn is_reachable(&self, from: usize, to: usize) -> bool {
+        !self.offline.contains(&from)
+            && !self.offline.contains(&to)
+            && self.partitions.get(&from).unwrap_or(&0) == self.partitions.get(&to).unwrap_or(&0)
+    }
+}
+
+/// An in-process network whose endpoints reach each other over libp2p's memory transport.
+///
+/// The conditions can be changed at any time, the links already established being subject to them:
+/// a link between two endpoints that can't reach each other anymore is broken.
+#[derive(Clone, Debug)]
+pub struct SimulatedNetwork {
+    state: Arc<Mutex<SimulationState>>,
+    /// Wakes up the links waiting on their peer, for them to find out they have been cut off
+    changed: Arc<Notify>,
+}
+
+impl SimulatedNetwork {
+    /// Creates a network with no latency nor packet loss, whose randomness is drawn from `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(SimulationState {
+                rng: StdRng::seed_from_u64(seed),
+                latency: (Duration::ZERO, Duration::ZERO),
+                packet_loss: 0.0,
+                endpoints_by_port: HashMap::new(),
+                offline: HashSet::new(),
+                partitions: HashMap::new(),
+            })),
+            changed: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Adds an endpoint to the network, with a keypair drawn from the seed of the network.
+    pub fn new_endpoint(&self) -> SimulatedEndpoint {
+        let port = NEXT_MEMORY_PORT.fetch_add(1, Ordering::Relaxed);
+        let mut state = self.lock_state();
+        let index = state.endpoints_by_port.len();
+        let _ = state.endpoints_by_port.insert(port, index);
+        let keypair = Keypair::ed25519_from_bytes(state.rng.gen::<[u8; 32]>())
+            .expect("32 bytes make a valid ed25519 secret key");
+
+        SimulatedEndpoint {
+            network: self.clone(),
+            index,
+            port,
+            keypair,
+        }
+    }
+
+    /// Each write on a link is delayed by a duration picked between `min` and `max`.
+    pub fn set_latency(&self, min: Duration, max: Duration) {
+        self.lock_state().latency = (min, max.max(min));
+    }
+
+    /// Each write on a link is lost with the probability `rate`, between 0 and 1.
+    ///
+    /// As the links carry reliable streams, a lost write breaks the link it was written to, as a timed
+    /// out connection would on a real network.
+    pub fn set_packet_loss(&self, rate: f64) {
+        self.lock_state().packet_loss = rate.clamp(0.0, 1.0);
+    }
+
+    /// Splits the `endpoints` from the others. Each call creates a new partition, the endpoints of
+    /// different partitions not reaching each other until [`Self::heal`] is called.
+    pub fn partition(&self, endpoints: &[usize]) {
+        {
+            let mut state = self.lock_state();
+            let partition = state.partitions.values().max().unwrap_or(&0) + 1;
+            for endpoint in endpoints {
+                let _ = state.partitions.insert(*endpoint, partition);
+            }
+        }
+        self.changed.notify_waiters();
+    }
+
+    /// Removes all the partitions.
+    pub fn heal(&self) {
+        self.lock_state().partitions.clear();
+    }
+
+    /// Cuts the endpoint off the network, as its process would have stopped.
+    pub fn disconnect(&self, endpoint: usize) {
+        let _ = self.lock_state().offline.insert(endpoint);
+        self.changed.notify_waiters();
+    }
+
+    /// Brings back an endpoint cut off the network.
+    pub fn reconnect(&self, endpoint: usize) {
+        let _ = self.lock_state().offline.remove(&endpoint);
+    }
+
+    /// Whether the endpoint `from` can reach the endpoint `to`.
+    pub fn is_reachable(&self, from: usize, to: usize) -> bool {
+        self.lock_state().is_reachable(from, to)
+    }
+
+    fn endpoint_listening_on(&self, port: u64) -> Option<usize> {
+        self.lock_state().endpoints_by_port.get(&port).copied()
+    }
+
+    /// Draws the latency of a write and whether it is lost.
+    fn draw_write_conditions(&self) -> (Duration, bool) {
+        let mut state = self.lock_state();
+        let (min, max) = state.latency;
+        let latency = if max > min {
+            state.rng.gen_range(min..=max)
+        } else {
+            min
+        };
+        let packet_loss = state.packet_loss;
+        let lost = packet_loss > 0.0 && state.rng.gen_bool(packet_loss);
+        (latency, lost)
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, SimulationState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// An endpoint of a [`SimulatedNetwork`], to be given to the `NetworkBuilder` of a node or client.
+#[derive(Clone, Debug)]
+pub struct SimulatedEndpoint {
+    network: SimulatedNetwork,
+    index: usize,
+    port: u64,
+    keypair: Keypair,
+}
+
+impl SimulatedEndpoint {
+    /// The index of the endpoint within its network.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The keypair of the endpoint, drawn from the seed of its network.
+    pub fn keypair(&self) -> &Keypair {
+        &self.keypair
+    }
+
+    /// The peer id of the endpoint.
+    pub fn peer_id(&self) -> PeerId {
+        self.keypair.public().to_peer_id()
+    }
+
+    /// The address the endpoint listens on, if it's a node.
+    pub fn listen_addr(&self) -> Multiaddr {
+        Multiaddr::empty().with(Protocol::Memory(self.port))
+    }
+
+    /// The memory transport of the endpoint, with the noise and yamux upgrades of our other transports.
+    pub(crate) fn build_transport(
+        &self,
+        keypair: &Keypair,
+    ) -> transport::Boxed<(PeerId, StreamMuxerBox)> {
+        let network = self.network.clone();
+        let local = self.index;
+
+        MemoryTransport::default()
+            .and_then(move |mut stream, connected_point| async move {
+                // The dialer introduces itself, as the listener only sees an ephemeral port.
+                let remote = match connected_point {
+                    ConnectedPoint::Dialer { address, .. } => {
+                        stream.write_all(&(local as u64).to_be_bytes()).await?;
+                        stream.flush().await?;
+                        address
+                            .iter()
+                            .find_map(|protocol| match protocol {
+                                Protocol::Memory(port) => network.endpoint_listening_on(port),
+                                _ => None,
+                            })
+                            .ok_or_else(|| {
+                                io::Error::new(
+                                    io::ErrorKind::NotFound,
+                                    "Unknown simulated endpoint",
+                                )
+                            })?
+                    }
+                    ConnectedPoint::Listener { .. } => {
+                        let mut remote = [0; 8];
+                        stream.read_exact(&mut remote).await?;
+                        u64::from_be_bytes(remote) as usize
+                    }
+                };
+
+                if !network.is_reachable(local, remote) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::ConnectionRefused,
+                        "Simulated endpoint is unreachable",
+                    ));
+                }
+                Ok(SimulatedLink::new(network, local, remote, stream))
+            })
+            .upgrade(upgrade::Version::V1)
+            .authenticate(
+                noise::Config::new(keypair)
+                    .expect("Signing libp2p-noise static DH keypair failed."),
+            )
+            .multiplex(yamux::Config::default())
+            .map(|(peer_id, muxer), _| (peer_id, StreamMuxerBox::new(muxer)))
+            .boxed()
+    }
+}
+
+/// A stream between two endpoints, subject to the conditions of their network.
+struct SimulatedLink<S> {
+    network: SimulatedNetwork,
+    local: usize,
+    remote: usize,
+    inner: S,
+    /// Whether the conditions of the write in progress have been drawn
+    write_drawn: bool,
+    /// The latency of the write in progress
+    write_delay: Option<Pin<Box<Sleep>>>,
+    /// Resolves when the conditions of the network change
+    network_changed: Pin<Box<dyn Future<Output = ()> + Send>>,
+}
+
+impl<S> SimulatedLink<S> {
+    fn new(network: SimulatedNetwork, local: usize, remote: usize, inner: S) -> Self {
+        let network_changed = Self::network_changed(&network);
+        Self {
+            network,
+            local,
+            remote,
+            inner,
+            write_drawn: false,
+            write_delay: None,
+            network_changed,
+        }
+    }
+
+    fn network_changed(network: &SimulatedNetwork) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        let changed = Arc::clone(&network.changed);
+        Box::pin(async move { changed.notified().await })
+    }
+
+    /// Fails once the endpoints can't reach each other, registering for the next change otherwise.
+    fn poll_reachable(&mut self, cx: &mut Context<'_>) -> io::Result<()> {
+        while self.network_changed.as_mut().poll(cx).is_ready() {
+            self.network_changed = Self::network_changed(&self.network);
+        }
+        if self.network.is_reachable(self.local, self.remote) {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::ConnectionReset,
+                "Simulated endpoints can't reach each other",
+            ))
+        }
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for SimulatedLink<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        this.poll_reachable(cx)?;
+        Pin::new(&mut this.inner).poll_read(cx, buf)
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for SimulatedLink<S> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        this.poll_reachable(cx)?;
+
+        if !this.write_drawn {
+            let (latency, lost) = this.network.draw_write_conditions();
+            if lost {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::ConnectionReset,
+                    "Simulated packet loss",
+                )));
+            }
+            this.write_drawn = true;
+            if !latency.is_zero() {
+                this.write_delay = Some(Box::pin(tokio::time::sleep(latency)));
+            }
+        }
+        if let Some(write_delay) = this.write_delay.as_mut() {
+            ready!(write_delay.as_mut().poll(cx));
+        }
+
+        let written = ready!(Pin::new(&mut this.inner).poll_write(cx, buf));
+        this.write_drawn = false;
+        this.write_delay = None;
+        Poll::Ready(written)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_close(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoints_are_drawn_from_the_seed() {
+        let peer_ids = |seed| {
+            let network = SimulatedNetwork::new(seed);
+            (0..3)
+                .map(|_| network.new_endpoint().peer_id())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(peer_ids(1), peer_ids(1));
+        assert_ne!(peer_ids(1), peer_ids(2));
+    }
+
+    #[test]
+    fn partitioned_and_disconnected_endpoints_are_unreachable() {
+        let network = SimulatedNetwork::new(0);
+        let endpoints: Vec<_> = (0..4).map(|_| network.new_endpoint().index()).collect();
+        assert!(network.is_reachable(endpoints[0], endpoints[3]));
+
+        network.partition(&endpoints[2..]);
+        assert!(network.is_reachable(endpoints[0], endpoints[1]));
+        assert!(network.is_reachable(endpoints[2], endpoints[3]));
+        assert!(!network.is_reachable(endpoints[0], endpoints[3]));
+
+        network.heal();
+        network.disconnect(endpoints[1]);
+        assert!(network.is_reachable(endpoints[0], endpoints[3]));
+        assert!(!network.is_reachable(endpoints[0], endpoints[1]));
+        assert!(!network.is_reachable(endpoints[1], endpoints[0]));
+
+        network.reconnect(endpoints[1]);
+        assert!(network.is_reachable(endpoints[0], endpoints[1]));
+    }
+}
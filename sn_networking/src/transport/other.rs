@@ -1,48 +1,51 @@
 #[cfg(feature = "open-metrics")]
 use crate::MetricsRegistries;
-#[cfg(feature = "websockets")]
 use futures::future::Either;
-#[cfg(feature = "websockets")]
-use libp2p::{core::upgrade, noise, yamux};
 use libp2p::{
-    core::{muxing::StreamMuxerBox, transport},
+    core::{muxing::StreamMuxerBox, transport, upgrade},
     identity::Keypair,
-    PeerId, Transport as _,
+    noise, yamux, PeerId, Transport as _,
 };
 
+/// Builds the QUIC transport, with TCP as a fallback for the networks blocking UDP if `tcp` is set.
 pub(crate) fn build_transport(
     keypair: &Keypair,
+    tcp: bool,
     #[cfg(feature = "open-metrics")] registries: &mut MetricsRegistries,
 ) -> transport::Boxed<(PeerId, StreamMuxerBox)> {
-    let trans = generate_quic_transport(keypair);
-    #[cfg(feature = "open-metrics")]
-    let trans = libp2p::metrics::BandwidthTransport::new(trans, &mut registries.standard_metrics);
+    let mut trans = generate_quic_transport(keypair)
+        .map(|(peer_id, muxer), _| (peer_id, StreamMuxerBox::new(muxer)))
+        .boxed();
 
-    #[cfg(feature = "websockets")]
-    // Using a closure here due to the complex return type
-    let generate_ws_transport = || {
-        let tcp = libp2p::tcp::tokio::Transport::new(libp2p::tcp::Config::default());
-        libp2p::websocket::WsConfig::new(tcp)
-            .upgrade(upgrade::Version::V1)
-            .authenticate(
-                noise::Config::new(keypair)
-                    .expect("Signing libp2p-noise static DH keypair failed."),
-            )
-            .multiplex(yamux::Config::default())
-    };
+    if tcp {
+        trans = or_transport(trans, generate_tcp_transport(keypair));
+    }
 
     // With the `websockets` feature enabled, we add it as a fallback transport.
     #[cfg(feature = "websockets")]
-    let trans = trans
-        .or_transport(generate_ws_transport())
-        .map(|either_output, _| match either_output {
-            Either::Left((peer_id, muxer)) => (peer_id, StreamMuxerBox::new(muxer)),
-            Either::Right((peer_id, muxer)) => (peer_id, StreamMuxerBox::new(muxer)),
-        });
-    #[cfg(not(feature = "websockets"))]
-    let trans = trans.map(|(peer_id, muxer), _| (peer_id, StreamMuxerBox::new(muxer)));
+    {
+        trans = or_transport(trans, generate_ws_transport(keypair));
+    }
+
+    #[cfg(feature = "open-metrics")]
+    let trans = libp2p::metrics::BandwidthTransport::new(trans, &mut registries.standard_metrics)
+        .map(|(peer_id, muxer), _| (peer_id, StreamMuxerBox::new(muxer)))
+        .boxed();
+
+    trans
+}
 
-    trans.boxed()
+/// Combine two transports, the first one being tried first for each address.
+fn or_transport(
+    first: transport::Boxed<(PeerId, StreamMuxerBox)>,
+    second: transport::Boxed<(PeerId, StreamMuxerBox)>,
+) -> transport::Boxed<(PeerId, StreamMuxerBox)> {
+    first
+        .or_transport(second)
+        .map(|either_output, _| match either_output {
+            Either::Left(output) | Either::Right(output) => output,
+        })
+        .boxed()
 }
 
 fn generate_quic_transport(
@@ -50,3 +53,27 @@ fn generate_quic_transport(
 ) -> libp2p::quic::GenTransport<libp2p::quic::tokio::Provider> {
     libp2p::quic::tokio::Transport::new(libp2p::quic::Config::new(keypair))
 }
+
+fn generate_tcp_transport(keypair: &Keypair) -> transport::Boxed<(PeerId, StreamMuxerBox)> {
+    libp2p::tcp::tokio::Transport::new(libp2p::tcp::Config::default().nodelay(true))
+        .upgrade(upgrade::Version::V1)
+        .authenticate(
+            noise::Config::new(keypair).expect("Signing libp2p-noise static DH keypair failed."),
+        )
+        .multiplex(yamux::Config::default())
+        .map(|(peer_id, muxer), _| (peer_id, StreamMuxerBox::new(muxer)))
+        .boxed()
+}
+
+#[cfg(feature = "websockets")]
+fn generate_ws_transport(keypair: &Keypair) -> transport::Boxed<(PeerId, StreamMuxerBox)> {
+    let tcp = libp2p::tcp::tokio::Transport::new(libp2p::tcp::Config::default());
+    libp2p::websocket::WsConfig::new(tcp)
+        .upgrade(upgrade::Version::V1)
+        .authenticate(
+            noise::Config::new(keypair).expect("Signing libp2p-noise static DH keypair failed."),
+        )
+        .multiplex(yamux::Config::default())
+        .map(|(peer_id, muxer), _| (peer_id, StreamMuxerBox::new(muxer)))
+        .boxed()
+}
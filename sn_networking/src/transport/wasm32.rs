@@ -6,7 +6,11 @@ use libp2p::{
     noise, websocket_websys, yamux, PeerId, Transport as _,
 };
 
-pub(crate) fn build_transport(keypair: &Keypair) -> transport::Boxed<(PeerId, StreamMuxerBox)> {
+// Plain TCP is not available in the browser, so `_tcp` is ignored.
+pub(crate) fn build_transport(
+    keypair: &Keypair,
+    _tcp: bool,
+) -> transport::Boxed<(PeerId, StreamMuxerBox)> {
     // We build a single transport here, WebSockets.
     websocket_websys::Transport::default()
         .upgrade(upgrade::Version::V1)
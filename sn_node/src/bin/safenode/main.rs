@@ -21,11 +21,16 @@ use sn_evm::{get_evm_network_from_env, EvmNetwork, RewardsAddress};
 #[cfg(feature = "metrics")]
 use sn_logging::metrics::init_metrics;
 use sn_logging::{Level, LogFormat, LogOutputDest, ReloadHandle};
+use sn_networking::{
+    BandwidthLimits, RecordStorageBackendKind, RelayServerLimits, DEFAULT_MAX_STORAGE_BYTES,
+    DEFAULT_RECORDS_CACHE_BYTES,
+};
 use sn_node::{Marker, NodeBuilder, NodeEvent, NodeEventsReceiver};
 use sn_peers_acquisition::PeersArgs;
 use sn_protocol::{
     node::get_safenode_root_dir,
     node_rpc::{NodeCtrl, StopResult},
+    storage::StorageSize,
     version::IDENTIFY_PROTOCOL_STR,
 };
 use std::{
@@ -89,6 +94,11 @@ struct Opt {
     #[clap(long, default_value_t = false)]
     upnp: bool,
 
+    /// Also listen on TCP, on the same port as QUIC, so clients and nodes on networks blocking UDP can
+    /// connect to the node.
+    #[clap(long, default_value_t = false)]
+    tcp: bool,
+
     /// Specify the logging output destination.
     ///
     /// Valid values are "stdout", "data-dir", or a custom path.
@@ -159,9 +169,14 @@ struct Opt {
 
     /// Specify the IP to listen on.
     ///
-    /// The special value `0.0.0.0` binds to all network interfaces available.
-    #[clap(long, default_value_t = IpAddr::V4(Ipv4Addr::UNSPECIFIED))]
-    ip: IpAddr,
+    /// The special value `0.0.0.0` binds to all IPv4 network interfaces available, while `::` binds to all the
+    /// IPv6 ones.
+    ///
+    /// The argument can be repeated, or given a comma-separated list, to listen on several IPs. For example,
+    /// `--ip 0.0.0.0,::` listens on both IPv4 and IPv6. The `--port` given is used for all of them, though
+    /// with the default `0` the OS assigns each of them a different random port.
+    #[clap(long, value_delimiter = ',', default_value = "0.0.0.0")]
+    ip: Vec<IpAddr>,
 
     #[command(flatten)]
     peers: PeersArgs,
@@ -182,6 +197,73 @@ struct Opt {
     #[clap(long)]
     owner: Option<String>,
 
+    /// Specify the storage engine used to persist the records.
+    ///
+    /// Valid values are "flat-file", one file per record, or "log-kv", an append-only log with an index.
+    ///
+    /// Records stored with another engine are not loaded, use `--migrate-record-store` to bring them over.
+    #[clap(long, default_value_t = RecordStorageBackendKind::FlatFile, verbatim_doc_comment)]
+    record_store_backend: RecordStorageBackendKind,
+
+    /// Move the records stored with the other storage engines into the one selected with
+    /// `--record-store-backend` before starting the node.
+    #[clap(long, verbatim_doc_comment)]
+    migrate_record_store: bool,
+
+    /// Specify the storage capacity offered by the node, e.g. "200GB" or "500MiB".
+    ///
+    /// The farthest records get pruned to stay under it, and the store cost rises as it fills up.
+    #[clap(long, default_value_t = StorageSize(DEFAULT_MAX_STORAGE_BYTES), verbatim_doc_comment)]
+    max_storage: StorageSize,
+
+    /// Specify the memory used to cache the most requested records, e.g. "256MiB".
+    ///
+    /// The cached records are served without being re-read from disk and decrypted.
+    #[clap(
+        long,
+        default_value_t = StorageSize(DEFAULT_RECORDS_CACHE_BYTES as u64),
+        verbatim_doc_comment
+    )]
+    records_cache_size: StorageSize,
+
+    /// Specify the max bytes per second uploaded by the node, e.g. "2MB" or "512KiB".
+    ///
+    /// All the connections share the limit. The replication is held back while the node uses most
+    /// of it, leaving the rest to the client requests. Unlimited if not set.
+    #[clap(long, verbatim_doc_comment)]
+    max_upload_rate: Option<StorageSize>,
+
+    /// Specify the max bytes per second downloaded by the node, e.g. "2MB" or "512KiB".
+    ///
+    /// All the connections share the limit. The replication is held back while the node uses most
+    /// of it, leaving the rest to the client requests. Unlimited if not set.
+    #[clap(long, verbatim_doc_comment)]
+    max_download_rate: Option<StorageSize>,
+
+    /// Specify the max peers behind a home network the node holds a relay reservation for.
+    ///
+    /// Defaults to 128.
+    #[clap(long, verbatim_doc_comment)]
+    relay_max_reservations: Option<usize>,
+
+    /// Specify the max connections the node relays at once, for the peers behind a home network.
+    ///
+    /// Defaults to 1024.
+    #[clap(long, verbatim_doc_comment)]
+    relay_max_circuits: Option<usize>,
+
+    /// Specify the max bytes relayed over a connection before it gets closed, e.g. "5MiB".
+    ///
+    /// Defaults to the max packet size of the network.
+    #[clap(long, verbatim_doc_comment)]
+    relay_max_circuit_bytes: Option<StorageSize>,
+
+    /// Specify the max seconds a relayed connection is kept open for.
+    ///
+    /// Defaults to 120 seconds.
+    #[clap(long, verbatim_doc_comment)]
+    relay_max_circuit_duration: Option<u64>,
+
     #[cfg(feature = "open-metrics")]
     /// Specify the port for the OpenMetrics server.
     ///
@@ -264,13 +346,25 @@ fn main() -> Result<()> {
         .unwrap_or_else(get_evm_network_from_env)?;
     println!("EVM network: {evm_network:?}");
 
-    let node_socket_addr = SocketAddr::new(opt.ip, opt.port);
+    let mut node_socket_addrs = opt.ip.iter().map(|ip| SocketAddr::new(*ip, opt.port));
+    let node_socket_addr = node_socket_addrs
+        .next()
+        .unwrap_or_else(|| SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), opt.port));
+    let additional_node_socket_addrs: Vec<_> = node_socket_addrs.collect();
     let (root_dir, keypair) = get_root_dir_and_keypair(&opt.root_dir)?;
 
     let (log_output_dest, log_reload_handle, _log_appender_guard) =
         init_logging(&opt, keypair.public().to_peer_id())?;
 
     let rt = Runtime::new()?;
+    let bootstrap_cache_path = if opt.local {
+        None
+    } else {
+        opt.peers.bootstrap_cache_path().unwrap_or_else(|err| {
+            warn!("Not recording the peers reached to the bootstrap cache: {err}");
+            None
+        })
+    };
     let bootstrap_peers = rt.block_on(opt.peers.get_peers())?;
     let msg = format!(
         "Running {} v{}",
@@ -306,6 +400,39 @@ fn main() -> Result<()> {
             opt.upnp,
         );
         node_builder.is_behind_home_network = opt.home_network;
+        for addr in additional_node_socket_addrs {
+            node_builder.additional_listen_addr(addr);
+        }
+        node_builder.tcp(opt.tcp);
+        node_builder.record_store_backend(opt.record_store_backend);
+        node_builder.migrate_record_store(opt.migrate_record_store);
+        node_builder.max_storage_bytes(opt.max_storage.as_bytes());
+        node_builder.records_cache_bytes(opt.records_cache_size.as_bytes() as usize);
+        node_builder.bandwidth_limits(BandwidthLimits {
+            upload: opt.max_upload_rate.map(|rate| rate.as_bytes()),
+            download: opt.max_download_rate.map(|rate| rate.as_bytes()),
+        });
+        let default_relay_limits = RelayServerLimits::default();
+        node_builder.relay_server_limits(RelayServerLimits {
+            max_reservations: opt
+                .relay_max_reservations
+                .unwrap_or(default_relay_limits.max_reservations),
+            max_circuits: opt
+                .relay_max_circuits
+                .unwrap_or(default_relay_limits.max_circuits),
+            max_circuit_bytes: opt
+                .relay_max_circuit_bytes
+                .map_or(default_relay_limits.max_circuit_bytes, |size| {
+                    size.as_bytes()
+                }),
+            max_circuit_duration: opt.relay_max_circuit_duration.map_or(
+                default_relay_limits.max_circuit_duration,
+                Duration::from_secs,
+            ),
+        });
+        if let Some(path) = bootstrap_cache_path {
+            node_builder.bootstrap_cache_path(path);
+        }
         #[cfg(feature = "open-metrics")]
         let mut node_builder = node_builder;
         // if enable flag is provided or only if the port is specified then enable the server by setting Some()
@@ -449,7 +576,7 @@ You can check your reward balance by running:
             addr,
             log_output_dest,
             running_node.clone(),
-            ctrl_tx,
+            ctrl_tx.clone(),
             started_instant,
             log_reload_handle,
         );
@@ -497,6 +624,49 @@ You can check your reward balance by running:
                 // TODO: implement self-update once safenode app releases are published again
                 println!("No self-update supported yet.");
             }
+            Some(NodeCtrl::Drain {
+                timeout,
+                stop_when_drained,
+            }) => {
+                let msg = format!("Node is draining its records, for up to {timeout:?}...");
+                info!("{msg}");
+                println!("{msg}");
+
+                // Drain in the background, so the node keeps serving its records and the RPC
+                // requests for the progress, or can still be stopped, while draining.
+                let running_node = running_node.clone();
+                let ctrl_tx = ctrl_tx.clone();
+                let _handle = tokio::spawn(async move {
+                    let result = match running_node.drain(timeout).await {
+                        Ok(progress) => {
+                            let msg = format!(
+                                "Node drained {}/{} records, {} left unconfirmed",
+                                progress.records_confirmed,
+                                progress.records_total,
+                                progress.records_unconfirmed
+                            );
+                            info!("{msg}");
+                            println!("{msg}");
+                            StopResult::Success(msg)
+                        }
+                        Err(err) => {
+                            error!("Failed to drain the node: {err}");
+                            StopResult::Error(eyre!("Failed to drain the node: {err}"))
+                        }
+                    };
+                    if stop_when_drained {
+                        if let Err(err) = ctrl_tx
+                            .send(NodeCtrl::Stop {
+                                delay: Duration::from_secs(1),
+                                result,
+                            })
+                            .await
+                        {
+                            error!("Failed to send node control msg to safenode bin main thread: {err}");
+                        }
+                    }
+                });
+            }
             None => {
                 info!("Internal node ctrl cmds channel has been closed, restarting node");
                 break Err(eyre!("Internal node ctrl cmds channel has been closed"));
@@ -8,15 +8,18 @@
 
 use eyre::{ErrReport, Result};
 use sn_logging::ReloadHandle;
-use sn_node::RunningNode;
+use sn_node::{DrainState, RunningNode};
 use sn_protocol::node_rpc::{NodeCtrl, StopResult};
 use sn_protocol::safenode_proto::{
-    k_buckets_response,
+    drain_status_response, k_buckets_response, peer_reputations_response, rewards_ledger_response,
     safe_node_server::{SafeNode, SafeNodeServer},
-    KBucketsRequest, KBucketsResponse, NetworkInfoRequest, NetworkInfoResponse, NodeEvent,
-    NodeEventsRequest, NodeInfoRequest, NodeInfoResponse, RecordAddressesRequest,
-    RecordAddressesResponse, RestartRequest, RestartResponse, StopRequest, StopResponse,
-    UpdateLogLevelRequest, UpdateLogLevelResponse, UpdateRequest, UpdateResponse,
+    DrainRequest, DrainResponse, DrainStatusRequest, DrainStatusResponse, KBucketsRequest,
+    KBucketsResponse, NetworkInfoRequest, NetworkInfoResponse, NodeEvent, NodeEventsRequest,
+    NodeInfoRequest, NodeInfoResponse, PeerReputationsRequest, PeerReputationsResponse,
+    RecordAddressesRequest, RecordAddressesResponse, RecordStoreScrubRequest,
+    RecordStoreScrubResponse, RestartRequest, RestartResponse, RewardsLedgerRequest,
+    RewardsLedgerResponse, StopRequest, StopResponse, UpdateLogLevelRequest,
+    UpdateLogLevelResponse, UpdateRequest, UpdateResponse,
 };
 use std::{
     collections::HashMap,
@@ -89,10 +92,22 @@ impl SafeNode for SafeNodeRpcService {
             .expect("failed to get local swarm state");
         let connected_peers = state.connected_peers.iter().map(|p| p.to_bytes()).collect();
         let listeners = state.listeners.iter().map(|m| m.to_string()).collect();
+        let network_size = self
+            .running_node
+            .get_network_size_estimate()
+            .await
+            .map_err(|err| {
+                Status::new(
+                    Code::Internal,
+                    format!("Failed to estimate the network size: {err}"),
+                )
+            })?;
 
         let resp = Response::new(NetworkInfoResponse {
             connected_peers,
             listeners,
+            estimated_network_size: network_size.estimated_size,
+            peers_in_routing_table: network_size.peers_in_routing_table as u64,
         });
 
         Ok(resp)
@@ -186,6 +201,105 @@ impl SafeNode for SafeNodeRpcService {
         Ok(Response::new(KBucketsResponse { kbuckets }))
     }
 
+    async fn rewards_ledger(
+        &self,
+        request: Request<RewardsLedgerRequest>,
+    ) -> Result<Response<RewardsLedgerResponse>, Status> {
+        debug!(
+            "RPC request received at {}: {:?}",
+            self.addr,
+            request.get_ref()
+        );
+
+        let summary = self.running_node.rewards_summary();
+        let payments = self
+            .running_node
+            .rewards_history(
+                request.get_ref().offset as usize,
+                request.get_ref().limit as usize,
+            )
+            .map_err(|err| {
+                Status::new(
+                    Code::Internal,
+                    format!("Failed to read the rewards ledger: {err}"),
+                )
+            })?
+            .into_iter()
+            .map(|entry| rewards_ledger_response::Payment {
+                quote_hash: entry.quote_hash.to_vec(),
+                tx_hash: entry.tx_hash.to_vec(),
+                record_key: hex::decode(&entry.record_key).unwrap_or_default(),
+                amount: entry.amount.as_atto().to_string(),
+                timestamp_secs: entry.timestamp,
+            })
+            .collect();
+
+        Ok(Response::new(RewardsLedgerResponse {
+            total_earned: summary.total_earned.as_atto().to_string(),
+            payments_count: summary.payments_count,
+            payments,
+        }))
+    }
+
+    async fn record_store_scrub(
+        &self,
+        request: Request<RecordStoreScrubRequest>,
+    ) -> Result<Response<RecordStoreScrubResponse>, Status> {
+        debug!(
+            "RPC request received at {}: {:?}",
+            self.addr,
+            request.get_ref()
+        );
+
+        let stats = self
+            .running_node
+            .get_record_scrub_stats()
+            .await
+            .map_err(|err| {
+                Status::new(
+                    Code::Internal,
+                    format!("Failed to get the record store scrub stats: {err}"),
+                )
+            })?;
+
+        Ok(Response::new(RecordStoreScrubResponse {
+            records_checked: stats.records_checked,
+            corrupt_records: stats.corrupt_records,
+            passes_completed: stats.passes_completed,
+        }))
+    }
+
+    async fn peer_reputations(
+        &self,
+        request: Request<PeerReputationsRequest>,
+    ) -> Result<Response<PeerReputationsResponse>, Status> {
+        debug!(
+            "RPC request received at {}: {:?}",
+            self.addr,
+            request.get_ref()
+        );
+
+        let peers = self
+            .running_node
+            .get_peer_reputations()
+            .await
+            .map_err(|err| {
+                Status::new(
+                    Code::Internal,
+                    format!("Failed to get the peer reputations: {err}"),
+                )
+            })?
+            .into_iter()
+            .map(|(peer_id, reputation)| peer_reputations_response::Peer {
+                peer_id: peer_id.to_bytes(),
+                score: reputation.score,
+                is_bad: reputation.is_bad,
+            })
+            .collect();
+
+        Ok(Response::new(PeerReputationsResponse { peers }))
+    }
+
     async fn stop(&self, request: Request<StopRequest>) -> Result<Response<StopResponse>, Status> {
         debug!(
             "RPC request received at {}: {:?}",
@@ -218,6 +332,71 @@ impl SafeNode for SafeNodeRpcService {
         }
     }
 
+    async fn drain(
+        &self,
+        request: Request<DrainRequest>,
+    ) -> Result<Response<DrainResponse>, Status> {
+        debug!(
+            "RPC request received at {}: {:?}",
+            self.addr,
+            request.get_ref()
+        );
+
+        if matches!(
+            self.running_node.drain_progress().state,
+            DrainState::HandingOff | DrainState::Confirming
+        ) {
+            return Err(Status::new(
+                Code::FailedPrecondition,
+                "The node is already draining",
+            ));
+        }
+
+        let timeout = Duration::from_secs(request.get_ref().timeout_secs);
+        match self
+            .ctrl_tx
+            .send(NodeCtrl::Drain {
+                timeout,
+                stop_when_drained: request.get_ref().stop_when_drained,
+            })
+            .await
+        {
+            Ok(()) => Ok(Response::new(DrainResponse {})),
+            Err(err) => Err(Status::new(
+                Code::Internal,
+                format!("Failed to drain the node: {err}"),
+            )),
+        }
+    }
+
+    async fn drain_status(
+        &self,
+        request: Request<DrainStatusRequest>,
+    ) -> Result<Response<DrainStatusResponse>, Status> {
+        debug!(
+            "RPC request received at {}: {:?}",
+            self.addr,
+            request.get_ref()
+        );
+
+        let progress = self.running_node.drain_progress();
+        let (state, error) = match progress.state {
+            DrainState::Idle => (drain_status_response::State::Idle, String::new()),
+            DrainState::HandingOff => (drain_status_response::State::HandingOff, String::new()),
+            DrainState::Confirming => (drain_status_response::State::Confirming, String::new()),
+            DrainState::Completed => (drain_status_response::State::Completed, String::new()),
+            DrainState::Failed(error) => (drain_status_response::State::Failed, error),
+        };
+
+        Ok(Response::new(DrainStatusResponse {
+            state: state.into(),
+            records_total: progress.records_total as u64,
+            records_confirmed: progress.records_confirmed as u64,
+            records_unconfirmed: progress.records_unconfirmed as u64,
+            error,
+        }))
+    }
+
     async fn restart(
         &self,
         request: Request<RestartRequest>,
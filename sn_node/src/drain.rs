@@ -0,0 +1,340 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    error::{Error, Result},
+    node::chunk_proof_verify_peer,
+};
+use futures::{stream, StreamExt};
+use libp2p::{kad::Record, PeerId};
+use sn_networking::{close_group_majority, sort_peers_by_address_and_limit, Network};
+use sn_protocol::{
+    messages::{Cmd, Query, QueryResponse, Request, Response},
+    storage::{get_type_from_record, try_deserialize_record, RecordType, Scratchpad},
+    NetworkAddress, CLOSE_GROUP_SIZE,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Interval between the rounds checking the records got fetched by the peers taking them over.
+const DRAIN_CONFIRMATION_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The max number of keys sent to a peer in a single replication list while draining.
+const DRAIN_KEYS_PER_REQUEST: usize = 500;
+
+/// The max number of storage proofs awaited at the same time while draining.
+const DRAIN_CONCURRENT_CHECKS: usize = 16;
+
+/// The stage a drain of the node is at.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum DrainState {
+    /// No drain has been requested.
+    #[default]
+    Idle,
+    /// The records are being handed off to the peers taking over their responsibility.
+    HandingOff,
+    /// Waiting for the peers to prove they fetched the records handed off.
+    Confirming,
+    /// Every record was confirmed as handed off.
+    Completed,
+    /// The drain was aborted by an error, or timed out with records left unconfirmed.
+    Failed(String),
+}
+
+/// The progress of the hand off of the records held by the node.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DrainProgress {
+    /// The stage the drain is at
+    pub state: DrainState,
+    /// The number of records to hand off
+    pub records_total: usize,
+    /// The number of records a majority of the peers taking them over proved to hold
+    pub records_confirmed: usize,
+    /// The number of records not confirmed yet, or at all once completed
+    pub records_unconfirmed: usize,
+}
+
+/// A record being handed off, along with the peers taking it over.
+struct HandOff {
+    record_type: RecordType,
+    proven: usize,
+    unproven: Vec<PeerId>,
+}
+
+/// Hands off the records held by the node to the peers that become responsible for them once
+/// the node leaves, i.e. the closest peers to each record other than the node itself.
+///
+/// The peers are sent the keys as a replication list, fetching the records from the node,
+/// and are then checked until a majority of them hold each record: chunks with storage proofs,
+/// the mutable records by fetching them back from the peers.
+#[derive(Debug, Default)]
+pub(crate) struct Drainer {
+    progress: Mutex<DrainProgress>,
+}
+
+impl Drainer {
+    pub(crate) fn progress(&self) -> DrainProgress {
+        self.progress
+            .lock()
+            .map(|progress| progress.clone())
+            .unwrap_or_default()
+    }
+
+    fn update(&self, update: impl FnOnce(&mut DrainProgress)) {
+        if let Ok(mut progress) = self.progress.lock() {
+            update(&mut progress);
+        }
+    }
+
+    /// Hand off the records held by the node, giving up on the ones not confirmed within `timeout`.
+    pub(crate) async fn drain(
+        &self,
+        network: &Network,
+        timeout: Duration,
+    ) -> Result<DrainProgress> {
+        {
+            let mut progress = self
+                .progress
+                .lock()
+                .map_err(|_| Error::Drain("Drain progress lock is poisoned".to_string()))?;
+            if matches!(
+                progress.state,
+                DrainState::HandingOff | DrainState::Confirming
+            ) {
+                return Err(Error::Drain("A drain is already in progress".to_string()));
+            }
+            *progress = DrainProgress {
+                state: DrainState::HandingOff,
+                ..Default::default()
+            };
+        }
+        let deadline = Instant::now() + timeout;
+
+        let result = self.hand_off(network, deadline).await;
+        self.update(|progress| {
+            progress.state = match &result {
+                Ok(()) => DrainState::Completed,
+                Err(err) => DrainState::Failed(err.to_string()),
+            }
+        });
+        result?;
+
+        let progress = self.progress();
+        info!("Drain completed: {progress:?}");
+        Ok(progress)
+    }
+
+    async fn hand_off(&self, network: &Network, deadline: Instant) -> Result<()> {
+        let records = network.get_all_local_record_addresses().await?;
+        let peers = network.get_all_local_peers_excluding_self().await?;
+        let peers_count = peers.len();
+        let mut pending = successors(peers, records)?;
+        info!("Draining {} records to {peers_count} peers", pending.len());
+        self.update(|progress| {
+            progress.records_total = pending.len();
+            progress.records_unconfirmed = pending.len();
+        });
+
+        while !pending.is_empty() {
+            let now = Instant::now();
+            if now >= deadline {
+                warn!("Drain timed out with {} records unconfirmed", pending.len());
+                return Err(Error::Drain(format!(
+                    "Timed out with {} records unconfirmed",
+                    pending.len()
+                )));
+            }
+
+            self.send_hand_off_lists(network, &pending);
+            self.update(|progress| progress.state = DrainState::Confirming);
+            tokio::time::sleep(DRAIN_CONFIRMATION_INTERVAL.min(deadline - now)).await;
+
+            pending = confirm_hand_offs(network, pending).await;
+            self.update(|progress| {
+                progress.records_confirmed = progress.records_total - pending.len();
+                progress.records_unconfirmed = pending.len();
+            });
+        }
+        Ok(())
+    }
+
+    /// Send each peer the keys of the records it is yet to prove it holds.
+    fn send_hand_off_lists(&self, network: &Network, pending: &BTreeMap<NetworkAddress, HandOff>) {
+        let mut lists: HashMap<PeerId, Vec<(NetworkAddress, RecordType)>> = HashMap::new();
+        for (key, hand_off) in pending.iter() {
+            for peer_id in hand_off.unproven.iter() {
+                lists
+                    .entry(*peer_id)
+                    .or_default()
+                    .push((key.clone(), hand_off.record_type.clone()));
+            }
+        }
+
+        let holder = NetworkAddress::from_peer(network.peer_id());
+        for (peer_id, keys) in lists {
+            debug!("Handing off {} keys to {peer_id:?}", keys.len());
+            for keys in keys.chunks(DRAIN_KEYS_PER_REQUEST) {
+                let request = Request::Cmd(Cmd::Replicate {
+                    holder: holder.clone(),
+                    keys: keys.to_vec(),
+                });
+                network.send_req_ignore_reply(request, peer_id);
+            }
+        }
+    }
+}
+
+/// Check the peers yet to prove they hold the records handed off, returning the records
+/// still missing a majority of proofs.
+async fn confirm_hand_offs(
+    network: &Network,
+    pending: BTreeMap<NetworkAddress, HandOff>,
+) -> BTreeMap<NetworkAddress, HandOff> {
+    stream::iter(pending)
+        .map(|(key, mut hand_off)| async move {
+            let mut unproven = vec![];
+            for peer_id in hand_off.unproven {
+                if is_handed_off(network, peer_id, &key, &hand_off.record_type).await {
+                    hand_off.proven += 1;
+                } else {
+                    unproven.push(peer_id);
+                }
+            }
+            hand_off.unproven = unproven;
+            (key, hand_off)
+        })
+        .buffer_unordered(DRAIN_CONCURRENT_CHECKS)
+        .filter(|(_, hand_off)| std::future::ready(hand_off.proven < close_group_majority()))
+        .collect()
+        .await
+}
+
+/// Whether a peer holds a record handed off to it.
+///
+/// A storage proof only holds for the exact content, which is fine for the immutable chunks.
+/// The other records are fetched back from the peer and compared, a scratchpad holding a newer
+/// version written meanwhile counting as handed off.
+async fn is_handed_off(
+    network: &Network,
+    peer_id: PeerId,
+    key: &NetworkAddress,
+    record_type: &RecordType,
+) -> bool {
+    if *record_type == RecordType::Chunk {
+        return chunk_proof_verify_peer(network, peer_id, std::slice::from_ref(key)).await;
+    }
+
+    let request = Request::Query(Query::GetReplicatedRecord {
+        requester: NetworkAddress::from_peer(network.peer_id()),
+        key: key.clone(),
+    });
+    let value = match network.send_request(request, peer_id).await {
+        Ok(Response::Query(QueryResponse::GetReplicatedRecord(Ok((_holder, value))))) => value,
+        other => {
+            debug!("{peer_id:?} doesn't hold the handed off {key:?} yet: {other:?}");
+            return false;
+        }
+    };
+    let record = Record::new(key.to_record_key(), value.to_vec());
+
+    match (record_type, get_type_from_record(&record)) {
+        (RecordType::Scratchpad, Ok(RecordType::Scratchpad)) => {
+            let Ok(theirs) = try_deserialize_record::<Scratchpad>(&record) else {
+                return false;
+            };
+            match network.get_local_record(&key.to_record_key()).await {
+                Ok(Some(ours)) => try_deserialize_record::<Scratchpad>(&ours)
+                    .is_ok_and(|ours| theirs.count() >= ours.count()),
+                // ours got removed meanwhile, any valid version is as good
+                _ => true,
+            }
+        }
+        (record_type, Ok(fetched_type)) => *record_type == fetched_type,
+        (_, Err(err)) => {
+            warn!("{peer_id:?} returned an invalid record for the handed off {key:?}: {err:?}");
+            false
+        }
+    }
+}
+
+/// The peers taking over the responsibility of each record once we leave, i.e. its closest peers
+/// excluding us. Nothing can be handed off without enough peers to form a close group.
+fn successors(
+    peers: Vec<PeerId>,
+    records: HashMap<NetworkAddress, RecordType>,
+) -> Result<BTreeMap<NetworkAddress, HandOff>> {
+    if !records.is_empty() && peers.len() < CLOSE_GROUP_SIZE {
+        return Err(Error::Drain(format!(
+            "Only {} peers known, {CLOSE_GROUP_SIZE} are needed to take the {} records over",
+            peers.len(),
+            records.len()
+        )));
+    }
+
+    records
+        .into_iter()
+        .map(|(key, record_type)| {
+            let closest =
+                sort_peers_by_address_and_limit(&peers, &key, CLOSE_GROUP_SIZE).map_err(|err| {
+                    Error::Drain(format!(
+                        "Failed to find the peers closest to {key:?}: {err}"
+                    ))
+                })?;
+            let hand_off = HandOff {
+                record_type,
+                proven: 0,
+                unproven: closest.into_iter().copied().collect(),
+            };
+            Ok((key, hand_off))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sn_protocol::storage::ChunkAddress;
+    use xor_name::XorName;
+
+    #[test]
+    fn records_are_handed_off_to_their_closest_peers() {
+        let peers: Vec<_> = (0..20).map(|_| PeerId::random()).collect();
+        let records: HashMap<_, _> = (0..50u32)
+            .map(|i| {
+                let key = NetworkAddress::from_chunk_address(ChunkAddress::new(
+                    XorName::from_content(&i.to_le_bytes()),
+                ));
+                (key, RecordType::Chunk)
+            })
+            .collect();
+
+        let hand_offs = successors(peers.clone(), records.clone()).expect("enough peers");
+        assert_eq!(hand_offs.len(), records.len());
+        for (key, hand_off) in hand_offs.iter() {
+            assert_eq!(hand_off.unproven.len(), CLOSE_GROUP_SIZE);
+
+            let closest = sort_peers_by_address_and_limit(&peers, key, CLOSE_GROUP_SIZE)
+                .expect("enough peers");
+            for peer_id in hand_off.unproven.iter() {
+                assert!(closest.contains(&peer_id));
+            }
+        }
+
+        // with fewer peers than a close group, no one can take the records over
+        assert!(matches!(
+            successors(peers[..CLOSE_GROUP_SIZE - 1].to_vec(), records),
+            Err(Error::Drain(_))
+        ));
+        assert!(successors(vec![], HashMap::new())
+            .expect("nothing to hand off")
+            .is_empty());
+    }
+}
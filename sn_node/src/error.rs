@@ -100,4 +100,14 @@ pub enum Error {
     EvmNetwork(String),
     #[error("Invalid quote timestamp: {0}")]
     InvalidQuoteTimestamp(String),
+
+    #[error("Rewards ledger error: {0}")]
+    RewardsLedger(String),
+
+    #[error("Drain error: {0}")]
+    Drain(String),
+
+    #[cfg(feature = "simulation")]
+    #[error("Simulation error: {0}")]
+    Simulation(String),
 }
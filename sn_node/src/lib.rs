@@ -27,30 +27,46 @@
 #[macro_use]
 extern crate tracing;
 
+mod drain;
 mod error;
 mod event;
 mod log_markers;
 #[cfg(feature = "open-metrics")]
 mod metrics;
 mod node;
+mod payment_verifier;
 mod put_validation;
 mod quote;
 mod replication;
+mod rewards_ledger;
+#[cfg(feature = "simulation")]
+mod simulation;
+mod storage_audit;
 
+#[cfg(feature = "simulation")]
+pub use self::simulation::{MockEvm, Simulation};
 pub use self::{
+    drain::{DrainProgress, DrainState},
     event::{NodeEvent, NodeEventsChannel, NodeEventsReceiver},
     log_markers::Marker,
     node::{NodeBuilder, PERIODIC_REPLICATION_INTERVAL_MAX_S},
+    rewards_ledger::{RewardsLedgerEntry, RewardsSummary},
 };
 
+use crate::{drain::Drainer, rewards_ledger::RewardsLedger};
+
 use crate::error::{Error, Result};
 
 use libp2p::PeerId;
-use sn_networking::{Network, SwarmLocalState};
+use sn_networking::{
+    Network, NetworkSizeEstimate, PeerReputation, RecordScrubStats, SwarmLocalState,
+};
 use sn_protocol::{get_port_from_multiaddr, NetworkAddress};
 use std::{
     collections::{BTreeMap, HashSet},
     path::PathBuf,
+    sync::Arc,
+    time::Duration,
 };
 
 /// Once a node is started and running, the user obtains
@@ -60,6 +76,8 @@ pub struct RunningNode {
     network: Network,
     node_events_channel: NodeEventsChannel,
     root_dir_path: PathBuf,
+    rewards_ledger: Arc<RewardsLedger>,
+    drainer: Arc<Drainer>,
 }
 
 impl RunningNode {
@@ -86,6 +104,12 @@ impl RunningNode {
         Ok(state)
     }
 
+    /// Returns an estimate of the number of nodes in the network, made from the node's routing table
+    pub async fn get_network_size_estimate(&self) -> Result<NetworkSizeEstimate> {
+        let estimate = self.network.get_network_size_estimate().await?;
+        Ok(estimate)
+    }
+
     /// Return the node's listening port
     pub async fn get_node_listening_port(&self) -> Result<u16> {
         let listen_addrs = self.network.get_swarm_local_state().await?.listeners;
@@ -121,4 +145,39 @@ impl RunningNode {
         let kbuckets = self.network.get_kbuckets().await?;
         Ok(kbuckets)
     }
+
+    /// Returns the outcome of the periodic re-reads and verifications of the records held by the node
+    pub async fn get_record_scrub_stats(&self) -> Result<RecordScrubStats> {
+        let stats = self.network.get_record_scrub_stats().await?;
+        Ok(stats)
+    }
+
+    /// Returns the reputation the node holds of each of the peers it interacted with
+    pub async fn get_peer_reputations(&self) -> Result<Vec<(PeerId, PeerReputation)>> {
+        let reputations = self.network.get_peer_reputations().await?;
+        Ok(reputations)
+    }
+
+    /// Returns the totals of the payments accepted by the node, as recorded in its rewards ledger
+    pub fn rewards_summary(&self) -> RewardsSummary {
+        self.rewards_ledger.summary()
+    }
+
+    /// Returns a page of the payments accepted by the node, the most recent first
+    pub fn rewards_history(&self, offset: usize, limit: usize) -> Result<Vec<RewardsLedgerEntry>> {
+        self.rewards_ledger.history(offset, limit)
+    }
+
+    /// Hands off the records held by the node to the peers taking over their responsibility once
+    /// it leaves, waiting for them to prove they hold the records for up to `timeout`.
+    ///
+    /// The node keeps running once drained, it is up to the caller to stop it.
+    pub async fn drain(&self, timeout: Duration) -> Result<DrainProgress> {
+        self.drainer.drain(&self.network, timeout).await
+    }
+
+    /// Returns the progress of the latest drain of the node
+    pub fn drain_progress(&self) -> DrainProgress {
+        self.drainer.progress()
+    }
 }
@@ -60,6 +60,16 @@ pub enum Marker<'a> {
 
     /// Interval based bad_nodes check
     IntervalBadNodesCheckTriggered,
+
+    /// Peer proved it holds the records challenged during a storage audit
+    StorageAuditPassed(&'a PeerId),
+    /// Peer failed to prove it holds the records challenged during a storage audit
+    StorageAuditFailed(&'a PeerId),
+
+    /// The payments of a transaction were already known locally when verifying a payment
+    PaymentVerificationCacheHit,
+    /// The payments of a transaction had to be fetched on-chain when verifying a payment
+    PaymentVerificationCacheMiss,
 }
 
 impl<'a> Marker<'a> {
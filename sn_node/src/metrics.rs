@@ -36,6 +36,14 @@ pub(crate) struct NodeMetricsRecorder {
     peer_added_to_routing_table: Counter,
     peer_removed_from_routing_table: Counter,
 
+    // storage audits
+    storage_audit_passed: Counter,
+    storage_audit_failed: Counter,
+
+    // payment verification
+    payment_verification_cache_hit: Counter,
+    payment_verification_cache_miss: Counter,
+
     // wallet
     pub(crate) current_reward_wallet_balance: Gauge,
     pub(crate) _total_forwarded_rewards: Gauge,
@@ -115,6 +123,34 @@ impl NodeMetricsRecorder {
             peer_removed_from_routing_table.clone(),
         );
 
+        let storage_audit_passed = Counter::default();
+        sub_registry.register(
+            "storage_audit_passed",
+            "Number of storage audits passed by close group peers",
+            storage_audit_passed.clone(),
+        );
+
+        let storage_audit_failed = Counter::default();
+        sub_registry.register(
+            "storage_audit_failed",
+            "Number of storage audits failed by close group peers",
+            storage_audit_failed.clone(),
+        );
+
+        let payment_verification_cache_hit = Counter::default();
+        sub_registry.register(
+            "payment_verification_cache_hit",
+            "Number of payment verifications served without an on-chain lookup",
+            payment_verification_cache_hit.clone(),
+        );
+
+        let payment_verification_cache_miss = Counter::default();
+        sub_registry.register(
+            "payment_verification_cache_miss",
+            "Number of payment verifications requiring an on-chain lookup",
+            payment_verification_cache_miss.clone(),
+        );
+
         let current_reward_wallet_balance = Gauge::default();
         sub_registry.register(
             "current_reward_wallet_balance",
@@ -143,6 +179,10 @@ impl NodeMetricsRecorder {
             replication_keys_to_fetch,
             peer_added_to_routing_table,
             peer_removed_from_routing_table,
+            storage_audit_passed,
+            storage_audit_failed,
+            payment_verification_cache_hit,
+            payment_verification_cache_miss,
             current_reward_wallet_balance,
             _total_forwarded_rewards: total_forwarded_rewards,
             started_instant: Instant::now(),
@@ -200,6 +240,22 @@ impl NodeMetricsRecorder {
                 let _ = self.peer_removed_from_routing_table.inc();
             }
 
+            Marker::StorageAuditPassed(_) => {
+                let _ = self.storage_audit_passed.inc();
+            }
+
+            Marker::StorageAuditFailed(_) => {
+                let _ = self.storage_audit_failed.inc();
+            }
+
+            Marker::PaymentVerificationCacheHit => {
+                let _ = self.payment_verification_cache_hit.inc();
+            }
+
+            Marker::PaymentVerificationCacheMiss => {
+                let _ = self.payment_verification_cache_miss.inc();
+            }
+
             _ => {}
         }
     }
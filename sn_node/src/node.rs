@@ -11,17 +11,28 @@ use super::{
 };
 #[cfg(feature = "open-metrics")]
 use crate::metrics::NodeMetricsRecorder;
-use crate::RunningNode;
+#[cfg(feature = "simulation")]
+use crate::simulation::MockEvm;
+use crate::{
+    payment_verifier::PaymentVerifier,
+    rewards_ledger::RewardsLedger,
+    storage_audit::{StorageAuditor, PERIODIC_STORAGE_AUDIT_INTERVAL_MAX_S},
+    RunningNode,
+};
 use bytes::Bytes;
 use libp2p::{identity::Keypair, Multiaddr, PeerId};
 use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
 use sn_evm::{AttoTokens, RewardsAddress};
 #[cfg(feature = "open-metrics")]
 use sn_networking::MetricsRegistries;
+#[cfg(feature = "simulation")]
+use sn_networking::SimulatedEndpoint;
 use sn_networking::{
-    close_group_majority, Instant, Network, NetworkBuilder, NetworkError, NetworkEvent, NodeIssue,
-    SwarmDriver,
+    close_group_majority, BandwidthLimits, Instant, Network, NetworkBuilder, NetworkError,
+    NetworkEvent, NodeIssue, RecordStorageBackendKind, RelayServerLimits, SwarmDriver,
+    DEFAULT_MAX_STORAGE_BYTES, DEFAULT_RECORDS_CACHE_BYTES,
 };
+use sn_peers_acquisition::BootstrapCache;
 use sn_protocol::{
     error::Error as ProtocolError,
     messages::{ChunkProof, CmdResponse, Query, QueryResponse, Request, Response},
@@ -70,6 +81,8 @@ pub struct NodeBuilder {
     evm_address: RewardsAddress,
     evm_network: EvmNetwork,
     addr: SocketAddr,
+    /// The addresses listened on alongside `addr`, e.g. an IPv6 address for a dual-stack node
+    additional_listen_addrs: Vec<SocketAddr>,
     initial_peers: Vec<Multiaddr>,
     local: bool,
     root_dir: PathBuf,
@@ -78,6 +91,25 @@ pub struct NodeBuilder {
     metrics_server_port: Option<u16>,
     /// Enable hole punching for nodes connecting from home networks.
     pub is_behind_home_network: bool,
+    /// The storage engine used to persist the records
+    record_store_backend: RecordStorageBackendKind,
+    /// Move the records of the other storage engines into the selected one at startup
+    migrate_record_store: bool,
+    /// The storage capacity of the node, in bytes
+    max_storage_bytes: u64,
+    /// The bytes of popular records kept in memory
+    records_cache_bytes: usize,
+    /// The upload and download rate limits, in bytes per second
+    bandwidth_limits: BandwidthLimits,
+    /// The resources lent to the peers behind a home network, by relaying their connections
+    relay_server_limits: RelayServerLimits,
+    /// The file recording the peers reached, for the next start to bootstrap from
+    bootstrap_cache_path: Option<PathBuf>,
+    /// Listen on TCP alongside QUIC
+    tcp: bool,
+    /// Run on a simulated network, verifying the payments against a mock EVM
+    #[cfg(feature = "simulation")]
+    simulated: Option<(SimulatedEndpoint, MockEvm)>,
     #[cfg(feature = "upnp")]
     upnp: bool,
 }
@@ -100,17 +132,82 @@ impl NodeBuilder {
             evm_address,
             evm_network,
             addr,
+            additional_listen_addrs: vec![],
             initial_peers,
             local,
             root_dir,
             #[cfg(feature = "open-metrics")]
             metrics_server_port: None,
             is_behind_home_network: false,
+            record_store_backend: RecordStorageBackendKind::default(),
+            migrate_record_store: false,
+            max_storage_bytes: DEFAULT_MAX_STORAGE_BYTES,
+            records_cache_bytes: DEFAULT_RECORDS_CACHE_BYTES,
+            bandwidth_limits: BandwidthLimits::default(),
+            relay_server_limits: RelayServerLimits::default(),
+            bootstrap_cache_path: None,
+            tcp: false,
+            #[cfg(feature = "simulation")]
+            simulated: None,
             #[cfg(feature = "upnp")]
             upnp,
         }
     }
 
+    /// Set the storage engine used to persist the records
+    pub fn record_store_backend(&mut self, backend: RecordStorageBackendKind) {
+        self.record_store_backend = backend;
+    }
+
+    /// Move the records stored by the other storage engines into the selected one at startup
+    pub fn migrate_record_store(&mut self, migrate: bool) {
+        self.migrate_record_store = migrate;
+    }
+
+    /// Set the storage capacity of the node, in bytes
+    pub fn max_storage_bytes(&mut self, max_storage_bytes: u64) {
+        self.max_storage_bytes = max_storage_bytes;
+    }
+
+    /// Set the bytes of popular records kept in memory, sparing them from being re-read from disk
+    pub fn records_cache_bytes(&mut self, records_cache_bytes: usize) {
+        self.records_cache_bytes = records_cache_bytes;
+    }
+
+    /// Set the upload and download rate limits, in bytes per second
+    pub fn bandwidth_limits(&mut self, bandwidth_limits: BandwidthLimits) {
+        self.bandwidth_limits = bandwidth_limits;
+    }
+
+    /// Set the resources lent to the peers behind a home network, by relaying their connections
+    pub fn relay_server_limits(&mut self, relay_server_limits: RelayServerLimits) {
+        self.relay_server_limits = relay_server_limits;
+    }
+
+    /// Listen on another address alongside the one provided at construction, e.g. on `::` alongside
+    /// `0.0.0.0` to listen on both IPv4 and IPv6
+    pub fn additional_listen_addr(&mut self, addr: SocketAddr) {
+        self.additional_listen_addrs.push(addr);
+    }
+
+    /// Enable the TCP transport, listening on it alongside QUIC
+    pub fn tcp(&mut self, enable: bool) {
+        self.tcp = enable;
+    }
+
+    /// Run the node on a simulated network rather than on the real transports, verifying the
+    /// payments against the mock EVM rather than the chain.
+    #[cfg(feature = "simulation")]
+    pub(crate) fn simulated(&mut self, endpoint: SimulatedEndpoint, mock_evm: MockEvm) {
+        self.simulated = Some((endpoint, mock_evm));
+    }
+
+    /// Set the bootstrap cache file the peers reached are recorded to, for the next start to
+    /// bootstrap from
+    pub fn bootstrap_cache_path(&mut self, path: PathBuf) {
+        self.bootstrap_cache_path = Some(path);
+    }
+
     #[cfg(feature = "open-metrics")]
     /// Set the port for the OpenMetrics server. Defaults to a random port if not set
     pub fn metrics_server_port(&mut self, port: Option<u16>) {
@@ -146,17 +243,36 @@ impl NodeBuilder {
         };
 
         network_builder.listen_addr(self.addr);
+        for addr in &self.additional_listen_addrs {
+            network_builder.listen_addr(*addr);
+        }
         #[cfg(feature = "open-metrics")]
         network_builder.metrics_server_port(self.metrics_server_port);
         network_builder.initial_peers(self.initial_peers.clone());
         network_builder.is_behind_home_network(self.is_behind_home_network);
+        network_builder.tcp(self.tcp);
+        network_builder.record_store_backend(self.record_store_backend);
+        network_builder.migrate_record_store(self.migrate_record_store);
+        network_builder.max_storage_bytes(self.max_storage_bytes);
+        network_builder.records_cache_bytes(self.records_cache_bytes);
+        network_builder.bandwidth_limits(self.bandwidth_limits);
+        network_builder.relay_server_limits(self.relay_server_limits);
+        if let Some(path) = self.bootstrap_cache_path {
+            network_builder.bootstrap_cache(BootstrapCache::new(path));
+        }
 
         #[cfg(feature = "upnp")]
         network_builder.upnp(self.upnp);
+        #[cfg(feature = "simulation")]
+        let mock_evm = self.simulated.map(|(endpoint, mock_evm)| {
+            network_builder.simulated_endpoint(endpoint);
+            mock_evm
+        });
 
         let (network, network_event_receiver, swarm_driver) =
             network_builder.build_node(self.root_dir.clone())?;
         let node_events_channel = NodeEventsChannel::default();
+        let rewards_ledger = Arc::new(RewardsLedger::open(&self.root_dir)?);
 
         let node = NodeInner {
             network: network.clone(),
@@ -166,6 +282,11 @@ impl NodeBuilder {
             #[cfg(feature = "open-metrics")]
             metrics_recorder,
             evm_network: self.evm_network,
+            rewards_ledger: Arc::clone(&rewards_ledger),
+            storage_auditor: StorageAuditor::default(),
+            payment_verifier: PaymentVerifier::default(),
+            #[cfg(feature = "simulation")]
+            mock_evm,
         };
         let node = Node {
             inner: Arc::new(node),
@@ -174,6 +295,8 @@ impl NodeBuilder {
             network,
             node_events_channel,
             root_dir_path: self.root_dir,
+            rewards_ledger,
+            drainer: Default::default(),
         };
 
         // Run the node
@@ -202,6 +325,11 @@ struct NodeInner {
     metrics_recorder: Option<NodeMetricsRecorder>,
     reward_address: RewardsAddress,
     evm_network: EvmNetwork,
+    rewards_ledger: Arc<RewardsLedger>,
+    storage_auditor: StorageAuditor,
+    payment_verifier: PaymentVerifier,
+    #[cfg(feature = "simulation")]
+    mock_evm: Option<MockEvm>,
 }
 
 impl Node {
@@ -236,6 +364,27 @@ impl Node {
         &self.inner.evm_network
     }
 
+    /// Returns the ledger of the payments accepted by the node
+    pub(crate) fn rewards_ledger(&self) -> &Arc<RewardsLedger> {
+        &self.inner.rewards_ledger
+    }
+
+    /// Returns the tracker of the storage audits of the close group peers
+    pub(crate) fn storage_auditor(&self) -> &StorageAuditor {
+        &self.inner.storage_auditor
+    }
+
+    /// Returns the verifier of the payments for the records stored
+    pub(crate) fn payment_verifier(&self) -> &PaymentVerifier {
+        &self.inner.payment_verifier
+    }
+
+    /// Returns the mock EVM the payments are verified against, when running on a simulated network
+    #[cfg(feature = "simulation")]
+    pub(crate) fn mock_evm(&self) -> Option<&MockEvm> {
+        self.inner.mock_evm.as_ref()
+    }
+
     /// Runs the provided `SwarmDriver` and spawns a task to process for `NetworkEvents`
     fn run(self, swarm_driver: SwarmDriver, mut network_event_receiver: Receiver<NetworkEvent>) {
         let mut rng = StdRng::from_entropy();
@@ -268,6 +417,16 @@ impl Node {
 
             let mut rolling_index = 0;
 
+            // use a random timeout to ensure not sync when transmit messages.
+            let storage_audit_interval: u64 = rng.gen_range(
+                PERIODIC_STORAGE_AUDIT_INTERVAL_MAX_S / 2..PERIODIC_STORAGE_AUDIT_INTERVAL_MAX_S,
+            );
+            let storage_audit_time = Duration::from_secs(storage_audit_interval);
+            debug!("StorageAudit interval set to {storage_audit_time:?}");
+
+            let mut storage_audit_interval = tokio::time::interval(storage_audit_time);
+            let _ = storage_audit_interval.tick().await; // first tick completes immediately
+
             let mut uptime_metrics_update_interval =
                 tokio::time::interval(UPTIME_METRICS_UPDATE_INTERVAL);
             let _ = uptime_metrics_update_interval.tick().await; // first tick completes immediately
@@ -327,6 +486,17 @@ impl Node {
                             rolling_index += 1;
                         }
                     }
+                    // runs every storage_audit_time time
+                    _ = storage_audit_interval.tick() => {
+                        let start = Instant::now();
+                        debug!("Periodic storage audit triggered");
+                        let node = self.clone();
+
+                        let _handle = spawn(async move {
+                            node.try_storage_audit().await;
+                            trace!("Periodic storage audit took {:?}", start.elapsed());
+                        });
+                    }
                     _ = uptime_metrics_update_interval.tick() => {
                         #[cfg(feature = "open-metrics")]
                         if let Some(metrics_recorder) = self.metrics_recorder() {
@@ -429,13 +599,17 @@ impl Node {
                     error!("Error while trying to fetch replicated data {err:?}");
                 }
             }
-            NetworkEvent::QueryRequestReceived { query, channel } => {
+            NetworkEvent::QueryRequestReceived {
+                query,
+                peer,
+                channel,
+            } => {
                 event_header = "QueryRequestReceived";
                 let network = self.network().clone();
                 let payment_address = *self.reward_address();
 
                 let _handle = spawn(async move {
-                    let res = Self::handle_query(&network, query, payment_address).await;
+                    let res = Self::handle_query(&network, query, peer, payment_address).await;
                     debug!("Sending response {res:?}");
 
                     network.send_response(res, channel);
@@ -596,6 +770,7 @@ impl Node {
     async fn handle_query(
         network: &Network,
         query: Query,
+        peer: PeerId,
         payment_address: RewardsAddress,
     ) -> Response {
         let resp: QueryResponse = match query {
@@ -604,7 +779,7 @@ impl Node {
                 let record_key = address.to_record_key();
                 let self_id = network.peer_id();
 
-                let store_cost = network.get_local_storecost(record_key.clone()).await;
+                let store_cost = network.get_local_storecost(record_key.clone(), peer).await;
 
                 match store_cost {
                     Ok((cost, quoting_metrics, bad_nodes)) => {
@@ -768,7 +943,7 @@ impl Node {
     }
 }
 
-async fn chunk_proof_verify_peer(
+pub(crate) async fn chunk_proof_verify_peer(
     network: &Network,
     peer_id: PeerId,
     keys: &[NetworkAddress],
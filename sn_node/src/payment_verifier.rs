@@ -0,0 +1,205 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use sn_evm::{DataPayments, TxHash};
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::sync::{Mutex as AsyncMutex, OnceCell, Semaphore};
+
+/// How long the payments of a transaction are kept once fetched.
+/// A confirmed transaction doesn't change, this only bounds how long it is held in memory.
+const CACHED_TRANSACTION_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// The max number of transactions whose payments are kept in memory.
+const MAX_CACHED_TRANSACTIONS: usize = 10_000;
+
+/// The max number of on-chain lookups undertaken at the same time.
+const MAX_CONCURRENT_LOOKUPS: usize = 4;
+
+/// The min interval between two on-chain lookups, to not get throttled by the RPC provider.
+const MIN_LOOKUP_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The payments of a transaction, fetched at most once however many verifications wait on them.
+struct CachedTransaction {
+    cached_at: Instant,
+    payments: Arc<OnceCell<Arc<DataPayments>>>,
+}
+
+/// Verifies the payments for the records stored, fetching the payments of each transaction
+/// only once.
+///
+/// All the quotes paid by the same transaction, e.g. all the chunks of an uploaded file,
+/// are then verified out of a single `eth_getTransactionReceipt` lookup. The concurrent
+/// verifications of the payments of a transaction wait on the same lookup, and the lookups
+/// are rate limited.
+pub(crate) struct PaymentVerifier {
+    transactions: Mutex<HashMap<TxHash, CachedTransaction>>,
+    lookup_permits: Semaphore,
+    next_lookup_at: AsyncMutex<Instant>,
+}
+
+impl Default for PaymentVerifier {
+    fn default() -> Self {
+        Self {
+            transactions: Mutex::new(HashMap::new()),
+            lookup_permits: Semaphore::new(MAX_CONCURRENT_LOOKUPS),
+            next_lookup_at: AsyncMutex::new(Instant::now()),
+        }
+    }
+}
+
+impl PaymentVerifier {
+    /// The payments made by `tx_hash`, fetched through `lookup` unless already known,
+    /// along with whether they were already known (or being fetched) without calling `lookup`.
+    ///
+    /// A failed lookup isn't cached, as the transaction may just not be confirmed yet.
+    pub(crate) async fn data_payments<F, Fut, E>(
+        &self,
+        tx_hash: TxHash,
+        lookup: F,
+    ) -> Result<(Arc<DataPayments>, bool), E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<DataPayments, E>>,
+    {
+        let cell = self.cached_transaction(tx_hash);
+
+        let mut looked_up = false;
+        let payments = cell
+            .get_or_try_init(|| {
+                looked_up = true;
+                self.rate_limited(lookup)
+            })
+            .await?;
+
+        Ok((Arc::clone(payments), !looked_up))
+    }
+
+    /// The cell holding the payments of `tx_hash`, inserting an empty one when unknown or expired.
+    fn cached_transaction(&self, tx_hash: TxHash) -> Arc<OnceCell<Arc<DataPayments>>> {
+        let mut transactions = self
+            .transactions
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        if let Some(cached) = transactions.get(&tx_hash) {
+            if cached.cached_at.elapsed() < CACHED_TRANSACTION_TTL {
+                return Arc::clone(&cached.payments);
+            }
+        }
+
+        if transactions.len() >= MAX_CACHED_TRANSACTIONS {
+            transactions.retain(|_, cached| cached.cached_at.elapsed() < CACHED_TRANSACTION_TTL);
+        }
+        if transactions.len() >= MAX_CACHED_TRANSACTIONS {
+            let oldest = transactions
+                .iter()
+                .min_by_key(|(_, cached)| cached.cached_at)
+                .map(|(tx_hash, _)| *tx_hash);
+            if let Some(oldest) = oldest {
+                let _ = transactions.remove(&oldest);
+            }
+        }
+
+        let payments = Arc::new(OnceCell::new());
+        let _ = transactions.insert(
+            tx_hash,
+            CachedTransaction {
+                cached_at: Instant::now(),
+                payments: Arc::clone(&payments),
+            },
+        );
+        payments
+    }
+
+    /// Run `lookup` once a lookup slot is available and the min interval since the last one elapsed.
+    async fn rate_limited<F, Fut, E>(&self, lookup: F) -> Result<Arc<DataPayments>, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<DataPayments, E>>,
+    {
+        // The semaphore is never closed
+        let _permit = self.lookup_permits.acquire().await;
+
+        {
+            let mut next_lookup_at = self.next_lookup_at.lock().await;
+            let now = Instant::now();
+            if *next_lookup_at > now {
+                tokio::time::sleep(*next_lookup_at - now).await;
+            }
+            *next_lookup_at = Instant::now() + MIN_LOOKUP_INTERVAL;
+        }
+
+        lookup().await.map(Arc::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn payments(block_timestamp: u64) -> DataPayments {
+        DataPayments {
+            block_timestamp,
+            payments: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn concurrent_verifications_share_a_single_lookup() -> eyre::Result<()> {
+        let verifier = PaymentVerifier::default();
+        let lookups = AtomicUsize::new(0);
+        let tx_hash = TxHash::repeat_byte(1);
+
+        let lookup = || async {
+            let _ = lookups.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok::<_, eyre::Report>(payments(1))
+        };
+        let results =
+            futures::future::try_join_all((0..10).map(|_| verifier.data_payments(tx_hash, lookup)))
+                .await?;
+
+        assert_eq!(lookups.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            results.iter().filter(|(_, cache_hit)| !cache_hit).count(),
+            1
+        );
+        assert!(results.iter().all(|(fetched, _)| **fetched == payments(1)));
+
+        let (_, cache_hit) = verifier.data_payments(tx_hash, lookup).await?;
+        assert!(cache_hit);
+        assert_eq!(lookups.load(Ordering::SeqCst), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn failed_lookups_are_retried() -> eyre::Result<()> {
+        let verifier = PaymentVerifier::default();
+        let tx_hash = TxHash::repeat_byte(2);
+
+        let result = verifier
+            .data_payments(tx_hash, || async {
+                Err::<DataPayments, _>(eyre::eyre!("not confirmed yet"))
+            })
+            .await;
+        assert!(result.is_err());
+
+        let (fetched, cache_hit) = verifier
+            .data_payments(tx_hash, || async { Ok::<_, eyre::Report>(payments(2)) })
+            .await?;
+        assert!(!cache_hit);
+        assert_eq!(*fetched, payments(2));
+        Ok(())
+    }
+}
@@ -6,7 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::{node::Node, Error, Marker, Result};
+use crate::{node::Node, rewards_ledger::RewardsLedgerEntry, Error, Marker, Result};
 use libp2p::kad::{Record, RecordKey};
 use sn_evm::ProofOfPayment;
 use sn_networking::{get_raw_signed_spends_from_record, GetRecordError, NetworkError};
@@ -20,8 +20,9 @@ use sn_protocol::{
 use sn_registers::SignedRegister;
 use sn_transfers::{SignedSpend, TransferError, UniquePubkey, QUOTE_EXPIRATION_SECS};
 use std::collections::BTreeSet;
-use std::time::{Duration, UNIX_EPOCH};
-use tokio::task::JoinSet;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::task::{spawn_blocking, JoinSet};
 use xor_name::XorName;
 
 impl Node {
@@ -623,21 +624,59 @@ impl Node {
 
         // check if payment is valid on chain
         debug!("Verifying payment for record {pretty_key}");
-        self.evm_network()
-            .verify_data_payment(
-                payment.tx_hash,
+        let (data_payments, cache_hit) = self
+            .payment_verifier()
+            .data_payments(payment.tx_hash, || async {
+                #[cfg(feature = "simulation")]
+                if let Some(mock_evm) = self.mock_evm() {
+                    return mock_evm.data_payments(payment.tx_hash);
+                }
+                self.evm_network().get_data_payments(payment.tx_hash).await
+            })
+            .await
+            .map_err(|e| Error::EvmNetwork(format!("Failed to verify chunk payment: {e}")))?;
+        if cache_hit {
+            self.record_metrics(Marker::PaymentVerificationCacheHit);
+        } else {
+            self.record_metrics(Marker::PaymentVerificationCacheMiss);
+        }
+        data_payments
+            .verify(
                 payment.quote.hash(),
                 *self.reward_address(),
                 storecost.as_atto(),
                 quote_expiration_time_in_secs,
             )
-            .await
             .map_err(|e| Error::EvmNetwork(format!("Failed to verify chunk payment: {e}")))?;
         debug!("Payment is valid for record {pretty_key}");
 
         // Notify `record_store` that the node received a payment.
         self.network().notify_payment_received();
 
+        // Keep a record of the payment, which must not fail the PUT it has been made for.
+        // The ledger skips the payments it already holds, e.g. verified again for a re-PUT record.
+        let ledger_entry = RewardsLedgerEntry {
+            quote_hash: payment.quote.hash(),
+            tx_hash: payment.tx_hash,
+            record_key: hex::encode(key.as_ref()),
+            amount: storecost,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default(),
+        };
+        // The append syncs the ledger file, so it is kept off the async runtime workers.
+        let rewards_ledger = Arc::clone(self.rewards_ledger());
+        match spawn_blocking(move || rewards_ledger.append(&ledger_entry)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => {
+                error!("Failed to record the payment for {pretty_key} in the rewards ledger: {err}")
+            }
+            Err(err) => {
+                error!("Failed to record the payment for {pretty_key} in the rewards ledger: {err}")
+            }
+        }
+
         #[cfg(feature = "open-metrics")]
         if let Some(metrics_recorder) = self.metrics_recorder() {
             let _ = metrics_recorder
@@ -9,7 +9,7 @@
 use crate::{node::Node, Error, Result};
 use libp2p::PeerId;
 use sn_evm::{AttoTokens, PaymentQuote, QuotingMetrics, RewardsAddress};
-use sn_networking::{calculate_cost_for_records, Network, NodeIssue};
+use sn_networking::{Network, NodeIssue};
 use sn_protocol::{error::Error as ProtocolError, storage::ChunkAddress, NetworkAddress};
 use std::time::Duration;
 
@@ -125,7 +125,7 @@ pub(crate) async fn quotes_verification(network: &Network, quotes: Vec<(PeerId,
                 .collect();
 
             quotes_for_nodes_duty.retain(|(peer_id, quote)| {
-                let cost = calculate_cost_for_records(quote.quoting_metrics.close_records_stored);
+                let cost = network.pricing_strategy().store_cost(&quote.quoting_metrics);
                 let is_same_as_expected = quote.cost == AttoTokens::from_u64(cost);
 
                 if !is_same_as_expected {
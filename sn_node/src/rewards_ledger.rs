@@ -0,0 +1,433 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use sn_evm::{AttoTokens, QuoteHash, TxHash};
+use std::{
+    collections::HashSet,
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// The file, within the node's root dir, holding the rewards ledger.
+const REWARDS_LEDGER_FILENAME: &str = "rewards_ledger.jsonl";
+
+/// The number of bytes read at once when reading the ledger back from its most recent entries.
+const HISTORY_READ_CHUNK_SIZE: u64 = 64 * 1024;
+
+/// A payment accepted by the node for storing a record.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RewardsLedgerEntry {
+    /// The hash of the quote the payment was made for
+    pub quote_hash: QuoteHash,
+    /// The hash of the transaction carrying the payment
+    pub tx_hash: TxHash,
+    /// The hex encoded key of the record paid for
+    pub record_key: String,
+    /// The amount paid to the node
+    pub amount: AttoTokens,
+    /// When the payment was accepted, in seconds since the unix epoch
+    pub timestamp: u64,
+}
+
+impl RewardsLedgerEntry {
+    /// What identifies a payment, which may be verified again for the same record
+    fn payment_id(&self) -> PaymentId {
+        (self.quote_hash, self.tx_hash, self.record_key.clone())
+    }
+}
+
+type PaymentId = (QuoteHash, TxHash, String);
+
+/// The totals of the payments in the rewards ledger.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RewardsSummary {
+    /// The sum of all the payments accepted
+    pub total_earned: AttoTokens,
+    /// The number of payments accepted
+    pub payments_count: u64,
+}
+
+impl Default for RewardsSummary {
+    fn default() -> Self {
+        Self {
+            total_earned: AttoTokens::zero(),
+            payments_count: 0,
+        }
+    }
+}
+
+/// An append-only ledger of the payments accepted by the node, persisted within its root dir,
+/// one JSON entry per line.
+///
+/// A payment is recorded once, however many times it is verified, e.g. when a record is PUT again.
+///
+/// The totals are kept in memory, the history is read back from disk when queried,
+/// from the most recent entry backwards.
+#[derive(Debug)]
+pub(crate) struct RewardsLedger {
+    path: PathBuf,
+    state: Mutex<LedgerState>,
+}
+
+#[derive(Debug)]
+struct LedgerState {
+    summary: RewardsSummary,
+    /// The length of the ledger file up to the last entry fully written.
+    len: u64,
+    /// The payments recorded
+    recorded: HashSet<PaymentId>,
+}
+
+impl RewardsLedger {
+    /// Open the ledger within `root_dir`, computing the totals of the payments already recorded.
+    pub(crate) fn open(root_dir: &Path) -> Result<Self> {
+        let path = root_dir.join(REWARDS_LEDGER_FILENAME);
+        Self::terminate_last_line(&path)?;
+
+        let mut summary = RewardsSummary::default();
+        let mut recorded = HashSet::new();
+        for entry in Self::read_entries(&path)? {
+            if !recorded.insert(entry.payment_id()) {
+                continue;
+            }
+            summary.total_earned = summary
+                .total_earned
+                .checked_add(entry.amount)
+                .ok_or(Error::NumericOverflow)?;
+            summary.payments_count += 1;
+        }
+        info!(
+            "Opened the rewards ledger at {path:?} with {} payments totalling {:?}",
+            summary.payments_count, summary.total_earned
+        );
+
+        let len = match std::fs::metadata(&path) {
+            Ok(metadata) => metadata.len(),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => 0,
+            Err(err) => {
+                return Err(Error::RewardsLedger(format!(
+                    "Failed to read the metadata of {path:?}: {err}"
+                )))
+            }
+        };
+
+        Ok(Self {
+            path,
+            state: Mutex::new(LedgerState {
+                summary,
+                len,
+                recorded,
+            }),
+        })
+    }
+
+    /// Append a payment to the ledger, updating the totals once it is written to disk.
+    /// A payment already recorded is skipped.
+    pub(crate) fn append(&self, entry: &RewardsLedgerEntry) -> Result<()> {
+        let mut line = serde_json::to_string(entry)
+            .map_err(|err| Error::RewardsLedger(format!("Failed to serialise entry: {err}")))?;
+        line.push('\n');
+
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| Error::RewardsLedger("Ledger lock is poisoned".to_string()))?;
+        let payment_id = entry.payment_id();
+        if state.recorded.contains(&payment_id) {
+            debug!(
+                "Payment {:?} for record {} is already in the rewards ledger",
+                entry.quote_hash, entry.record_key
+            );
+            return Ok(());
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|err| {
+                Error::RewardsLedger(format!("Failed to open {:?}: {err}", self.path))
+            })?;
+        if let Err(err) = file
+            .write_all(line.as_bytes())
+            .and_then(|_| file.sync_data())
+        {
+            // don't leave a partial entry in front of the next one
+            if let Err(truncate_err) = file.set_len(state.len) {
+                error!(
+                    "Failed to truncate the rewards ledger back to its last entry: {truncate_err}"
+                );
+            }
+            return Err(Error::RewardsLedger(format!(
+                "Failed to write to {:?}: {err}",
+                self.path
+            )));
+        }
+
+        state.summary.total_earned = state
+            .summary
+            .total_earned
+            .checked_add(entry.amount)
+            .ok_or(Error::NumericOverflow)?;
+        state.summary.payments_count += 1;
+        state.len += line.len() as u64;
+        let _ = state.recorded.insert(payment_id);
+        Ok(())
+    }
+
+    /// The totals of the payments accepted.
+    pub(crate) fn summary(&self) -> RewardsSummary {
+        self.state
+            .lock()
+            .map(|state| state.summary)
+            .unwrap_or_default()
+    }
+
+    /// A page of the payments accepted, the most recent first.
+    ///
+    /// Only the entries written when the read starts are considered, so the appends
+    /// carry on while the page is read.
+    pub(crate) fn history(&self, offset: usize, limit: usize) -> Result<Vec<RewardsLedgerEntry>> {
+        if limit == 0 {
+            return Ok(vec![]);
+        }
+        let len = self
+            .state
+            .lock()
+            .map(|state| state.len)
+            .map_err(|_| Error::RewardsLedger("Ledger lock is poisoned".to_string()))?;
+        Self::read_entries_backwards(&self.path, len, offset, limit)
+    }
+
+    /// Make sure an entry truncated by a crash midway a write doesn't swallow the next one.
+    fn terminate_last_line(path: &Path) -> Result<()> {
+        let mut file = match OpenOptions::new().read(true).append(true).open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => {
+                return Err(Error::RewardsLedger(format!(
+                    "Failed to open {path:?}: {err}"
+                )))
+            }
+        };
+
+        let mut last_byte = [0; 1];
+        let is_terminated = match file.seek(SeekFrom::End(-1)) {
+            Ok(_) => file.read_exact(&mut last_byte).is_ok() && last_byte[0] == b'\n',
+            // an empty file
+            Err(_) => true,
+        };
+        if !is_terminated {
+            warn!("The last entry of the rewards ledger at {path:?} is truncated");
+            file.write_all(b"\n").map_err(|err| {
+                Error::RewardsLedger(format!("Failed to write to {path:?}: {err}"))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Read up to `limit` entries, skipping the `offset` most recent ones,
+    /// walking the first `len` bytes of the ledger back from its end.
+    fn read_entries_backwards(
+        path: &Path,
+        len: u64,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<RewardsLedgerEntry>> {
+        let mut file = match File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => {
+                return Err(Error::RewardsLedger(format!(
+                    "Failed to open {path:?}: {err}"
+                )))
+            }
+        };
+
+        let mut entries = vec![];
+        let mut skipped = 0;
+        let mut pos = len;
+        // the start of a line, carrying on in the chunk read before
+        let mut partial_line = vec![];
+        while pos > 0 && entries.len() < limit {
+            let start = pos.saturating_sub(HISTORY_READ_CHUNK_SIZE);
+            let mut chunk = vec![0; (pos - start) as usize];
+            file.seek(SeekFrom::Start(start))
+                .and_then(|_| file.read_exact(&mut chunk))
+                .map_err(|err| Error::RewardsLedger(format!("Failed to read {path:?}: {err}")))?;
+            chunk.extend_from_slice(&partial_line);
+            pos = start;
+
+            let mut lines = chunk.split(|byte| *byte == b'\n');
+            // unless at the start of the file, the first line is cut by the chunk boundary
+            let first_line = if pos > 0 { lines.next() } else { None };
+            for line in lines.rev() {
+                if line.iter().all(u8::is_ascii_whitespace) {
+                    continue;
+                }
+                // A crash midway a write can leave a truncated line, which is skipped
+                let entry = match serde_json::from_slice(line) {
+                    Ok(entry) => entry,
+                    Err(err) => {
+                        warn!("Skipping an invalid line of the rewards ledger: {err}");
+                        continue;
+                    }
+                };
+                if skipped < offset {
+                    skipped += 1;
+                } else if entries.len() < limit {
+                    entries.push(entry);
+                } else {
+                    break;
+                }
+            }
+            partial_line = first_line.map(<[u8]>::to_vec).unwrap_or_default();
+        }
+        Ok(entries)
+    }
+
+    fn read_entries(path: &Path) -> Result<Vec<RewardsLedgerEntry>> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => {
+                return Err(Error::RewardsLedger(format!(
+                    "Failed to open {path:?}: {err}"
+                )))
+            }
+        };
+
+        let mut entries = vec![];
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line
+                .map_err(|err| Error::RewardsLedger(format!("Failed to read {path:?}: {err}")))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            // A crash midway a write can leave a truncated last line, which is skipped
+            match serde_json::from_str(&line) {
+                Ok(entry) => entries.push(entry),
+                Err(err) => warn!("Skipping invalid line {index} of the rewards ledger: {err}"),
+            }
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::TempDir;
+
+    fn entry(amount: u64, timestamp: u64) -> RewardsLedgerEntry {
+        RewardsLedgerEntry {
+            quote_hash: QuoteHash::repeat_byte(1),
+            tx_hash: TxHash::repeat_byte(2),
+            record_key: hex::encode(timestamp.to_be_bytes()),
+            amount: AttoTokens::from_u64(amount),
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn payments_are_persisted_across_reopens() -> eyre::Result<()> {
+        let root_dir = TempDir::new()?;
+
+        let ledger = RewardsLedger::open(root_dir.path())?;
+        assert_eq!(ledger.summary(), RewardsSummary::default());
+        for i in 1..=5 {
+            ledger.append(&entry(i * 10, i))?;
+        }
+
+        let ledger = RewardsLedger::open(root_dir.path())?;
+        assert_eq!(
+            ledger.summary(),
+            RewardsSummary {
+                total_earned: AttoTokens::from_u64(150),
+                payments_count: 5,
+            }
+        );
+
+        let page = ledger.history(1, 2)?;
+        assert_eq!(page, vec![entry(40, 4), entry(30, 3)]);
+        assert!(ledger.history(5, 10)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn history_pages_span_the_read_chunks() -> eyre::Result<()> {
+        let root_dir = TempDir::new()?;
+        let ledger = RewardsLedger::open(root_dir.path())?;
+        // enough entries for the file to be read over several chunks
+        let count = 2000;
+        for i in 0..count {
+            ledger.append(&entry(i, i))?;
+        }
+        assert!(
+            std::fs::metadata(root_dir.path().join(REWARDS_LEDGER_FILENAME))?.len()
+                > 2 * HISTORY_READ_CHUNK_SIZE
+        );
+
+        let history = ledger.history(0, count as usize)?;
+        let expected: Vec<_> = (0..count).rev().map(|i| entry(i, i)).collect();
+        assert_eq!(history, expected);
+
+        let page = ledger.history(1000, 3)?;
+        assert_eq!(
+            page,
+            vec![entry(999, 999), entry(998, 998), entry(997, 997)]
+        );
+        assert!(ledger.history(0, 0)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn payments_verified_again_are_recorded_once() -> eyre::Result<()> {
+        let root_dir = TempDir::new()?;
+        let ledger = RewardsLedger::open(root_dir.path())?;
+        ledger.append(&entry(10, 1))?;
+        ledger.append(&entry(10, 1))?;
+        // the same payment, accepted again later on
+        ledger.append(&RewardsLedgerEntry {
+            timestamp: 2,
+            ..entry(10, 1)
+        })?;
+        assert_eq!(ledger.summary().payments_count, 1);
+
+        let ledger = RewardsLedger::open(root_dir.path())?;
+        ledger.append(&entry(10, 1))?;
+        assert_eq!(ledger.summary().payments_count, 1);
+        assert_eq!(ledger.history(0, 10)?, vec![entry(10, 1)]);
+        Ok(())
+    }
+
+    #[test]
+    fn truncated_lines_are_skipped() -> eyre::Result<()> {
+        let root_dir = TempDir::new()?;
+        let ledger = RewardsLedger::open(root_dir.path())?;
+        ledger.append(&entry(10, 1))?;
+
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(root_dir.path().join(REWARDS_LEDGER_FILENAME))?;
+        file.write_all(b"{\"quote_hash\":\"0x01")?;
+
+        let ledger = RewardsLedger::open(root_dir.path())?;
+        assert_eq!(ledger.summary().payments_count, 1);
+
+        ledger.append(&entry(20, 2))?;
+        let ledger = RewardsLedger::open(root_dir.path())?;
+        assert_eq!(ledger.summary().payments_count, 2);
+        assert_eq!(ledger.history(0, 10)?, vec![entry(20, 2), entry(10, 1)]);
+        Ok(())
+    }
+}
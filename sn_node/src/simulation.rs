@@ -0,0 +1,376 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! A harness running many nodes in a single process, over a [`SimulatedNetwork`], for the tests of
+//! replication, pricing or bad-node detection to run in seconds rather than over a local network.
+//!
+//! The payments are made on a [`MockEvm`] which the nodes verify them against, in place of the
+//! on-chain transactions.
+
+use crate::{
+    error::{Error, Result},
+    NodeBuilder, RunningNode,
+};
+use assert_fs::TempDir;
+use libp2p::{
+    kad::{Quorum, Record},
+    multiaddr::Protocol,
+    Multiaddr, PeerId,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use sn_evm::{
+    ChunkPaymentEvent, DataPayments, EvmNetwork, EvmTransactionError, ProofOfPayment, QuotePayment,
+    RewardsAddress, TxHash,
+};
+use sn_networking::{
+    Network, NetworkBuilder, NetworkEvent, PutRecordCfg, SimulatedEndpoint, SimulatedNetwork,
+};
+use sn_protocol::{
+    storage::{try_serialize_record, Chunk, RecordKind},
+    NetworkAddress, CLOSE_GROUP_SIZE,
+};
+use std::{
+    collections::HashMap,
+    net::{Ipv4Addr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::watch;
+
+/// The max number of peers a new node is bootstrapped from.
+const MAX_BOOTSTRAP_PEERS: usize = 3;
+
+/// How long a client waits to know enough peers to upload to.
+const CLIENT_CONNECTION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The interval between two checks of the state of the nodes.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The payments made on a mock EVM, which the nodes of a simulation verify instead of the
+/// on-chain transactions.
+#[derive(Clone, Debug, Default)]
+pub struct MockEvm {
+    transactions: Arc<Mutex<HashMap<TxHash, DataPayments>>>,
+}
+
+impl MockEvm {
+    /// Pays the quotes in a single transaction, returning its hash.
+    pub fn pay_for_quotes(&self, quote_payments: impl IntoIterator<Item = QuotePayment>) -> TxHash {
+        let payments = quote_payments
+            .into_iter()
+            .map(|(quote_hash, rewards_address, amount)| ChunkPaymentEvent {
+                rewards_address,
+                amount,
+                quote_hash,
+            })
+            .collect();
+        let block_timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+
+        let mut transactions = self
+            .transactions
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut tx_hash = [0; 32];
+        tx_hash[24..].copy_from_slice(&(transactions.len() as u64 + 1).to_be_bytes());
+        let tx_hash = TxHash::from(tx_hash);
+        let _ = transactions.insert(
+            tx_hash,
+            DataPayments {
+                block_timestamp,
+                payments,
+            },
+        );
+        tx_hash
+    }
+
+    /// The payments made by `tx_hash`, as the nodes would get them from the chain.
+    pub(crate) fn data_payments(
+        &self,
+        tx_hash: TxHash,
+    ) -> std::result::Result<DataPayments, EvmTransactionError> {
+        self.transactions
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(&tx_hash)
+            .cloned()
+            .ok_or(EvmTransactionError::TransactionNotFound)
+    }
+}
+
+struct SimulatedNode {
+    endpoint: SimulatedEndpoint,
+    node: RunningNode,
+    online: bool,
+}
+
+/// Many nodes running in this process over a [`SimulatedNetwork`], paid through a [`MockEvm`].
+///
+/// The nodes, their keys and the choices made by the simulation, such as which node to churn, are
+/// drawn from the seed of the simulation, so a run can be reproduced from its seed.
+/// The nodes are left running once cut off the network, until the runtime is shut down.
+pub struct Simulation {
+    network: SimulatedNetwork,
+    mock_evm: MockEvm,
+    rng: StdRng,
+    root_dir: TempDir,
+    nodes: Vec<SimulatedNode>,
+}
+
+impl Simulation {
+    /// Starts `node_count` nodes, each bootstrapping from the first ones started.
+    ///
+    /// Must be called from within a tokio runtime, the nodes being spawned on it.
+    pub fn start(node_count: usize, seed: u64) -> Result<Self> {
+        let mut simulation = Self {
+            network: SimulatedNetwork::new(seed),
+            mock_evm: MockEvm::default(),
+            rng: StdRng::seed_from_u64(seed),
+            root_dir: TempDir::new().map_err(|err| Error::Simulation(err.to_string()))?,
+            nodes: vec![],
+        };
+        for _ in 0..node_count {
+            let _ = simulation.add_node()?;
+        }
+        Ok(simulation)
+    }
+
+    /// The simulated network, to change the latency or packet loss of its links.
+    pub fn network(&self) -> &SimulatedNetwork {
+        &self.network
+    }
+
+    /// The mock EVM the nodes verify the payments against.
+    pub fn mock_evm(&self) -> &MockEvm {
+        &self.mock_evm
+    }
+
+    /// The node at `index`, in the order the nodes were started, if it's still online.
+    pub fn node(&self, index: usize) -> Option<&RunningNode> {
+        self.nodes
+            .get(index)
+            .filter(|node| node.online)
+            .map(|node| &node.node)
+    }
+
+    /// The nodes still online, along with their index.
+    pub fn nodes(&self) -> impl Iterator<Item = (usize, &RunningNode)> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.online)
+            .map(|(index, node)| (index, &node.node))
+    }
+
+    /// Starts a new node, returning its index.
+    pub fn add_node(&mut self) -> Result<usize> {
+        let endpoint = self.network.new_endpoint();
+        let index = self.nodes.len();
+        let root_dir = self.root_dir.path().join(format!("node_{index}"));
+        std::fs::create_dir_all(&root_dir).map_err(|err| Error::Simulation(err.to_string()))?;
+        let rewards_address = RewardsAddress::from(self.rng.gen::<[u8; 20]>());
+
+        let mut node_builder = NodeBuilder::new(
+            endpoint.keypair().clone(),
+            rewards_address,
+            EvmNetwork::ArbitrumSepolia,
+            // not listened on, the node listening on its endpoint only
+            SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0),
+            self.bootstrap_peers(),
+            true,
+            root_dir,
+            #[cfg(feature = "upnp")]
+            false,
+        );
+        node_builder.simulated(endpoint.clone(), self.mock_evm.clone());
+        let node = node_builder.build_and_run()?;
+        info!(
+            "Started simulated node {index} with peer id {}",
+            node.peer_id()
+        );
+
+        self.nodes.push(SimulatedNode {
+            endpoint,
+            node,
+            online: true,
+        });
+        Ok(index)
+    }
+
+    /// Cuts the node at `index` off the network, as its process would have stopped.
+    pub fn remove_node(&mut self, index: usize) {
+        if let Some(node) = self.nodes.get_mut(index) {
+            info!("Removing simulated node {index}");
+            self.network.disconnect(node.endpoint.index());
+            node.online = false;
+        }
+    }
+
+    /// Cuts a node drawn from the seed off the network and starts a new one, returning the index
+    /// of the node removed and of the one added.
+    pub fn churn(&mut self) -> Result<(usize, usize)> {
+        let online: Vec<_> = self.nodes().map(|(index, _)| index).collect();
+        if online.is_empty() {
+            return Err(Error::Simulation("No node online to churn".to_string()));
+        }
+        let removed = online[self.rng.gen_range(0..online.len())];
+        self.remove_node(removed);
+        let added = self.add_node()?;
+        Ok((removed, added))
+    }
+
+    /// Splits the nodes at `indexes` from the others, until [`Self::heal`] is called.
+    pub fn partition(&self, indexes: &[usize]) {
+        let endpoints: Vec<_> = indexes
+            .iter()
+            .filter_map(|index| self.nodes.get(*index))
+            .map(|node| node.endpoint.index())
+            .collect();
+        self.network.partition(&endpoints);
+    }
+
+    /// Removes all the partitions.
+    pub fn heal(&self) {
+        self.network.heal();
+    }
+
+    /// Waits for each node online to have at least `min_peers` peers in its routing table, returning
+    /// whether they all did within `timeout`.
+    pub async fn wait_for_routing_tables(
+        &self,
+        min_peers: usize,
+        timeout: Duration,
+    ) -> Result<bool> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let mut all_filled = true;
+            for (_, node) in self.nodes() {
+                let peers: usize = node.get_kbuckets().await?.values().map(Vec::len).sum();
+                if peers < min_peers {
+                    all_filled = false;
+                    break;
+                }
+            }
+            if all_filled {
+                return Ok(true);
+            }
+            if Instant::now() >= deadline {
+                return Ok(false);
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Waits for at least `min_holders` nodes online to hold the chunk, returning the nodes holding
+    /// it once they do, or those holding it after `timeout`.
+    pub async fn wait_for_chunk_holders(
+        &self,
+        chunk: &Chunk,
+        min_holders: usize,
+        timeout: Duration,
+    ) -> Result<Vec<usize>> {
+        let address = NetworkAddress::from_record_key(&chunk.network_address().to_record_key());
+        let deadline = Instant::now() + timeout;
+        loop {
+            let mut holders = vec![];
+            for (index, node) in self.nodes() {
+                if node.get_all_record_addresses().await?.contains(&address) {
+                    holders.push(index);
+                }
+            }
+            if holders.len() >= min_holders || Instant::now() >= deadline {
+                return Ok(holders);
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Starts a client on the simulated network, returning once it knows enough peers to upload to.
+    pub async fn client(&self) -> Result<Network> {
+        let endpoint = self.network.new_endpoint();
+        let bootstrap_peers = self.bootstrap_peers();
+
+        let mut network_builder = NetworkBuilder::new(endpoint.keypair().clone(), true);
+        network_builder.simulated_endpoint(endpoint);
+        network_builder.initial_peers(bootstrap_peers.clone());
+        let (network, mut event_receiver, swarm_driver) = network_builder.build_client()?;
+        let _handle = tokio::spawn(swarm_driver.run());
+
+        let (connected_sender, mut connected_receiver) = watch::channel(false);
+        let _handle = tokio::spawn(async move {
+            while let Some(event) = event_receiver.recv().await {
+                if let NetworkEvent::PeerAdded(_, peers_len) = event {
+                    if peers_len >= CLOSE_GROUP_SIZE {
+                        let _ = connected_sender.send(true);
+                    }
+                }
+            }
+        });
+
+        for peer in bootstrap_peers {
+            if let Err(err) = network.dial(peer.clone()).await {
+                warn!("Simulated client failed to dial {peer}: {err:?}");
+            }
+        }
+        if tokio::time::timeout(
+            CLIENT_CONNECTION_TIMEOUT,
+            connected_receiver.wait_for(|connected| *connected),
+        )
+        .await
+        .is_err()
+        {
+            warn!("Simulated client did not find {CLOSE_GROUP_SIZE} peers in {CLIENT_CONNECTION_TIMEOUT:?}");
+        }
+        Ok(network)
+    }
+
+    /// Pays for the chunk on the mock EVM and uploads it through the `client`, returning the peer
+    /// that has been paid to store it.
+    pub async fn upload_chunk(&self, client: &Network, chunk: &Chunk) -> Result<PeerId> {
+        let address = chunk.network_address();
+        let (payee, rewards_address, quote) = client
+            .get_store_costs_from_network(address.clone(), vec![])
+            .await?;
+        let tx_hash =
+            self.mock_evm
+                .pay_for_quotes([(quote.hash(), rewards_address, quote.cost.as_atto())]);
+
+        let payment = ProofOfPayment { quote, tx_hash };
+        let record = Record {
+            key: address.to_record_key(),
+            value: try_serialize_record(&(payment, chunk.clone()), RecordKind::ChunkWithPayment)?
+                .to_vec(),
+            publisher: None,
+            expires: None,
+        };
+        let put_cfg = PutRecordCfg {
+            put_quorum: Quorum::One,
+            retry_strategy: None,
+            use_put_record_to: Some(vec![payee]),
+            verification: None,
+        };
+        client.put_record(record, &put_cfg).await?;
+        Ok(payee)
+    }
+
+    /// The addresses of the first nodes online, for a new node or client to bootstrap from.
+    fn bootstrap_peers(&self) -> Vec<Multiaddr> {
+        self.nodes
+            .iter()
+            .filter(|node| node.online)
+            .take(MAX_BOOTSTRAP_PEERS)
+            .map(|node| {
+                node.endpoint
+                    .listen_addr()
+                    .with(Protocol::P2p(node.endpoint.peer_id()))
+            })
+            .collect()
+    }
+}
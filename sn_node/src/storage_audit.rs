@@ -0,0 +1,273 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    node::{chunk_proof_verify_peer, Node},
+    Marker,
+};
+use libp2p::PeerId;
+use rand::{seq::IteratorRandom, thread_rng};
+use sn_networking::{sort_peers_by_address_and_limit, NodeIssue};
+use sn_protocol::{storage::RecordType, NetworkAddress, CLOSE_GROUP_SIZE};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+    time::Duration,
+};
+
+/// Interval to audit a close group peer does hold the records it is responsible for.
+/// This is the max time it should take. Minimum interval at any node will be half this
+pub(crate) const PERIODIC_STORAGE_AUDIT_INTERVAL_MAX_S: u64 = 300;
+
+/// The number of locally held chunks sampled to find the records shared with a peer.
+const AUDIT_SAMPLED_KEYS: usize = 50;
+
+/// The number of records a peer is challenged for in each audit round.
+const AUDIT_KEYS_PER_ROUND: usize = 3;
+
+/// The extra rounds a peer failing an audit is put through, each failure being reported
+/// as a `NodeIssue`, so a peer failing all of them gets considered as bad.
+const AUDIT_FOLLOW_UP_ROUNDS: usize = 2;
+
+/// Interval between the follow up rounds, beyond the interval between the same issues
+/// to be accounted for as separate ones.
+const AUDIT_FOLLOW_UP_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The outcomes of the storage audits undertaken against a peer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct AuditStats {
+    pub(crate) passed: u32,
+    pub(crate) failed: u32,
+}
+
+impl AuditStats {
+    /// The share of the audits the peer failed.
+    pub(crate) fn failure_rate(&self) -> f64 {
+        let total = self.passed + self.failed;
+        if total == 0 {
+            0.0
+        } else {
+            self.failed as f64 / total as f64
+        }
+    }
+}
+
+/// Tracks the storage audits pass/fail rates of the peers in the routing table.
+#[derive(Debug, Default)]
+pub(crate) struct StorageAuditor {
+    stats: Mutex<HashMap<PeerId, AuditStats>>,
+}
+
+impl StorageAuditor {
+    /// Record the outcome of an audit round against `peer_id`, returning its updated stats.
+    pub(crate) fn record(&self, peer_id: PeerId, passed: bool) -> AuditStats {
+        let Ok(mut stats) = self.stats.lock() else {
+            return AuditStats::default();
+        };
+        let peer_stats = stats.entry(peer_id).or_default();
+        if passed {
+            peer_stats.passed += 1;
+        } else {
+            peer_stats.failed += 1;
+        }
+        *peer_stats
+    }
+
+    /// Drop the stats of the peers no longer in the routing table.
+    fn retain_peers(&self, peers: &[PeerId]) {
+        if let Ok(mut stats) = self.stats.lock() {
+            stats.retain(|peer_id, _| peers.contains(peer_id));
+        }
+    }
+}
+
+impl Node {
+    /// Audit a random close group peer, challenging it for the proofs of records
+    /// we are both responsible for.
+    pub(crate) async fn try_storage_audit(&self) {
+        let Some((peer_id, keys)) = self.audit_candidate(None).await else {
+            debug!("No close group peer sharing records with us to audit");
+            return;
+        };
+
+        if self.storage_audit_round(peer_id, keys).await {
+            return;
+        }
+
+        // Repeat the audit with other records, to tell a peer not holding the data apart from
+        // one that just hasn't got a copy of the challenged records yet.
+        for _ in 0..AUDIT_FOLLOW_UP_ROUNDS {
+            tokio::time::sleep(AUDIT_FOLLOW_UP_INTERVAL).await;
+            let Some((_, keys)) = self.audit_candidate(Some(peer_id)).await else {
+                debug!("No more records shared with {peer_id:?} to audit");
+                return;
+            };
+            if self.storage_audit_round(peer_id, keys).await {
+                return;
+            }
+        }
+    }
+
+    /// Challenge `peer_id` for the proofs of `keys`, recording and reporting the outcome.
+    /// The round is passed when the peer proves it holds most of the records.
+    async fn storage_audit_round(&self, peer_id: PeerId, keys: Vec<NetworkAddress>) -> bool {
+        let mut proven = 0;
+        for key in keys.iter() {
+            // chunk_proof_verify_peer challenges the peer with a fresh nonce every time
+            if chunk_proof_verify_peer(self.network(), peer_id, std::slice::from_ref(key)).await {
+                proven += 1;
+            }
+        }
+        let passed = proven * 2 > keys.len();
+
+        let stats = self.storage_auditor().record(peer_id, passed);
+        info!(
+            "Storage audit of {peer_id:?} {}, proving {proven}/{} records. Audits passed: {}, failed: {}, failure rate: {:.2}",
+            if passed { "passed" } else { "failed" },
+            keys.len(),
+            stats.passed,
+            stats.failed,
+            stats.failure_rate()
+        );
+
+        if passed {
+            self.record_metrics(Marker::StorageAuditPassed(&peer_id));
+        } else {
+            self.record_metrics(Marker::StorageAuditFailed(&peer_id));
+            self.network()
+                .record_node_issues(peer_id, NodeIssue::FailedStorageAudit);
+        }
+        passed
+    }
+
+    /// Pick a peer to audit, along with the records to challenge it for.
+    /// When `target` is provided, only records shared with that peer are picked.
+    async fn audit_candidate(
+        &self,
+        target: Option<PeerId>,
+    ) -> Option<(PeerId, Vec<NetworkAddress>)> {
+        let network = self.network();
+        let records = match network.get_all_local_record_addresses().await {
+            Ok(records) => records,
+            Err(err) => {
+                warn!("Failed to get the local records to audit: {err:?}");
+                return None;
+            }
+        };
+        let peers = match network.get_all_local_peers_excluding_self().await {
+            Ok(peers) => peers,
+            Err(err) => {
+                warn!("Failed to get the local peers to audit: {err:?}");
+                return None;
+            }
+        };
+        self.storage_auditor().retain_peers(&peers);
+
+        // Only chunks are immutable, hence can be proven the same by all their holders
+        let mut rng = thread_rng();
+        let sampled_keys = records
+            .into_iter()
+            .filter(|(_, record_type)| *record_type == RecordType::Chunk)
+            .map(|(key, _)| key)
+            .choose_multiple(&mut rng, AUDIT_SAMPLED_KEYS);
+
+        let mut candidates = shared_records(network.peer_id(), peers, &sampled_keys);
+        let (peer_id, keys) = match target {
+            Some(peer_id) => candidates.remove_entry(&peer_id)?,
+            None => candidates.into_iter().choose(&mut rng)?,
+        };
+        let keys = keys
+            .into_iter()
+            .choose_multiple(&mut rng, AUDIT_KEYS_PER_ROUND);
+        Some((peer_id, keys))
+    }
+}
+
+/// The peers sharing the responsibility of any of the `keys` with us, along with the shared keys.
+/// A key is shared with the peers that are, along with us, within its close group.
+fn shared_records(
+    self_peer_id: PeerId,
+    mut peers: Vec<PeerId>,
+    keys: &[NetworkAddress],
+) -> BTreeMap<PeerId, Vec<NetworkAddress>> {
+    peers.push(self_peer_id);
+
+    let mut shared: BTreeMap<PeerId, Vec<NetworkAddress>> = BTreeMap::new();
+    for key in keys {
+        let Ok(close_group) = sort_peers_by_address_and_limit(&peers, key, CLOSE_GROUP_SIZE) else {
+            // not enough peers to tell the close group apart
+            return BTreeMap::new();
+        };
+        if !close_group.contains(&&self_peer_id) {
+            continue;
+        }
+        for peer_id in close_group {
+            if *peer_id != self_peer_id {
+                shared.entry(*peer_id).or_default().push(key.clone());
+            }
+        }
+    }
+    shared
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sn_protocol::storage::ChunkAddress;
+    use xor_name::XorName;
+
+    #[test]
+    fn records_are_only_shared_within_close_groups_including_us() {
+        let self_peer_id = PeerId::random();
+        let peers: Vec<_> = (0..50).map(|_| PeerId::random()).collect();
+        let keys: Vec<_> = (0..100u32)
+            .map(|i| {
+                NetworkAddress::from_chunk_address(ChunkAddress::new(XorName::from_content(
+                    &i.to_le_bytes(),
+                )))
+            })
+            .collect();
+
+        let shared = shared_records(self_peer_id, peers.clone(), &keys);
+
+        let mut all_peers = peers;
+        all_peers.push(self_peer_id);
+        for (peer_id, shared_keys) in shared.iter() {
+            assert_ne!(*peer_id, self_peer_id);
+            for key in shared_keys {
+                let close_group =
+                    sort_peers_by_address_and_limit(&all_peers, key, CLOSE_GROUP_SIZE)
+                        .expect("enough peers");
+                assert!(close_group.contains(&peer_id));
+                assert!(close_group.contains(&&self_peer_id));
+            }
+        }
+    }
+
+    #[test]
+    fn audit_stats_track_the_failure_rate() {
+        let auditor = StorageAuditor::default();
+        let peer_id = PeerId::random();
+
+        let _ = auditor.record(peer_id, true);
+        let _ = auditor.record(peer_id, false);
+        let _ = auditor.record(peer_id, true);
+        let stats = auditor.record(peer_id, true);
+        assert_eq!(
+            stats,
+            AuditStats {
+                passed: 3,
+                failed: 1
+            }
+        );
+        assert_eq!(stats.failure_rate(), 0.25);
+
+        auditor.retain_peers(&[]);
+        assert_eq!(auditor.record(peer_id, false).failed, 1);
+    }
+}
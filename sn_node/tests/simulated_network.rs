@@ -0,0 +1,243 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Runs against an in-process simulated network, so doesn't need a local network to be started.
+
+use bytes::Bytes;
+use color_eyre::Result;
+use libp2p::kad::Quorum;
+use sn_logging::LogBuilder;
+use sn_networking::{close_group_majority, GetRecordCfg, Network};
+use sn_node::{DrainState, Simulation};
+use sn_protocol::{storage::Chunk, CLOSE_GROUP_SIZE};
+use std::{
+    collections::HashSet,
+    time::{Duration, Instant},
+};
+use tracing::info;
+
+const NODE_COUNT: usize = 20;
+const SEED: u64 = 42;
+const TIMEOUT: Duration = Duration::from_secs(60);
+/// How long a fetch has to keep failing for a chunk to be considered unreachable
+const UNREACHABLE_WINDOW: Duration = Duration::from_secs(10);
+/// The probability of a write getting lost on the lossy simulated networks
+const PACKET_LOSS: f64 = 0.01;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn chunk_is_replicated_and_survives_churn() -> Result<()> {
+    let _log_appender_guard = LogBuilder::init_multi_threaded_tokio_test(
+        "simulated_chunk_is_replicated_and_survives_churn",
+        false,
+    );
+
+    let mut simulation = Simulation::start(NODE_COUNT, SEED)?;
+    assert!(
+        simulation
+            .wait_for_routing_tables(CLOSE_GROUP_SIZE, TIMEOUT)
+            .await?,
+        "The routing tables of the simulated nodes should fill up"
+    );
+
+    let client = simulation.client().await?;
+    let chunk = Chunk::new(Bytes::from(vec![7; 1024]));
+    let payee = simulation.upload_chunk(&client, &chunk).await?;
+    info!("Uploaded chunk {:?} paid to {payee}", chunk.address());
+
+    let holders = simulation
+        .wait_for_chunk_holders(&chunk, CLOSE_GROUP_SIZE, TIMEOUT)
+        .await?;
+    assert!(
+        holders.len() >= CLOSE_GROUP_SIZE,
+        "The chunk should be replicated to its close group, held by {holders:?}"
+    );
+
+    // churn out a holder of the chunk, the others keeping it
+    let holder = holders[0];
+    simulation.remove_node(holder);
+    let _ = simulation.add_node()?;
+    let holders = simulation
+        .wait_for_chunk_holders(&chunk, close_group_majority(), TIMEOUT)
+        .await?;
+    assert!(!holders.contains(&holder));
+    assert!(
+        holders.len() >= close_group_majority(),
+        "The chunk should survive the churn, held by {holders:?}"
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn chunk_is_unreachable_until_its_holders_partition_heals() -> Result<()> {
+    let simulation = Simulation::start(NODE_COUNT, SEED)?;
+    simulation
+        .network()
+        .set_latency(Duration::from_millis(5), Duration::from_millis(20));
+    assert!(
+        simulation
+            .wait_for_routing_tables(CLOSE_GROUP_SIZE, TIMEOUT)
+            .await?,
+        "The routing tables of the simulated nodes should fill up over slow links"
+    );
+
+    let client = simulation.client().await?;
+    let chunk = Chunk::new(Bytes::from(vec![9; 1024]));
+    let _ = simulation.upload_chunk(&client, &chunk).await?;
+    let holders = simulation
+        .wait_for_chunk_holders(&chunk, CLOSE_GROUP_SIZE, TIMEOUT)
+        .await?;
+    assert!(
+        holders.len() >= CLOSE_GROUP_SIZE,
+        "The chunk should be replicated over slow links, held by {holders:?}"
+    );
+
+    // cut all the holders off the client and the other nodes
+    simulation.partition(&holders);
+    assert!(
+        chunk_stays_unreachable(&client, &chunk, UNREACHABLE_WINDOW).await,
+        "The chunk should not be fetched while its holders are partitioned off"
+    );
+
+    simulation.heal();
+    assert!(
+        fetch_chunk(&client, &chunk, TIMEOUT).await,
+        "The chunk should be fetched back once the partition heals"
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn chunk_survives_churn_over_lossy_links() -> Result<()> {
+    let mut simulation = Simulation::start(NODE_COUNT, SEED)?;
+    assert!(
+        simulation
+            .wait_for_routing_tables(CLOSE_GROUP_SIZE, TIMEOUT)
+            .await?,
+        "The routing tables of the simulated nodes should fill up"
+    );
+
+    let client = simulation.client().await?;
+    let chunk = Chunk::new(Bytes::from(vec![5; 1024]));
+    let _ = simulation.upload_chunk(&client, &chunk).await?;
+    let holders = simulation
+        .wait_for_chunk_holders(&chunk, CLOSE_GROUP_SIZE, TIMEOUT)
+        .await?;
+    assert!(
+        holders.len() >= CLOSE_GROUP_SIZE,
+        "The chunk should be replicated to its close group, held by {holders:?}"
+    );
+
+    // the links now break at random while a holder and another node are churned out
+    simulation.network().set_packet_loss(PACKET_LOSS);
+    let holder = holders[0];
+    simulation.remove_node(holder);
+    let _ = simulation.add_node()?;
+    let (removed, added) = simulation.churn()?;
+    info!("Churned node {removed} out and node {added} in over lossy links");
+
+    let holders = simulation
+        .wait_for_chunk_holders(&chunk, close_group_majority(), TIMEOUT)
+        .await?;
+    assert!(!holders.contains(&holder));
+    assert!(
+        holders.len() >= close_group_majority(),
+        "The chunk should survive the churn over lossy links, held by {holders:?}"
+    );
+    assert!(
+        fetch_chunk(&client, &chunk, TIMEOUT).await,
+        "The chunk should be fetched over lossy links"
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn drain_timing_out_with_unconfirmed_records_fails() -> Result<()> {
+    let simulation = Simulation::start(NODE_COUNT, SEED)?;
+    assert!(
+        simulation
+            .wait_for_routing_tables(CLOSE_GROUP_SIZE, TIMEOUT)
+            .await?,
+        "The routing tables of the simulated nodes should fill up"
+    );
+
+    let client = simulation.client().await?;
+    let chunk = Chunk::new(Bytes::from(vec![3; 1024]));
+    let _ = simulation.upload_chunk(&client, &chunk).await?;
+    let holders = simulation
+        .wait_for_chunk_holders(&chunk, 1, TIMEOUT)
+        .await?;
+    let holder = simulation
+        .node(holders[0])
+        .expect("The holder should be running");
+
+    // no time is left for the peers to confirm they took the chunk over
+    assert!(holder.drain(Duration::ZERO).await.is_err());
+    let progress = holder.drain_progress();
+    assert!(
+        matches!(progress.state, DrainState::Failed(_)),
+        "The drain should fail, got {progress:?}"
+    );
+    assert!(progress.records_unconfirmed > 0);
+
+    Ok(())
+}
+
+/// Tries to fetch the chunk until `timeout`, at least once, returning whether it was fetched.
+async fn fetch_chunk(client: &Network, chunk: &Chunk, timeout: Duration) -> bool {
+    let cfg = GetRecordCfg {
+        get_quorum: Quorum::One,
+        retry_strategy: None,
+        target_record: None,
+        expected_holders: HashSet::new(),
+        is_register: false,
+    };
+    let key = chunk.network_address().to_record_key();
+    let deadline = Instant::now() + timeout;
+    loop {
+        match client.get_record_from_network(key.clone(), &cfg).await {
+            Ok(_) => return true,
+            Err(err) => info!("Failed to fetch chunk {:?}: {err:?}", chunk.address()),
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
+
+/// Tries to fetch the chunk every second for `window`, returning whether every attempt failed.
+async fn chunk_stays_unreachable(client: &Network, chunk: &Chunk, window: Duration) -> bool {
+    let deadline = Instant::now() + window;
+    while Instant::now() < deadline {
+        if fetch_chunk(client, chunk, Duration::ZERO).await {
+            return false;
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+    true
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn simulation_is_reproducible_from_its_seed() -> Result<()> {
+    let peer_ids = |simulation: &Simulation| {
+        simulation
+            .nodes()
+            .map(|(_, node)| node.peer_id())
+            .collect::<Vec<_>>()
+    };
+
+    let first = Simulation::start(5, SEED)?;
+    let second = Simulation::start(5, SEED)?;
+    let other = Simulation::start(5, SEED + 1)?;
+    assert_eq!(peer_ids(&first), peer_ids(&second));
+    assert_ne!(peer_ids(&first), peer_ids(&other));
+    Ok(())
+}
@@ -13,7 +13,7 @@ use sn_evm::{EvmNetwork, RewardsAddress};
 use sn_logging::LogFormat;
 use std::{
     ffi::OsString,
-    net::{Ipv4Addr, SocketAddr},
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr},
     path::PathBuf,
     str::FromStr,
 };
@@ -82,8 +82,14 @@ pub struct InstallNodeServiceCtxBuilder {
     pub name: String,
     pub max_archived_log_files: Option<usize>,
     pub max_log_files: Option<usize>,
+    pub max_storage: Option<u64>,
+    pub max_download_rate: Option<u64>,
+    pub max_upload_rate: Option<u64>,
+    pub bootstrap_cache_path: Option<PathBuf>,
+    pub ignore_bootstrap_cache: bool,
     pub metrics_port: Option<u16>,
     pub node_ip: Option<Ipv4Addr>,
+    pub node_ipv6: Option<Ipv6Addr>,
     pub node_port: Option<u16>,
     pub owner: Option<String>,
     pub rewards_address: RewardsAddress,
@@ -125,6 +131,10 @@ impl InstallNodeServiceCtxBuilder {
             args.push(OsString::from("--ip"));
             args.push(OsString::from(node_ip.to_string()));
         }
+        if let Some(node_ipv6) = self.node_ipv6 {
+            args.push(OsString::from("--ip"));
+            args.push(OsString::from(node_ipv6.to_string()));
+        }
         if let Some(node_port) = self.node_port {
             args.push(OsString::from("--port"));
             args.push(OsString::from(node_port.to_string()));
@@ -145,6 +155,27 @@ impl InstallNodeServiceCtxBuilder {
             args.push(OsString::from("--max-log-files"));
             args.push(OsString::from(log_files.to_string()));
         }
+        if let Some(max_storage) = self.max_storage {
+            args.push(OsString::from("--max-storage"));
+            args.push(OsString::from(max_storage.to_string()));
+        }
+        if let Some(max_download_rate) = self.max_download_rate {
+            args.push(OsString::from("--max-download-rate"));
+            args.push(OsString::from(max_download_rate.to_string()));
+        }
+        if let Some(max_upload_rate) = self.max_upload_rate {
+            args.push(OsString::from("--max-upload-rate"));
+            args.push(OsString::from(max_upload_rate.to_string()));
+        }
+        if let Some(bootstrap_cache_path) = self.bootstrap_cache_path {
+            args.push(OsString::from("--bootstrap-cache-path"));
+            args.push(OsString::from(
+                bootstrap_cache_path.to_string_lossy().to_string(),
+            ));
+        }
+        if self.ignore_bootstrap_cache {
+            args.push(OsString::from("--ignore-bootstrap-cache"));
+        }
 
         if !self.bootstrap_peers.is_empty() {
             let peers_str = self
@@ -202,8 +233,14 @@ pub struct AddNodeServiceOptions {
     pub log_format: Option<LogFormat>,
     pub max_archived_log_files: Option<usize>,
     pub max_log_files: Option<usize>,
+    pub max_storage: Option<u64>,
+    pub max_download_rate: Option<u64>,
+    pub max_upload_rate: Option<u64>,
+    pub bootstrap_cache_path: Option<PathBuf>,
+    pub ignore_bootstrap_cache: bool,
     pub metrics_port: Option<PortRange>,
     pub node_ip: Option<Ipv4Addr>,
+    pub node_ipv6: Option<Ipv6Addr>,
     pub node_port: Option<PortRange>,
     pub owner: Option<String>,
     pub rewards_address: RewardsAddress,
@@ -363,8 +400,14 @@ mod tests {
             name: "test-node".to_string(),
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             owner: None,
             rewards_address: RewardsAddress::from_str("0x03B770D9cD32077cC0bF330c13C114a87643B124")
@@ -401,8 +444,14 @@ mod tests {
             name: "test-node".to_string(),
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             owner: None,
             rewards_address: RewardsAddress::from_str("0x03B770D9cD32077cC0bF330c13C114a87643B124")
@@ -439,8 +488,14 @@ mod tests {
             name: "test-node".to_string(),
             max_archived_log_files: Some(10),
             max_log_files: Some(10),
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             owner: None,
             rewards_address: RewardsAddress::from_str("0x03B770D9cD32077cC0bF330c13C114a87643B124")
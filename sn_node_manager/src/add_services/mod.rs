@@ -230,9 +230,15 @@ pub async fn add_node(
             log_format: options.log_format,
             max_archived_log_files: options.max_archived_log_files,
             max_log_files: options.max_log_files,
+            max_storage: options.max_storage,
+            max_download_rate: options.max_download_rate,
+            max_upload_rate: options.max_upload_rate,
+            bootstrap_cache_path: options.bootstrap_cache_path.clone(),
+            ignore_bootstrap_cache: options.ignore_bootstrap_cache,
             metrics_port: metrics_free_port,
             name: service_name.clone(),
             node_ip: options.node_ip,
+            node_ipv6: options.node_ipv6,
             node_port,
             owner: owner.clone(),
             rewards_address: options.rewards_address,
@@ -267,8 +273,14 @@ pub async fn add_node(
                     log_format: options.log_format,
                     max_archived_log_files: options.max_archived_log_files,
                     max_log_files: options.max_log_files,
+                    max_storage: options.max_storage,
+                    max_download_rate: options.max_download_rate,
+                    max_upload_rate: options.max_upload_rate,
+                    bootstrap_cache_path: options.bootstrap_cache_path.clone(),
+                    ignore_bootstrap_cache: options.ignore_bootstrap_cache,
                     metrics_port: metrics_free_port,
                     node_ip: options.node_ip,
+                    node_ipv6: options.node_ipv6,
                     node_port,
                     number: node_number,
                     rewards_address: options.rewards_address,
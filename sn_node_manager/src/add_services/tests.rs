@@ -31,7 +31,7 @@ use sn_service_management::{
 };
 use std::{
     ffi::OsString,
-    net::{IpAddr, Ipv4Addr, SocketAddr},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
     path::{Path, PathBuf},
     str::FromStr,
 };
@@ -131,9 +131,15 @@ async fn add_genesis_node_should_use_latest_version_and_add_one_service() -> Res
         log_format: None,
         max_archived_log_files: None,
         max_log_files: None,
+        max_storage: None,
+        max_download_rate: None,
+        max_upload_rate: None,
+        bootstrap_cache_path: None,
+        ignore_bootstrap_cache: false,
         metrics_port: None,
         name: "safenode1".to_string(),
         node_ip: None,
+        node_ipv6: None,
         node_port: None,
         owner: None,
         rewards_address: RewardsAddress::from_str("0x03B770D9cD32077cC0bF330c13C114a87643B124")?,
@@ -168,9 +174,15 @@ async fn add_genesis_node_should_use_latest_version_and_add_one_service() -> Res
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             owner: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             rpc_address: None,
             rpc_port: None,
@@ -280,8 +292,14 @@ async fn add_genesis_node_should_return_an_error_if_there_is_already_a_genesis_n
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             pid: None,
@@ -330,9 +348,15 @@ async fn add_genesis_node_should_return_an_error_if_there_is_already_a_genesis_n
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             owner: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             rpc_address: Some(custom_rpc_address),
             rpc_port: None,
@@ -413,9 +437,15 @@ async fn add_genesis_node_should_return_an_error_if_count_is_greater_than_1() ->
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             owner: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             rpc_address: None,
             rpc_port: None,
@@ -511,9 +541,15 @@ async fn add_node_should_use_latest_version_and_add_three_services() -> Result<(
         log_format: None,
         max_archived_log_files: None,
         max_log_files: None,
+        max_storage: None,
+        max_download_rate: None,
+        max_upload_rate: None,
+        bootstrap_cache_path: None,
+        ignore_bootstrap_cache: false,
         metrics_port: None,
         name: "safenode1".to_string(),
         node_ip: None,
+        node_ipv6: None,
         node_port: None,
         owner: None,
         rpc_socket_addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8081),
@@ -561,9 +597,15 @@ async fn add_node_should_use_latest_version_and_add_three_services() -> Result<(
         log_format: None,
         max_archived_log_files: None,
         max_log_files: None,
+        max_storage: None,
+        max_download_rate: None,
+        max_upload_rate: None,
+        bootstrap_cache_path: None,
+        ignore_bootstrap_cache: false,
         metrics_port: None,
         name: "safenode2".to_string(),
         node_ip: None,
+        node_ipv6: None,
         node_port: None,
         owner: None,
         rewards_address: RewardsAddress::from_str("0x03B770D9cD32077cC0bF330c13C114a87643B124")?,
@@ -611,9 +653,15 @@ async fn add_node_should_use_latest_version_and_add_three_services() -> Result<(
         log_dir_path: node_logs_dir.to_path_buf().join("safenode3"),
         max_archived_log_files: None,
         max_log_files: None,
+        max_storage: None,
+        max_download_rate: None,
+        max_upload_rate: None,
+        bootstrap_cache_path: None,
+        ignore_bootstrap_cache: false,
         metrics_port: None,
         name: "safenode3".to_string(),
         node_ip: None,
+        node_ipv6: None,
         node_port: None,
         owner: None,
         rewards_address: RewardsAddress::from_str("0x03B770D9cD32077cC0bF330c13C114a87643B124")?,
@@ -649,9 +697,15 @@ async fn add_node_should_use_latest_version_and_add_three_services() -> Result<(
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             owner: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             rpc_address: None,
             rpc_port: None,
@@ -796,9 +850,15 @@ async fn add_node_should_update_the_bootstrap_peers_inside_node_registry() -> Re
         log_format: None,
         max_archived_log_files: None,
         max_log_files: None,
+        max_storage: None,
+        max_download_rate: None,
+        max_upload_rate: None,
+        bootstrap_cache_path: None,
+        ignore_bootstrap_cache: false,
         metrics_port: None,
         name: "safenode1".to_string(),
         node_ip: None,
+        node_ipv6: None,
         node_port: None,
         owner: None,
         rewards_address: RewardsAddress::from_str("0x03B770D9cD32077cC0bF330c13C114a87643B124")?,
@@ -834,9 +894,15 @@ async fn add_node_should_update_the_bootstrap_peers_inside_node_registry() -> Re
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             owner: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             rpc_address: None,
             rpc_port: None,
@@ -955,9 +1021,15 @@ async fn add_node_should_update_the_environment_variables_inside_node_registry()
         log_format: None,
         max_archived_log_files: None,
         max_log_files: None,
+        max_storage: None,
+        max_download_rate: None,
+        max_upload_rate: None,
+        bootstrap_cache_path: None,
+        ignore_bootstrap_cache: false,
         metrics_port: None,
         name: "safenode1".to_string(),
         node_ip: None,
+        node_ipv6: None,
         node_port: None,
         owner: None,
         rewards_address: RewardsAddress::from_str("0x03B770D9cD32077cC0bF330c13C114a87643B124")?,
@@ -992,9 +1064,15 @@ async fn add_node_should_update_the_environment_variables_inside_node_registry()
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             owner: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             rpc_address: None,
             rpc_port: None,
@@ -1087,8 +1165,14 @@ async fn add_new_node_should_add_another_service() -> Result<()> {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -1146,9 +1230,15 @@ async fn add_new_node_should_add_another_service() -> Result<()> {
         log_format: None,
         max_archived_log_files: None,
         max_log_files: None,
+        max_storage: None,
+        max_download_rate: None,
+        max_upload_rate: None,
+        bootstrap_cache_path: None,
+        ignore_bootstrap_cache: false,
         metrics_port: None,
         name: "safenode2".to_string(),
         node_ip: None,
+        node_ipv6: None,
         node_port: None,
         rewards_address: RewardsAddress::from_str("0x03B770D9cD32077cC0bF330c13C114a87643B124")?,
         rpc_socket_addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8083),
@@ -1184,9 +1274,15 @@ async fn add_new_node_should_add_another_service() -> Result<()> {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             owner: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             rpc_address: None,
             rpc_port: None,
@@ -1343,9 +1439,15 @@ async fn add_node_should_use_custom_ip() -> Result<()> {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             owner: None,
             node_ip: Some(custom_ip),
+            node_ipv6: None,
             node_port: None,
             rpc_address: None,
             rpc_port: None,
@@ -1386,6 +1488,162 @@ async fn add_node_should_use_custom_ip() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn add_node_should_listen_on_ipv4_and_ipv6() -> Result<()> {
+    let tmp_data_dir = assert_fs::TempDir::new()?;
+    let node_reg_path = tmp_data_dir.child("node_reg.json");
+
+    let mut mock_service_control = MockServiceControl::new();
+
+    let mut node_registry = NodeRegistry {
+        auditor: None,
+        faucet: None,
+        save_path: node_reg_path.to_path_buf(),
+        nat_status: None,
+        nodes: vec![],
+        bootstrap_peers: vec![],
+        environment_variables: None,
+        daemon: None,
+    };
+    let latest_version = "0.96.4";
+    let temp_dir = assert_fs::TempDir::new()?;
+    let node_data_dir = temp_dir.child("data");
+    node_data_dir.create_dir_all()?;
+    let node_logs_dir = temp_dir.child("logs");
+    node_logs_dir.create_dir_all()?;
+    let safenode_download_path = temp_dir.child(SAFENODE_FILE_NAME);
+    safenode_download_path.write_binary(b"fake safenode bin")?;
+
+    let custom_ip = Ipv4Addr::UNSPECIFIED;
+    let custom_ipv6 = Ipv6Addr::UNSPECIFIED;
+
+    let mut seq = Sequence::new();
+
+    mock_service_control
+        .expect_get_available_port()
+        .times(1)
+        .returning(|| Ok(12001))
+        .in_sequence(&mut seq);
+
+    mock_service_control
+        .expect_install()
+        .times(1)
+        .with(
+            eq(ServiceInstallCtx {
+                args: vec![
+                    OsString::from("--rpc"),
+                    OsString::from("127.0.0.1:12001"),
+                    OsString::from("--root-dir"),
+                    OsString::from(
+                        node_data_dir
+                            .to_path_buf()
+                            .join("safenode1")
+                            .to_string_lossy()
+                            .to_string(),
+                    ),
+                    OsString::from("--log-output-dest"),
+                    OsString::from(
+                        node_logs_dir
+                            .to_path_buf()
+                            .join("safenode1")
+                            .to_string_lossy()
+                            .to_string(),
+                    ),
+                    OsString::from("--ip"),
+                    OsString::from(custom_ip.to_string()),
+                    OsString::from("--ip"),
+                    OsString::from(custom_ipv6.to_string()),
+                    OsString::from("--rewards-address"),
+                    OsString::from("0x03B770D9cD32077cC0bF330c13C114a87643B124"),
+                    OsString::from("evm-custom"),
+                    OsString::from("--rpc-url"),
+                    OsString::from("http://localhost:8545/"),
+                    OsString::from("--payment-token-address"),
+                    OsString::from("0x5FbDB2315678afecb367f032d93F642f64180aa3"),
+                    OsString::from("--data-payments-address"),
+                    OsString::from("0x8464135c8F25Da09e49BC8782676a84730C318bC"),
+                ],
+                autostart: false,
+                contents: None,
+                environment: None,
+                label: "safenode1".parse()?,
+                program: node_data_dir
+                    .to_path_buf()
+                    .join("safenode1")
+                    .join(SAFENODE_FILE_NAME),
+                username: Some(get_username()),
+                working_directory: None,
+            }),
+            eq(false),
+        )
+        .returning(|_, _| Ok(()))
+        .in_sequence(&mut seq);
+
+    add_node(
+        AddNodeServiceOptions {
+            auto_restart: false,
+            auto_set_nat_flags: false,
+            bootstrap_peers: vec![],
+            count: None,
+            delete_safenode_src: true,
+            enable_metrics_server: false,
+            env_variables: None,
+            genesis: false,
+            home_network: false,
+            local: false,
+            log_format: None,
+            max_archived_log_files: None,
+            max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
+            metrics_port: None,
+            owner: None,
+            node_ip: Some(custom_ip),
+            node_ipv6: Some(custom_ipv6),
+            node_port: None,
+            rpc_address: None,
+            rpc_port: None,
+            safenode_dir_path: temp_dir.to_path_buf(),
+            safenode_src_path: safenode_download_path.to_path_buf(),
+            service_data_dir_path: node_data_dir.to_path_buf(),
+            service_log_dir_path: node_logs_dir.to_path_buf(),
+            upnp: false,
+            user: Some(get_username()),
+            user_mode: false,
+            version: latest_version.to_string(),
+            evm_network: EvmNetwork::Custom(CustomNetwork {
+                rpc_url_http: "http://localhost:8545".parse()?,
+                payment_token_address: RewardsAddress::from_str(
+                    "0x5FbDB2315678afecb367f032d93F642f64180aa3",
+                )?,
+                data_payments_address: RewardsAddress::from_str(
+                    "0x8464135c8F25Da09e49BC8782676a84730C318bC",
+                )?,
+            }),
+            rewards_address: RewardsAddress::from_str(
+                "0x03B770D9cD32077cC0bF330c13C114a87643B124",
+            )?,
+        },
+        &mut node_registry,
+        &mock_service_control,
+        VerbosityLevel::Normal,
+    )
+    .await?;
+
+    safenode_download_path.assert(predicate::path::missing());
+    node_data_dir.assert(predicate::path::is_dir());
+    node_logs_dir.assert(predicate::path::is_dir());
+
+    assert_eq!(node_registry.nodes.len(), 1);
+    assert_eq!(node_registry.nodes[0].node_ip, Some(custom_ip));
+    assert_eq!(node_registry.nodes[0].node_ipv6, Some(custom_ipv6));
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn add_node_should_use_custom_ports_for_one_service() -> Result<()> {
     let tmp_data_dir = assert_fs::TempDir::new()?;
@@ -1442,9 +1700,15 @@ async fn add_node_should_use_custom_ports_for_one_service() -> Result<()> {
         log_format: None,
         max_archived_log_files: None,
         max_log_files: None,
+        max_storage: None,
+        max_download_rate: None,
+        max_upload_rate: None,
+        bootstrap_cache_path: None,
+        ignore_bootstrap_cache: false,
         metrics_port: None,
         name: "safenode1".to_string(),
         node_ip: None,
+        node_ipv6: None,
         node_port: Some(custom_port),
         owner: None,
         rewards_address: RewardsAddress::from_str("0x03B770D9cD32077cC0bF330c13C114a87643B124")?,
@@ -1480,9 +1744,15 @@ async fn add_node_should_use_custom_ports_for_one_service() -> Result<()> {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             owner: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: Some(PortRange::Single(custom_port)),
             rpc_address: None,
             rpc_port: None,
@@ -1740,9 +2010,15 @@ async fn add_node_should_use_a_custom_port_range() -> Result<()> {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             owner: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: Some(PortRange::Range(12000, 12002)),
             rpc_address: None,
             rpc_port: None,
@@ -1815,8 +2091,14 @@ async fn add_node_should_return_an_error_if_duplicate_custom_port_is_used() -> R
             log_dir_path: PathBuf::from("/var/log/safenode/safenode1"),
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: Some(12000),
             number: 1,
             owner: None,
@@ -1863,9 +2145,15 @@ async fn add_node_should_return_an_error_if_duplicate_custom_port_is_used() -> R
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             owner: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: Some(PortRange::Single(12000)),
             rpc_address: None,
             rpc_port: None,
@@ -1936,8 +2224,14 @@ async fn add_node_should_return_an_error_if_duplicate_custom_port_in_range_is_us
             log_dir_path: PathBuf::from("/var/log/safenode/safenode1"),
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: Some(12000),
             number: 1,
             owner: None,
@@ -1984,9 +2278,15 @@ async fn add_node_should_return_an_error_if_duplicate_custom_port_in_range_is_us
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             owner: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: Some(PortRange::Range(12000, 12002)),
             rpc_address: None,
             rpc_port: None,
@@ -2065,9 +2365,15 @@ async fn add_node_should_return_an_error_if_port_and_node_count_do_not_match() -
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             owner: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: Some(PortRange::Range(12000, 12002)),
             rpc_address: None,
             rpc_port: None,
@@ -2151,9 +2457,15 @@ async fn add_node_should_return_an_error_if_multiple_services_are_specified_with
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             owner: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: Some(PortRange::Single(12000)),
             rpc_address: None,
             rpc_port: None,
@@ -2299,9 +2611,15 @@ async fn add_node_should_set_random_ports_if_enable_metrics_server_is_true() ->
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             owner: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             rpc_address: None,
             rpc_port: None,
@@ -2439,9 +2757,15 @@ async fn add_node_should_set_max_archived_log_files() -> Result<()> {
             log_format: None,
             max_archived_log_files: Some(20),
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             owner: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             rpc_address: None,
             rpc_port: None,
@@ -2580,9 +2904,15 @@ async fn add_node_should_set_max_log_files() -> Result<()> {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: Some(20),
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             owner: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             rpc_address: None,
             rpc_port: None,
@@ -2618,6 +2948,223 @@ async fn add_node_should_set_max_log_files() -> Result<()> {
     Ok(())
 }
 
+/// Add a single node with the default options changed by `set_options`, expecting it to be installed with
+/// `forwarded_args` after its log dir, and return the node registry it was added to.
+async fn add_one_node_with_options(
+    forwarded_args: Vec<OsString>,
+    set_options: impl FnOnce(&mut AddNodeServiceOptions),
+) -> Result<NodeRegistry> {
+    let tmp_data_dir = assert_fs::TempDir::new()?;
+    let node_reg_path = tmp_data_dir.child("node_reg.json");
+
+    let mut mock_service_control = MockServiceControl::new();
+
+    let mut node_registry = NodeRegistry {
+        auditor: None,
+        faucet: None,
+        save_path: node_reg_path.to_path_buf(),
+        nat_status: None,
+        nodes: vec![],
+        bootstrap_peers: vec![],
+        environment_variables: None,
+        daemon: None,
+    };
+
+    let latest_version = "0.96.4";
+    let temp_dir = assert_fs::TempDir::new()?;
+    let node_data_dir = temp_dir.child("data");
+    node_data_dir.create_dir_all()?;
+    let node_logs_dir = temp_dir.child("logs");
+    node_logs_dir.create_dir_all()?;
+    let safenode_download_path = temp_dir.child(SAFENODE_FILE_NAME);
+    safenode_download_path.write_binary(b"fake safenode bin")?;
+
+    let mut seq = Sequence::new();
+
+    // Expected calls for first installation
+    mock_service_control
+        .expect_get_available_port()
+        .times(1)
+        .returning(|| Ok(8081))
+        .in_sequence(&mut seq);
+
+    let mut args = vec![
+        OsString::from("--rpc"),
+        OsString::from("127.0.0.1:8081"),
+        OsString::from("--root-dir"),
+        OsString::from(
+            node_data_dir
+                .to_path_buf()
+                .join("safenode1")
+                .to_string_lossy()
+                .to_string(),
+        ),
+        OsString::from("--log-output-dest"),
+        OsString::from(
+            node_logs_dir
+                .to_path_buf()
+                .join("safenode1")
+                .to_string_lossy()
+                .to_string(),
+        ),
+    ];
+    args.extend(forwarded_args);
+    args.extend([
+        OsString::from("--rewards-address"),
+        OsString::from("0x03B770D9cD32077cC0bF330c13C114a87643B124"),
+        OsString::from("evm-custom"),
+        OsString::from("--rpc-url"),
+        OsString::from("http://localhost:8545/"),
+        OsString::from("--payment-token-address"),
+        OsString::from("0x5FbDB2315678afecb367f032d93F642f64180aa3"),
+        OsString::from("--data-payments-address"),
+        OsString::from("0x8464135c8F25Da09e49BC8782676a84730C318bC"),
+    ]);
+    mock_service_control
+        .expect_install()
+        .times(1)
+        .with(
+            eq(ServiceInstallCtx {
+                args,
+                autostart: false,
+                contents: None,
+                environment: None,
+                label: "safenode1".parse()?,
+                program: node_data_dir
+                    .to_path_buf()
+                    .join("safenode1")
+                    .join(SAFENODE_FILE_NAME),
+                username: Some(get_username()),
+                working_directory: None,
+            }),
+            eq(false),
+        )
+        .returning(|_, _| Ok(()))
+        .in_sequence(&mut seq);
+
+    let mut options = AddNodeServiceOptions {
+        auto_restart: false,
+        auto_set_nat_flags: false,
+        bootstrap_peers: vec![],
+        count: Some(1),
+        delete_safenode_src: false,
+        enable_metrics_server: false,
+        env_variables: None,
+        genesis: false,
+        home_network: false,
+        local: false,
+        log_format: None,
+        max_archived_log_files: None,
+        max_log_files: None,
+        max_storage: None,
+        max_download_rate: None,
+        max_upload_rate: None,
+        bootstrap_cache_path: None,
+        ignore_bootstrap_cache: false,
+        metrics_port: None,
+        owner: None,
+        node_ip: None,
+        node_ipv6: None,
+        node_port: None,
+        rpc_address: None,
+        rpc_port: None,
+        safenode_dir_path: temp_dir.to_path_buf(),
+        safenode_src_path: safenode_download_path.to_path_buf(),
+        service_data_dir_path: node_data_dir.to_path_buf(),
+        service_log_dir_path: node_logs_dir.to_path_buf(),
+        upnp: false,
+        user: Some(get_username()),
+        user_mode: false,
+        version: latest_version.to_string(),
+        evm_network: EvmNetwork::Custom(CustomNetwork {
+            rpc_url_http: "http://localhost:8545".parse()?,
+            payment_token_address: RewardsAddress::from_str(
+                "0x5FbDB2315678afecb367f032d93F642f64180aa3",
+            )?,
+            data_payments_address: RewardsAddress::from_str(
+                "0x8464135c8F25Da09e49BC8782676a84730C318bC",
+            )?,
+        }),
+        rewards_address: RewardsAddress::from_str("0x03B770D9cD32077cC0bF330c13C114a87643B124")?,
+    };
+    set_options(&mut options);
+
+    add_node(
+        options,
+        &mut node_registry,
+        &mock_service_control,
+        VerbosityLevel::Normal,
+    )
+    .await?;
+
+    Ok(node_registry)
+}
+
+#[tokio::test]
+async fn add_node_should_set_max_storage() -> Result<()> {
+    let node_registry = add_one_node_with_options(
+        vec![
+            OsString::from("--max-storage"),
+            OsString::from("200000000000"),
+        ],
+        |options| options.max_storage = Some(200_000_000_000),
+    )
+    .await?;
+
+    assert_matches!(node_registry.nodes[0].max_storage, Some(200_000_000_000));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn add_node_should_set_max_upload_and_download_rates() -> Result<()> {
+    let node_registry = add_one_node_with_options(
+        vec![
+            OsString::from("--max-download-rate"),
+            OsString::from("4000000"),
+            OsString::from("--max-upload-rate"),
+            OsString::from("1000000"),
+        ],
+        |options| {
+            options.max_download_rate = Some(4_000_000);
+            options.max_upload_rate = Some(1_000_000);
+        },
+    )
+    .await?;
+
+    assert_matches!(node_registry.nodes[0].max_download_rate, Some(4_000_000));
+    assert_matches!(node_registry.nodes[0].max_upload_rate, Some(1_000_000));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn add_node_should_forward_the_bootstrap_cache_args() -> Result<()> {
+    let cache_dir = assert_fs::TempDir::new()?;
+    let bootstrap_cache_path = cache_dir.child("bootstrap_cache.json").to_path_buf();
+
+    let node_registry = add_one_node_with_options(
+        vec![
+            OsString::from("--bootstrap-cache-path"),
+            OsString::from(bootstrap_cache_path.to_string_lossy().to_string()),
+            OsString::from("--ignore-bootstrap-cache"),
+        ],
+        |options| {
+            options.bootstrap_cache_path = Some(bootstrap_cache_path.clone());
+            options.ignore_bootstrap_cache = true;
+        },
+    )
+    .await?;
+
+    assert_eq!(
+        node_registry.nodes[0].bootstrap_cache_path,
+        Some(bootstrap_cache_path)
+    );
+    assert!(node_registry.nodes[0].ignore_bootstrap_cache);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn add_node_should_use_a_custom_port_range_for_metrics_server() -> Result<()> {
     let tmp_data_dir = assert_fs::TempDir::new()?;
@@ -2835,9 +3382,15 @@ async fn add_node_should_use_a_custom_port_range_for_metrics_server() -> Result<
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: Some(PortRange::Range(12000, 12002)),
             owner: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             rpc_address: None,
             rpc_port: None,
@@ -2907,8 +3460,14 @@ async fn add_node_should_return_an_error_if_duplicate_custom_metrics_port_is_use
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: Some(12000),
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -2955,9 +3514,15 @@ async fn add_node_should_return_an_error_if_duplicate_custom_metrics_port_is_use
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: Some(PortRange::Single(12000)),
             owner: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             rpc_address: None,
             rpc_port: None,
@@ -3029,8 +3594,14 @@ async fn add_node_should_return_an_error_if_duplicate_custom_metrics_port_in_ran
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: Some(12000),
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -3077,9 +3648,15 @@ async fn add_node_should_return_an_error_if_duplicate_custom_metrics_port_in_ran
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: Some(PortRange::Range(12000, 12002)),
             owner: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             rpc_address: None,
             rpc_port: None,
@@ -3315,9 +3892,15 @@ async fn add_node_should_use_a_custom_port_range_for_the_rpc_server() -> Result<
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             owner: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             rpc_address: None,
             rpc_port: Some(PortRange::Range(20000, 20002)),
@@ -3398,8 +3981,14 @@ async fn add_node_should_return_an_error_if_duplicate_custom_rpc_port_is_used()
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -3446,9 +4035,15 @@ async fn add_node_should_return_an_error_if_duplicate_custom_rpc_port_is_used()
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             owner: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             rpc_address: None,
             rpc_port: Some(PortRange::Single(8081)),
@@ -3520,8 +4115,14 @@ async fn add_node_should_return_an_error_if_duplicate_custom_rpc_port_in_range_i
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -3568,9 +4169,15 @@ async fn add_node_should_return_an_error_if_duplicate_custom_rpc_port_in_range_i
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             owner: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             rpc_address: None,
             rpc_port: Some(PortRange::Range(8081, 8082)),
@@ -3665,9 +4272,15 @@ async fn add_node_should_disable_upnp_and_home_network_if_nat_status_is_public()
         log_format: None,
         max_archived_log_files: None,
         max_log_files: None,
+        max_storage: None,
+        max_download_rate: None,
+        max_upload_rate: None,
+        bootstrap_cache_path: None,
+        ignore_bootstrap_cache: false,
         metrics_port: None,
         name: "safenode1".to_string(),
         node_ip: None,
+        node_ipv6: None,
         node_port: None,
         owner: None,
         rewards_address: RewardsAddress::from_str("0x03B770D9cD32077cC0bF330c13C114a87643B124")?,
@@ -3702,9 +4315,15 @@ async fn add_node_should_disable_upnp_and_home_network_if_nat_status_is_public()
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             owner: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             rpc_address: None,
             rpc_port: None,
@@ -3796,9 +4415,15 @@ async fn add_node_should_enable_upnp_if_nat_status_is_upnp() -> Result<()> {
         log_format: None,
         max_archived_log_files: None,
         max_log_files: None,
+        max_storage: None,
+        max_download_rate: None,
+        max_upload_rate: None,
+        bootstrap_cache_path: None,
+        ignore_bootstrap_cache: false,
         metrics_port: None,
         name: "safenode1".to_string(),
         node_ip: None,
+        node_ipv6: None,
         node_port: None,
         owner: None,
         rewards_address: RewardsAddress::from_str("0x03B770D9cD32077cC0bF330c13C114a87643B124")?,
@@ -3833,9 +4458,15 @@ async fn add_node_should_enable_upnp_if_nat_status_is_upnp() -> Result<()> {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             owner: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             rpc_address: None,
             rpc_port: None,
@@ -3927,9 +4558,15 @@ async fn add_node_should_enable_home_network_if_nat_status_is_private() -> Resul
         log_format: None,
         max_archived_log_files: None,
         max_log_files: None,
+        max_storage: None,
+        max_download_rate: None,
+        max_upload_rate: None,
+        bootstrap_cache_path: None,
+        ignore_bootstrap_cache: false,
         metrics_port: None,
         name: "safenode1".to_string(),
         node_ip: None,
+        node_ipv6: None,
         node_port: None,
         owner: None,
         rewards_address: RewardsAddress::from_str("0x03B770D9cD32077cC0bF330c13C114a87643B124")?,
@@ -3964,9 +4601,15 @@ async fn add_node_should_enable_home_network_if_nat_status_is_private() -> Resul
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             owner: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             rpc_address: None,
             rpc_port: None,
@@ -4053,9 +4696,15 @@ async fn add_node_should_return_an_error_if_nat_status_is_none_but_auto_set_nat_
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             owner: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             rpc_address: None,
             rpc_port: None,
@@ -4688,9 +5337,15 @@ async fn add_node_should_not_delete_the_source_binary_if_path_arg_is_used() -> R
         log_format: None,
         max_archived_log_files: None,
         max_log_files: None,
+        max_storage: None,
+        max_download_rate: None,
+        max_upload_rate: None,
+        bootstrap_cache_path: None,
+        ignore_bootstrap_cache: false,
         metrics_port: None,
         name: "safenode1".to_string(),
         node_ip: None,
+        node_ipv6: None,
         node_port: None,
         owner: None,
         rewards_address: RewardsAddress::from_str("0x03B770D9cD32077cC0bF330c13C114a87643B124")?,
@@ -4726,9 +5381,15 @@ async fn add_node_should_not_delete_the_source_binary_if_path_arg_is_used() -> R
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             owner: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             rpc_address: None,
             rpc_port: None,
@@ -4821,9 +5482,15 @@ async fn add_node_should_apply_the_home_network_flag_if_it_is_used() -> Result<(
         log_format: None,
         max_archived_log_files: None,
         max_log_files: None,
+        max_storage: None,
+        max_download_rate: None,
+        max_upload_rate: None,
+        bootstrap_cache_path: None,
+        ignore_bootstrap_cache: false,
         metrics_port: None,
         name: "safenode1".to_string(),
         node_ip: None,
+        node_ipv6: None,
         node_port: None,
         owner: None,
         rewards_address: RewardsAddress::from_str("0x03B770D9cD32077cC0bF330c13C114a87643B124")?,
@@ -4859,9 +5526,15 @@ async fn add_node_should_apply_the_home_network_flag_if_it_is_used() -> Result<(
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             owner: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             rpc_address: None,
             rpc_port: None,
@@ -4954,9 +5627,15 @@ async fn add_node_should_add_the_node_in_user_mode() -> Result<()> {
         log_format: None,
         max_archived_log_files: None,
         max_log_files: None,
+        max_storage: None,
+        max_download_rate: None,
+        max_upload_rate: None,
+        bootstrap_cache_path: None,
+        ignore_bootstrap_cache: false,
         metrics_port: None,
         name: "safenode1".to_string(),
         node_ip: None,
+        node_ipv6: None,
         node_port: None,
         owner: None,
         rewards_address: RewardsAddress::from_str("0x03B770D9cD32077cC0bF330c13C114a87643B124")?,
@@ -4992,9 +5671,15 @@ async fn add_node_should_add_the_node_in_user_mode() -> Result<()> {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             owner: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             rpc_address: None,
             rpc_port: None,
@@ -5084,9 +5769,15 @@ async fn add_node_should_add_the_node_with_upnp_enabled() -> Result<()> {
         log_format: None,
         max_archived_log_files: None,
         max_log_files: None,
+        max_storage: None,
+        max_download_rate: None,
+        max_upload_rate: None,
+        bootstrap_cache_path: None,
+        ignore_bootstrap_cache: false,
         metrics_port: None,
         name: "safenode1".to_string(),
         node_ip: None,
+        node_ipv6: None,
         node_port: None,
         owner: None,
         rewards_address: RewardsAddress::from_str("0x03B770D9cD32077cC0bF330c13C114a87643B124")?,
@@ -5122,9 +5813,15 @@ async fn add_node_should_add_the_node_with_upnp_enabled() -> Result<()> {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             owner: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             rpc_address: None,
             rpc_port: None,
@@ -5261,9 +5958,15 @@ async fn add_node_should_assign_an_owner_in_lowercase() -> Result<()> {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             owner: Some("Discord_Username".to_string()),
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             rpc_address: None,
             rpc_port: None,
@@ -5402,9 +6105,15 @@ async fn add_node_should_auto_restart() -> Result<()> {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             owner: Some("discord_username".to_string()),
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             rpc_address: None,
             rpc_port: None,
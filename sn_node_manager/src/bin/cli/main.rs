@@ -17,10 +17,15 @@ use sn_logging::{LogBuilder, LogFormat};
 use sn_node_manager::{
     add_services::config::PortRange,
     cmd::{self},
-    VerbosityLevel, DEFAULT_NODE_STARTUP_CONNECTION_TIMEOUT_S,
+    VerbosityLevel, DEFAULT_NODE_DRAIN_TIMEOUT_S, DEFAULT_NODE_STARTUP_CONNECTION_TIMEOUT_S,
 };
 use sn_peers_acquisition::PeersArgs;
-use std::{net::Ipv4Addr, path::PathBuf};
+use sn_protocol::storage::StorageSize;
+use std::{
+    net::{Ipv4Addr, Ipv6Addr},
+    path::PathBuf,
+    time::Duration,
+};
 use tracing::Level;
 
 const DEFAULT_NODE_COUNT: u16 = 25;
@@ -164,6 +169,24 @@ pub enum SubCmd {
         /// After reaching this limit, the older archived files are deleted.
         #[clap(long, verbatim_doc_comment)]
         max_archived_log_files: Option<usize>,
+        /// Specify the storage capacity offered by each node, e.g. "200GB" or "500MiB".
+        ///
+        /// The node prunes its farthest records to stay under it, and its store cost rises as it
+        /// fills up. If not set, the safenode default is used.
+        #[clap(long)]
+        max_storage: Option<StorageSize>,
+        /// Specify the max bytes per second downloaded by each node, e.g. "2MB" or "512KiB".
+        ///
+        /// The node holds back its replication traffic while it uses most of it, leaving the rest
+        /// to the client requests. Unlimited if not set.
+        #[clap(long)]
+        max_download_rate: Option<StorageSize>,
+        /// Specify the max bytes per second uploaded by each node, e.g. "2MB" or "512KiB".
+        ///
+        /// The node holds back its replication traffic while it uses most of it, leaving the rest
+        /// to the client requests. Unlimited if not set.
+        #[clap(long)]
+        max_upload_rate: Option<StorageSize>,
         /// Specify a port for the open metrics server.
         ///
         /// If you're passing the compiled safenode via --node-path, make sure to enable the open-metrics feature
@@ -182,6 +205,13 @@ pub enum SubCmd {
         /// If not set, we bind to all the available network interfaces.
         #[clap(long)]
         node_ip: Option<Ipv4Addr>,
+        /// Specify the IPv6 address for the safenode service(s).
+        ///
+        /// The service(s) then listen on IPv6 on the same port as IPv4. Provide --node-ip as well to listen on
+        /// both, e.g. '--node-ip 0.0.0.0 --node-ipv6 ::'. Otherwise the service(s) only listen on IPv6, which
+        /// suits the IPv6-only hosts.
+        #[clap(long)]
+        node_ipv6: Option<Ipv6Addr>,
         /// Specify a port for the safenode service(s).
         ///
         /// If not used, ports will be selected at random.
@@ -283,7 +313,7 @@ pub enum SubCmd {
     ///
     /// If no peer ID(s) or service name(s) are supplied, all services will be removed.
     ///
-    /// Services must be stopped before they can be removed.
+    /// Services must be stopped before they can be removed, unless the 'drain' argument is used.
     ///
     /// On Windows, this command must run as the administrative user. On Linux/macOS, run using
     /// sudo if you defined system-wide services; otherwise, do not run the command elevated.
@@ -302,6 +332,17 @@ pub enum SubCmd {
         /// Set this flag to keep the node's data and log directories.
         #[clap(long)]
         keep_directories: bool,
+        /// Set this flag to have running nodes hand off their records to the peers taking them
+        /// over, then stop them, before removing them.
+        ///
+        /// This lowers the risk of data loss when many nodes are retired at once.
+        #[clap(long)]
+        drain: bool,
+        /// The max time in seconds to wait for the records of a node to be handed off.
+        ///
+        /// Only applies with the 'drain' argument. Defaults to 600s.
+        #[clap(long, default_value_t = DEFAULT_NODE_DRAIN_TIMEOUT_S)]
+        drain_timeout: u64,
     },
     /// Reset back to a clean base state.
     ///
@@ -386,6 +427,17 @@ pub enum SubCmd {
         /// The argument can be used multiple times to stop many services.
         #[clap(long, conflicts_with = "peer_id")]
         service_name: Vec<String>,
+        /// Set this flag to have running nodes hand off their records to the peers taking them
+        /// over before stopping them.
+        ///
+        /// This lowers the risk of data loss when many nodes are retired at once.
+        #[clap(long)]
+        drain: bool,
+        /// The max time in seconds to wait for the records of a node to be handed off.
+        ///
+        /// Only applies with the 'drain' argument. Defaults to 600s.
+        #[clap(long, default_value_t = DEFAULT_NODE_DRAIN_TIMEOUT_S)]
+        drain_timeout: u64,
     },
     /// Upgrade safenode services.
     ///
@@ -1095,8 +1147,12 @@ async fn main() -> Result<()> {
             log_format,
             max_archived_log_files,
             max_log_files,
+            max_storage,
+            max_download_rate,
+            max_upload_rate,
             metrics_port,
             node_ip,
+            node_ipv6,
             node_port,
             owner,
             path,
@@ -1123,8 +1179,12 @@ async fn main() -> Result<()> {
                 log_format,
                 max_archived_log_files,
                 max_log_files,
+                max_storage.map(|size| size.as_bytes()),
+                max_download_rate.map(|rate| rate.as_bytes()),
+                max_upload_rate.map(|rate| rate.as_bytes()),
                 metrics_port,
                 node_ip,
+                node_ipv6,
                 node_port,
                 owner,
                 peers,
@@ -1342,9 +1402,21 @@ async fn main() -> Result<()> {
         }
         Some(SubCmd::Remove {
             keep_directories,
+            drain,
+            drain_timeout,
             peer_id: peer_ids,
             service_name: service_names,
-        }) => cmd::node::remove(keep_directories, peer_ids, service_names, verbosity).await,
+        }) => {
+            let drain_timeout = drain.then(|| Duration::from_secs(drain_timeout));
+            cmd::node::remove(
+                keep_directories,
+                drain_timeout,
+                peer_ids,
+                service_names,
+                verbosity,
+            )
+            .await
+        }
         Some(SubCmd::Reset { force }) => cmd::node::reset(force, verbosity).await,
         Some(SubCmd::Start {
             connection_timeout,
@@ -1367,9 +1439,14 @@ async fn main() -> Result<()> {
             json,
         }) => cmd::node::status(details, fail, json).await,
         Some(SubCmd::Stop {
+            drain,
+            drain_timeout,
             peer_id: peer_ids,
             service_name: service_names,
-        }) => cmd::node::stop(peer_ids, service_names, verbosity).await,
+        }) => {
+            let drain_timeout = drain.then(|| Duration::from_secs(drain_timeout));
+            cmd::node::stop(drain_timeout, peer_ids, service_names, verbosity).await
+        }
         Some(SubCmd::Upgrade {
             connection_timeout,
             do_not_start,
@@ -28,11 +28,19 @@ use sn_peers_acquisition::PeersArgs;
 use sn_releases::{ReleaseType, SafeReleaseRepoActions};
 use sn_service_management::{
     control::{ServiceControl, ServiceController},
-    rpc::RpcClient,
-    NodeRegistry, NodeService, ServiceStateActions, ServiceStatus, UpgradeOptions, UpgradeResult,
+    rpc::{DrainState, RpcActions, RpcClient},
+    NodeRegistry, NodeService, NodeServiceData, ServiceStateActions, ServiceStatus, UpgradeOptions,
+    UpgradeResult,
 };
 use sn_transfers::HotWallet;
-use std::{cmp::Ordering, io::Write, net::Ipv4Addr, path::PathBuf, str::FromStr, time::Duration};
+use std::{
+    cmp::Ordering,
+    io::Write,
+    net::{Ipv4Addr, Ipv6Addr},
+    path::PathBuf,
+    str::FromStr,
+    time::Duration,
+};
 use tracing::debug;
 
 /// Returns the added service names
@@ -50,8 +58,12 @@ pub async fn add(
     log_format: Option<LogFormat>,
     max_archived_log_files: Option<usize>,
     max_log_files: Option<usize>,
+    max_storage: Option<u64>,
+    max_download_rate: Option<u64>,
+    max_upload_rate: Option<u64>,
     metrics_port: Option<PortRange>,
     node_ip: Option<Ipv4Addr>,
+    node_ipv6: Option<Ipv6Addr>,
     node_port: Option<PortRange>,
     owner: Option<String>,
     peers_args: PeersArgs,
@@ -121,6 +133,8 @@ pub async fn add(
     // If the `safenode` binary we're using has `network-contacts` enabled (which is the case for released binaries),
     // it's fine if the service definition doesn't call `safenode` with a `--peer` argument.
     let is_first = peers_args.first;
+    let bootstrap_cache_path = peers_args.bootstrap_cache_path.clone();
+    let ignore_bootstrap_cache = peers_args.ignore_bootstrap_cache;
     let bootstrap_peers = match peers_args.get_peers_exclude_network_contacts().await {
         Ok(peers) => {
             info!("Obtained peers of length {}", peers.len());
@@ -141,6 +155,7 @@ pub async fn add(
     let options = AddNodeServiceOptions {
         auto_restart,
         auto_set_nat_flags,
+        bootstrap_cache_path,
         bootstrap_peers,
         count,
         delete_safenode_src: src_path.is_none(),
@@ -149,12 +164,17 @@ pub async fn add(
         env_variables,
         genesis: is_first,
         home_network,
+        ignore_bootstrap_cache,
         local,
         log_format,
         max_archived_log_files,
         max_log_files,
+        max_storage,
+        max_download_rate,
+        max_upload_rate,
         metrics_port,
         node_ip,
+        node_ipv6,
         node_port,
         owner,
         rewards_address,
@@ -222,8 +242,11 @@ pub async fn balance(
     Ok(())
 }
 
+/// Remove the services. With a `drain_timeout`, the running services have their records handed off
+/// to the peers taking them over and are stopped before being removed.
 pub async fn remove(
     keep_directories: bool,
+    drain_timeout: Option<Duration>,
     peer_ids: Vec<String>,
     service_names: Vec<String>,
     verbosity: VerbosityLevel,
@@ -231,7 +254,7 @@ pub async fn remove(
     if verbosity != VerbosityLevel::Minimal {
         print_banner("Remove Safenode Services");
     }
-    info!("Removing safe node services with keep_dirs=({keep_directories}) for: {peer_ids:?}, {service_names:?}");
+    info!("Removing safe node services with keep_dirs=({keep_directories}), drain_timeout=({drain_timeout:?}) for: {peer_ids:?}, {service_names:?}");
 
     let mut node_registry = NodeRegistry::load(&config::get_node_registry_path()?)?;
     refresh_node_registry(
@@ -256,11 +279,28 @@ pub async fn remove(
     let mut failed_services = Vec::new();
     for &index in &service_indices {
         let node = &mut node_registry.nodes[index];
+        let drain_and_stop = drain_timeout.is_some() && node.status == ServiceStatus::Running;
+        if let (true, Some(timeout)) = (drain_and_stop, drain_timeout) {
+            if let Err(err) = drain(node, timeout, verbosity).await {
+                error!("Failed to drain service {}: {err}", node.service_name);
+                failed_services.push((node.service_name.clone(), err.to_string()));
+                continue;
+            }
+        }
+
         let rpc_client = RpcClient::from_socket_addr(node.rpc_socket_addr);
         let service = NodeService::new(node, Box::new(rpc_client));
         let mut service_manager =
             ServiceManager::new(service, Box::new(ServiceController {}), verbosity);
-        match service_manager.remove(keep_directories).await {
+        let result = if drain_and_stop {
+            match service_manager.stop().await {
+                Ok(()) => service_manager.remove(keep_directories).await,
+                Err(err) => Err(err),
+            }
+        } else {
+            service_manager.remove(keep_directories).await
+        };
+        match result {
             Ok(()) => {
                 debug!("Removed service {}", node.service_name);
                 node_registry.save()?;
@@ -293,8 +333,8 @@ pub async fn reset(force: bool, verbosity: VerbosityLevel) -> Result<()> {
         }
     }
 
-    stop(vec![], vec![], verbosity).await?;
-    remove(false, vec![], vec![], verbosity).await?;
+    stop(None, vec![], vec![], verbosity).await?;
+    remove(false, None, vec![], vec![], verbosity).await?;
 
     // Due the possibility of repeated runs of the `reset` command, we need to check for the
     // existence of this file before attempting to delete it, since `remove_file` will return an
@@ -405,7 +445,10 @@ pub async fn status(details: bool, fail: bool, json: bool) -> Result<()> {
     Ok(())
 }
 
+/// Stop the services. With a `drain_timeout`, the running services have their records handed off
+/// to the peers taking them over before being stopped.
 pub async fn stop(
+    drain_timeout: Option<Duration>,
     peer_ids: Vec<String>,
     service_names: Vec<String>,
     verbosity: VerbosityLevel,
@@ -413,7 +456,7 @@ pub async fn stop(
     if verbosity != VerbosityLevel::Minimal {
         print_banner("Stop Safenode Services");
     }
-    info!("Stopping safenode services for: {peer_ids:?}, {service_names:?}");
+    info!("Stopping safenode services with drain_timeout=({drain_timeout:?}) for: {peer_ids:?}, {service_names:?}");
 
     let mut node_registry = NodeRegistry::load(&config::get_node_registry_path()?)?;
     refresh_node_registry(
@@ -438,6 +481,16 @@ pub async fn stop(
     let mut failed_services = Vec::new();
     for &index in &service_indices {
         let node = &mut node_registry.nodes[index];
+        if let Some(timeout) = drain_timeout {
+            if node.status == ServiceStatus::Running {
+                if let Err(err) = drain(node, timeout, verbosity).await {
+                    error!("Failed to drain service {}: {err}", node.service_name);
+                    failed_services.push((node.service_name.clone(), err.to_string()));
+                    continue;
+                }
+            }
+        }
+
         let rpc_client = RpcClient::from_socket_addr(node.rpc_socket_addr);
         let service = NodeService::new(node, Box::new(rpc_client));
         let mut service_manager =
@@ -457,6 +510,66 @@ pub async fn stop(
     summarise_any_failed_ops(failed_services, "stop", verbosity)
 }
 
+/// Interval between the checks of the progress of a node drain.
+const DRAIN_STATUS_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Have a running node hand off its records to the peers taking them over, waiting for the drain
+/// to complete. The node is left running, to be stopped by the caller.
+async fn drain(node: &NodeServiceData, timeout: Duration, verbosity: VerbosityLevel) -> Result<()> {
+    if verbosity != VerbosityLevel::Minimal {
+        println!("Draining {}...", node.service_name);
+    }
+    let rpc_client = RpcClient::from_socket_addr(node.rpc_socket_addr);
+    rpc_client.node_drain(timeout, false).await?;
+
+    // Give the node a little longer than the timeout to report its drain as completed.
+    let give_up_at = std::time::Instant::now() + timeout + DRAIN_STATUS_POLL_INTERVAL * 2;
+    let mut last_confirmed = None;
+    loop {
+        tokio::time::sleep(DRAIN_STATUS_POLL_INTERVAL).await;
+        let status = rpc_client.drain_status().await?;
+        debug!("Drain status of {}: {status:?}", node.service_name);
+
+        if verbosity != VerbosityLevel::Minimal && last_confirmed != Some(status.records_confirmed)
+        {
+            println!(
+                "  - {}/{} records handed off",
+                status.records_confirmed, status.records_total
+            );
+            last_confirmed = Some(status.records_confirmed);
+        }
+
+        if let DrainState::Failed(err) = &status.state {
+            return Err(eyre!("The drain of {} failed: {err}", node.service_name));
+        }
+        if status.state == DrainState::Completed {
+            if status.records_unconfirmed > 0 {
+                warn!(
+                    "{} records of {} were not confirmed as handed off",
+                    status.records_unconfirmed, node.service_name
+                );
+                if verbosity != VerbosityLevel::Minimal {
+                    println!(
+                        "{} {} records of {} could not be confirmed as handed off",
+                        "!".yellow(),
+                        status.records_unconfirmed,
+                        node.service_name
+                    );
+                }
+            } else if verbosity != VerbosityLevel::Minimal {
+                println!("{} Drained {}", "✓".green(), node.service_name);
+            }
+            return Ok(());
+        }
+        if std::time::Instant::now() >= give_up_at {
+            return Err(eyre!(
+                "The drain of {} did not complete within {timeout:?}",
+                node.service_name
+            ));
+        }
+    }
+}
+
 pub async fn upgrade(
     connection_timeout_s: u64,
     do_not_start: bool,
@@ -617,8 +730,12 @@ pub async fn maintain_n_running_nodes(
     log_format: Option<LogFormat>,
     max_archived_log_files: Option<usize>,
     max_log_files: Option<usize>,
+    max_storage: Option<u64>,
+    max_download_rate: Option<u64>,
+    max_upload_rate: Option<u64>,
     metrics_port: Option<PortRange>,
     node_ip: Option<Ipv4Addr>,
+    node_ipv6: Option<Ipv6Addr>,
     node_port: Option<PortRange>,
     owner: Option<String>,
     peers: PeersArgs,
@@ -662,7 +779,7 @@ pub async fn maintain_n_running_nodes(
                 "Stopping {} excess nodes: {:?}",
                 to_stop_count, services_to_stop
             );
-            stop(vec![], services_to_stop, verbosity).await?;
+            stop(None, vec![], services_to_stop, verbosity).await?;
         }
         Ordering::Less => {
             let to_start_count = target_count - running_count;
@@ -722,8 +839,12 @@ pub async fn maintain_n_running_nodes(
                         log_format,
                         max_archived_log_files,
                         max_log_files,
+                        max_storage,
+                        max_download_rate,
+                        max_upload_rate,
                         metrics_port.clone(),
                         node_ip,
+                        node_ipv6,
                         Some(PortRange::Single(port)),
                         owner.clone(),
                         peers.clone(),
@@ -19,6 +19,7 @@ pub mod rpc;
 pub mod rpc_client;
 
 pub const DEFAULT_NODE_STARTUP_CONNECTION_TIMEOUT_S: u64 = 300;
+pub const DEFAULT_NODE_DRAIN_TIMEOUT_S: u64 = 600;
 
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum VerbosityLevel {
@@ -42,6 +43,7 @@ use crate::error::{Error, Result};
 use colored::Colorize;
 use semver::Version;
 use sn_evm::AttoTokens;
+use sn_protocol::PrettyPrintRecordKey;
 use sn_service_management::rpc::RpcActions;
 use sn_service_management::{
     control::ServiceControl, error::Error as ServiceError, rpc::RpcClient, NodeRegistry,
@@ -425,6 +427,9 @@ pub async fn status_report(
                     .map_or("-".to_string(), |b| b.to_string())
             );
             println!("Rewards address: {}", node.rewards_address);
+            if node.status == ServiceStatus::Running {
+                print_rewards_ledger(node).await;
+            }
             println!();
         }
 
@@ -627,6 +632,47 @@ pub async fn refresh_node_registry(
     Ok(())
 }
 
+/// The number of most recent payments listed in the detailed status of a node.
+const RECENT_PAYMENTS_DISPLAYED: u64 = 5;
+
+/// Print the payments accepted by a running node, as recorded in its rewards ledger.
+async fn print_rewards_ledger(node: &NodeServiceData) {
+    let mut rpc_client = RpcClient::from_socket_addr(node.rpc_socket_addr);
+    rpc_client.set_max_attempts(1);
+    match rpc_client
+        .rewards_ledger(0, RECENT_PAYMENTS_DISPLAYED)
+        .await
+    {
+        Ok(ledger) => {
+            println!(
+                "Rewards earned: {} attos over {} payments",
+                ledger.total_earned.as_atto(),
+                ledger.payments_count
+            );
+            if !ledger.payments.is_empty() {
+                println!("Recent payments:");
+            }
+            for payment in ledger.payments {
+                let timestamp: chrono::DateTime<chrono::Utc> = payment.timestamp.into();
+                println!(
+                    "  {} - {} attos for record {} (tx {})",
+                    timestamp.format("%Y-%m-%d %H:%M:%S"),
+                    payment.amount.as_atto(),
+                    PrettyPrintRecordKey::from(&payment.record_key),
+                    payment.tx_hash
+                );
+            }
+        }
+        Err(err) => {
+            debug!(
+                "Failed to obtain the rewards ledger of {}: {err:?}",
+                node.service_name
+            );
+            println!("Rewards earned: -");
+        }
+    }
+}
+
 pub fn print_banner(text: &str) {
     let padding = 2;
     let text_width = text.len() + padding * 2;
@@ -673,7 +719,10 @@ mod tests {
     use sn_service_management::{
         error::{Error as ServiceControlError, Result as ServiceControlResult},
         node::{NodeService, NodeServiceData},
-        rpc::{NetworkInfo, NodeInfo, RecordAddress, RpcActions},
+        rpc::{
+            DrainStatus, NetworkInfo, NodeInfo, PeerReputation, RecordAddress, RecordStoreScrub,
+            RewardsLedger, RpcActions,
+        },
         UpgradeOptions, UpgradeResult,
     };
     use std::{
@@ -691,8 +740,13 @@ mod tests {
             async fn node_info(&self) -> ServiceControlResult<NodeInfo>;
             async fn network_info(&self) -> ServiceControlResult<NetworkInfo>;
             async fn record_addresses(&self) -> ServiceControlResult<Vec<RecordAddress>>;
+            async fn rewards_ledger(&self, offset: u64, limit: u64) -> ServiceControlResult<RewardsLedger>;
+            async fn record_store_scrub(&self) -> ServiceControlResult<RecordStoreScrub>;
+            async fn peer_reputations(&self) -> ServiceControlResult<Vec<PeerReputation>>;
             async fn node_restart(&self, delay_millis: u64, retain_peer_id: bool) -> ServiceControlResult<()>;
             async fn node_stop(&self, delay_millis: u64) -> ServiceControlResult<()>;
+            async fn node_drain(&self, timeout: std::time::Duration, stop_when_drained: bool) -> ServiceControlResult<()>;
+            async fn drain_status(&self) -> ServiceControlResult<DrainStatus>;
             async fn node_update(&self, delay_millis: u64) -> ServiceControlResult<()>;
             async fn is_node_connected_to_network(&self, timeout: std::time::Duration) -> ServiceControlResult<()>;
             async fn update_log_level(&self, log_levels: String) -> ServiceControlResult<()>;
@@ -756,6 +810,8 @@ mod tests {
                         "12D3KooWS2tpXGGTmg2AHFiDh57yPQnat49YHnyqoggzXZWpqkCR",
                     )?],
                     listeners: Vec::new(),
+                    estimated_network_size: 0,
+                    peers_in_routing_table: 0,
                 })
             });
 
@@ -780,8 +836,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -872,6 +934,8 @@ mod tests {
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    estimated_network_size: 0,
+                    peers_in_routing_table: 0,
                 })
             });
 
@@ -896,8 +960,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -977,8 +1047,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -1077,6 +1153,8 @@ mod tests {
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    estimated_network_size: 0,
+                    peers_in_routing_table: 0,
                 })
             });
 
@@ -1101,8 +1179,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -1195,8 +1279,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -1275,6 +1365,8 @@ mod tests {
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    estimated_network_size: 0,
+                    peers_in_routing_table: 0,
                 })
             });
 
@@ -1299,8 +1391,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -1378,6 +1476,8 @@ mod tests {
                         "12D3KooWS2tpXGGTmg2AHFiDh57yPQnat49YHnyqoggzXZWpqkCR",
                     )?],
                     listeners: Vec::new(),
+                    estimated_network_size: 0,
+                    peers_in_routing_table: 0,
                 })
             });
 
@@ -1402,8 +1502,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -1475,8 +1581,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -1538,8 +1650,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -1599,8 +1717,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -1663,8 +1787,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -1740,8 +1870,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -1856,6 +1992,8 @@ mod tests {
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    estimated_network_size: 0,
+                    peers_in_routing_table: 0,
                 })
             });
 
@@ -1880,8 +2018,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -1982,8 +2126,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -2105,6 +2255,8 @@ mod tests {
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    estimated_network_size: 0,
+                    peers_in_routing_table: 0,
                 })
             });
 
@@ -2129,8 +2281,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -2264,6 +2422,8 @@ mod tests {
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    estimated_network_size: 0,
+                    peers_in_routing_table: 0,
                 })
             });
 
@@ -2288,8 +2448,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -2442,8 +2608,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -2573,6 +2745,8 @@ mod tests {
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    estimated_network_size: 0,
+                    peers_in_routing_table: 0,
                 })
             });
 
@@ -2597,8 +2771,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -2759,6 +2939,8 @@ mod tests {
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    estimated_network_size: 0,
+                    peers_in_routing_table: 0,
                 })
             });
 
@@ -2775,8 +2957,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -2920,6 +3108,8 @@ mod tests {
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    estimated_network_size: 0,
+                    peers_in_routing_table: 0,
                 })
             });
 
@@ -2936,8 +3126,14 @@ mod tests {
             log_format: Some(LogFormat::Json),
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             owner: None,
             number: 1,
@@ -3084,6 +3280,8 @@ mod tests {
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    estimated_network_size: 0,
+                    peers_in_routing_table: 0,
                 })
             });
 
@@ -3100,8 +3298,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -3245,6 +3449,8 @@ mod tests {
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    estimated_network_size: 0,
+                    peers_in_routing_table: 0,
                 })
             });
 
@@ -3261,9 +3467,15 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             number: 1,
             node_ip: Some(Ipv4Addr::new(192, 168, 1, 1)),
+            node_ipv6: None,
             node_port: None,
             owner: None,
             peer_id: Some(PeerId::from_str(
@@ -3409,6 +3621,8 @@ mod tests {
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    estimated_network_size: 0,
+                    peers_in_routing_table: 0,
                 })
             });
 
@@ -3425,9 +3639,15 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             number: 1,
             node_ip: None,
+            node_ipv6: None,
             node_port: Some(12000),
             owner: None,
             peer_id: Some(PeerId::from_str(
@@ -3570,6 +3790,8 @@ mod tests {
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    estimated_network_size: 0,
+                    peers_in_routing_table: 0,
                 })
             });
 
@@ -3585,8 +3807,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: Some(20),
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -3734,6 +3962,8 @@ mod tests {
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    estimated_network_size: 0,
+                    peers_in_routing_table: 0,
                 })
             });
 
@@ -3749,8 +3979,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: Some(20),
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -3895,6 +4131,8 @@ mod tests {
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    estimated_network_size: 0,
+                    peers_in_routing_table: 0,
                 })
             });
 
@@ -3911,8 +4149,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: Some(12000),
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -4059,6 +4303,8 @@ mod tests {
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    estimated_network_size: 0,
+                    peers_in_routing_table: 0,
                 })
             });
 
@@ -4075,8 +4321,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: Some(12000),
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -4223,6 +4475,8 @@ mod tests {
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    estimated_network_size: 0,
+                    peers_in_routing_table: 0,
                 })
             });
 
@@ -4239,8 +4493,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: Some("discord_username".to_string()),
@@ -4387,6 +4647,8 @@ mod tests {
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    estimated_network_size: 0,
+                    peers_in_routing_table: 0,
                 })
             });
 
@@ -4403,8 +4665,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: Some("discord_username".to_string()),
@@ -4554,6 +4822,8 @@ mod tests {
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    estimated_network_size: 0,
+                    peers_in_routing_table: 0,
                 })
             });
 
@@ -4578,8 +4848,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: Some("discord_username".to_string()),
@@ -4726,6 +5002,8 @@ mod tests {
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    estimated_network_size: 0,
+                    peers_in_routing_table: 0,
                 })
             });
 
@@ -4742,8 +5020,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -4828,8 +5112,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -4899,8 +5189,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -4986,8 +5282,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -5065,8 +5367,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
@@ -5142,8 +5450,14 @@ mod tests {
             log_format: None,
             max_archived_log_files: None,
             max_log_files: None,
+            max_storage: None,
+            max_download_rate: None,
+            max_upload_rate: None,
+            bootstrap_cache_path: None,
+            ignore_bootstrap_cache: false,
             metrics_port: None,
             node_ip: None,
+            node_ipv6: None,
             node_port: None,
             number: 1,
             owner: None,
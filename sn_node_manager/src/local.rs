@@ -501,8 +501,14 @@ pub async fn run_node(
         log_format: run_options.log_format,
         max_archived_log_files: None,
         max_log_files: None,
+        max_storage: None,
+        max_download_rate: None,
+        max_upload_rate: None,
+        bootstrap_cache_path: None,
+        ignore_bootstrap_cache: false,
         metrics_port: run_options.metrics_port,
         node_ip: None,
+        node_ipv6: None,
         node_port: run_options.node_port,
         number: run_options.number,
         owner: run_options.owner,
@@ -593,7 +599,10 @@ mod tests {
     use sn_evm::utils::dummy_address;
     use sn_service_management::{
         error::Result as RpcResult,
-        rpc::{NetworkInfo, NodeInfo, RecordAddress, RpcActions},
+        rpc::{
+            DrainStatus, NetworkInfo, NodeInfo, PeerReputation, RecordAddress, RecordStoreScrub,
+            RewardsLedger, RpcActions,
+        },
     };
     use std::str::FromStr;
 
@@ -604,8 +613,13 @@ mod tests {
             async fn node_info(&self) -> RpcResult<NodeInfo>;
             async fn network_info(&self) -> RpcResult<NetworkInfo>;
             async fn record_addresses(&self) -> RpcResult<Vec<RecordAddress>>;
+            async fn rewards_ledger(&self, offset: u64, limit: u64) -> RpcResult<RewardsLedger>;
+            async fn record_store_scrub(&self) -> RpcResult<RecordStoreScrub>;
+            async fn peer_reputations(&self) -> RpcResult<Vec<PeerReputation>>;
             async fn node_restart(&self, delay_millis: u64, retain_peer_id: bool) -> RpcResult<()>;
             async fn node_stop(&self, delay_millis: u64) -> RpcResult<()>;
+            async fn node_drain(&self, timeout: std::time::Duration, stop_when_drained: bool) -> RpcResult<()>;
+            async fn drain_status(&self) -> RpcResult<DrainStatus>;
             async fn node_update(&self, delay_millis: u64) -> RpcResult<()>;
             async fn is_node_connected_to_network(&self, timeout: std::time::Duration) -> RpcResult<()>;
             async fn update_log_level(&self, log_levels: String) -> RpcResult<()>;
@@ -665,6 +679,8 @@ mod tests {
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    estimated_network_size: 0,
+                    peers_in_routing_table: 0,
                 })
             });
 
@@ -74,10 +74,16 @@ pub async fn restart_node_service(
             log_format: current_node_clone.log_format,
             max_archived_log_files: current_node_clone.max_archived_log_files,
             max_log_files: current_node_clone.max_log_files,
+            max_storage: current_node_clone.max_storage,
+            max_download_rate: current_node_clone.max_download_rate,
+            max_upload_rate: current_node_clone.max_upload_rate,
+            bootstrap_cache_path: current_node_clone.bootstrap_cache_path.clone(),
+            ignore_bootstrap_cache: current_node_clone.ignore_bootstrap_cache,
             metrics_port: None,
             owner: current_node_clone.owner.clone(),
             name: current_node_clone.service_name.clone(),
             node_ip: current_node_clone.node_ip,
+            node_ipv6: current_node_clone.node_ipv6,
             node_port: current_node_clone.get_safenode_port(),
             rewards_address: current_node_clone.rewards_address,
             rpc_socket_addr: current_node_clone.rpc_socket_addr,
@@ -195,8 +201,14 @@ pub async fn restart_node_service(
             name: new_service_name.clone(),
             max_archived_log_files: current_node_clone.max_archived_log_files,
             max_log_files: current_node_clone.max_log_files,
+            max_storage: current_node_clone.max_storage,
+            max_download_rate: current_node_clone.max_download_rate,
+            max_upload_rate: current_node_clone.max_upload_rate,
+            bootstrap_cache_path: current_node_clone.bootstrap_cache_path.clone(),
+            ignore_bootstrap_cache: current_node_clone.ignore_bootstrap_cache,
             metrics_port: None,
             node_ip: current_node_clone.node_ip,
+            node_ipv6: current_node_clone.node_ipv6,
             node_port: None,
             owner: None,
             rewards_address: current_node_clone.rewards_address,
@@ -223,8 +235,14 @@ pub async fn restart_node_service(
             log_format: current_node_clone.log_format,
             max_archived_log_files: current_node_clone.max_archived_log_files,
             max_log_files: current_node_clone.max_log_files,
+            max_storage: current_node_clone.max_storage,
+            max_download_rate: current_node_clone.max_download_rate,
+            max_upload_rate: current_node_clone.max_upload_rate,
+            bootstrap_cache_path: current_node_clone.bootstrap_cache_path.clone(),
+            ignore_bootstrap_cache: current_node_clone.ignore_bootstrap_cache,
             metrics_port: None,
             node_ip: current_node_clone.node_ip,
+            node_ipv6: current_node_clone.node_ipv6,
             node_port: None,
             number: new_node_number as u16,
             owner: None,
@@ -173,6 +173,16 @@ pub async fn network_info(addr: SocketAddr) -> Result<()> {
         println!("Listener: {multiaddr_str}");
     }
 
+    println!();
+    println!(
+        "Estimated network size: {} nodes",
+        network_info.estimated_network_size
+    );
+    println!(
+        "Peers in routing table: {}",
+        network_info.peers_in_routing_table
+    );
+
     Ok(())
 }
 
@@ -0,0 +1,320 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::error::{Error, Result};
+use fs2::FileExt;
+use libp2p::{multiaddr::Protocol, Multiaddr};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::*;
+
+/// The name of the bootstrap cache file within the data directory.
+const BOOTSTRAP_CACHE_FILE_NAME: &str = "bootstrap_cache.json";
+
+/// The max number of peers kept in the cache, the best scored ones being kept.
+const MAX_CACHED_PEERS: usize = 200;
+
+/// Peers not seen for this long are dropped from the cache.
+const MAX_PEER_AGE_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// The success score of a peer is capped, so a peer that was reliable for a long time doesn't
+/// outlive many failures.
+const MAX_SUCCESS_SCORE: i32 = 10;
+
+/// Peers failing to be reached so often that their score drops below this are dropped.
+const MIN_SUCCESS_SCORE: i32 = -3;
+
+/// A peer that has been reachable, stored in the bootstrap cache.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BootstrapPeer {
+    /// The address of the peer, ending with its peer ID.
+    pub addr: Multiaddr,
+    /// When the peer was last seen, as seconds since the UNIX epoch.
+    pub last_seen: u64,
+    /// The number of times the peer was reachable less the number of times it was not.
+    pub success_score: i32,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheFile {
+    peers: Vec<BootstrapPeer>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct PendingUpdate {
+    successes: i32,
+    failures: i32,
+    last_seen: Option<u64>,
+}
+
+/// A file caching the peers this node or client has been able to reach, so the next start can
+/// bootstrap from them rather than from the network contacts alone.
+///
+/// The updates are held in memory until [`BootstrapCache::sync`] merges them into the file.
+/// The file is shared by every node and client on the machine: the writers hold an exclusive
+/// lock while merging and the file is replaced atomically, so concurrent writers don't lose
+/// each other's updates and readers never see a partially written file.
+#[derive(Clone, Debug)]
+pub struct BootstrapCache {
+    path: PathBuf,
+    pending: HashMap<Multiaddr, PendingUpdate>,
+}
+
+impl BootstrapCache {
+    /// Creates a cache backed by the file at `path`, which is created on the first sync.
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// The path of the cache file shared by the nodes and clients of this machine.
+    pub fn default_path() -> Result<PathBuf> {
+        let data_dir = dirs_next::data_dir().ok_or(Error::CouldNotObtainDataDir)?;
+        Ok(data_dir.join("safe").join(BOOTSTRAP_CACHE_FILE_NAME))
+    }
+
+    /// The path of the cache file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Records that the peer could be reached at `addr`, which must end with its peer ID.
+    pub fn record_success(&mut self, addr: Multiaddr) {
+        if !ends_with_peer_id(&addr) {
+            return;
+        }
+        let update = self.pending.entry(addr).or_default();
+        update.successes += 1;
+        update.last_seen = Some(now_secs());
+    }
+
+    /// Records that the peer could not be reached at `addr`, which must end with its peer ID.
+    ///
+    /// The failures of peers not cached are dropped on sync, so dialing a bad peer doesn't make
+    /// it into the cache.
+    pub fn record_failure(&mut self, addr: Multiaddr) {
+        if !ends_with_peer_id(&addr) {
+            return;
+        }
+        self.pending.entry(addr).or_default().failures += 1;
+    }
+
+    /// Whether there are updates to be merged into the file.
+    pub fn has_pending_updates(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Moves the pending updates into a new cache, to be synced without holding up this one.
+    pub fn take_pending_updates(&mut self) -> Self {
+        Self {
+            path: self.path.clone(),
+            pending: std::mem::take(&mut self.pending),
+        }
+    }
+
+    /// Returns the addresses of the cached peers, the most reliable and most recently seen first.
+    ///
+    /// A missing cache file holds no peers.
+    pub fn read_peers(&self) -> Result<Vec<Multiaddr>> {
+        let lock = self.lock_file()?;
+        lock.lock_shared()?;
+        let cache_file = read_cache_file(&self.path);
+        let _ = lock.unlock();
+
+        let mut peers = cache_file?.peers;
+        prune(&mut peers, now_secs());
+        Ok(peers.into_iter().map(|peer| peer.addr).collect())
+    }
+
+    /// Merges the pending updates into the file, dropping the stale and unreachable peers.
+    ///
+    /// This blocks on the file lock and IO, so should be run off the async runtime.
+    pub fn sync(&mut self) -> Result<()> {
+        let lock = self.lock_file()?;
+        lock.lock_exclusive()?;
+        let result = self.merge_into_file();
+        let _ = lock.unlock();
+        result
+    }
+
+    fn merge_into_file(&mut self) -> Result<()> {
+        let mut peers = match read_cache_file(&self.path) {
+            Ok(cache_file) => cache_file.peers,
+            Err(Error::BootstrapCacheSerialization(err)) => {
+                warn!(
+                    "Discarding the corrupt bootstrap cache at {:?}: {err}",
+                    self.path
+                );
+                vec![]
+            }
+            Err(err) => return Err(err),
+        };
+
+        let mut pending = std::mem::take(&mut self.pending);
+        for peer in peers.iter_mut() {
+            if let Some(update) = pending.remove(&peer.addr) {
+                peer.success_score = (peer.success_score + update.successes - update.failures)
+                    .min(MAX_SUCCESS_SCORE);
+                if let Some(last_seen) = update.last_seen {
+                    peer.last_seen = peer.last_seen.max(last_seen);
+                }
+            }
+        }
+        for (addr, update) in pending {
+            if let Some(last_seen) = update.last_seen {
+                peers.push(BootstrapPeer {
+                    addr,
+                    last_seen,
+                    success_score: (update.successes - update.failures).min(MAX_SUCCESS_SCORE),
+                });
+            }
+        }
+        prune(&mut peers, now_secs());
+
+        let contents = serde_json::to_vec_pretty(&CacheFile { peers })?;
+        let tmp_path = self.path.with_extension("json.tmp");
+        let mut tmp_file = File::create(&tmp_path)?;
+        tmp_file.write_all(&contents)?;
+        tmp_file.sync_all()?;
+        fs::rename(&tmp_path, &self.path)?;
+        debug!("Synced the bootstrap cache at {:?}", self.path);
+        Ok(())
+    }
+
+    /// The lock is held on a file next to the cache, as the cache file itself gets replaced.
+    fn lock_file(&self) -> Result<File> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let lock_file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.path.with_extension("json.lock"))?;
+        Ok(lock_file)
+    }
+}
+
+fn read_cache_file(path: &Path) -> Result<CacheFile> {
+    match fs::read(path) {
+        Ok(contents) => Ok(serde_json::from_slice(&contents)?),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(CacheFile::default()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Drops the stale and unreachable peers, keeping the best [`MAX_CACHED_PEERS`] of the others.
+fn prune(peers: &mut Vec<BootstrapPeer>, now: u64) {
+    peers.retain(|peer| {
+        peer.success_score >= MIN_SUCCESS_SCORE
+            && now.saturating_sub(peer.last_seen) <= MAX_PEER_AGE_SECS
+    });
+    peers.sort_by(|a, b| {
+        b.success_score
+            .cmp(&a.success_score)
+            .then(b.last_seen.cmp(&a.last_seen))
+    });
+    peers.truncate(MAX_CACHED_PEERS);
+}
+
+fn ends_with_peer_id(addr: &Multiaddr) -> bool {
+    matches!(addr.iter().last(), Some(Protocol::P2p(_)))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libp2p::PeerId;
+
+    fn peer_addr(port: u16) -> Multiaddr {
+        format!("/ip4/1.2.3.4/udp/{port}/quic-v1/p2p/{}", PeerId::random())
+            .parse()
+            .expect("valid multiaddr")
+    }
+
+    fn temp_cache_path() -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("bootstrap_cache_test_{}", rand::random::<u64>()))
+            .join(BOOTSTRAP_CACHE_FILE_NAME)
+    }
+
+    #[test]
+    fn synced_peers_are_read_back_best_scored_first() -> Result<()> {
+        let path = temp_cache_path();
+        let (reliable, flaky, unknown) = (peer_addr(1), peer_addr(2), peer_addr(3));
+
+        // two writers sharing the same file
+        let mut first = BootstrapCache::new(path.clone());
+        first.record_success(flaky.clone());
+        first.record_success(reliable.clone());
+        first.record_success(reliable.clone());
+        first.sync()?;
+
+        let mut second = BootstrapCache::new(path.clone());
+        second.record_failure(flaky.clone());
+        second.record_failure(unknown.clone());
+        // addresses without a peer ID are useless for bootstrapping
+        second.record_success(
+            "/ip4/1.2.3.4/udp/4/quic-v1"
+                .parse()
+                .expect("valid multiaddr"),
+        );
+        second.sync()?;
+
+        assert_eq!(second.read_peers()?, vec![reliable, flaky.clone()]);
+
+        // enough failures drop the peer
+        for _ in 0..4 {
+            second.record_failure(flaky.clone());
+        }
+        second.sync()?;
+        assert!(!second.read_peers()?.contains(&flaky));
+
+        let _ = fs::remove_dir_all(path.parent().expect("has a parent"));
+        Ok(())
+    }
+
+    #[test]
+    fn stale_and_excess_peers_are_pruned() {
+        let now = now_secs();
+        let mut peers: Vec<_> = (0..MAX_CACHED_PEERS as u16 + 10)
+            .map(|port| BootstrapPeer {
+                addr: peer_addr(port),
+                last_seen: now - u64::from(port),
+                success_score: 1,
+            })
+            .collect();
+        let stale = BootstrapPeer {
+            addr: peer_addr(0),
+            last_seen: now - MAX_PEER_AGE_SECS - 1,
+            success_score: MAX_SUCCESS_SCORE,
+        };
+        peers.insert(0, stale.clone());
+
+        prune(&mut peers, now);
+        assert_eq!(peers.len(), MAX_CACHED_PEERS);
+        assert!(!peers.contains(&stale));
+        // the most recently seen are kept among the equally scored
+        assert_eq!(peers[0].last_seen, now);
+    }
+}
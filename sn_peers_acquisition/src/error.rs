@@ -4,6 +4,13 @@ pub type Result<T, E = Error> = std::result::Result<T, E>;
 
 #[derive(Debug, Error)]
 pub enum Error {
+    #[error("Could not obtain the data directory to store the bootstrap cache in")]
+    CouldNotObtainDataDir,
+    #[error("Could not access the bootstrap cache: {0}")]
+    BootstrapCacheIo(#[from] std::io::Error),
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error("Could not parse the bootstrap cache: {0}")]
+    BootstrapCacheSerialization(#[from] serde_json::Error),
     #[error("Could not parse the supplied multiaddr or socket address")]
     InvalidPeerAddr(#[from] libp2p::multiaddr::Error),
     #[error("Could not obtain network contacts from {0} after {1} retries")]
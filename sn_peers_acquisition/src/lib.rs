@@ -6,8 +6,13 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+#[cfg(not(target_arch = "wasm32"))]
+mod bootstrap_cache;
 pub mod error;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub use bootstrap_cache::{BootstrapCache, BootstrapPeer};
+
 use crate::error::{Error, Result};
 use clap::Args;
 #[cfg(feature = "network-contacts")]
@@ -15,7 +20,7 @@ use lazy_static::lazy_static;
 use libp2p::{multiaddr::Protocol, Multiaddr};
 use rand::{seq::SliceRandom, thread_rng};
 use reqwest::Client;
-use std::time::Duration;
+use std::{path::PathBuf, time::Duration};
 use tracing::*;
 use url::Url;
 
@@ -60,6 +65,17 @@ pub struct PeersArgs {
     #[cfg(feature = "network-contacts")]
     #[clap(long, conflicts_with = "first")]
     pub network_contacts_url: Option<Url>,
+
+    /// Specify the path of the bootstrap cache, holding the peers reached in previous runs.
+    ///
+    /// The cache is shared by the nodes and clients on this machine and is located in the data
+    /// directory by default.
+    #[clap(long, value_name = "path", conflicts_with = "first")]
+    pub bootstrap_cache_path: Option<PathBuf>,
+
+    /// Set to not bootstrap from the peers held in the bootstrap cache.
+    #[clap(long, conflicts_with = "first")]
+    pub ignore_bootstrap_cache: bool,
 }
 
 impl PeersArgs {
@@ -71,7 +87,9 @@ impl PeersArgs {
     /// * The `--peer` argument.
     /// * The `SAFE_PEERS` environment variable.
     /// * Using the `local` feature, which will return an empty peer list.
-    /// * Using the `network-contacts` feature, which will download the peer list from a file on S3.
+    /// * The bootstrap cache, unless `--ignore-bootstrap-cache` is used, followed by the peers
+    ///   obtained through the `network-contacts` feature, which will download the peer list from a
+    ///   file on S3. Failing to download the list is not an error if the cache held peers.
     ///
     /// Note: the current behaviour is that `--peer` and `SAFE_PEERS` will be combined. Some tests
     /// currently rely on this. We will change it soon.
@@ -113,22 +131,86 @@ impl PeersArgs {
         } else if skip_network_contacts {
             info!("Skipping network contacts");
             return Ok(vec![]);
-        } else if cfg!(feature = "network-contacts") {
-            self.get_network_contacts().await?
         } else {
-            vec![]
+            let cached_peers = self.get_bootstrap_cache_peers().await;
+            let mut contacts = if cfg!(feature = "network-contacts") {
+                match self.get_network_contacts().await {
+                    Ok(contacts) => contacts,
+                    Err(err) if !cached_peers.is_empty() => {
+                        warn!("Bootstrapping from the cached peers only, as the network contacts could not be obtained: {err}");
+                        vec![]
+                    }
+                    Err(err) => return Err(err),
+                }
+            } else {
+                vec![]
+            };
+            // The cached peers were reachable before, so they are tried first, best scored first.
+            contacts.shuffle(&mut thread_rng());
+            return Self::checked_peers(cached_peers.into_iter().chain(contacts).collect());
         };
 
+        // Randomly sort peers before we return them to avoid overly hitting any one peer
+        let mut rng = thread_rng();
+        peers.shuffle(&mut rng);
+
+        Self::checked_peers(peers)
+    }
+
+    fn checked_peers(peers: Vec<Multiaddr>) -> Result<Vec<Multiaddr>> {
         if peers.is_empty() {
             error!("Peers not obtained through any available options");
             return Err(Error::PeersNotObtained);
         };
+        Ok(peers)
+    }
 
-        // Randomly sort peers before we return them to avoid overly hitting any one peer
-        let mut rng = thread_rng();
-        peers.shuffle(&mut rng);
+    /// The path of the bootstrap cache, the shared one in the data directory unless
+    /// `--bootstrap-cache-path` is used. None if `--ignore-bootstrap-cache` is used.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn bootstrap_cache_path(&self) -> Result<Option<PathBuf>> {
+        if self.ignore_bootstrap_cache {
+            return Ok(None);
+        }
+        match &self.bootstrap_cache_path {
+            Some(path) => Ok(Some(path.clone())),
+            None => BootstrapCache::default_path().map(Some),
+        }
+    }
 
-        Ok(peers)
+    /// The peers held in the bootstrap cache, read off the async runtime as it blocks on the
+    /// file lock shared with the other nodes and clients.
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn get_bootstrap_cache_peers(&self) -> Vec<Multiaddr> {
+        let path = match self.bootstrap_cache_path() {
+            Ok(Some(path)) => path,
+            Ok(None) => return vec![],
+            Err(err) => {
+                warn!("Not bootstrapping from the bootstrap cache: {err}");
+                return vec![];
+            }
+        };
+        let cache = BootstrapCache::new(path);
+        match tokio::task::spawn_blocking(move || cache.read_peers()).await {
+            Ok(Ok(peers)) => {
+                info!("Obtained {} peers from the bootstrap cache", peers.len());
+                peers
+            }
+            Ok(Err(err)) => {
+                warn!("Could not read the bootstrap cache: {err}");
+                vec![]
+            }
+            Err(err) => {
+                warn!("Reading the bootstrap cache did not complete: {err}");
+                vec![]
+            }
+        }
+    }
+
+    #[expect(clippy::unused_async)]
+    #[cfg(target_arch = "wasm32")]
+    async fn get_bootstrap_cache_peers(&self) -> Vec<Multiaddr> {
+        vec![]
     }
 
     // should not be reachable, but needed for the compiler to be happy.
@@ -152,7 +234,18 @@ impl PeersArgs {
 }
 
 /// Parse strings like `1.2.3.4:1234` and `/ip4/1.2.3.4/tcp/1234` into a multiaddr.
+///
+/// A socket address is turned into a QUIC multiaddr, unless prefixed with `tcp://` for the TCP
+/// transport, e.g. `tcp://1.2.3.4:1234`.
 pub fn parse_peer_addr(addr: &str) -> std::result::Result<Multiaddr, libp2p::multiaddr::Error> {
+    // Parse a TCP socket address, e.g. `tcp://1.2.3.4:1234`.
+    if let Some(tcp_addr) = addr.strip_prefix("tcp://") {
+        let Ok(tcp_addr) = tcp_addr.parse::<std::net::SocketAddrV4>() else {
+            return Err(libp2p::multiaddr::Error::InvalidMultiaddr);
+        };
+        return Ok(Multiaddr::from(*tcp_addr.ip()).with(Protocol::Tcp(tcp_addr.port())));
+    }
+
     // Parse valid IPv4 socket address, e.g. `1.2.3.4:1234`.
     if let Ok(addr) = addr.parse::<std::net::SocketAddrV4>() {
         let start_addr = Multiaddr::from(*addr.ip());
@@ -240,3 +333,27 @@ pub async fn get_peers_from_url(url: Url) -> Result<Vec<Multiaddr>> {
         tokio::time::sleep(Duration::from_secs(1)).await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn socket_addresses_are_parsed_for_the_transport() -> Result<()> {
+        #[cfg(not(feature = "websockets"))]
+        assert_eq!(
+            parse_peer_addr("1.2.3.4:1234")?,
+            "/ip4/1.2.3.4/udp/1234/quic-v1".parse::<Multiaddr>()?
+        );
+        assert_eq!(
+            parse_peer_addr("tcp://1.2.3.4:1234")?,
+            "/ip4/1.2.3.4/tcp/1234".parse::<Multiaddr>()?
+        );
+        assert_eq!(
+            parse_peer_addr("/ip4/1.2.3.4/tcp/1234")?,
+            "/ip4/1.2.3.4/tcp/1234".parse::<Multiaddr>()?
+        );
+        assert!(parse_peer_addr("tcp://1.2.3.4").is_err());
+        Ok(())
+    }
+}
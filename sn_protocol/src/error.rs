@@ -24,6 +24,8 @@ pub enum Error {
     CouldNotObtainPortFromMultiAddr,
     #[error("Could not parse RetryStrategy")]
     ParseRetryStrategyError,
+    #[error("Could not parse storage size {0:?}, expected a value such as 500MB or 200GB")]
+    ParseStorageSizeError(String),
     #[error("Could not obtain data dir")]
     CouldNotObtainDataDir,
 
@@ -12,6 +12,7 @@ mod cmd;
 mod node_id;
 mod query;
 mod register;
+mod replication_summary;
 mod response;
 
 pub use self::{
@@ -20,6 +21,7 @@ pub use self::{
     node_id::NodeId,
     query::Query,
     register::RegisterCmd,
+    replication_summary::{BucketDigest, ReplicationSummary},
     response::{CmdResponse, QueryResponse},
 };
 
@@ -53,6 +55,19 @@ impl Request {
             Request::Query(query) => query.dst(),
         }
     }
+
+    /// The wire protocol version the request is supported since.
+    pub fn since_protocol_version(&self) -> u16 {
+        match self {
+            Request::Cmd(cmd) => cmd.since_protocol_version(),
+            Request::Query(query) => query.since_protocol_version(),
+        }
+    }
+
+    /// Whether a peer speaking `peer_version` supports the request, it shall not be sent it otherwise.
+    pub fn is_supported_by(&self, peer_version: u16) -> bool {
+        peer_version >= self.since_protocol_version()
+    }
 }
 
 impl std::fmt::Display for Response {
@@ -7,7 +7,12 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 #![allow(clippy::mutable_key_type)] // for Bytes in NetworkAddress
 
-use crate::{storage::RecordType, NetworkAddress};
+use super::ReplicationSummary;
+use crate::{
+    storage::RecordType,
+    version::{MIN_PROTOCOL_VERSION, REPLICATION_SUMMARY_PROTOCOL_VERSION},
+    NetworkAddress,
+};
 use serde::{Deserialize, Serialize};
 pub use sn_evm::PaymentQuote;
 
@@ -28,6 +33,17 @@ pub enum Cmd {
         /// Keys of copy that shall be replicated.
         keys: Vec<(NetworkAddress, RecordType)>,
     },
+    /// Write operation to notify peer of a summary of the keys held by the holder.
+    ///
+    /// The peer responds with the buckets of the summary that differ from its own keys,
+    /// and the holder follows up with a [`Cmd::Replicate`] carrying only the keys of those buckets.
+    /// Peers on older versions don't support it and get sent the full [`Cmd::Replicate`] list instead.
+    ReplicateSummary {
+        /// Holder of the replication keys.
+        holder: NetworkAddress,
+        /// Summary of the keys held.
+        summary: ReplicationSummary,
+    },
     /// Write operation to notify nodes a list of PaymentQuote collected.
     QuoteVerification {
         target: NetworkAddress,
@@ -52,6 +68,11 @@ impl std::fmt::Debug for Cmd {
                     .field("first_ten_keys", &first_ten_keys)
                     .finish()
             }
+            Cmd::ReplicateSummary { holder, summary } => f
+                .debug_struct("Cmd::ReplicateSummary")
+                .field("holder", holder)
+                .field("summary", summary)
+                .finish(),
             Cmd::QuoteVerification { target, quotes } => f
                 .debug_struct("Cmd::QuoteVerification")
                 .field("target", target)
@@ -76,10 +97,25 @@ impl Cmd {
     pub fn dst(&self) -> NetworkAddress {
         match self {
             Cmd::Replicate { holder, .. } => holder.clone(),
+            Cmd::ReplicateSummary { holder, .. } => holder.clone(),
             Cmd::QuoteVerification { target, .. } => target.clone(),
             Cmd::PeerConsideredAsBad { bad_peer, .. } => bad_peer.clone(),
         }
     }
+
+    /// The wire protocol version the cmd is supported since. Peers speaking an older version
+    /// shall not be sent it.
+    ///
+    /// Each variant is tied to the version introducing it, so a new variant must bump
+    /// [`crate::version::MAX_PROTOCOL_VERSION`] and be listed here with it.
+    pub fn since_protocol_version(&self) -> u16 {
+        match self {
+            Cmd::ReplicateSummary { .. } => REPLICATION_SUMMARY_PROTOCOL_VERSION,
+            Cmd::Replicate { .. }
+            | Cmd::QuoteVerification { .. }
+            | Cmd::PeerConsideredAsBad { .. } => MIN_PROTOCOL_VERSION,
+        }
+    }
 }
 
 impl std::fmt::Display for Cmd {
@@ -93,6 +129,15 @@ impl std::fmt::Display for Cmd {
                     keys.len()
                 )
             }
+            Cmd::ReplicateSummary { holder, summary } => {
+                write!(
+                    f,
+                    "Cmd::ReplicateSummary({:?} has {} keys in {} buckets)",
+                    holder.as_peer_id(),
+                    summary.keys_count(),
+                    summary.buckets.len()
+                )
+            }
             Cmd::QuoteVerification { target, quotes } => {
                 write!(
                     f,
@@ -112,3 +157,20 @@ impl std::fmt::Display for Cmd {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::Request;
+    use libp2p::PeerId;
+
+    #[test]
+    fn replicate_summary_is_not_sent_to_v1_peers() {
+        let holder = NetworkAddress::from_peer(PeerId::random());
+        let summary = ReplicationSummary::new(&holder, std::iter::empty(), None);
+        let request = Request::Cmd(Cmd::ReplicateSummary { holder, summary });
+
+        assert!(!request.is_supported_by(MIN_PROTOCOL_VERSION));
+        assert!(request.is_supported_by(REPLICATION_SUMMARY_PROTOCOL_VERSION));
+    }
+}
@@ -6,7 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::{messages::Nonce, NetworkAddress};
+use crate::{messages::Nonce, version::MIN_PROTOCOL_VERSION, NetworkAddress};
 use serde::{Deserialize, Serialize};
 
 /// Data queries - retrieving data and inspecting their structure.
@@ -64,6 +64,21 @@ impl Query {
             | Query::GetChunkExistenceProof { key, .. } => key.clone(),
         }
     }
+
+    /// The wire protocol version the query is supported since. Peers speaking an older version
+    /// shall not be sent it.
+    ///
+    /// Each variant is tied to the version introducing it, so a new variant must bump
+    /// [`crate::version::MAX_PROTOCOL_VERSION`] and be listed here with it.
+    pub fn since_protocol_version(&self) -> u16 {
+        match self {
+            Query::GetStoreCost(_)
+            | Query::GetReplicatedRecord { .. }
+            | Query::GetRegisterRecord { .. }
+            | Query::GetChunkExistenceProof { .. }
+            | Query::CheckNodeInProblem(_) => MIN_PROTOCOL_VERSION,
+        }
+    }
 }
 
 impl std::fmt::Display for Query {
@@ -0,0 +1,280 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{storage::RecordType, NetworkAddress};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt};
+
+/// The number of keys aimed for in each bucket of a summary.
+/// The fewer, the smaller the difference sent for a mismatching bucket, but the larger the summary.
+const TARGET_KEYS_PER_BUCKET: usize = 32;
+
+/// The max number of leading distance bits used to bucket the keys, i.e. at most 64K buckets.
+const MAX_BUCKET_BITS: u8 = 16;
+
+/// A compact summary of the record keys held by a node, used to reconcile replication key sets.
+///
+/// Only the keys within the holder's replication range are summarised, the ones out of it are still listed.
+/// The keys are split into buckets by the leading bits of their distance to the holder,
+/// each bucket summarised by the number of keys in it and an order independent digest of them.
+/// The receiver builds the same buckets over its own keys within that range and only asks for
+/// the keys of the buckets that differ, instead of the holder sending its whole key list.
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ReplicationSummary {
+    /// The `ilog2` of the largest distance to the holder of the keys summarised,
+    /// `None` if all the keys held are summarised.
+    pub range: Option<u32>,
+    /// The number of leading distance bits used to bucket the keys.
+    pub bucket_bits: u8,
+    /// The digests of the non empty buckets, sorted by bucket.
+    pub buckets: Vec<BucketDigest>,
+}
+
+/// The digest of the keys in one bucket of a [`ReplicationSummary`].
+#[derive(Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BucketDigest {
+    /// The leading distance bits shared by the keys of the bucket.
+    pub bucket: u32,
+    /// The number of keys in the bucket.
+    pub keys_count: u32,
+    /// The XOR of the hashes of the keys (and their record types) in the bucket.
+    pub digest: [u8; 32],
+}
+
+impl ReplicationSummary {
+    /// Summarise the keys held by `holder` within `range`, sizing the buckets after the number of keys.
+    pub fn new<'a>(
+        holder: &NetworkAddress,
+        keys: impl Iterator<Item = &'a (NetworkAddress, RecordType)>,
+        range: Option<u32>,
+    ) -> Self {
+        let keys: Vec<_> = keys
+            .filter(|(addr, _)| Self::is_in_range(holder, addr, range))
+            .collect();
+        let buckets_needed = keys.len().div_ceil(TARGET_KEYS_PER_BUCKET).max(1);
+        let bucket_bits =
+            (buckets_needed.next_power_of_two().trailing_zeros() as u8).min(MAX_BUCKET_BITS);
+        Self::with_bucket_bits(holder, keys.into_iter(), range, bucket_bits)
+    }
+
+    /// Summarise the keys held by `holder` within `range` into `2^bucket_bits` buckets.
+    pub fn with_bucket_bits<'a>(
+        holder: &NetworkAddress,
+        keys: impl Iterator<Item = &'a (NetworkAddress, RecordType)>,
+        range: Option<u32>,
+        bucket_bits: u8,
+    ) -> Self {
+        let bucket_bits = bucket_bits.min(MAX_BUCKET_BITS);
+        let mut buckets: BTreeMap<u32, BucketDigest> = BTreeMap::new();
+        for (addr, record_type) in keys.filter(|(addr, _)| Self::is_in_range(holder, addr, range)) {
+            let bucket = Self::bucket_of(holder, addr, bucket_bits);
+            let entry = buckets.entry(bucket).or_insert(BucketDigest {
+                bucket,
+                keys_count: 0,
+                digest: [0; 32],
+            });
+            entry.keys_count += 1;
+            for (byte, key_byte) in entry
+                .digest
+                .iter_mut()
+                .zip(key_digest(addr, record_type).iter())
+            {
+                *byte ^= key_byte;
+            }
+        }
+
+        Self {
+            range,
+            bucket_bits,
+            buckets: buckets.into_values().collect(),
+        }
+    }
+
+    /// Whether a key is within the `ilog2` distance `range` of `holder`, i.e. gets summarised.
+    pub fn is_in_range(holder: &NetworkAddress, key: &NetworkAddress, range: Option<u32>) -> bool {
+        let Some(range) = range else {
+            return true;
+        };
+        holder
+            .as_kbucket_key()
+            .distance(&key.as_kbucket_key())
+            .ilog2()
+            .is_none_or(|distance| distance <= range)
+    }
+
+    /// The bucket a key falls in, given the leading distance bits used by a summary of `holder`.
+    pub fn bucket_of(holder: &NetworkAddress, key: &NetworkAddress, bucket_bits: u8) -> u32 {
+        if bucket_bits == 0 {
+            return 0;
+        }
+        let holder_key = holder.as_kbucket_key();
+        let key = key.as_kbucket_key();
+        let mut leading_bytes = [0; 4];
+        for (byte, (a, b)) in leading_bytes.iter_mut().zip(
+            holder_key
+                .hashed_bytes()
+                .iter()
+                .zip(key.hashed_bytes().iter()),
+        ) {
+            *byte = a ^ b;
+        }
+        u32::from_be_bytes(leading_bytes) >> (32 - bucket_bits.min(MAX_BUCKET_BITS) as u32)
+    }
+
+    /// The buckets of this summary that hold keys which may be missing or outdated in `local_keys`.
+    ///
+    /// Only the local keys within the range of the summary are compared,
+    /// the ones out of the holder's range would otherwise always mismatch.
+    pub fn mismatched_buckets<'a>(
+        &self,
+        holder: &NetworkAddress,
+        local_keys: impl Iterator<Item = &'a (NetworkAddress, RecordType)>,
+    ) -> Vec<u32> {
+        let local = Self::with_bucket_bits(holder, local_keys, self.range, self.bucket_bits);
+        let local_buckets: BTreeMap<u32, &BucketDigest> = local
+            .buckets
+            .iter()
+            .map(|digest| (digest.bucket, digest))
+            .collect();
+
+        self.buckets
+            .iter()
+            .filter(|digest| local_buckets.get(&digest.bucket) != Some(digest))
+            .map(|digest| digest.bucket)
+            .collect()
+    }
+
+    /// The number of keys summarised.
+    pub fn keys_count(&self) -> usize {
+        self.buckets
+            .iter()
+            .map(|digest| digest.keys_count as usize)
+            .sum()
+    }
+}
+
+fn key_digest(addr: &NetworkAddress, record_type: &RecordType) -> [u8; 32] {
+    use tiny_keccak::{Hasher, Sha3};
+
+    let mut sha3 = Sha3::v256();
+    let mut output = [0; 32];
+    sha3.update(&addr.as_bytes());
+    match record_type {
+        RecordType::Chunk => sha3.update(&[0]),
+        RecordType::Scratchpad => sha3.update(&[1]),
+        RecordType::NonChunk(content_hash) => {
+            sha3.update(&[2]);
+            sha3.update(&content_hash.0);
+        }
+    }
+    sha3.finalize(&mut output);
+    output
+}
+
+impl fmt::Debug for ReplicationSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReplicationSummary")
+            .field("range", &self.range)
+            .field("bucket_bits", &self.bucket_bits)
+            .field("buckets_len", &self.buckets.len())
+            .field("keys_count", &self.keys_count())
+            .finish()
+    }
+}
+
+impl fmt::Debug for BucketDigest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BucketDigest")
+            .field("bucket", &self.bucket)
+            .field("keys_count", &self.keys_count)
+            .field("digest", &hex::encode(self.digest))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libp2p::PeerId;
+    use xor_name::XorName;
+
+    fn random_keys(count: usize) -> Vec<(NetworkAddress, RecordType)> {
+        (0..count)
+            .map(|_| {
+                (
+                    NetworkAddress::from_peer(PeerId::random()),
+                    RecordType::Chunk,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn identical_key_sets_have_no_mismatched_buckets() {
+        let holder = NetworkAddress::from_peer(PeerId::random());
+        let keys = random_keys(1000);
+
+        let summary = ReplicationSummary::new(&holder, keys.iter(), None);
+        assert_eq!(summary.keys_count(), 1000);
+        assert!(summary.bucket_bits > 0);
+
+        let mut shuffled = keys.clone();
+        shuffled.reverse();
+        assert!(summary
+            .mismatched_buckets(&holder, shuffled.iter())
+            .is_empty());
+    }
+
+    #[test]
+    fn only_buckets_with_differences_mismatch() {
+        let holder = NetworkAddress::from_peer(PeerId::random());
+        let mut keys = random_keys(1000);
+        let summary = ReplicationSummary::new(&holder, keys.iter(), None);
+
+        // the receiver misses one key and holds an outdated version of another one
+        let (missing, _) = keys.remove(0);
+        let (outdated, _) = keys[0].clone();
+        keys[0].1 = RecordType::NonChunk(XorName::from_content(b"updated content"));
+
+        let mismatched = summary.mismatched_buckets(&holder, keys.iter());
+        let expected: Vec<_> = [missing, outdated]
+            .iter()
+            .map(|key| ReplicationSummary::bucket_of(&holder, key, summary.bucket_bits))
+            .collect();
+
+        assert!(!mismatched.is_empty() && mismatched.len() <= 2);
+        for bucket in expected {
+            assert!(mismatched.contains(&bucket));
+        }
+    }
+
+    #[test]
+    fn keys_out_of_the_summary_range_are_not_compared() {
+        let holder = NetworkAddress::from_peer(PeerId::random());
+        let keys = random_keys(2000);
+        let range = Some(254);
+        let summary = ReplicationSummary::new(&holder, keys.iter(), range);
+
+        let (in_range, out_of_range): (Vec<_>, Vec<_>) = keys
+            .into_iter()
+            .partition(|(addr, _)| ReplicationSummary::is_in_range(&holder, addr, range));
+        assert!(!in_range.is_empty() && !out_of_range.is_empty());
+        assert_eq!(summary.keys_count(), in_range.len());
+
+        // the receiver holds the keys in range, plus others the holder isn't responsible for
+        let mut local_keys = in_range;
+        local_keys.extend(
+            random_keys(1000)
+                .into_iter()
+                .filter(|(addr, _)| !ReplicationSummary::is_in_range(&holder, addr, range)),
+        );
+        assert!(summary
+            .mismatched_buckets(&holder, local_keys.iter())
+            .is_empty());
+    }
+}
@@ -124,6 +124,9 @@ pub enum CmdResponse {
     //
     /// Response to replication cmd
     Replicate(Result<()>),
+    /// Response to replication summary cmd, with the buckets of the summary that differ from
+    /// the keys held by the responder
+    ReplicateSummary(Result<Vec<u32>>),
     //
     // ===== QuoteVerification =====
     //
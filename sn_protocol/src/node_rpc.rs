@@ -25,6 +25,13 @@ pub enum NodeCtrl {
     },
     // Request to update the safenode app, and restart it, after the requested delay.
     Update(Duration),
+    /// Request to hand off the records held by the node to the peers taking over their responsibility,
+    /// giving up on the records not confirmed within `timeout`. Set `stop_when_drained` to `true` to stop
+    /// the execution of the safenode app once done.
+    Drain {
+        timeout: Duration,
+        stop_when_drained: bool,
+    },
 }
 
 #[derive(Debug)]
@@ -77,3 +77,112 @@ impl fmt::Display for RetryStrategy {
         write!(f, "{self:?}")
     }
 }
+
+/// An amount of storage space in bytes, e.g. the capacity a node is willing to offer.
+///
+/// Parses from plain byte counts (`1073741824`) or values with a decimal (`KB`, `MB`, `GB`, `TB`)
+/// or binary (`KiB`, `MiB`, `GiB`, `TiB`) unit suffix, such as `200GB` or `1.5TiB`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StorageSize(pub u64);
+
+impl StorageSize {
+    pub const KB: u64 = 1000;
+    pub const MB: u64 = 1000 * Self::KB;
+    pub const GB: u64 = 1000 * Self::MB;
+    pub const TB: u64 = 1000 * Self::GB;
+
+    pub const fn from_gb(gb: u64) -> Self {
+        Self(gb * Self::GB)
+    }
+
+    pub const fn as_bytes(&self) -> u64 {
+        self.0
+    }
+}
+
+impl FromStr for StorageSize {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        let split_at = trimmed
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(trimmed.len());
+        let (value, unit) = trimmed.split_at(split_at);
+
+        let multiplier: u64 = match unit.trim().to_lowercase().as_str() {
+            "" | "b" => 1,
+            "kb" | "k" => Self::KB,
+            "mb" | "m" => Self::MB,
+            "gb" | "g" => Self::GB,
+            "tb" | "t" => Self::TB,
+            "kib" => 1 << 10,
+            "mib" => 1 << 20,
+            "gib" => 1 << 30,
+            "tib" => 1 << 40,
+            _ => return Err(Error::ParseStorageSizeError(s.to_string())),
+        };
+
+        let bytes = if let Ok(whole) = value.parse::<u64>() {
+            whole.checked_mul(multiplier)
+        } else {
+            value
+                .parse::<f64>()
+                .ok()
+                .filter(|v| v.is_finite() && *v >= 0.0)
+                .map(|v| (v * multiplier as f64) as u64)
+        };
+
+        bytes
+            .map(StorageSize)
+            .ok_or_else(|| Error::ParseStorageSizeError(s.to_string()))
+    }
+}
+
+impl fmt::Display for StorageSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bytes = self.0;
+        let units = [
+            ("TB", Self::TB),
+            ("GB", Self::GB),
+            ("MB", Self::MB),
+            ("KB", Self::KB),
+            ("TiB", 1 << 40),
+            ("GiB", 1 << 30),
+            ("MiB", 1 << 20),
+            ("KiB", 1 << 10),
+        ];
+        for (unit, size) in units {
+            if bytes >= size && bytes % size == 0 {
+                return write!(f, "{}{unit}", bytes / size);
+            }
+        }
+        write!(f, "{bytes}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn storage_size_should_parse_units() {
+        assert_eq!("1024".parse::<StorageSize>().unwrap(), StorageSize(1024));
+        assert_eq!(
+            "200GB".parse::<StorageSize>().unwrap(),
+            StorageSize(200_000_000_000)
+        );
+        assert_eq!("1.5 kb".parse::<StorageSize>().unwrap(), StorageSize(1500));
+        assert_eq!("2GiB".parse::<StorageSize>().unwrap(), StorageSize(2 << 30));
+        assert!("200XB".parse::<StorageSize>().is_err());
+        assert!("GB".parse::<StorageSize>().is_err());
+    }
+
+    #[test]
+    fn storage_size_display_should_round_trip() {
+        for value in ["200GB", "35GB", "1TB", "32GiB", "1234"] {
+            let size: StorageSize = value.parse().unwrap();
+            assert_eq!(size.to_string(), value);
+        }
+    }
+}
@@ -25,7 +25,7 @@ pub struct Scratchpad {
     address: ScratchpadAddress,
     /// Data encoding: custom apps using scratchpad should use this so they can identify the type of data they are storing
     data_encoding: u64,
-    /// Contained data. This should be encrypted
+    /// Contained data. This should be encrypted, unless the owner deliberately stores it in plaintext
     #[debug(skip)]
     encrypted_data: Bytes,
     /// Monotonically increasing counter to track the number of times this has been updated.
@@ -68,11 +68,24 @@ impl Scratchpad {
     ///
     /// Encrypts data and updates the signature with provided sk
     pub fn update_and_sign(&mut self, unencrypted_data: Bytes, sk: &SecretKey) -> u64 {
-        let next_count = self.increment();
-
         let pk = self.owner();
+        let encrypted_data = Bytes::from(pk.encrypt(unencrypted_data).to_bytes());
 
-        self.encrypted_data = Bytes::from(pk.encrypt(unencrypted_data).to_bytes());
+        self.store_and_sign(encrypted_data, sk)
+    }
+
+    /// Returns the next counter value,
+    ///
+    /// Stores the data as is, without encrypting it, and updates the signature with provided sk
+    /// Anyone can read such data through [`Scratchpad::unencrypted_data`]
+    pub fn update_and_sign_unencrypted(&mut self, data: Bytes, sk: &SecretKey) -> u64 {
+        self.store_and_sign(data, sk)
+    }
+
+    fn store_and_sign(&mut self, data: Bytes, sk: &SecretKey) -> u64 {
+        let next_count = self.increment();
+
+        self.encrypted_data = data;
 
         let encrypted_data_xorname = self.encrypted_data_hash().to_vec();
 
@@ -111,6 +124,16 @@ impl Scratchpad {
         Ok(Bytes::from(bytes))
     }
 
+    /// Whether the data is encrypted, as opposed to stored in plaintext via [`Scratchpad::update_and_sign_unencrypted`]
+    pub fn is_encrypted(&self) -> bool {
+        Ciphertext::from_bytes(&self.encrypted_data).is_ok()
+    }
+
+    /// Returns the data stored without encryption via [`Scratchpad::update_and_sign_unencrypted`]
+    pub fn unencrypted_data(&self) -> &Bytes {
+        &self.encrypted_data
+    }
+
     /// Returns the encrypted_data hash
     pub fn encrypted_data_hash(&self) -> XorName {
         XorName::from_content(&self.encrypted_data)
@@ -153,5 +176,18 @@ mod tests {
         let mut scratchpad = Scratchpad::new(pk, 42);
         scratchpad.update_and_sign(Bytes::from_static(b"data to be encrypted"), &sk);
         assert!(scratchpad.is_valid());
+        assert!(scratchpad.is_encrypted());
+    }
+
+    #[test]
+    fn test_scratchpad_unencrypted_is_valid() {
+        let sk = SecretKey::random();
+        let pk = sk.public_key();
+        let mut scratchpad = Scratchpad::new(pk, 42);
+        let data = Bytes::from_static(b"data in plaintext");
+        assert_eq!(scratchpad.update_and_sign_unencrypted(data.clone(), &sk), 1);
+        assert!(scratchpad.is_valid());
+        assert_eq!(scratchpad.unencrypted_data(), &data);
+        assert!(!scratchpad.is_encrypted());
     }
 }
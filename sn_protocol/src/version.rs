@@ -9,32 +9,83 @@
 use lazy_static::lazy_static;
 use sn_transfers::{FOUNDATION_PK, GENESIS_PK, NETWORK_ROYALTIES_PK};
 
+/// The oldest wire protocol version spoken, the peers only speaking older ones being refused.
+/// It is the one of the peers predating the version negotiation, which only advertise the legacy
+/// req/response protocol.
+pub const MIN_PROTOCOL_VERSION: u16 = 1;
+
+/// The newest wire protocol version spoken. It is bumped whenever a message is added, with the
+/// message declaring it as the version it is supported since.
+pub const MAX_PROTOCOL_VERSION: u16 = 3;
+
+/// The wire protocol version introducing `Cmd::ReplicateSummary`.
+pub const REPLICATION_SUMMARY_PROTOCOL_VERSION: u16 = 2;
+
+/// The wire protocol version quoting the storage in bytes, the peers on older versions being
+/// quoted by record count.
+pub const STORAGE_BYTES_QUOTE_PROTOCOL_VERSION: u16 = 3;
+
+/// A range of wire protocol versions, both ends included.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProtocolVersionRange {
+    pub min: u16,
+    pub max: u16,
+}
+
+impl ProtocolVersionRange {
+    /// The range of wire protocol versions spoken by this build.
+    pub const SUPPORTED: Self = Self {
+        min: MIN_PROTOCOL_VERSION,
+        max: MAX_PROTOCOL_VERSION,
+    };
+
+    /// The highest version within both ranges, if they overlap.
+    pub fn negotiate(&self, other: &Self) -> Option<u16> {
+        let highest_common = self.max.min(other.max);
+        (highest_common >= self.min.max(other.min)).then_some(highest_common)
+    }
+}
+
 lazy_static! {
     /// The node version used during Identify Behaviour.
     pub static ref IDENTIFY_NODE_VERSION_STR: String =
         format!(
-            "safe/node/{}/{}",
+            "{IDENTIFY_NODE_VERSION_PREFIX}{}/{}",
             get_truncate_version_str(),
             get_key_version_str(),
         );
 
-    /// The client version used during Identify Behaviour.
+    /// The client version used during Identify Behaviour, advertising the range of wire protocol
+    /// versions supported, as the clients don't advertise the req/response protocols they speak.
     pub static ref IDENTIFY_CLIENT_VERSION_STR: String =
         format!(
-            "safe/client/{}/{}",
+            "safe/client/{}/{}/v{MIN_PROTOCOL_VERSION}-v{MAX_PROTOCOL_VERSION}",
             get_truncate_version_str(),
             get_key_version_str(),
         );
 
-    /// The req/response protocol version
-    pub static ref REQ_RESPONSE_VERSION_STR: String =
+    /// The req/response protocol of the peers predating the version negotiation, which speak
+    /// [`MIN_PROTOCOL_VERSION`].
+    pub static ref LEGACY_REQ_RESPONSE_VERSION_STR: String =
         format!(
             "/safe/node/{}/{}",
             get_truncate_version_str(),
             get_key_version_str(),
         );
 
-    /// The identify protocol version
+    /// The req/response protocols, one per wire protocol version supported, the newest first
+    /// so the highest version supported by both sides gets negotiated. The legacy protocol
+    /// comes last, for the peers predating the version negotiation to still reach us.
+    pub static ref REQ_RESPONSE_VERSION_STRS: Vec<String> =
+        (MIN_PROTOCOL_VERSION..=MAX_PROTOCOL_VERSION)
+            .rev()
+            .map(req_response_version_str)
+            .chain(std::iter::once(LEGACY_REQ_RESPONSE_VERSION_STR.clone()))
+            .collect();
+
+    /// The identify protocol version, the peers advertising another one being refused.
+    /// It is left as the peers predating the version negotiation expect it, the range of wire
+    /// protocol versions supported being advertised through the req/response protocols.
     pub static ref IDENTIFY_PROTOCOL_STR: String =
         format!(
             "safe/{}/{}",
@@ -43,6 +94,77 @@ lazy_static! {
         );
 }
 
+const IDENTIFY_NODE_VERSION_PREFIX: &str = "safe/node/";
+
+/// The req/response protocol of a wire protocol version.
+pub fn req_response_version_str(version: u16) -> String {
+    format!("/safe/node/v{version}/{}", get_key_version_str())
+}
+
+/// Parses the range of wire protocol versions advertised by a peer through Identify: the
+/// versioned req/response protocols listed by a node, or the range ending the agent version of a
+/// client. The peers predating the version negotiation advertise neither and only speak
+/// [`MIN_PROTOCOL_VERSION`].
+/// None if the peer is on another network or release, or advertises a malformed range.
+pub fn parse_identify_info<'a>(
+    identify_protocol: &str,
+    agent_version: &str,
+    protocols: impl IntoIterator<Item = &'a str>,
+) -> Option<ProtocolVersionRange> {
+    if identify_protocol != IDENTIFY_PROTOCOL_STR.as_str() {
+        return None;
+    }
+
+    if let Some((_, range)) = agent_version
+        .strip_prefix("safe/client/")
+        .and_then(|version| version.rsplit_once("/v"))
+    {
+        let (min, max) = range.split_once("-v")?;
+        let range = ProtocolVersionRange {
+            min: min.parse().ok()?,
+            max: max.parse().ok()?,
+        };
+        return (range.min <= range.max).then_some(range);
+    }
+
+    let key_suffix = format!("/{}", get_key_version_str());
+    let versions: Vec<u16> = protocols
+        .into_iter()
+        .filter_map(|protocol| {
+            protocol
+                .strip_prefix("/safe/node/v")?
+                .strip_suffix(key_suffix.as_str())?
+                .parse()
+                .ok()
+        })
+        .collect();
+    let min = versions.iter().min().copied();
+    let max = versions.iter().max().copied();
+    Some(ProtocolVersionRange {
+        min: min.unwrap_or(MIN_PROTOCOL_VERSION),
+        max: max.unwrap_or(MIN_PROTOCOL_VERSION),
+    })
+}
+
+/// The wire protocol version to speak with a peer, out of its Identify info: the highest version
+/// supported by both. None if there is none.
+pub fn negotiate_protocol_version<'a>(
+    identify_protocol: &str,
+    agent_version: &str,
+    protocols: impl IntoIterator<Item = &'a str>,
+) -> Option<u16> {
+    ProtocolVersionRange::SUPPORTED.negotiate(&parse_identify_info(
+        identify_protocol,
+        agent_version,
+        protocols,
+    )?)
+}
+
+/// Whether the Identify agent version is the one of a node, rather than of a client.
+pub fn is_node_agent_version(agent_version: &str) -> bool {
+    agent_version.starts_with(IDENTIFY_NODE_VERSION_PREFIX)
+}
+
 // Protocol support shall be downward compatible for patch only version update.
 // i.e. versions of `A.B.X` or `A.B.X-alpha.Y` shall be considered as a same protocol of `A.B`
 fn get_truncate_version_str() -> String {
@@ -67,3 +189,139 @@ pub fn get_key_version_str() -> String {
     let _ = n_k_str.split_off(6);
     format!("{f_k_str}_{g_k_str}_{n_k_str}")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn versioned_protocols(min: u16, max: u16) -> Vec<String> {
+        (min..=max).rev().map(req_response_version_str).collect()
+    }
+
+    fn negotiate_with_node(protocols: &[String]) -> Option<u16> {
+        negotiate_protocol_version(
+            &IDENTIFY_PROTOCOL_STR,
+            &IDENTIFY_NODE_VERSION_STR,
+            protocols.iter().map(String::as_str),
+        )
+    }
+
+    #[test]
+    fn the_highest_common_protocol_version_is_negotiated() {
+        let range = |min, max| ProtocolVersionRange { min, max };
+        assert_eq!(range(1, 3).negotiate(&range(2, 5)), Some(3));
+        assert_eq!(range(2, 5).negotiate(&range(1, 3)), Some(3));
+        assert_eq!(range(1, 2).negotiate(&range(2, 2)), Some(2));
+        assert_eq!(range(1, 2).negotiate(&range(3, 4)), None);
+
+        assert_eq!(
+            negotiate_with_node(&REQ_RESPONSE_VERSION_STRS),
+            Some(MAX_PROTOCOL_VERSION)
+        );
+        assert_eq!(
+            negotiate_protocol_version(
+                &IDENTIFY_PROTOCOL_STR,
+                &IDENTIFY_CLIENT_VERSION_STR,
+                std::iter::empty()
+            ),
+            Some(MAX_PROTOCOL_VERSION)
+        );
+        let newer_peer = versioned_protocols(MAX_PROTOCOL_VERSION, MAX_PROTOCOL_VERSION + 3);
+        assert_eq!(negotiate_with_node(&newer_peer), Some(MAX_PROTOCOL_VERSION));
+        let too_new_peer = versioned_protocols(MAX_PROTOCOL_VERSION + 1, MAX_PROTOCOL_VERSION + 3);
+        assert_eq!(negotiate_with_node(&too_new_peer), None);
+
+        // peers on another network are refused
+        let other_network = "safe/0.17/000000_000000_000000";
+        assert_eq!(
+            negotiate_protocol_version(
+                other_network,
+                &IDENTIFY_NODE_VERSION_STR,
+                REQ_RESPONSE_VERSION_STRS.iter().map(String::as_str)
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn legacy_peers_negotiate_the_first_protocol_version() {
+        // the identify string is the one the peers predating the version negotiation expect
+        assert_eq!(
+            *IDENTIFY_PROTOCOL_STR,
+            format!(
+                "safe/{}/{}",
+                get_truncate_version_str(),
+                get_key_version_str()
+            )
+        );
+
+        let legacy_peer = [LEGACY_REQ_RESPONSE_VERSION_STR.clone()];
+        assert_eq!(
+            parse_identify_info(
+                &IDENTIFY_PROTOCOL_STR,
+                &IDENTIFY_NODE_VERSION_STR,
+                legacy_peer.iter().map(String::as_str)
+            ),
+            Some(ProtocolVersionRange {
+                min: MIN_PROTOCOL_VERSION,
+                max: MIN_PROTOCOL_VERSION,
+            })
+        );
+        assert_eq!(
+            negotiate_with_node(&legacy_peer),
+            Some(MIN_PROTOCOL_VERSION)
+        );
+        let legacy_client = format!(
+            "safe/client/{}/{}",
+            get_truncate_version_str(),
+            get_key_version_str()
+        );
+        assert_eq!(
+            negotiate_protocol_version(&IDENTIFY_PROTOCOL_STR, &legacy_client, std::iter::empty()),
+            Some(MIN_PROTOCOL_VERSION)
+        );
+        let malformed_client = format!("{legacy_client}/v1-vx");
+        assert_eq!(
+            negotiate_protocol_version(
+                &IDENTIFY_PROTOCOL_STR,
+                &malformed_client,
+                std::iter::empty()
+            ),
+            None
+        );
+
+        // the legacy req/response protocol is still spoken, after the versioned ones
+        assert_eq!(
+            REQ_RESPONSE_VERSION_STRS.last(),
+            Some(&*LEGACY_REQ_RESPONSE_VERSION_STR)
+        );
+        assert_eq!(
+            REQ_RESPONSE_VERSION_STRS.first(),
+            Some(&req_response_version_str(MAX_PROTOCOL_VERSION))
+        );
+    }
+
+    #[test]
+    fn peers_of_another_release_are_refused() {
+        let version = get_truncate_version_str();
+        let (major, minor) = version.split_once('.').expect("a major.minor version");
+        let next_minor: u64 = minor.parse::<u64>().expect("a numeric minor version") + 1;
+        let other_release = format!("safe/{major}.{next_minor}/{}", get_key_version_str());
+        assert_eq!(
+            negotiate_protocol_version(
+                &other_release,
+                &IDENTIFY_NODE_VERSION_STR,
+                [LEGACY_REQ_RESPONSE_VERSION_STR.as_str()]
+            ),
+            None
+        );
+        assert_eq!(
+            negotiate_protocol_version(
+                &other_release,
+                &IDENTIFY_NODE_VERSION_STR,
+                REQ_RESPONSE_VERSION_STRS.iter().map(String::as_str)
+            ),
+            None
+        );
+    }
+}
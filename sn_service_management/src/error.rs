@@ -38,10 +38,18 @@ pub enum Error {
     RpcNodeRestartError(String),
     #[error("Could not stop node through RPC: {0}")]
     RpcNodeStopError(String),
+    #[error("Could not drain node through RPC: {0}")]
+    RpcNodeDrainError(String),
     #[error("Could not update node through RPC: {0}")]
     RpcNodeUpdateError(String),
     #[error("Could not obtain record addresses through RPC: {0}")]
     RpcRecordAddressError(String),
+    #[error("Could not obtain the rewards ledger through RPC: {0}")]
+    RpcRewardsLedgerError(String),
+    #[error("Could not obtain the record store scrub stats through RPC: {0}")]
+    RpcRecordStoreScrubError(String),
+    #[error("Could not obtain the peer reputations through RPC: {0}")]
+    RpcPeerReputationsError(String),
     #[error("Could not find process at '{0}'")]
     ServiceProcessNotFound(String),
     #[error("The service '{0}' does not exists and cannot be removed.")]
@@ -16,7 +16,7 @@ use sn_logging::LogFormat;
 use sn_protocol::get_port_from_multiaddr;
 use std::{
     ffi::OsString,
-    net::{Ipv4Addr, SocketAddr},
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr},
     path::PathBuf,
     str::FromStr,
     time::Duration,
@@ -93,6 +93,11 @@ impl<'a> ServiceStateActions for NodeService<'a> {
             args.push(OsString::from(node_ip.to_string()));
         }
 
+        if let Some(node_ipv6) = self.service_data.node_ipv6 {
+            args.push(OsString::from("--ip"));
+            args.push(OsString::from(node_ipv6.to_string()));
+        }
+
         if let Some(node_port) = self.service_data.node_port {
             args.push(OsString::from("--port"));
             args.push(OsString::from(node_port.to_string()));
@@ -109,6 +114,27 @@ impl<'a> ServiceStateActions for NodeService<'a> {
             args.push(OsString::from("--max-log-files"));
             args.push(OsString::from(max_log_files.to_string()));
         }
+        if let Some(max_storage) = self.service_data.max_storage {
+            args.push(OsString::from("--max-storage"));
+            args.push(OsString::from(max_storage.to_string()));
+        }
+        if let Some(max_download_rate) = self.service_data.max_download_rate {
+            args.push(OsString::from("--max-download-rate"));
+            args.push(OsString::from(max_download_rate.to_string()));
+        }
+        if let Some(max_upload_rate) = self.service_data.max_upload_rate {
+            args.push(OsString::from("--max-upload-rate"));
+            args.push(OsString::from(max_upload_rate.to_string()));
+        }
+        if let Some(bootstrap_cache_path) = &self.service_data.bootstrap_cache_path {
+            args.push(OsString::from("--bootstrap-cache-path"));
+            args.push(OsString::from(
+                bootstrap_cache_path.to_string_lossy().to_string(),
+            ));
+        }
+        if self.service_data.ignore_bootstrap_cache {
+            args.push(OsString::from("--ignore-bootstrap-cache"));
+        }
 
         if let Some(owner) = &self.service_data.owner {
             args.push(OsString::from("--owner"));
@@ -294,12 +320,24 @@ pub struct NodeServiceData {
     pub max_archived_log_files: Option<usize>,
     pub max_log_files: Option<usize>,
     #[serde(default)]
+    pub max_storage: Option<u64>,
+    #[serde(default)]
+    pub max_download_rate: Option<u64>,
+    #[serde(default)]
+    pub max_upload_rate: Option<u64>,
+    #[serde(default)]
+    pub bootstrap_cache_path: Option<PathBuf>,
+    #[serde(default)]
+    pub ignore_bootstrap_cache: bool,
+    #[serde(default)]
     pub metrics_port: Option<u16>,
     #[serde(default)]
     pub owner: Option<String>,
     #[serde(default)]
     pub node_ip: Option<Ipv4Addr>,
     #[serde(default)]
+    pub node_ipv6: Option<Ipv6Addr>,
+    #[serde(default)]
     pub node_port: Option<u16>,
     pub number: u16,
     #[serde(
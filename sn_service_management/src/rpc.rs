@@ -9,14 +9,22 @@
 use crate::error::{Error, Result};
 use async_trait::async_trait;
 use libp2p::{kad::RecordKey, Multiaddr, PeerId};
+use sn_evm::{Amount, AttoTokens, QuoteHash, TxHash};
 use sn_protocol::{
     safenode_proto::{
-        safe_node_client::SafeNodeClient, NetworkInfoRequest, NodeInfoRequest,
-        RecordAddressesRequest, RestartRequest, StopRequest, UpdateLogLevelRequest, UpdateRequest,
+        drain_status_response, safe_node_client::SafeNodeClient, DrainRequest, DrainStatusRequest,
+        NetworkInfoRequest, NodeInfoRequest, PeerReputationsRequest, RecordAddressesRequest,
+        RecordStoreScrubRequest, RestartRequest, RewardsLedgerRequest, StopRequest,
+        UpdateLogLevelRequest, UpdateRequest,
     },
     CLOSE_GROUP_SIZE,
 };
-use std::{net::SocketAddr, path::PathBuf, str::FromStr};
+use std::{
+    net::SocketAddr,
+    path::PathBuf,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
 use tokio::time::Duration;
 use tonic::Request;
 use tracing::error;
@@ -36,6 +44,8 @@ pub struct NodeInfo {
 pub struct NetworkInfo {
     pub connected_peers: Vec<PeerId>,
     pub listeners: Vec<Multiaddr>,
+    pub estimated_network_size: u64,
+    pub peers_in_routing_table: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -43,13 +53,75 @@ pub struct RecordAddress {
     pub key: RecordKey,
 }
 
+/// The payments accepted by a node, as recorded in its rewards ledger.
+#[derive(Debug, Clone)]
+pub struct RewardsLedger {
+    pub total_earned: AttoTokens,
+    pub payments_count: u64,
+    /// The requested page of the payments, the most recent first.
+    pub payments: Vec<RewardPayment>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RewardPayment {
+    pub quote_hash: QuoteHash,
+    pub tx_hash: TxHash,
+    pub record_key: RecordKey,
+    pub amount: AttoTokens,
+    pub timestamp: SystemTime,
+}
+
+/// The outcome of the periodic re-reads and verifications of the records stored by a node.
+#[derive(Debug, Clone)]
+pub struct RecordStoreScrub {
+    pub records_checked: u64,
+    /// The records found corrupt, which got removed and re-fetched.
+    pub corrupt_records: u64,
+    pub passes_completed: u64,
+}
+
+/// The reputation a node holds of one of the peers it interacted with.
+#[derive(Debug, Clone)]
+pub struct PeerReputation {
+    pub peer_id: PeerId,
+    /// From -100 to 100, 0 being a peer the node knows nothing about.
+    pub score: f64,
+    /// Whether the node considers the peer bad, and thus blocks it.
+    pub is_bad: bool,
+}
+
+/// The stage a drain of a node is at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DrainState {
+    Idle,
+    HandingOff,
+    Confirming,
+    Completed,
+    /// The drain was aborted, with the error reported by the node.
+    Failed(String),
+}
+
+/// The progress of the hand off of the records held by a node to the peers taking them over.
+#[derive(Debug, Clone)]
+pub struct DrainStatus {
+    pub state: DrainState,
+    pub records_total: u64,
+    pub records_confirmed: u64,
+    pub records_unconfirmed: u64,
+}
+
 #[async_trait]
 pub trait RpcActions: Sync {
     async fn node_info(&self) -> Result<NodeInfo>;
     async fn network_info(&self) -> Result<NetworkInfo>;
     async fn record_addresses(&self) -> Result<Vec<RecordAddress>>;
+    async fn rewards_ledger(&self, offset: u64, limit: u64) -> Result<RewardsLedger>;
+    async fn record_store_scrub(&self) -> Result<RecordStoreScrub>;
+    async fn peer_reputations(&self) -> Result<Vec<PeerReputation>>;
     async fn node_restart(&self, delay_millis: u64, retain_peer_id: bool) -> Result<()>;
     async fn node_stop(&self, delay_millis: u64) -> Result<()>;
+    async fn node_drain(&self, timeout: Duration, stop_when_drained: bool) -> Result<()>;
+    async fn drain_status(&self) -> Result<DrainStatus>;
     async fn node_update(&self, delay_millis: u64) -> Result<()>;
     async fn is_node_connected_to_network(&self, timeout: Duration) -> Result<()>;
     async fn update_log_level(&self, log_levels: String) -> Result<()>;
@@ -168,6 +240,8 @@ impl RpcActions for RpcClient {
         Ok(NetworkInfo {
             connected_peers,
             listeners,
+            estimated_network_size: network_info.estimated_network_size,
+            peers_in_routing_table: network_info.peers_in_routing_table as usize,
         })
     }
 
@@ -188,6 +262,82 @@ impl RpcActions for RpcClient {
         Ok(record_addresses)
     }
 
+    async fn rewards_ledger(&self, offset: u64, limit: u64) -> Result<RewardsLedger> {
+        let mut client = self.connect_with_retry().await?;
+        let response = client
+            .rewards_ledger(Request::new(RewardsLedgerRequest { offset, limit }))
+            .await
+            .map_err(|e| {
+                error!("Could not obtain the rewards ledger through RPC: {e:?}");
+                Error::RpcRewardsLedgerError(e.to_string())
+            })?;
+        let response = response.get_ref();
+
+        let parse_amount = |amount: &str| {
+            Amount::from_str(amount)
+                .map(AttoTokens::from_atto)
+                .map_err(|e| Error::RpcRewardsLedgerError(format!("Invalid amount {amount}: {e}")))
+        };
+        let mut payments = vec![];
+        for payment in response.payments.iter() {
+            payments.push(RewardPayment {
+                quote_hash: QuoteHash::try_from(payment.quote_hash.as_slice()).map_err(|e| {
+                    Error::RpcRewardsLedgerError(format!("Invalid quote hash: {e}"))
+                })?,
+                tx_hash: TxHash::try_from(payment.tx_hash.as_slice())
+                    .map_err(|e| Error::RpcRewardsLedgerError(format!("Invalid tx hash: {e}")))?,
+                record_key: RecordKey::from(payment.record_key.clone()),
+                amount: parse_amount(&payment.amount)?,
+                timestamp: UNIX_EPOCH + Duration::from_secs(payment.timestamp_secs),
+            });
+        }
+
+        Ok(RewardsLedger {
+            total_earned: parse_amount(&response.total_earned)?,
+            payments_count: response.payments_count,
+            payments,
+        })
+    }
+
+    async fn record_store_scrub(&self) -> Result<RecordStoreScrub> {
+        let mut client = self.connect_with_retry().await?;
+        let response = client
+            .record_store_scrub(Request::new(RecordStoreScrubRequest {}))
+            .await
+            .map_err(|e| {
+                error!("Could not obtain the record store scrub stats through RPC: {e:?}");
+                Error::RpcRecordStoreScrubError(e.to_string())
+            })?;
+        let response = response.get_ref();
+        Ok(RecordStoreScrub {
+            records_checked: response.records_checked,
+            corrupt_records: response.corrupt_records,
+            passes_completed: response.passes_completed,
+        })
+    }
+
+    async fn peer_reputations(&self) -> Result<Vec<PeerReputation>> {
+        let mut client = self.connect_with_retry().await?;
+        let response = client
+            .peer_reputations(Request::new(PeerReputationsRequest {}))
+            .await
+            .map_err(|e| {
+                error!("Could not obtain the peer reputations through RPC: {e:?}");
+                Error::RpcPeerReputationsError(e.to_string())
+            })?;
+        let mut reputations = vec![];
+        for peer in response.get_ref().peers.iter() {
+            let peer_id = PeerId::from_bytes(&peer.peer_id)
+                .map_err(|e| Error::RpcPeerReputationsError(format!("Invalid peer id: {e}")))?;
+            reputations.push(PeerReputation {
+                peer_id,
+                score: peer.score,
+                is_bad: peer.is_bad,
+            });
+        }
+        Ok(reputations)
+    }
+
     async fn node_restart(&self, delay_millis: u64, retain_peer_id: bool) -> Result<()> {
         let mut client = self.connect_with_retry().await?;
         let _response = client
@@ -215,6 +365,47 @@ impl RpcActions for RpcClient {
         Ok(())
     }
 
+    async fn node_drain(&self, timeout: Duration, stop_when_drained: bool) -> Result<()> {
+        let mut client = self.connect_with_retry().await?;
+        let _response = client
+            .drain(Request::new(DrainRequest {
+                timeout_secs: timeout.as_secs(),
+                stop_when_drained,
+            }))
+            .await
+            .map_err(|e| {
+                error!("Could not drain node through RPC: {e:?}");
+                Error::RpcNodeDrainError(e.to_string())
+            })?;
+        Ok(())
+    }
+
+    async fn drain_status(&self) -> Result<DrainStatus> {
+        let mut client = self.connect_with_retry().await?;
+        let response = client
+            .drain_status(Request::new(DrainStatusRequest {}))
+            .await
+            .map_err(|e| {
+                error!("Could not obtain the drain status through RPC: {e:?}");
+                Error::RpcNodeDrainError(e.to_string())
+            })?;
+        let response = response.get_ref();
+
+        let state = match response.state() {
+            drain_status_response::State::Idle => DrainState::Idle,
+            drain_status_response::State::HandingOff => DrainState::HandingOff,
+            drain_status_response::State::Confirming => DrainState::Confirming,
+            drain_status_response::State::Completed => DrainState::Completed,
+            drain_status_response::State::Failed => DrainState::Failed(response.error.clone()),
+        };
+        Ok(DrainStatus {
+            state,
+            records_total: response.records_total,
+            records_confirmed: response.records_confirmed,
+            records_unconfirmed: response.records_unconfirmed,
+        })
+    }
+
     async fn node_update(&self, delay_millis: u64) -> Result<()> {
         let mut client = self.connect_with_retry().await?;
         let _response = client